@@ -367,7 +367,10 @@ class _IsarCollectionImpl<ID, OBJ> extends IsarCollection<ID, OBJ> {
             ) as R;
     }
 
-    final queryPtr = IsarCore.b.isar_query_build(builderPtr);
+    final queryPtrPtr = malloc<Pointer<CIsarQuery>>();
+    IsarCore.b.isar_query_build(builderPtr, queryPtrPtr).checkNoError();
+    final queryPtr = queryPtrPtr.ptrValue;
+    free(queryPtrPtr);
     return _IsarQueryImpl(
       instanceId: isar.instanceId,
       ptrAddress: queryPtr.address,
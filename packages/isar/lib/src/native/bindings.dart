@@ -1005,20 +1005,23 @@ class IsarCoreBindings {
   late final _isar_query_add_distinct = _isar_query_add_distinctPtr
       .asFunction<void Function(ffi.Pointer<CIsarQueryBuilder>, int, bool)>();
 
-  ffi.Pointer<CIsarQuery> isar_query_build(
+  int isar_query_build(
     ffi.Pointer<CIsarQueryBuilder> builder,
+    ffi.Pointer<ffi.Pointer<CIsarQuery>> query,
   ) {
     return _isar_query_build(
       builder,
+      query,
     );
   }
 
   late final _isar_query_buildPtr = _lookup<
       ffi.NativeFunction<
-          ffi.Pointer<CIsarQuery> Function(
-              ffi.Pointer<CIsarQueryBuilder>)>>('isar_query_build');
+          ffi.Uint8 Function(ffi.Pointer<CIsarQueryBuilder>,
+              ffi.Pointer<ffi.Pointer<CIsarQuery>>)>>('isar_query_build');
   late final _isar_query_build = _isar_query_buildPtr.asFunction<
-      ffi.Pointer<CIsarQuery> Function(ffi.Pointer<CIsarQueryBuilder>)>();
+      int Function(ffi.Pointer<CIsarQueryBuilder>,
+          ffi.Pointer<ffi.Pointer<CIsarQuery>>)>();
 
   int isar_query_cursor(
     ffi.Pointer<CIsarInstance> isar,
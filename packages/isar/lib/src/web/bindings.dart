@@ -453,10 +453,13 @@ extension IsarBindingsX on JSIsar {
     bool case_sensitive,
   );
 
-  @ffi.Native<ffi.Pointer<CIsarQuery> Function(ffi.Pointer<CIsarQueryBuilder>)>(
+  @ffi.Native<
+          ffi.Uint8 Function(ffi.Pointer<CIsarQueryBuilder>,
+              ffi.Pointer<ffi.Pointer<CIsarQuery>>)>(
       symbol: 'isar_query_build')
-  external ffi.Pointer<CIsarQuery> isar_query_build(
+  external int isar_query_build(
     ffi.Pointer<CIsarQueryBuilder> builder,
+    ffi.Pointer<ffi.Pointer<CIsarQuery>> query,
   );
 
   @ffi.Native<
@@ -0,0 +1,72 @@
+use isar_core::convert::{convert, Backend};
+use isar_core::core::schema::IsarSchema;
+use std::env;
+use std::fs;
+use std::process::exit;
+
+const USAGE: &str = "Usage: isar-convert <native|sqlite> <schema.json> <dir> <name> <target_dir> \
+[max_size_mib] [encryption_key]
+
+Converts the <native|sqlite> database <name> in <dir> to the other backend and writes it to
+<target_dir>. The schema file contains the JSON array of collection schemas the database was
+opened with. The encryption key is used for the SQLite database.";
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.len() < 5 || args.len() > 7 {
+        eprintln!("{}", USAGE);
+        exit(2);
+    }
+
+    let source = match args[0].parse::<Backend>() {
+        Ok(source) => source,
+        Err(_) => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+    };
+    let schemas = match read_schemas(&args[1]) {
+        Ok(schemas) => schemas,
+        Err(err) => {
+            eprintln!("Could not read schema: {}", err);
+            exit(1);
+        }
+    };
+    let max_size_mib = match args.get(5).map(|s| s.parse::<u32>()) {
+        Some(Ok(max_size_mib)) => max_size_mib,
+        Some(Err(_)) => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+        None => 1024,
+    };
+    let encryption_key = args.get(6).map(|s| s.as_str());
+
+    match convert(
+        source,
+        &args[3],
+        &args[2],
+        &args[4],
+        schemas,
+        max_size_mib,
+        encryption_key,
+    ) {
+        Ok(summary) => {
+            for collection in summary {
+                println!(
+                    "{}: {} objects, checksum {:016x}",
+                    collection.name, collection.count, collection.checksum
+                );
+            }
+        }
+        Err(err) => {
+            eprintln!("Conversion failed: {}", err);
+            exit(1);
+        }
+    }
+}
+
+fn read_schemas(path: &str) -> Result<Vec<IsarSchema>, String> {
+    let json = fs::read(path).map_err(|e| e.to_string())?;
+    IsarSchema::from_json(&json).map_err(|e| e.to_string())
+}
@@ -0,0 +1,30 @@
+use super::{AnyCursor, AnyQueryCursor, AnyReader};
+use crate::core::cursor::{IsarCursor, IsarQueryCursor};
+
+impl<'a> IsarCursor for AnyCursor<'a> {
+    type Reader<'b>
+        = AnyReader<'b>
+    where
+        Self: 'b;
+
+    fn next(&mut self, id: i64) -> Option<Self::Reader<'_>> {
+        match self {
+            AnyCursor::Native(cursor) => cursor.next(id).map(AnyReader::Native),
+            AnyCursor::SQLite(cursor) => cursor.next(id).map(AnyReader::SQLite),
+        }
+    }
+}
+
+impl<'a> IsarQueryCursor for AnyQueryCursor<'a> {
+    type Reader<'b>
+        = AnyReader<'b>
+    where
+        Self: 'b;
+
+    fn next(&mut self) -> Option<Self::Reader<'_>> {
+        match self {
+            AnyQueryCursor::Native(cursor) => cursor.next().map(AnyReader::Native),
+            AnyQueryCursor::SQLite(cursor) => cursor.next().map(AnyReader::SQLite),
+        }
+    }
+}
@@ -0,0 +1,494 @@
+use super::{
+    AnyCursor, AnyInsert, AnyInstance, AnyQuery, AnyQueryBuilder, AnyQueryCursor, AnyReader,
+    AnyTxn, Backend,
+};
+use crate::core::data_type::DataType;
+use crate::core::error::{IsarError, Result};
+use crate::core::instance::{Aggregation, CompactCondition, IsarInstance, RestoreResult};
+use crate::core::schema::IsarSchema;
+use crate::core::update::PropertyUpdate;
+use crate::core::value::IsarValue;
+use crate::core::watcher::{WatchHandle, WatcherCallback};
+use crate::native::native_instance::NativeInstance;
+use crate::sqlite::sqlite_instance::SQLiteInstance;
+use crate::SQLITE_MEMORY_DIR;
+
+macro_rules! dispatch {
+    ($instance:expr, $isar:ident => $body:expr) => {
+        match $instance {
+            AnyInstance::Native($isar) => $body,
+            AnyInstance::SQLite($isar) => $body,
+        }
+    };
+}
+
+macro_rules! dispatch_txn {
+    ($instance:expr, $txn:expr, $isar:ident, $inner:ident => $body:expr) => {
+        match ($instance, $txn) {
+            (AnyInstance::Native($isar), AnyTxn::Native($inner)) => $body,
+            (AnyInstance::SQLite($isar), AnyTxn::SQLite($inner)) => $body,
+            _ => Err(IsarError::InstanceMismatch {}),
+        }
+    };
+}
+
+macro_rules! dispatch_query {
+    ($instance:expr, $txn:expr, $query:expr, $isar:ident, $inner:ident, $q:ident => $body:expr) => {
+        match ($instance, $txn, $query) {
+            (AnyInstance::Native($isar), AnyTxn::Native($inner), AnyQuery::Native($q)) => $body,
+            (AnyInstance::SQLite($isar), AnyTxn::SQLite($inner), AnyQuery::SQLite($q)) => $body,
+            _ => Err(IsarError::InstanceMismatch {}),
+        }
+    };
+}
+
+impl AnyInstance {
+    #[allow(clippy::too_many_arguments)]
+    pub fn open(
+        backend: Backend,
+        instance_id: u32,
+        name: &str,
+        dir: &str,
+        schemas: Vec<IsarSchema>,
+        max_size_mib: u32,
+        encryption_key: Option<&str>,
+        compact_condition: Option<CompactCondition>,
+    ) -> Result<Self> {
+        match backend {
+            Backend::Native => NativeInstance::open_instance(
+                instance_id,
+                name,
+                dir,
+                schemas,
+                max_size_mib,
+                encryption_key,
+                compact_condition,
+            )
+            .map(AnyInstance::Native),
+            Backend::SQLite => SQLiteInstance::open_instance(
+                instance_id,
+                name,
+                dir,
+                schemas,
+                max_size_mib,
+                encryption_key,
+                compact_condition,
+            )
+            .map(AnyInstance::SQLite),
+        }
+    }
+
+    pub fn backend(&self) -> Backend {
+        match self {
+            AnyInstance::Native(_) => Backend::Native,
+            AnyInstance::SQLite(_) => Backend::SQLite,
+        }
+    }
+}
+
+impl IsarInstance for AnyInstance {
+    type Instance = Self;
+
+    type Txn = AnyTxn;
+
+    type Reader<'a> = AnyReader<'a>;
+
+    type Cursor<'a> = AnyCursor<'a>;
+
+    type Insert<'a> = AnyInsert<'a>;
+
+    type QueryBuilder<'a> = AnyQueryBuilder<'a>;
+
+    type Query = AnyQuery;
+
+    type QueryCursor<'a> = AnyQueryCursor<'a>;
+
+    fn get_instance(instance_id: u32) -> Option<Self::Instance> {
+        NativeInstance::get_instance(instance_id)
+            .map(AnyInstance::Native)
+            .or_else(|| SQLiteInstance::get_instance(instance_id).map(AnyInstance::SQLite))
+    }
+
+    fn get_name(&self) -> &str {
+        dispatch!(self, isar => isar.get_name())
+    }
+
+    fn get_dir(&self) -> &str {
+        dispatch!(self, isar => isar.get_dir())
+    }
+
+    fn get_collections(&self) -> impl Iterator<Item = &str> {
+        let collections: Box<dyn Iterator<Item = &str>> =
+            dispatch!(self, isar => Box::new(isar.get_collections()));
+        collections
+    }
+
+    fn get_id_name(&self, collection_index: u16) -> Result<Option<&str>> {
+        dispatch!(self, isar => isar.get_id_name(collection_index))
+    }
+
+    fn get_schema(&self, collection_index: u16) -> Result<&IsarSchema> {
+        dispatch!(self, isar => isar.get_schema(collection_index))
+    }
+
+    fn get_version_property(&self, collection_index: u16) -> Result<Option<u16>> {
+        dispatch!(self, isar => isar.get_version_property(collection_index))
+    }
+
+    fn get_properties(
+        &self,
+        collection_index: u16,
+    ) -> Result<impl Iterator<Item = (&str, DataType, Option<u16>)>> {
+        let properties: Box<dyn Iterator<Item = (&str, DataType, Option<u16>)>> =
+            dispatch!(self, isar => Box::new(isar.get_properties(collection_index)?));
+        Ok(properties)
+    }
+
+    fn get_links(&self, collection_index: u16) -> Result<impl Iterator<Item = (&str, u16, bool)>> {
+        let links: Box<dyn Iterator<Item = (&str, u16, bool)>> =
+            dispatch!(self, isar => Box::new(isar.get_links(collection_index)?));
+        Ok(links)
+    }
+
+    /// Opens a native instance unless it is in memory or encrypted which only the SQLite backend
+    /// supports. Use `AnyInstance::open()` to select the backend explicitly.
+    fn open_instance(
+        instance_id: u32,
+        name: &str,
+        dir: &str,
+        schemas: Vec<IsarSchema>,
+        max_size_mib: u32,
+        encryption_key: Option<&str>,
+        compact_condition: Option<CompactCondition>,
+    ) -> Result<Self::Instance> {
+        let backend = if dir == SQLITE_MEMORY_DIR || encryption_key.is_some() {
+            Backend::SQLite
+        } else {
+            Backend::Native
+        };
+        Self::open(
+            backend,
+            instance_id,
+            name,
+            dir,
+            schemas,
+            max_size_mib,
+            encryption_key,
+            compact_condition,
+        )
+    }
+
+    fn change_encryption_key(&self, encryption_key: Option<&str>) -> Result<()> {
+        dispatch!(self, isar => isar.change_encryption_key(encryption_key))
+    }
+
+    fn begin_txn(&self, write: bool) -> Result<Self::Txn> {
+        match self {
+            AnyInstance::Native(isar) => isar.begin_txn(write).map(AnyTxn::Native),
+            AnyInstance::SQLite(isar) => isar.begin_txn(write).map(AnyTxn::SQLite),
+        }
+    }
+
+    fn commit_txn(&self, txn: Self::Txn) -> Result<()> {
+        dispatch_txn!(self, txn, isar, txn => isar.commit_txn(txn))
+    }
+
+    fn abort_txn(&self, txn: Self::Txn) {
+        match (self, txn) {
+            (AnyInstance::Native(isar), AnyTxn::Native(txn)) => isar.abort_txn(txn),
+            (AnyInstance::SQLite(isar), AnyTxn::SQLite(txn)) => isar.abort_txn(txn),
+            _ => unreachable!("transaction of a different backend"),
+        }
+    }
+
+    fn begin_savepoint(&self, txn: &mut Self::Txn) -> Result<()> {
+        dispatch_txn!(self, txn, isar, txn => isar.begin_savepoint(txn))
+    }
+
+    fn release_savepoint(&self, txn: &mut Self::Txn) -> Result<()> {
+        dispatch_txn!(self, txn, isar, txn => isar.release_savepoint(txn))
+    }
+
+    fn rollback_to_savepoint(&self, txn: &mut Self::Txn) -> Result<()> {
+        dispatch_txn!(self, txn, isar, txn => isar.rollback_to_savepoint(txn))
+    }
+
+    fn auto_increment(&self, collection_index: u16) -> i64 {
+        dispatch!(self, isar => isar.auto_increment(collection_index))
+    }
+
+    fn update_auto_increment(&self, collection_index: u16, id: i64) {
+        dispatch!(self, isar => isar.update_auto_increment(collection_index, id))
+    }
+
+    fn cursor<'a>(&'a self, txn: &'a Self::Txn, collection_index: u16) -> Result<Self::Cursor<'a>> {
+        match (self, txn) {
+            (AnyInstance::Native(isar), AnyTxn::Native(txn)) => {
+                isar.cursor(txn, collection_index).map(AnyCursor::Native)
+            }
+            (AnyInstance::SQLite(isar), AnyTxn::SQLite(txn)) => {
+                isar.cursor(txn, collection_index).map(AnyCursor::SQLite)
+            }
+            _ => Err(IsarError::InstanceMismatch {}),
+        }
+    }
+
+    fn insert(
+        &self,
+        txn: Self::Txn,
+        collection_index: u16,
+        count: u32,
+    ) -> Result<Self::Insert<'_>> {
+        match (self, txn) {
+            (AnyInstance::Native(isar), AnyTxn::Native(txn)) => isar
+                .insert(txn, collection_index, count)
+                .map(AnyInsert::Native),
+            (AnyInstance::SQLite(isar), AnyTxn::SQLite(txn)) => isar
+                .insert(txn, collection_index, count)
+                .map(AnyInsert::SQLite),
+            _ => Err(IsarError::InstanceMismatch {}),
+        }
+    }
+
+    fn update(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        id: i64,
+        updates: &[(u16, Option<IsarValue>)],
+    ) -> Result<bool> {
+        dispatch_txn!(self, txn, isar, txn => isar.update(txn, collection_index, id, updates))
+    }
+
+    fn update_properties(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        id: i64,
+        updates: &[PropertyUpdate],
+    ) -> Result<bool> {
+        dispatch_txn!(self, txn, isar, txn => {
+            isar.update_properties(txn, collection_index, id, updates)
+        })
+    }
+
+    fn delete(&self, txn: &Self::Txn, collection_index: u16, id: i64) -> Result<bool> {
+        dispatch_txn!(self, txn, isar, txn => isar.delete(txn, collection_index, id))
+    }
+
+    fn count(&self, txn: &Self::Txn, collection_index: u16) -> Result<u32> {
+        dispatch_txn!(self, txn, isar, txn => isar.count(txn, collection_index))
+    }
+
+    fn link(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+        target_id: i64,
+    ) -> Result<bool> {
+        dispatch_txn!(self, txn, isar, txn => {
+            isar.link(txn, collection_index, link_index, id, target_id)
+        })
+    }
+
+    fn unlink(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+        target_id: i64,
+    ) -> Result<bool> {
+        dispatch_txn!(self, txn, isar, txn => {
+            isar.unlink(txn, collection_index, link_index, id, target_id)
+        })
+    }
+
+    fn unlink_all(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+    ) -> Result<u32> {
+        dispatch_txn!(self, txn, isar, txn => isar.unlink_all(txn, collection_index, link_index, id))
+    }
+
+    fn get_linked_ids(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+    ) -> Result<Vec<i64>> {
+        dispatch_txn!(self, txn, isar, txn => {
+            isar.get_linked_ids(txn, collection_index, link_index, id)
+        })
+    }
+
+    fn count_links(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+    ) -> Result<u32> {
+        dispatch_txn!(self, txn, isar, txn => isar.count_links(txn, collection_index, link_index, id))
+    }
+
+    fn clear(&self, txn: &Self::Txn, collection_index: u16) -> Result<()> {
+        dispatch_txn!(self, txn, isar, txn => isar.clear(txn, collection_index))
+    }
+
+    fn purge_expired(&self, txn: &Self::Txn) -> Result<u32> {
+        dispatch_txn!(self, txn, isar, txn => isar.purge_expired(txn))
+    }
+
+    fn get_size(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        include_indexes: bool,
+    ) -> Result<u64> {
+        dispatch_txn!(self, txn, isar, txn => {
+            isar.get_size(txn, collection_index, include_indexes)
+        })
+    }
+
+    fn query(&self, collection_index: u16) -> Result<Self::QueryBuilder<'_>> {
+        match self {
+            AnyInstance::Native(isar) => isar.query(collection_index).map(AnyQueryBuilder::Native),
+            AnyInstance::SQLite(isar) => isar.query(collection_index).map(AnyQueryBuilder::SQLite),
+        }
+    }
+
+    fn query_cursor<'a>(
+        &'a self,
+        txn: &'a Self::Txn,
+        query: &'a Self::Query,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<Self::QueryCursor<'a>> {
+        match (self, txn, query) {
+            (AnyInstance::Native(isar), AnyTxn::Native(txn), AnyQuery::Native(query)) => isar
+                .query_cursor(txn, query, offset, limit)
+                .map(|cursor| AnyQueryCursor::Native(Box::new(cursor))),
+            (AnyInstance::SQLite(isar), AnyTxn::SQLite(txn), AnyQuery::SQLite(query)) => isar
+                .query_cursor(txn, query, offset, limit)
+                .map(AnyQueryCursor::SQLite),
+            _ => Err(IsarError::InstanceMismatch {}),
+        }
+    }
+
+    fn query_aggregate(
+        &self,
+        txn: &Self::Txn,
+        query: &Self::Query,
+        aggregation: Aggregation,
+        property_index: Option<u16>,
+    ) -> Result<Option<IsarValue>> {
+        dispatch_query!(self, txn, query, isar, txn, query => {
+            isar.query_aggregate(txn, query, aggregation, property_index)
+        })
+    }
+
+    fn query_update(
+        &self,
+        txn: &Self::Txn,
+        query: &Self::Query,
+        offset: Option<u32>,
+        limit: Option<u32>,
+        updates: &[(u16, Option<IsarValue>)],
+    ) -> Result<u32> {
+        dispatch_query!(self, txn, query, isar, txn, query => {
+            isar.query_update(txn, query, offset, limit, updates)
+        })
+    }
+
+    fn query_update_properties(
+        &self,
+        txn: &Self::Txn,
+        query: &Self::Query,
+        offset: Option<u32>,
+        limit: Option<u32>,
+        updates: &[PropertyUpdate],
+    ) -> Result<u32> {
+        dispatch_query!(self, txn, query, isar, txn, query => {
+            isar.query_update_properties(txn, query, offset, limit, updates)
+        })
+    }
+
+    fn query_delete(
+        &self,
+        txn: &Self::Txn,
+        query: &Self::Query,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<u32> {
+        dispatch_query!(self, txn, query, isar, txn, query => {
+            isar.query_delete(txn, query, offset, limit)
+        })
+    }
+
+    fn watch(&self, collection_index: u16, callback: WatcherCallback) -> Result<WatchHandle> {
+        dispatch!(self, isar => isar.watch(collection_index, callback))
+    }
+
+    fn watch_object(
+        &self,
+        collection_index: u16,
+        id: i64,
+        callback: WatcherCallback,
+    ) -> Result<WatchHandle> {
+        dispatch!(self, isar => isar.watch_object(collection_index, id, callback))
+    }
+
+    fn watch_query(&self, query: &Self::Query, callback: WatcherCallback) -> Result<WatchHandle> {
+        match (self, query) {
+            (AnyInstance::Native(isar), AnyQuery::Native(query)) => {
+                isar.watch_query(query, callback)
+            }
+            (AnyInstance::SQLite(isar), AnyQuery::SQLite(query)) => {
+                isar.watch_query(query, callback)
+            }
+            _ => Err(IsarError::InstanceMismatch {}),
+        }
+    }
+
+    fn copy(&self, path: &str) -> Result<()> {
+        dispatch!(self, isar => isar.copy(path))
+    }
+
+    fn backup(&self, path: &str, progress: impl FnMut(u64, u64)) -> Result<()> {
+        dispatch!(self, isar => isar.backup(path, progress))
+    }
+
+    fn restore(
+        instance: Self::Instance,
+        path: &str,
+        schemas: Vec<IsarSchema>,
+        max_size_mib: u32,
+    ) -> RestoreResult<Self::Instance> {
+        match instance {
+            AnyInstance::Native(isar) => NativeInstance::restore(isar, path, schemas, max_size_mib)
+                .map(AnyInstance::Native)
+                .map_err(|(isar, e)| (isar.map(AnyInstance::Native), e)),
+            AnyInstance::SQLite(isar) => SQLiteInstance::restore(isar, path, schemas, max_size_mib)
+                .map(AnyInstance::SQLite)
+                .map_err(|(isar, e)| (isar.map(AnyInstance::SQLite), e)),
+        }
+    }
+
+    fn verify(&self, txn: &Self::Txn) -> Result<()> {
+        dispatch_txn!(self, txn, isar, txn => isar.verify(txn))
+    }
+
+    fn close(instance: Self::Instance, delete: bool) -> bool {
+        match instance {
+            AnyInstance::Native(isar) => NativeInstance::close(isar, delete),
+            AnyInstance::SQLite(isar) => SQLiteInstance::close(isar, delete),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use super::{AnyQuery, AnyQueryBuilder};
+use crate::core::error::Result;
+use crate::core::filter::Filter;
+use crate::core::query_builder::{IsarQueryBuilder, Sort};
+
+impl<'a> IsarQueryBuilder for AnyQueryBuilder<'a> {
+    type Query = AnyQuery;
+
+    fn set_filter(&mut self, filter: Filter) {
+        match self {
+            AnyQueryBuilder::Native(builder) => builder.set_filter(filter),
+            AnyQueryBuilder::SQLite(builder) => builder.set_filter(filter),
+        }
+    }
+
+    fn add_sort(&mut self, property_index: u16, sort: Sort, case_sensitive: bool) {
+        match self {
+            AnyQueryBuilder::Native(builder) => {
+                builder.add_sort(property_index, sort, case_sensitive)
+            }
+            AnyQueryBuilder::SQLite(builder) => {
+                builder.add_sort(property_index, sort, case_sensitive)
+            }
+        }
+    }
+
+    fn add_distance_sort(
+        &mut self,
+        property_index: u16,
+        latitude: f64,
+        longitude: f64,
+        sort: Sort,
+    ) {
+        match self {
+            AnyQueryBuilder::Native(builder) => {
+                builder.add_distance_sort(property_index, latitude, longitude, sort)
+            }
+            AnyQueryBuilder::SQLite(builder) => {
+                builder.add_distance_sort(property_index, latitude, longitude, sort)
+            }
+        }
+    }
+
+    fn add_similarity_sort(&mut self, property_index: u16, vector: Vec<f32>) {
+        match self {
+            AnyQueryBuilder::Native(builder) => builder.add_similarity_sort(property_index, vector),
+            AnyQueryBuilder::SQLite(builder) => builder.add_similarity_sort(property_index, vector),
+        }
+    }
+
+    fn add_distinct(&mut self, property_index: u16, case_sensitive: bool) {
+        match self {
+            AnyQueryBuilder::Native(builder) => {
+                builder.add_distinct(property_index, case_sensitive)
+            }
+            AnyQueryBuilder::SQLite(builder) => {
+                builder.add_distinct(property_index, case_sensitive)
+            }
+        }
+    }
+
+    fn build(self) -> Result<Self::Query> {
+        let query = match self {
+            AnyQueryBuilder::Native(builder) => AnyQuery::Native(builder.build()?),
+            AnyQueryBuilder::SQLite(builder) => AnyQuery::SQLite(builder.build()?),
+        };
+        Ok(query)
+    }
+}
@@ -0,0 +1,155 @@
+use super::AnyReader;
+use crate::core::data_type::DataType;
+use crate::core::reader::IsarReader;
+use crate::core::value::{DateTimeOffset, Decimal, Uuid};
+use std::borrow::Cow;
+
+macro_rules! dispatch {
+    ($reader:expr, $inner:ident => $body:expr) => {
+        match $reader {
+            AnyReader::Native($inner) => $body,
+            AnyReader::NativeList($inner) => $body,
+            AnyReader::SQLite($inner) => $body,
+            AnyReader::SQLiteObject($inner) => $body,
+            AnyReader::SQLiteList($inner) => $body,
+        }
+    };
+}
+
+impl<'a> IsarReader for AnyReader<'a> {
+    type ObjectReader<'b>
+        = AnyReader<'b>
+    where
+        Self: 'b;
+
+    type ListReader<'b>
+        = AnyReader<'b>
+    where
+        Self: 'b;
+
+    fn id_name(&self) -> Option<&str> {
+        dispatch!(self, reader => reader.id_name())
+    }
+
+    fn properties(&self) -> impl Iterator<Item = (&str, DataType)> {
+        let properties: Box<dyn Iterator<Item = (&str, DataType)>> =
+            dispatch!(self, reader => Box::new(reader.properties()));
+        properties
+    }
+
+    fn read_id(&self) -> i64 {
+        dispatch!(self, reader => reader.read_id())
+    }
+
+    fn is_null(&self, index: u32) -> bool {
+        dispatch!(self, reader => reader.is_null(index))
+    }
+
+    fn read_bool(&self, index: u32) -> Option<bool> {
+        dispatch!(self, reader => reader.read_bool(index))
+    }
+
+    fn read_byte(&self, index: u32) -> u8 {
+        dispatch!(self, reader => reader.read_byte(index))
+    }
+
+    fn read_int(&self, index: u32) -> i32 {
+        dispatch!(self, reader => reader.read_int(index))
+    }
+
+    fn read_float(&self, index: u32) -> f32 {
+        dispatch!(self, reader => reader.read_float(index))
+    }
+
+    fn read_long(&self, index: u32) -> i64 {
+        dispatch!(self, reader => reader.read_long(index))
+    }
+
+    fn read_double(&self, index: u32) -> f64 {
+        dispatch!(self, reader => reader.read_double(index))
+    }
+
+    fn read_string(&self, index: u32) -> Option<&str> {
+        dispatch!(self, reader => reader.read_string(index))
+    }
+
+    fn read_decimal(&self, index: u32) -> Option<Decimal> {
+        dispatch!(self, reader => reader.read_decimal(index))
+    }
+
+    fn read_uuid(&self, index: u32) -> Option<Uuid> {
+        dispatch!(self, reader => reader.read_uuid(index))
+    }
+
+    fn read_date_time_offset(&self, index: u32) -> Option<DateTimeOffset> {
+        dispatch!(self, reader => reader.read_date_time_offset(index))
+    }
+
+    fn read_blob(&self, index: u32) -> Option<Cow<'_, [u8]>> {
+        dispatch!(self, reader => reader.read_blob(index))
+    }
+
+    fn read_blob_length(&self, index: u32) -> Option<usize> {
+        dispatch!(self, reader => reader.read_blob_length(index))
+    }
+
+    fn read_blob_chunk(&self, index: u32, offset: usize, buffer: &mut [u8]) -> Option<usize> {
+        dispatch!(self, reader => reader.read_blob_chunk(index, offset, buffer))
+    }
+
+    fn read_object(&self, index: u32) -> Option<Self::ObjectReader<'_>> {
+        match self {
+            AnyReader::Native(reader) => reader.read_object(index).map(AnyReader::Native),
+            AnyReader::NativeList(reader) => reader.read_object(index).map(AnyReader::Native),
+            AnyReader::SQLite(reader) => reader.read_object(index).map(AnyReader::SQLiteObject),
+            AnyReader::SQLiteObject(reader) => {
+                reader.read_object(index).map(AnyReader::SQLiteObject)
+            }
+            AnyReader::SQLiteList(reader) => reader.read_object(index).map(AnyReader::SQLiteObject),
+        }
+    }
+
+    fn read_list(&self, index: u32) -> Option<(Self::ListReader<'_>, u32)> {
+        match self {
+            AnyReader::Native(reader) => reader
+                .read_list(index)
+                .map(|(list, length)| (AnyReader::NativeList(list), length)),
+            AnyReader::NativeList(reader) => reader
+                .read_list(index)
+                .map(|(list, length)| (AnyReader::NativeList(list), length)),
+            AnyReader::SQLite(reader) => reader
+                .read_list(index)
+                .map(|(list, length)| (AnyReader::SQLiteList(list), length)),
+            AnyReader::SQLiteObject(reader) => reader
+                .read_list(index)
+                .map(|(list, length)| (AnyReader::SQLiteList(list), length)),
+            AnyReader::SQLiteList(reader) => reader
+                .read_list(index)
+                .map(|(list, length)| (AnyReader::SQLiteList(list), length)),
+        }
+    }
+
+    fn read_map(&self, index: u32) -> Option<(Vec<Cow<'_, str>>, Self::ListReader<'_>)> {
+        match self {
+            AnyReader::Native(reader) => reader
+                .read_map(index)
+                .map(|(keys, values)| (keys, AnyReader::NativeList(values))),
+            AnyReader::NativeList(reader) => reader
+                .read_map(index)
+                .map(|(keys, values)| (keys, AnyReader::NativeList(values))),
+            AnyReader::SQLite(reader) => reader
+                .read_map(index)
+                .map(|(keys, values)| (keys, AnyReader::SQLiteList(values))),
+            AnyReader::SQLiteObject(reader) => reader
+                .read_map(index)
+                .map(|(keys, values)| (keys, AnyReader::SQLiteList(values))),
+            AnyReader::SQLiteList(reader) => reader
+                .read_map(index)
+                .map(|(keys, values)| (keys, AnyReader::SQLiteList(values))),
+        }
+    }
+
+    fn read_score(&self) -> Option<f64> {
+        dispatch!(self, reader => reader.read_score())
+    }
+}
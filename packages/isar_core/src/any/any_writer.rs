@@ -0,0 +1,189 @@
+use super::{AnyInsert, AnyTxn, AnyWriter};
+use crate::core::data_type::DataType;
+use crate::core::error::Result;
+use crate::core::insert::IsarInsert;
+use crate::core::value::{DateTimeOffset, Decimal, Uuid};
+use crate::core::writer::IsarWriter;
+
+// variant => (object writer variant, list writer variant)
+macro_rules! impl_writer {
+    ($writer:ident, $($variant:ident => ($object:ident, $list:ident)),+) => {
+        impl<'a> IsarWriter<'a> for $writer<'a> {
+            type ObjectWriter = AnyWriter<'a>;
+
+            type ListWriter = AnyWriter<'a>;
+
+            fn id_name(&self) -> Option<&str> {
+                match self {
+                    $($writer::$variant(writer) => writer.id_name(),)+
+                }
+            }
+
+            fn properties(&self) -> impl Iterator<Item = (&str, DataType)> {
+                let properties: Box<dyn Iterator<Item = (&str, DataType)>> = match self {
+                    $($writer::$variant(writer) => Box::new(writer.properties()),)+
+                };
+                properties
+            }
+
+            fn write_null(&mut self, index: u32) {
+                match self {
+                    $($writer::$variant(writer) => writer.write_null(index),)+
+                }
+            }
+
+            fn write_bool(&mut self, index: u32, value: bool) {
+                match self {
+                    $($writer::$variant(writer) => writer.write_bool(index, value),)+
+                }
+            }
+
+            fn write_byte(&mut self, index: u32, value: u8) {
+                match self {
+                    $($writer::$variant(writer) => writer.write_byte(index, value),)+
+                }
+            }
+
+            fn write_int(&mut self, index: u32, value: i32) {
+                match self {
+                    $($writer::$variant(writer) => writer.write_int(index, value),)+
+                }
+            }
+
+            fn write_float(&mut self, index: u32, value: f32) {
+                match self {
+                    $($writer::$variant(writer) => writer.write_float(index, value),)+
+                }
+            }
+
+            fn write_long(&mut self, index: u32, value: i64) {
+                match self {
+                    $($writer::$variant(writer) => writer.write_long(index, value),)+
+                }
+            }
+
+            fn write_double(&mut self, index: u32, value: f64) {
+                match self {
+                    $($writer::$variant(writer) => writer.write_double(index, value),)+
+                }
+            }
+
+            fn write_string(&mut self, index: u32, value: &str) {
+                match self {
+                    $($writer::$variant(writer) => writer.write_string(index, value),)+
+                }
+            }
+
+            fn write_decimal(&mut self, index: u32, value: Decimal) {
+                match self {
+                    $($writer::$variant(writer) => writer.write_decimal(index, value),)+
+                }
+            }
+
+            fn write_uuid(&mut self, index: u32, value: Uuid) {
+                match self {
+                    $($writer::$variant(writer) => writer.write_uuid(index, value),)+
+                }
+            }
+
+            fn write_date_time_offset(&mut self, index: u32, value: DateTimeOffset) {
+                match self {
+                    $($writer::$variant(writer) => writer.write_date_time_offset(index, value),)+
+                }
+            }
+
+            fn write_byte_list(&mut self, index: u32, value: &[u8]) {
+                match self {
+                    $($writer::$variant(writer) => writer.write_byte_list(index, value),)+
+                }
+            }
+
+            fn write_blob(&mut self, index: u32, value: &[u8]) {
+                match self {
+                    $($writer::$variant(writer) => writer.write_blob(index, value),)+
+                }
+            }
+
+            fn begin_object(&mut self, index: u32) -> Option<Self::ObjectWriter> {
+                match self {
+                    $($writer::$variant(writer) => {
+                        writer.begin_object(index).map(AnyWriter::$object)
+                    })+
+                }
+            }
+
+            fn end_object(&mut self, object: Self::ObjectWriter) {
+                match (self, object) {
+                    $(($writer::$variant(writer), AnyWriter::$object(object)) => {
+                        writer.end_object(object)
+                    })+
+                    _ => unreachable!("object writer of a different backend"),
+                }
+            }
+
+            fn begin_list(&mut self, index: u32, length: u32) -> Option<Self::ListWriter> {
+                match self {
+                    $($writer::$variant(writer) => {
+                        writer.begin_list(index, length).map(AnyWriter::$list)
+                    })+
+                }
+            }
+
+            fn end_list(&mut self, list: Self::ListWriter) {
+                match (self, list) {
+                    $(($writer::$variant(writer), AnyWriter::$list(list)) => {
+                        writer.end_list(list)
+                    })+
+                    _ => unreachable!("list writer of a different backend"),
+                }
+            }
+
+            fn begin_map(&mut self, index: u32, keys: &[&str]) -> Option<Self::ListWriter> {
+                match self {
+                    $($writer::$variant(writer) => {
+                        writer.begin_map(index, keys).map(AnyWriter::$list)
+                    })+
+                }
+            }
+        }
+    };
+}
+
+impl_writer!(
+    AnyInsert,
+    Native => (NativeObject, NativeList),
+    SQLite => (SQLiteObject, SQLiteList)
+);
+
+impl_writer!(
+    AnyWriter,
+    NativeObject => (NativeObject, NativeList),
+    NativeList => (NativeObject, NativeList),
+    SQLiteObject => (SQLiteObject, SQLiteList),
+    SQLiteList => (SQLiteObject, SQLiteList)
+);
+
+impl<'a> IsarInsert<'a> for AnyInsert<'a> {
+    type Txn = AnyTxn;
+
+    fn save(&mut self, id: i64) -> Result<()> {
+        match self {
+            AnyInsert::Native(insert) => insert.save(id),
+            AnyInsert::SQLite(insert) => insert.save(id),
+        }
+    }
+
+    fn finish(self) -> Result<Self::Txn> {
+        match self {
+            AnyInsert::Native(insert) => insert.finish().map(AnyTxn::Native),
+            AnyInsert::SQLite(insert) => insert.finish().map(AnyTxn::SQLite),
+        }
+    }
+
+    fn cancel(self) -> Self::Txn {
+        match self {
+            AnyInsert::Native(insert) => AnyTxn::Native(insert.cancel()),
+            AnyInsert::SQLite(insert) => AnyTxn::SQLite(insert.cancel()),
+        }
+    }
+}
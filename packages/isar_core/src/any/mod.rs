@@ -0,0 +1,145 @@
+use crate::core::cursor::IsarCursor;
+use crate::core::error::{IsarError, Result};
+use crate::core::instance::IsarInstance;
+use crate::core::reader::IsarReader;
+use crate::core::writer::IsarWriter;
+use crate::native::native_instance::NativeInstance;
+use crate::sqlite::sqlite_instance::SQLiteInstance;
+use std::str::FromStr;
+
+mod any_cursor;
+mod any_instance;
+mod any_query;
+mod any_reader;
+mod any_writer;
+
+type NTxn = <NativeInstance as IsarInstance>::Txn;
+type STxn = <SQLiteInstance as IsarInstance>::Txn;
+
+type NInsert<'a> = <NativeInstance as IsarInstance>::Insert<'a>;
+type SInsert<'a> = <SQLiteInstance as IsarInstance>::Insert<'a>;
+
+type NObjectWriter<'a> = <NInsert<'a> as IsarWriter<'a>>::ObjectWriter;
+type SObjectWriter<'a> = <SInsert<'a> as IsarWriter<'a>>::ObjectWriter;
+
+type NListWriter<'a> = <NInsert<'a> as IsarWriter<'a>>::ListWriter;
+type SListWriter<'a> = <SInsert<'a> as IsarWriter<'a>>::ListWriter;
+
+type NCursor<'a> = <NativeInstance as IsarInstance>::Cursor<'a>;
+type SCursor<'a> = <SQLiteInstance as IsarInstance>::Cursor<'a>;
+
+type NReader<'a> = <NCursor<'a> as IsarCursor>::Reader<'a>;
+type SReader<'a> = <SCursor<'a> as IsarCursor>::Reader<'a>;
+
+type SObjectReader<'a> = <SReader<'a> as IsarReader>::ObjectReader<'a>;
+
+type NListReader<'a> = <NReader<'a> as IsarReader>::ListReader<'a>;
+type SListReader<'a> = <SReader<'a> as IsarReader>::ListReader<'a>;
+
+type NQueryBuilder<'a> = <NativeInstance as IsarInstance>::QueryBuilder<'a>;
+type SQueryBuilder<'a> = <SQLiteInstance as IsarInstance>::QueryBuilder<'a>;
+
+type NQuery = <NativeInstance as IsarInstance>::Query;
+type SQuery = <SQLiteInstance as IsarInstance>::Query;
+
+type NQueryCursor<'a> = <NativeInstance as IsarInstance>::QueryCursor<'a>;
+type SQueryCursor<'a> = <SQLiteInstance as IsarInstance>::QueryCursor<'a>;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Backend {
+    Native,
+    SQLite,
+}
+
+impl Backend {
+    pub fn file_name(&self, name: &str) -> String {
+        match self {
+            Backend::Native => format!("{}.isar", name),
+            Backend::SQLite => format!("{}.sqlite", name),
+        }
+    }
+
+    pub fn other(&self) -> Backend {
+        match self {
+            Backend::Native => Backend::SQLite,
+            Backend::SQLite => Backend::Native,
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = IsarError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "native" | "mdbx" => Ok(Backend::Native),
+            "sqlite" => Ok(Backend::SQLite),
+            _ => Err(IsarError::IllegalArgument {}),
+        }
+    }
+}
+
+/// An instance of either backend that is selected at runtime.
+pub enum AnyInstance {
+    Native(<NativeInstance as IsarInstance>::Instance),
+    SQLite(<SQLiteInstance as IsarInstance>::Instance),
+}
+
+pub enum AnyTxn {
+    Native(NTxn),
+    SQLite(STxn),
+}
+
+pub enum AnyCursor<'a> {
+    Native(NCursor<'a>),
+    SQLite(SCursor<'a>),
+}
+
+pub enum AnyInsert<'a> {
+    Native(NInsert<'a>),
+    SQLite(SInsert<'a>),
+}
+
+/// The writer of embedded objects and lists.
+pub enum AnyWriter<'a> {
+    NativeObject(NObjectWriter<'a>),
+    NativeList(NListWriter<'a>),
+    SQLiteObject(SObjectWriter<'a>),
+    SQLiteList(SListWriter<'a>),
+}
+
+pub enum AnyReader<'a> {
+    Native(NReader<'a>),
+    NativeList(NListReader<'a>),
+    SQLite(SReader<'a>),
+    SQLiteObject(SObjectReader<'a>),
+    SQLiteList(SListReader<'a>),
+}
+
+pub enum AnyQueryBuilder<'a> {
+    Native(NQueryBuilder<'a>),
+    SQLite(SQueryBuilder<'a>),
+}
+
+#[derive(Clone)]
+pub enum AnyQuery {
+    Native(NQuery),
+    SQLite(SQuery),
+}
+
+pub enum AnyQueryCursor<'a> {
+    Native(Box<NQueryCursor<'a>>),
+    SQLite(SQueryCursor<'a>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_from_str() {
+        assert_eq!("native".parse::<Backend>(), Ok(Backend::Native));
+        assert_eq!("SQLite".parse::<Backend>(), Ok(Backend::SQLite));
+        assert_eq!("x".parse::<Backend>(), Err(IsarError::IllegalArgument {}));
+    }
+}
@@ -0,0 +1,77 @@
+use super::{for_each_backend, TestDb};
+use crate::any::{AnyInstance, Backend};
+use crate::core::collection::{IsarInstanceExt, IsarObject};
+use crate::core::error::IsarError;
+use crate::core::instance::IsarInstance;
+use crate::core::query_builder::IsarQueryBuilder;
+use crate::core::schema::IsarSchema;
+use isar_derive::IsarCollection;
+
+#[derive(IsarCollection, Clone, PartialEq, Debug)]
+struct Note {
+    id: i64,
+    text: String,
+    tags: Vec<String>,
+}
+
+fn schemas() -> Vec<IsarSchema> {
+    let mut schemas = vec![];
+    Note::add_schemas(&mut schemas);
+    schemas
+}
+
+#[test]
+fn test_any_instance() {
+    let notes = vec![
+        Note {
+            id: 1,
+            text: "a".to_string(),
+            tags: vec!["x".to_string()],
+        },
+        Note {
+            id: 2,
+            text: "b".to_string(),
+            tags: vec![],
+        },
+    ];
+    for_each_backend(|db| {
+        let isar = db.open(schemas());
+        assert_eq!(isar.backend(), db.backend);
+        assert_eq!(isar.get_collections().collect::<Vec<_>>(), vec!["Note"]);
+
+        let txn = isar.begin_txn(true).unwrap();
+        let txn = isar.put_all(txn, &notes).unwrap();
+        isar.commit_txn(txn).unwrap();
+
+        let txn = isar.begin_txn(false).unwrap();
+        assert_eq!(isar.count(&txn, 0), Ok(2));
+        assert_eq!(
+            isar.get_by_id::<Note>(&txn, 1).unwrap().as_ref(),
+            Some(&notes[0])
+        );
+        isar.abort_txn(txn);
+
+        let mut builder = isar.query(0).unwrap();
+        builder.set_filter(Note::TEXT.equal_to("b"));
+        let query = builder.build().unwrap();
+        let txn = isar.begin_txn(true).unwrap();
+        assert_eq!(isar.query_delete(&txn, &query, None, None), Ok(1));
+        assert_eq!(isar.count(&txn, 0), Ok(1));
+        isar.commit_txn(txn).unwrap();
+
+        AnyInstance::close(isar, true);
+    });
+}
+
+#[test]
+fn test_instance_mismatch() {
+    let native = TestDb::new(Backend::Native).open(schemas());
+    let sqlite = TestDb::new(Backend::SQLite).open(schemas());
+    let txn = native.begin_txn(false).unwrap();
+    assert_eq!(sqlite.count(&txn, 0), Err(IsarError::InstanceMismatch {}));
+    let query = sqlite.query(0).unwrap().build().unwrap();
+    assert!(native.query_cursor(&txn, &query, None, None).is_err());
+    native.abort_txn(txn);
+    AnyInstance::close(native, true);
+    AnyInstance::close(sqlite, true);
+}
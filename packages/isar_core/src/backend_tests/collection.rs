@@ -0,0 +1,433 @@
+use super::for_each_backend;
+use crate::any::AnyInstance;
+use crate::core::collection::{Blob, IsarInstanceExt, IsarObject, StringMap};
+use crate::core::cursor::IsarCursor;
+use crate::core::data_type::DataType;
+use crate::core::filter::Filter;
+use crate::core::instance::{IsarInstance, JsonFormat};
+use crate::core::query_builder::IsarQueryBuilder;
+use crate::core::query_builder::Sort;
+use crate::core::reader::IsarReader;
+use crate::core::schema::IsarSchema;
+use crate::core::value::IsarValue;
+use isar_derive::{IsarCollection, IsarEmbedded};
+
+#[derive(IsarEmbedded, Default, Clone, PartialEq, Debug)]
+struct Address {
+    city: String,
+    zip: Option<i32>,
+}
+
+#[derive(IsarCollection, Clone, PartialEq, Debug)]
+#[isar(name = "Users")]
+struct User {
+    #[isar(id)]
+    key: i64,
+    #[isar(index)]
+    name: String,
+    age: Option<i32>,
+    score: f64,
+    active: bool,
+    tags: Vec<String>,
+    data: Option<Vec<u8>>,
+    address: Option<Address>,
+    addresses: Vec<Address>,
+    attributes: StringMap<String>,
+    #[isar(ignore)]
+    cache: Option<String>,
+}
+
+fn users() -> Vec<User> {
+    let user = |key: i64, name: &str, age: Option<i32>, city: Option<&str>| User {
+        key,
+        name: name.to_string(),
+        age,
+        score: key as f64 / 2.0,
+        active: key % 2 == 0,
+        tags: vec![format!("tag{}", key)],
+        data: age.map(|age| vec![age as u8, 0, 255]),
+        address: city.map(|city| Address {
+            city: city.to_string(),
+            zip: age,
+        }),
+        addresses: vec![Address::default(); key as usize],
+        attributes: StringMap::from([
+            (
+                "color".to_string(),
+                ["red", "blue"][key as usize % 2].to_string(),
+            ),
+            (format!("key{}", key), String::new()),
+        ]),
+        cache: None,
+    };
+    vec![
+        user(1, "alice", Some(30), Some("Berlin")),
+        user(2, "bob", None, None),
+        user(3, "carol", Some(25), Some("Paris")),
+        user(4, "dave", Some(18), Some("bern")),
+    ]
+}
+
+fn schemas() -> Vec<IsarSchema> {
+    let mut schemas = vec![];
+    User::add_schemas(&mut schemas);
+    schemas
+}
+
+#[test]
+fn test_schema() {
+    let schemas = schemas();
+    assert_eq!(schemas.len(), 2);
+    let user = &schemas[0];
+    assert_eq!(user.name, "Users");
+    assert_eq!(user.id_name.as_deref(), Some("key"));
+    assert!(!user.embedded);
+    let properties = user
+        .properties
+        .iter()
+        .map(|p| (p.name.as_deref().unwrap(), p.data_type))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        properties,
+        vec![
+            ("name", DataType::String),
+            ("age", DataType::Int),
+            ("score", DataType::Double),
+            ("active", DataType::Bool),
+            ("tags", DataType::StringList),
+            ("data", DataType::ByteList),
+            ("address", DataType::Object),
+            ("addresses", DataType::ObjectList),
+            ("attributes", DataType::StringMap),
+        ]
+    );
+    assert_eq!(user.properties[6].collection.as_deref(), Some("Address"));
+    assert_eq!(user.indexes.len(), 1);
+    assert_eq!(schemas[1], Address::schema());
+    assert!(schemas[1].embedded);
+    assert_eq!(User::KEY.index(), 0);
+    assert_eq!(User::ADDRESSES.index(), 8);
+}
+
+#[test]
+fn test_typed_api() {
+    for_each_backend(|db| {
+        let isar = db.open(schemas());
+        let users = users();
+        let txn = isar.begin_txn(true).unwrap();
+        let txn = isar.put_all(txn, &users).unwrap();
+        isar.commit_txn(txn).unwrap();
+
+        let txn = isar.begin_txn(false).unwrap();
+        assert_eq!(
+            isar.get_by_id::<User>(&txn, 2).unwrap().as_ref(),
+            Some(&users[1])
+        );
+        assert_eq!(isar.get_by_id::<User>(&txn, 5).unwrap(), None);
+
+        let query = isar
+            .typed_query::<User>()
+            .unwrap()
+            .filter(User::AGE.greater_or_equal_to(25))
+            .sort_by(User::NAME, Sort::Desc)
+            .build()
+            .unwrap();
+        let result = isar.find_all(&txn, &query, None, None).unwrap();
+        assert_eq!(result, vec![users[2].clone(), users[0].clone()]);
+
+        let filter = Address::CITY.case_insensitive().starts_with("BER");
+        let query = isar
+            .typed_query::<User>()
+            .unwrap()
+            .filter(User::ADDRESS.nested(filter))
+            .sort_by(User::KEY, Sort::Asc)
+            .build()
+            .unwrap();
+        let result = isar.find_all(&txn, &query, None, None).unwrap();
+        assert_eq!(result, vec![users[0].clone(), users[3].clone()]);
+
+        let query = isar
+            .typed_query::<User>()
+            .unwrap()
+            .filter(User::TAGS.equal_to("tag3"))
+            .build()
+            .unwrap();
+        let result = isar.find_all(&txn, &query, None, None).unwrap();
+        assert_eq!(result, vec![users[2].clone()]);
+
+        let query = isar
+            .typed_query::<User>()
+            .unwrap()
+            .filter(User::ATTRIBUTES.entry("color").equal_to("red"))
+            .sort_by(User::KEY, Sort::Asc)
+            .build()
+            .unwrap();
+        let result = isar.find_all(&txn, &query, None, None).unwrap();
+        assert_eq!(result, vec![users[1].clone(), users[3].clone()]);
+
+        let query = isar
+            .typed_query::<User>()
+            .unwrap()
+            .filter(User::ATTRIBUTES.contains_key("key3"))
+            .build()
+            .unwrap();
+        let result = isar.find_all(&txn, &query, None, None).unwrap();
+        assert_eq!(result, vec![users[2].clone()]);
+        isar.abort_txn(txn);
+
+        let txn = isar.begin_txn(true).unwrap();
+        assert!(isar.delete_by_id::<User>(&txn, 1).unwrap());
+        assert!(!isar.delete_by_id::<User>(&txn, 1).unwrap());
+        isar.commit_txn(txn).unwrap();
+        AnyInstance::close(isar, true);
+    });
+}
+
+#[derive(IsarEmbedded, Default, Clone, PartialEq, Debug)]
+struct Label {
+    text: String,
+}
+
+#[derive(IsarCollection, Clone, PartialEq, Debug)]
+struct Item {
+    id: i64,
+    counts: StringMap<i64>,
+    scores: StringMap<Option<f64>>,
+    labels: StringMap<Label>,
+}
+
+#[test]
+fn test_string_map() {
+    let label = |text: &str| Label {
+        text: text.to_string(),
+    };
+    let items = vec![
+        Item {
+            id: 1,
+            counts: StringMap::from([("a".to_string(), 1), ("b".to_string(), 2)]),
+            scores: StringMap::from([("x".to_string(), Some(0.5)), ("y".to_string(), None)]),
+            labels: StringMap::from([("en".to_string(), label("Red"))]),
+        },
+        Item {
+            id: 2,
+            counts: StringMap::from([("a".to_string(), 5)]),
+            scores: StringMap::new(),
+            labels: StringMap::new(),
+        },
+        Item {
+            id: 3,
+            counts: StringMap::new(),
+            scores: StringMap::from([("y".to_string(), Some(2.0))]),
+            labels: StringMap::from([("de".to_string(), label("Rot"))]),
+        },
+    ];
+    for_each_backend(|db| {
+        let mut schemas = vec![];
+        Item::add_schemas(&mut schemas);
+        let isar = db.open(schemas);
+
+        let txn = isar.begin_txn(true).unwrap();
+        let txn = isar.put_all(txn, &items).unwrap();
+        isar.commit_txn(txn).unwrap();
+
+        let txn = isar.begin_txn(false).unwrap();
+        assert_eq!(
+            isar.get_by_id::<Item>(&txn, 1).unwrap().as_ref(),
+            Some(&items[0])
+        );
+        assert_eq!(
+            isar.get_by_id::<Item>(&txn, 2).unwrap().as_ref(),
+            Some(&items[1])
+        );
+
+        let find_ids = |filter: Filter| {
+            let query = isar
+                .typed_query::<Item>()
+                .unwrap()
+                .filter(filter)
+                .sort_by(Item::ID, Sort::Asc)
+                .build()
+                .unwrap();
+            let items = isar.find_all(&txn, &query, None, None).unwrap();
+            items.iter().map(|i| i.id).collect::<Vec<_>>()
+        };
+        assert_eq!(find_ids(Item::COUNTS.entry("a").greater_than(1)), vec![2]);
+        assert_eq!(find_ids(Item::COUNTS.entry("a").between(1, 5)), vec![1, 2]);
+        assert_eq!(find_ids(Item::COUNTS.entry("b").is_null()), vec![2, 3]);
+        assert_eq!(find_ids(Item::COUNTS.contains_key("b")), vec![1]);
+        assert_eq!(find_ids(Item::SCORES.contains_key("y")), vec![3]);
+        assert_eq!(find_ids(Item::SCORES.entry("x").greater_than(0.1)), vec![1]);
+        assert_eq!(find_ids(Item::LABELS.contains_key("de")), vec![3]);
+
+        let query = isar.query(0).unwrap().build().unwrap();
+        let mut json = vec![];
+        isar.export_json(&txn, &query, JsonFormat::Array, &mut json)
+            .unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.contains(r#""counts":{"a":1,"b":2}"#));
+        assert!(json.contains(r#""scores":{"x":0.5,"y":null}"#));
+        assert!(json.contains(r#""labels":{"en":{"text":"Red"}}"#));
+        isar.abort_txn(txn);
+
+        let txn = isar.begin_txn(true).unwrap();
+        isar.query_delete(&txn, &query, None, None).unwrap();
+        let mut deserializer = serde_json::Deserializer::from_str(&json);
+        let (txn, count) = isar.import_json(txn, 0, &mut deserializer, |_| 0).unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(
+            isar.get_by_id::<Item>(&txn, 1).unwrap().as_ref(),
+            Some(&items[0])
+        );
+        assert_eq!(
+            isar.get_by_id::<Item>(&txn, 3).unwrap().as_ref(),
+            Some(&items[2])
+        );
+        isar.abort_txn(txn);
+        AnyInstance::close(isar, true);
+    });
+}
+
+#[derive(IsarCollection, Clone, PartialEq, Debug)]
+struct Attachment {
+    id: i64,
+    name: String,
+    data: Option<Blob>,
+}
+
+#[test]
+fn test_blob() {
+    let payload = (0..100_000u32).map(|i| i as u8).collect::<Vec<_>>();
+    let attachments = vec![
+        Attachment {
+            id: 1,
+            name: "large".to_string(),
+            data: Some(Blob(payload.clone())),
+        },
+        Attachment {
+            id: 2,
+            name: "empty".to_string(),
+            data: None,
+        },
+        Attachment {
+            id: 3,
+            name: "small".to_string(),
+            data: Some(Blob(vec![1, 2, 3])),
+        },
+    ];
+    for_each_backend(|db| {
+        let mut schemas = vec![];
+        Attachment::add_schemas(&mut schemas);
+        let isar = db.open(schemas);
+
+        let txn = isar.begin_txn(true).unwrap();
+        let txn = isar.put_all(txn, &attachments).unwrap();
+        isar.commit_txn(txn).unwrap();
+
+        let txn = isar.begin_txn(false).unwrap();
+        for attachment in &attachments {
+            assert_eq!(
+                isar.get_by_id::<Attachment>(&txn, attachment.id)
+                    .unwrap()
+                    .as_ref(),
+                Some(attachment)
+            );
+        }
+
+        {
+            let mut cursor = isar.cursor(&txn, 0).unwrap();
+            let reader = cursor.next(1).unwrap();
+            assert_eq!(reader.read_blob_length(2), Some(payload.len()));
+            let mut buffer = [0u8; 64];
+            assert_eq!(reader.read_blob_chunk(2, 99_990, &mut buffer), Some(10));
+            assert_eq!(&buffer[..10], &payload[99_990..]);
+        }
+        {
+            let mut cursor = isar.cursor(&txn, 0).unwrap();
+            let reader = cursor.next(2).unwrap();
+            assert_eq!(reader.read_blob_length(2), None);
+        }
+
+        let query = isar
+            .typed_query::<Attachment>()
+            .unwrap()
+            .filter(Attachment::DATA.is_null())
+            .build()
+            .unwrap();
+        let found = isar.find_all(&txn, &query, None, None).unwrap();
+        assert_eq!(found, vec![attachments[1].clone()]);
+
+        let query = isar.query(0).unwrap().build().unwrap();
+        let mut json = vec![];
+        isar.export_json(&txn, &query, JsonFormat::Array, &mut json)
+            .unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.contains(r#""data":"AQID""#));
+        isar.abort_txn(txn);
+
+        let txn = isar.begin_txn(true).unwrap();
+        let updates = [(1, Some(IsarValue::String("renamed".to_string())))];
+        assert_eq!(isar.update(&txn, 0, 1, &updates), Ok(true));
+        let updated = isar.get_by_id::<Attachment>(&txn, 1).unwrap().unwrap();
+        assert_eq!(updated.name, "renamed");
+        assert_eq!(updated.data, attachments[0].data);
+
+        assert_eq!(isar.update(&txn, 0, 1, &[(2, None)]), Ok(true));
+        let updated = isar.get_by_id::<Attachment>(&txn, 1).unwrap().unwrap();
+        assert_eq!(updated.data, None);
+
+        let blob = Some(Blob(vec![9; 5]));
+        let replaced = Attachment {
+            data: blob.clone(),
+            ..attachments[2].clone()
+        };
+        let txn = isar.put_all(txn, &[replaced]).unwrap();
+        let updated = isar.get_by_id::<Attachment>(&txn, 3).unwrap().unwrap();
+        assert_eq!(updated.data, blob);
+
+        isar.query_delete(&txn, &query, None, None).unwrap();
+        let mut deserializer = serde_json::Deserializer::from_str(&json);
+        let (txn, count) = isar.import_json(txn, 0, &mut deserializer, |_| 0).unwrap();
+        assert_eq!(count, 3);
+        for attachment in &attachments {
+            assert_eq!(
+                isar.get_by_id::<Attachment>(&txn, attachment.id)
+                    .unwrap()
+                    .as_ref(),
+                Some(attachment)
+            );
+        }
+        isar.abort_txn(txn);
+        AnyInstance::close(isar, true);
+    });
+}
+
+#[test]
+fn test_reopen_blob() {
+    let attachment = Attachment {
+        id: 1,
+        name: "file".to_string(),
+        data: Some(Blob(vec![1, 2, 3])),
+    };
+    for_each_backend(|db| {
+        let schemas = || {
+            let mut schemas = vec![];
+            Attachment::add_schemas(&mut schemas);
+            schemas
+        };
+        let isar = db.open(schemas());
+        let txn = isar.begin_txn(true).unwrap();
+        let txn = isar.put_all(txn, &[attachment.clone()]).unwrap();
+        isar.commit_txn(txn).unwrap();
+        assert!(AnyInstance::close(isar, false));
+
+        let isar = db.open(schemas());
+        let txn = isar.begin_txn(false).unwrap();
+        assert_eq!(isar.verify(&txn), Ok(()));
+        assert_eq!(
+            isar.get_by_id::<Attachment>(&txn, 1).unwrap(),
+            Some(attachment.clone())
+        );
+        isar.abort_txn(txn);
+        AnyInstance::close(isar, true);
+    });
+}
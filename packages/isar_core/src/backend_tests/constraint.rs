@@ -0,0 +1,201 @@
+use super::{for_each_backend, query_ids};
+use crate::any::{AnyInsert, AnyInstance};
+use crate::core::data_type::DataType;
+use crate::core::error::{IsarError, Result};
+use crate::core::filter::{ConditionType, Filter, FilterCondition};
+use crate::core::insert::IsarInsert;
+use crate::core::instance::IsarInstance;
+use crate::core::query_builder::IsarQueryBuilder;
+use crate::core::schema::{
+    CheckSchema, IndexSchema, IsarSchema, PropertySchema, PropertyValue, VectorMetric,
+};
+use crate::core::update::{PropertyUpdate, UpdateOperation};
+use crate::core::value::IsarValue;
+use crate::core::writer::IsarWriter;
+
+fn account_schemas(score: bool) -> Vec<IsarSchema> {
+    let mut properties = vec![
+        PropertySchema::new("name", DataType::String, None)
+            .non_null()
+            .with_check(CheckSchema::Length {
+                min: Some(1),
+                max: Some(10),
+            }),
+        PropertySchema::new("email", DataType::String, None).with_check(CheckSchema::Regex {
+            pattern: "^[a-z]+@[a-z]+$".to_string(),
+        }),
+        PropertySchema::new("age", DataType::Int, None).with_check(CheckSchema::Range {
+            min: Some(PropertyValue::Integer(0)),
+            max: Some(PropertyValue::Integer(150)),
+        }),
+        PropertySchema::new("status", DataType::String, None)
+            .with_default(PropertyValue::String("active".to_string())),
+    ];
+    if score {
+        properties.push(
+            PropertySchema::new("score", DataType::Long, None)
+                .non_null()
+                .with_default(PropertyValue::Integer(10)),
+        );
+    }
+    vec![IsarSchema::new(
+        "Account",
+        Some("id"),
+        properties,
+        vec![],
+        false,
+    )]
+}
+
+fn save_account(
+    insert: &mut AnyInsert,
+    id: i64,
+    name: Option<&str>,
+    email: &str,
+    age: i32,
+) -> Result<()> {
+    if let Some(name) = name {
+        insert.write_string(1, name);
+    } else {
+        insert.write_null(1);
+    }
+    insert.write_string(2, email);
+    insert.write_int(3, age);
+    insert.write_null(4);
+    insert.save(id)
+}
+
+fn string_is(property_index: u16, value: &str) -> Filter {
+    Filter::Condition(FilterCondition::new(
+        property_index,
+        ConditionType::Equal,
+        vec![Some(IsarValue::String(value.to_string()))],
+        false,
+    ))
+}
+
+#[test]
+fn test_constraints() {
+    let violation = |property: &str, message: &str| IsarError::ConstraintViolation {
+        property: property.to_string(),
+        message: message.to_string(),
+    };
+    for_each_backend(|db| {
+        let isar = db.open(account_schemas(false));
+
+        let txn = isar.begin_txn(true).unwrap();
+        let mut insert = isar.insert(txn, 0, 1).unwrap();
+        save_account(&mut insert, 1, Some("a"), "a@b", 30).unwrap();
+        isar.commit_txn(insert.finish().unwrap()).unwrap();
+
+        // objects violating a constraint are discarded
+        let txn = isar.begin_txn(true).unwrap();
+        let mut insert = isar.insert(txn, 0, 1).unwrap();
+        assert_eq!(
+            save_account(&mut insert, 2, None, "a@b", 1),
+            Err(violation("name", "must not be null"))
+        );
+        assert_eq!(
+            save_account(&mut insert, 2, Some("abcdefghijk"), "a@b", 1),
+            Err(violation("name", "must have at most 10 characters"))
+        );
+        assert_eq!(
+            save_account(&mut insert, 2, Some("b"), "b", 1),
+            Err(violation("email", "must match '^[a-z]+@[a-z]+$'"))
+        );
+        assert_eq!(
+            save_account(&mut insert, 2, Some("b"), "a@b", 151),
+            Err(violation("age", "must be at most 150"))
+        );
+        save_account(&mut insert, 2, Some("b"), "b@c", 1).unwrap();
+        let txn = insert.finish().unwrap();
+        assert_eq!(isar.count(&txn, 0), Ok(2));
+        isar.abort_txn(txn);
+
+        let txn = isar.begin_txn(true).unwrap();
+        assert_eq!(query_ids(&isar, &txn, string_is(4, "active")), vec![1]);
+        assert_eq!(
+            isar.update(&txn, 0, 1, &[(3, Some(IsarValue::Integer(-1)))]),
+            Err(violation("age", "must be at least 0"))
+        );
+        assert_eq!(
+            isar.update(&txn, 0, 1, &[(1, None)]),
+            Err(violation("name", "must not be null"))
+        );
+        let updates = [(3, Some(IsarValue::Integer(40))), (4, None)];
+        assert_eq!(isar.update(&txn, 0, 1, &updates), Ok(true));
+        assert_eq!(query_ids(&isar, &txn, string_is(4, "active")), vec![1]);
+
+        let query = isar.query(0).unwrap().build().unwrap();
+        let updates = [(2, Some(IsarValue::String("x".to_string())))];
+        assert!(isar
+            .query_update(&txn, &query, None, None, &updates)
+            .is_err());
+        isar.commit_txn(txn).unwrap();
+        AnyInstance::close(isar, false);
+
+        // added properties are filled with their default value
+        let isar = db.open(account_schemas(true));
+        let txn = isar.begin_txn(false).unwrap();
+        assert_eq!(isar.verify(&txn), Ok(()));
+        let score_is_10 = Filter::Condition(FilterCondition::new(
+            5,
+            ConditionType::Equal,
+            vec![Some(IsarValue::Integer(10))],
+            false,
+        ));
+        assert_eq!(query_ids(&isar, &txn, score_is_10), vec![1]);
+        assert_eq!(query_ids(&isar, &txn, string_is(1, "a")), vec![1]);
+        assert_eq!(query_ids(&isar, &txn, string_is(2, "a@b")), vec![1]);
+        isar.abort_txn(txn);
+        AnyInstance::close(isar, true);
+    });
+}
+
+#[test]
+fn test_vector_dimensions() {
+    let violation = IsarError::ConstraintViolation {
+        property: "embedding".to_string(),
+        message: "must have 2 elements".to_string(),
+    };
+    let save = |insert: &mut AnyInsert, id: i64, vector: Option<&[f32]>| {
+        if let Some(vector) = vector {
+            let mut list = insert.begin_list(1, vector.len() as u32).unwrap();
+            for (i, value) in vector.iter().enumerate() {
+                list.write_float(i as u32, *value);
+            }
+            insert.end_list(list);
+        } else {
+            insert.write_null(1);
+        }
+        insert.save(id)
+    };
+    for_each_backend(|db| {
+        let properties = vec![PropertySchema::new("embedding", DataType::FloatList, None)];
+        let index = IndexSchema::vector("embedding", "embedding", 2, VectorMetric::L2);
+        let schema = IsarSchema::new("Doc", Some("id"), properties, vec![index], false);
+        let isar = db.open(vec![schema]);
+
+        let txn = isar.begin_txn(true).unwrap();
+        let mut insert = isar.insert(txn, 0, 2).unwrap();
+        assert_eq!(save(&mut insert, 1, Some(&[1.0])), Err(violation.clone()));
+        assert_eq!(
+            save(&mut insert, 1, Some(&[1.0, 2.0, 3.0])),
+            Err(violation.clone())
+        );
+        save(&mut insert, 1, Some(&[1.0, 2.0])).unwrap();
+        save(&mut insert, 2, None).unwrap();
+        let txn = insert.finish().unwrap();
+        assert_eq!(isar.count(&txn, 0), Ok(2));
+
+        // updates cannot change the length of the vector
+        let append = PropertyUpdate::new(vec![1], UpdateOperation::Append(None));
+        assert_eq!(
+            isar.update_properties(&txn, 0, 1, &[append]),
+            Err(IsarError::IllegalArgument {})
+        );
+        assert_eq!(isar.update(&txn, 0, 1, &[(1, None)]), Ok(true));
+        isar.commit_txn(txn).unwrap();
+        AnyInstance::close(isar, true);
+    });
+}
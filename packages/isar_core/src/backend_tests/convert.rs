@@ -0,0 +1,179 @@
+use crate::any::Backend;
+use crate::convert::{convert, copy_instance, summarize};
+use crate::core::data_type::DataType;
+use crate::core::error::IsarError;
+use crate::core::instance::IsarInstance;
+use crate::core::schema::{IsarSchema, LinkSchema, PropertySchema};
+use crate::native::native_instance::NativeInstance;
+use crate::sqlite::sqlite_instance::SQLiteInstance;
+use crate::test_util::{next_instance_id, test_dir};
+use std::fs;
+use std::path::Path;
+
+fn schemas() -> Vec<IsarSchema> {
+    let properties = vec![
+        PropertySchema::new("name", DataType::String, None),
+        PropertySchema::new("score", DataType::Float, None),
+        PropertySchema::new("data", DataType::ByteList, None),
+        PropertySchema::new("tag", DataType::Object, Some("Tag")),
+    ];
+    let tag = vec![PropertySchema::new("value", DataType::Int, None)];
+    vec![
+        IsarSchema::new("Item", Some("id"), properties, vec![], false),
+        IsarSchema::new("Tag", None, tag, vec![], true),
+    ]
+}
+
+#[test]
+fn test_copy_links() {
+    let dir = test_dir();
+    let dir = dir.as_str();
+    let properties = vec![PropertySchema::new("name", DataType::String, None)];
+    let schemas = vec![
+        IsarSchema::new("Item", Some("id"), properties, vec![], false).with_links(vec![
+            LinkSchema::new("related", "Item", false),
+            LinkSchema::backlink("relatedBy", "Item", "related"),
+        ]),
+    ];
+
+    let source = NativeInstance::open_instance(
+        next_instance_id(),
+        "links",
+        dir,
+        schemas.clone(),
+        10,
+        None,
+        None,
+    )
+    .unwrap();
+    let txn = source.begin_txn(true).unwrap();
+    let json = r#"[{"id": 1}, {"id": 2}, {"id": 3}]"#;
+    let mut deser = serde_json::Deserializer::from_str(json);
+    let (txn, _) = source.import_json(txn, 0, &mut deser, |_| 0).unwrap();
+    source.link(&txn, 0, 0, 1, 2).unwrap();
+    source.link(&txn, 0, 0, 1, 3).unwrap();
+    source.link(&txn, 0, 0, 3, 1).unwrap();
+    source.commit_txn(txn).unwrap();
+
+    let target =
+        SQLiteInstance::open_instance(next_instance_id(), "links", dir, schemas, 10, None, None)
+            .unwrap();
+    copy_instance(&*source, &target).unwrap();
+
+    let txn = target.begin_txn(false).unwrap();
+    let mut related = target.get_linked_ids(&txn, 0, 0, 1).unwrap();
+    related.sort();
+    assert_eq!(related, vec![2, 3]);
+    assert_eq!(target.get_linked_ids(&txn, 0, 1, 1), Ok(vec![3]));
+    assert_eq!(target.count_links(&txn, 0, 0, 2), Ok(0));
+    target.abort_txn(txn);
+
+    NativeInstance::close(source, true);
+    SQLiteInstance::close(target, true);
+}
+
+#[test]
+fn test_convert() {
+    let dir = Path::new(&test_dir()).join("convert");
+    let source_dir = dir.join("source");
+    let target_dir = dir.join("target");
+    fs::create_dir_all(&source_dir).unwrap();
+    fs::create_dir_all(&target_dir).unwrap();
+    let source_dir = source_dir.to_str().unwrap();
+    let target_dir = target_dir.to_str().unwrap();
+
+    let json = r#"[
+        {"id": 1, "name": "a", "score": 0.1, "data": [0, 255], "tag": {"value": 5}},
+        {"id": 2, "score": 1.5, "tag": {}},
+        {"id": 3}
+    ]"#;
+    let isar = NativeInstance::open_instance(
+        next_instance_id(),
+        "test",
+        source_dir,
+        schemas(),
+        10,
+        None,
+        None,
+    )
+    .unwrap();
+    let txn = isar.begin_txn(true).unwrap();
+    let mut deser = serde_json::Deserializer::from_str(json);
+    let (txn, _) = isar.import_json(txn, 0, &mut deser, |_| 0).unwrap();
+    isar.commit_txn(txn).unwrap();
+    let expected = summarize(&*isar).unwrap();
+    NativeInstance::close(isar, false);
+
+    // converting with other schemas does not migrate the source
+    let mut other_schemas = schemas();
+    other_schemas[0].properties.remove(2);
+    let other_dir = dir.join("other");
+    fs::create_dir_all(&other_dir).unwrap();
+    let other_dir = other_dir.to_str().unwrap();
+    let other = convert(
+        Backend::Native,
+        "test",
+        source_dir,
+        other_dir,
+        other_schemas,
+        10,
+        None,
+    )
+    .unwrap();
+    assert_ne!(other, expected);
+    assert!(!Path::new(other_dir).join("test_source.isar").exists());
+
+    let summary = convert(
+        Backend::Native,
+        "test",
+        source_dir,
+        target_dir,
+        schemas(),
+        10,
+        None,
+    )
+    .unwrap();
+    assert_eq!(summary, expected);
+    assert_eq!(summary[0].count, 3);
+    assert!(!Path::new(target_dir).join("test_source.isar").exists());
+
+    // the target already exists
+    let result = convert(
+        Backend::Native,
+        "test",
+        source_dir,
+        target_dir,
+        schemas(),
+        10,
+        None,
+    );
+    assert_eq!(result, Err(IsarError::IllegalArgument {}));
+
+    let back = convert(
+        Backend::SQLite,
+        "test",
+        target_dir,
+        source_dir,
+        schemas(),
+        10,
+        None,
+    );
+    // the native source still exists
+    assert_eq!(back, Err(IsarError::IllegalArgument {}));
+
+    fs::remove_file(Path::new(source_dir).join("test.isar")).unwrap();
+    let _ = fs::remove_file(Path::new(source_dir).join("test.isar.lock"));
+    let back = convert(
+        Backend::SQLite,
+        "test",
+        target_dir,
+        source_dir,
+        schemas(),
+        10,
+        None,
+    )
+    .unwrap();
+    assert_eq!(back, expected);
+
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,133 @@
+use super::for_each_backend;
+use crate::any::{AnyInstance, AnyTxn};
+use crate::core::data_type::DataType;
+use crate::core::de::{ErrorPolicy, ImportMode, ImportOptions, ImportReport};
+use crate::core::error::{IsarError, Result};
+use crate::core::instance::{IsarInstance, JsonFormat};
+use crate::core::query_builder::IsarQueryBuilder;
+use crate::core::schema::{CheckSchema, IsarSchema, PropertySchema};
+use crate::core::snapshot::read_ids;
+
+fn schemas() -> Vec<IsarSchema> {
+    let name =
+        PropertySchema::new("name", DataType::String, None).with_check(CheckSchema::Length {
+            min: Some(1),
+            max: None,
+        });
+    vec![IsarSchema::new(
+        "User",
+        Some("id"),
+        vec![name],
+        vec![],
+        false,
+    )]
+}
+
+fn ids(isar: &AnyInstance, txn: &AnyTxn) -> Vec<i64> {
+    let query = isar.query(0).unwrap().build().unwrap();
+    let mut ids = read_ids(isar.query_cursor(txn, &query, None, None).unwrap());
+    ids.sort();
+    ids
+}
+
+fn import(isar: &AnyInstance, json: &str, options: ImportOptions) -> Result<ImportReport> {
+    let txn = isar.begin_txn(true)?;
+    let (txn, report) = isar.import_json_stream(txn, 0, json.as_bytes(), options, |_| 0)?;
+    isar.commit_txn(txn)?;
+    Ok(report)
+}
+
+#[test]
+fn test_import_ndjson() {
+    for_each_backend(|db| {
+        let isar = db.open(schemas());
+        let json = "{\"id\": 3, \"name\": \"a\"}\n{\"name\": \"b\"}\n\n{\"id\": 1}\n{\"id\": null, \"name\": \"c\"}";
+        let options = ImportOptions {
+            format: JsonFormat::Ndjson,
+            batch_size: 2,
+            ..Default::default()
+        };
+        let report = import(&isar, json, options).unwrap();
+        assert_eq!(report.imported, 4);
+        assert!(report.rejected.is_empty());
+
+        // objects without id never get the id of an earlier object
+        let txn = isar.begin_txn(false).unwrap();
+        assert_eq!(ids(&isar, &txn), vec![1, 3, 4, 5]);
+        isar.abort_txn(txn);
+        AnyInstance::close(isar, true);
+    });
+}
+
+#[test]
+fn test_import_array_skip() {
+    for_each_backend(|db| {
+        let isar = db.open(schemas());
+        import(
+            &isar,
+            r#"[{"id": 1, "name": "a"}]"#,
+            ImportOptions::default(),
+        )
+        .unwrap();
+
+        let json = r#"[
+            {"id": 1, "name": "b"},
+            {"id": 2, "name": 5},
+            {"id": 2, "name": "c"},
+            {"id": 2, "name": "d"}
+        ]"#;
+        let options = ImportOptions {
+            mode: ImportMode::InsertOnly,
+            on_error: ErrorPolicy::Skip,
+            ..Default::default()
+        };
+        let report = import(&isar, json, options).unwrap();
+        assert_eq!(report.imported, 1);
+        let rejected = report
+            .rejected
+            .iter()
+            .map(|rejected| (rejected.line, rejected.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rejected,
+            vec![
+                (1, "An object with id 1 already exists"),
+                (2, "Invalid value for property name"),
+                (4, "An object with id 2 already exists"),
+            ]
+        );
+
+        let txn = isar.begin_txn(false).unwrap();
+        assert_eq!(ids(&isar, &txn), vec![1, 2]);
+        isar.abort_txn(txn);
+        AnyInstance::close(isar, true);
+    });
+}
+
+#[test]
+fn test_import_error_aborts_txn() {
+    for_each_backend(|db| {
+        let isar = db.open(schemas());
+        let ndjson = ImportOptions {
+            format: JsonFormat::Ndjson,
+            batch_size: 1,
+            ..Default::default()
+        };
+
+        let result = import(&isar, "{\"id\": 1}\n{\"id\": 2, \"name\": 5}", ndjson);
+        assert_eq!(
+            result,
+            Err(IsarError::JsonError {
+                message: "Line 2: Invalid value for property name".to_string()
+            })
+        );
+        // the object violates the check which is only noticed while writing it
+        assert!(import(&isar, "{\"id\": 1}\n{\"id\": 2, \"name\": \"\"}", ndjson).is_err());
+        assert!(import(&isar, r#"[{"id": 1}, {"id": 2"#, Default::default()).is_err());
+
+        let txn = isar.begin_txn(true).unwrap();
+        assert!(ids(&isar, &txn).is_empty());
+        isar.abort_txn(txn);
+        AnyInstance::close(isar, true);
+    });
+}
@@ -0,0 +1,139 @@
+use super::{for_each_backend, query_ids};
+use crate::any::AnyInstance;
+use crate::core::data_type::DataType;
+use crate::core::error::IsarError;
+use crate::core::filter::{ConditionType, Filter, FilterCondition};
+use crate::core::geo::{distance, within_box};
+use crate::core::insert::IsarInsert;
+use crate::core::instance::IsarInstance;
+use crate::core::query_builder::{IsarQueryBuilder, Sort};
+use crate::core::schema::{IndexSchema, IsarSchema, PropertySchema};
+use crate::core::value::IsarValue;
+use crate::core::writer::IsarWriter;
+
+fn geo_filter(property_index: u16, condition_type: ConditionType, values: &[f64]) -> Filter {
+    let values = values.iter().map(|v| Some(IsarValue::Real(*v))).collect();
+    Filter::Condition(FilterCondition::new(
+        property_index,
+        condition_type,
+        values,
+        false,
+    ))
+}
+
+#[test]
+fn test_geo_queries() {
+    let mut places = vec![];
+    for lat in (-80..=80).step_by(20) {
+        for lon in (-180..180).step_by(15) {
+            places.push((places.len() as i64 + 1, lat as f64, lon as f64));
+        }
+    }
+    places.push((places.len() as i64 + 1, 52.52, 13.405));
+
+    for_each_backend(|db| {
+        let schemas = vec![IsarSchema::new(
+            "Place",
+            Some("id"),
+            vec![
+                PropertySchema::new("lat", DataType::Double, None),
+                PropertySchema::new("lon", DataType::Double, None),
+                PropertySchema::new("name", DataType::String, None),
+            ],
+            vec![IndexSchema::geo("loc", "lat", "lon")],
+            false,
+        )];
+        let isar = db.open(schemas);
+
+        let txn = isar.begin_txn(true).unwrap();
+        let mut insert = isar.insert(txn, 0, places.len() as u32 + 1).unwrap();
+        for (id, lat, lon) in &places {
+            insert.write_double(1, *lat);
+            insert.write_double(2, *lon);
+            insert.write_string(3, "place");
+            insert.save(*id).unwrap();
+        }
+        // objects without coordinates are not indexed
+        insert.write_null(1);
+        insert.write_null(2);
+        insert.write_null(3);
+        insert.save(1000).unwrap();
+        isar.commit_txn(insert.finish().unwrap()).unwrap();
+
+        let expected = |matches: &dyn Fn(f64, f64) -> bool| {
+            places
+                .iter()
+                .filter(|(_, lat, lon)| matches(*lat, *lon))
+                .map(|(id, _, _)| *id)
+                .collect::<Vec<_>>()
+        };
+
+        let txn = isar.begin_txn(false).unwrap();
+        for bounds in [
+            (40.0, 0.0, 60.0, 30.0),
+            (-90.0, -180.0, 90.0, 180.0),
+            (-25.0, 170.0, 25.0, -150.0),
+            (10.0, 10.0, 0.0, 20.0),
+        ] {
+            let (min_lat, min_lon, max_lat, max_lon) = bounds;
+            let filter = geo_filter(
+                1,
+                ConditionType::GeoWithinBox,
+                &[min_lat, min_lon, max_lat, max_lon],
+            );
+            assert_eq!(
+                query_ids(&isar, &txn, filter),
+                expected(&|lat, lon| within_box(lat, lon, min_lat, min_lon, max_lat, max_lon))
+            );
+        }
+
+        for (lat, lon, radius) in [(52.0, 13.0, 500_000.0), (0.0, 179.0, 2_000_000.0)] {
+            let filter = geo_filter(1, ConditionType::GeoWithinRadius, &[lat, lon, radius]);
+            assert_eq!(
+                query_ids(&isar, &txn, filter),
+                expected(&|p_lat, p_lon| distance(lat, lon, p_lat, p_lon) <= radius)
+            );
+        }
+
+        let filter = Filter::And(vec![
+            Filter::Condition(FilterCondition::new(
+                3,
+                ConditionType::Equal,
+                vec![Some(IsarValue::String("place".to_string()))],
+                true,
+            )),
+            geo_filter(1, ConditionType::GeoWithinBox, &[50.0, 10.0, 55.0, 15.0]),
+        ]);
+        assert_eq!(query_ids(&isar, &txn, filter), vec![places.len() as i64]);
+
+        // only the latitude property of a geo index can be queried
+        for property_index in [2, 3] {
+            let mut builder = isar.query(0).unwrap();
+            builder.set_filter(Filter::Or(vec![geo_filter(
+                property_index,
+                ConditionType::GeoWithinRadius,
+                &[0.0, 0.0, 1000.0],
+            )]));
+            assert!(matches!(
+                builder.build(),
+                Err(IsarError::IllegalArgument {})
+            ));
+
+            let mut builder = isar.query(0).unwrap();
+            builder.add_distance_sort(property_index, 0.0, 0.0, Sort::Asc);
+            assert!(matches!(
+                builder.build(),
+                Err(IsarError::IllegalArgument {})
+            ));
+        }
+
+        let mut builder = isar.query(0).unwrap();
+        builder.add_distance_sort(1, f64::INFINITY, 0.0, Sort::Asc);
+        assert!(matches!(
+            builder.build(),
+            Err(IsarError::IllegalArgument {})
+        ));
+        isar.abort_txn(txn);
+        AnyInstance::close(isar, true);
+    });
+}
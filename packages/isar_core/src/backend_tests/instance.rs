@@ -0,0 +1,350 @@
+use super::{for_each_backend, import_json, query_ids};
+use crate::any::{AnyInstance, AnyTxn};
+use crate::core::cursor::IsarQueryCursor;
+use crate::core::data_type::DataType;
+use crate::core::error::IsarError;
+use crate::core::filter::{ConditionType, Filter, FilterCondition, FilterLink};
+use crate::core::instance::{Aggregation, IsarInstance};
+use crate::core::query_builder::IsarQueryBuilder;
+use crate::core::reader::IsarReader;
+use crate::core::schema::{IndexSchema, IsarSchema, LinkSchema, PropertySchema, TtlSchema};
+use crate::core::update::PropertyUpdate;
+use crate::core::value::IsarValue;
+use crate::test_util::test_dir;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+#[test]
+fn test_backup_restore() {
+    for_each_backend(|db| {
+        let properties = vec![PropertySchema::new("name", DataType::String, None)];
+        let schemas = vec![IsarSchema::new(
+            "Note",
+            Some("id"),
+            properties,
+            vec![],
+            false,
+        )];
+        let isar = db.open(schemas.clone());
+        let txn = isar.begin_txn(true).unwrap();
+        let json = r#"[{"id": 1, "name": "a"}, {"id": 2, "name": "b"}]"#;
+        let txn = import_json(&isar, txn, 0, json);
+        isar.commit_txn(txn).unwrap();
+
+        let path = format!("{}/backup_{}", test_dir(), db.instance_id);
+        let mut progress = vec![];
+        isar.backup(&path, |copied, total| progress.push((copied, total)))
+            .unwrap();
+        if let Some((copied, total)) = progress.last() {
+            assert_eq!(copied, total);
+        }
+
+        let txn = isar.begin_txn(true).unwrap();
+        assert_eq!(isar.delete(&txn, 0, 1), Ok(true));
+        isar.commit_txn(txn).unwrap();
+
+        // the instance is handed back if it is still in use elsewhere
+        let other = AnyInstance::get_instance(db.instance_id).unwrap();
+        let isar = match AnyInstance::restore(isar, &path, schemas.clone(), 10) {
+            Err((Some(isar), IsarError::Busy {})) => isar,
+            _ => panic!("restore must fail while the instance is in use"),
+        };
+        assert!(!AnyInstance::close(other, false));
+        let txn = isar.begin_txn(false).unwrap();
+        assert_eq!(isar.count(&txn, 0), Ok(1));
+        isar.abort_txn(txn);
+
+        let isar = match AnyInstance::restore(isar, &path, schemas, 10) {
+            Ok(isar) => isar,
+            Err((_, e)) => panic!("restore failed: {:?}", e),
+        };
+        let txn = isar.begin_txn(false).unwrap();
+        assert_eq!(isar.count(&txn, 0), Ok(2));
+        assert_eq!(isar.verify(&txn), Ok(()));
+        isar.abort_txn(txn);
+        AnyInstance::close(isar, true);
+        let _ = std::fs::remove_file(&path);
+    });
+}
+
+fn link_schemas(best: bool) -> Vec<IsarSchema> {
+    let name = vec![PropertySchema::new("name", DataType::String, None)];
+    let mut user_links = vec![LinkSchema::new("orders", "Item", false).with_cascade()];
+    if best {
+        user_links.push(LinkSchema::new("best", "Item", true));
+    }
+    let mut user_link = LinkSchema::backlink("user", "User", "orders");
+    user_link.single = true;
+    vec![
+        IsarSchema::new("User", Some("id"), name.clone(), vec![], false).with_links(user_links),
+        IsarSchema::new("Item", Some("id"), name, vec![], false).with_links(vec![user_link]),
+    ]
+}
+
+fn linked_ids(isar: &AnyInstance, txn: &AnyTxn, index: u16, link: u16, id: i64) -> Vec<i64> {
+    let mut ids = isar.get_linked_ids(txn, index, link, id).unwrap();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_links() {
+    for_each_backend(|db| {
+        let isar = db.open(link_schemas(true));
+        let links = isar.get_links(0).unwrap().collect::<Vec<_>>();
+        assert_eq!(links, vec![("orders", 1, false), ("best", 1, true)]);
+
+        let txn = isar.begin_txn(true).unwrap();
+        let users = r#"[{"id": 1, "name": "x"}, {"id": 2, "name": "y"}]"#;
+        let txn = import_json(&isar, txn, 0, users);
+        let orders = r#"[{"id": 10, "name": "a"}, {"id": 11, "name": "b"},
+            {"id": 12, "name": "c"}]"#;
+        let txn = import_json(&isar, txn, 1, orders);
+
+        assert_eq!(isar.link(&txn, 0, 0, 1, 10), Ok(true));
+        assert_eq!(isar.link(&txn, 0, 0, 1, 11), Ok(true));
+        assert_eq!(isar.link(&txn, 0, 0, 1, 99), Ok(false));
+        assert_eq!(linked_ids(&isar, &txn, 0, 0, 1), vec![10, 11]);
+        assert_eq!(isar.count_links(&txn, 0, 0, 1), Ok(2));
+        assert_eq!(linked_ids(&isar, &txn, 1, 0, 10), vec![1]);
+
+        // an order belongs to a single user
+        assert_eq!(isar.link(&txn, 0, 0, 2, 11), Ok(true));
+        assert_eq!(linked_ids(&isar, &txn, 0, 0, 1), vec![10]);
+        assert_eq!(linked_ids(&isar, &txn, 1, 0, 11), vec![2]);
+
+        assert_eq!(isar.link(&txn, 0, 1, 1, 10), Ok(true));
+        assert_eq!(isar.link(&txn, 0, 1, 1, 12), Ok(true));
+        assert_eq!(linked_ids(&isar, &txn, 0, 1, 1), vec![12]);
+
+        // linking through the backlink
+        assert_eq!(isar.link(&txn, 1, 0, 12, 2), Ok(true));
+        assert_eq!(linked_ids(&isar, &txn, 0, 0, 2), vec![11, 12]);
+
+        let name_is = |name: &str| {
+            Filter::Condition(FilterCondition::new(
+                1,
+                ConditionType::Equal,
+                vec![Some(IsarValue::String(name.to_string()))],
+                false,
+            ))
+        };
+        let filter = Filter::Link(FilterLink::new(0, Some(name_is("b"))));
+        assert_eq!(query_ids(&isar, &txn, filter), vec![2]);
+        let filter = Filter::Link(FilterLink::new(1, None));
+        assert_eq!(query_ids(&isar, &txn, filter), vec![1]);
+        let filter = Filter::Not(Box::new(Filter::Link(FilterLink::new(0, None))));
+        assert_eq!(query_ids(&isar, &txn, filter), Vec::<i64>::new());
+
+        // orders of user "y" through the backlink
+        let mut builder = isar.query(1).unwrap();
+        builder.set_filter(Filter::Link(FilterLink::new(0, Some(name_is("y")))));
+        let query = builder.build().unwrap();
+        let mut cursor = isar.query_cursor(&txn, &query, None, None).unwrap();
+        let mut order_ids = vec![];
+        while let Some(reader) = cursor.next() {
+            order_ids.push(reader.read_id());
+        }
+        assert_eq!(order_ids, vec![11, 12]);
+        drop(cursor);
+        assert_eq!(
+            isar.watch_query(&query, Box::new(|| {})).err(),
+            Some(IsarError::UnsupportedOperation {})
+        );
+
+        assert_eq!(isar.unlink(&txn, 0, 0, 2, 11), Ok(true));
+        assert_eq!(isar.unlink(&txn, 0, 0, 2, 11), Ok(false));
+        assert_eq!(isar.unlink_all(&txn, 0, 0, 2), Ok(1));
+        assert_eq!(linked_ids(&isar, &txn, 1, 0, 12), Vec::<i64>::new());
+
+        // deleting an object removes its edges
+        assert_eq!(isar.delete(&txn, 1, 10), Ok(true));
+        assert_eq!(linked_ids(&isar, &txn, 0, 0, 1), Vec::<i64>::new());
+
+        // deleting a user deletes its orders but not its best order
+        assert_eq!(isar.link(&txn, 0, 0, 1, 11), Ok(true));
+        assert_eq!(isar.delete(&txn, 0, 1), Ok(true));
+        assert_eq!(isar.count(&txn, 1), Ok(1));
+        assert_eq!(isar.link(&txn, 0, 0, 2, 12), Ok(true));
+        isar.commit_txn(txn).unwrap();
+        AnyInstance::close(isar, false);
+
+        let isar = db.open(link_schemas(false));
+        let txn = isar.begin_txn(false).unwrap();
+        assert_eq!(isar.verify(&txn), Ok(()));
+        assert_eq!(linked_ids(&isar, &txn, 0, 0, 2), vec![12]);
+        assert_eq!(linked_ids(&isar, &txn, 1, 0, 12), vec![2]);
+        isar.abort_txn(txn);
+
+        let txn = isar.begin_txn(true).unwrap();
+        isar.clear(&txn, 0).unwrap();
+        assert_eq!(isar.count(&txn, 1), Ok(0));
+        isar.commit_txn(txn).unwrap();
+        AnyInstance::close(isar, true);
+    });
+}
+
+fn ttl_schemas(purge_on_open: bool) -> Vec<IsarSchema> {
+    let properties = vec![PropertySchema::new("expires", DataType::Long, None)];
+    let ttl = TtlSchema::new("expires", 1000, purge_on_open);
+    let index = IndexSchema::new("expires", vec!["expires"], false, false);
+    vec![
+        IsarSchema::new("Cache", Some("id"), properties.clone(), vec![index], false)
+            .with_ttl(ttl.clone()),
+        IsarSchema::new("Session", Some("id"), properties, vec![], false).with_ttl(ttl),
+    ]
+}
+
+#[test]
+fn test_purge_expired() {
+    for_each_backend(|db| {
+        let isar = db.open(ttl_schemas(false));
+        let changes = Arc::new(AtomicU32::new(0));
+        let changes_clone = changes.clone();
+        let handle = isar
+            .watch(
+                1,
+                Box::new(move || {
+                    changes_clone.fetch_add(1, Ordering::SeqCst);
+                }),
+            )
+            .unwrap();
+
+        let now = TtlSchema::new("expires", 0, false).expired_until_now();
+        let json = format!(
+            r#"[{{"id": 1, "expires": {}}}, {{"id": 2, "expires": {}}},
+            {{"id": 3, "expires": {}}}, {{"id": 4}}]"#,
+            now - 10_000,
+            now - 100,
+            now + 100_000
+        );
+        let txn = isar.begin_txn(true).unwrap();
+        let txn = import_json(&isar, txn, 0, &json);
+        let txn = import_json(&isar, txn, 1, &json);
+        isar.commit_txn(txn).unwrap();
+        let changes_before = changes.load(Ordering::SeqCst);
+
+        // the second object is within the TTL duration of one second
+        let txn = isar.begin_txn(true).unwrap();
+        assert_eq!(isar.purge_expired(&txn), Ok(2));
+        assert_eq!(isar.count(&txn, 0), Ok(3));
+        assert_eq!(isar.count(&txn, 1), Ok(3));
+        assert_eq!(isar.purge_expired(&txn), Ok(0));
+        isar.commit_txn(txn).unwrap();
+        assert!(changes.load(Ordering::SeqCst) > changes_before);
+        drop(handle);
+
+        let txn = isar.begin_txn(true).unwrap();
+        let json = format!(r#"[{{"id": 5, "expires": {}}}]"#, now - 10_000);
+        let txn = import_json(&isar, txn, 1, &json);
+        isar.commit_txn(txn).unwrap();
+        AnyInstance::close(isar, false);
+
+        let isar = db.open(ttl_schemas(true));
+        let txn = isar.begin_txn(false).unwrap();
+        assert_eq!(isar.count(&txn, 1), Ok(3));
+        isar.abort_txn(txn);
+        AnyInstance::close(isar, true);
+    });
+}
+
+#[test]
+fn test_versioned_updates() {
+    let version = |isar: &AnyInstance, txn: &AnyTxn, id: i64| {
+        let mut query_builder = isar.query(0).unwrap();
+        query_builder.set_filter(Filter::Condition(FilterCondition::new(
+            0,
+            ConditionType::Equal,
+            vec![Some(IsarValue::Integer(id))],
+            false,
+        )));
+        let query = query_builder.build().unwrap();
+        isar.query_aggregate(txn, &query, Aggregation::Max, Some(2))
+            .unwrap()
+    };
+    let name_is = |value: &str| {
+        let value = Some(IsarValue::String(value.to_string()));
+        Filter::Condition(FilterCondition::new(
+            1,
+            ConditionType::Equal,
+            vec![value],
+            true,
+        ))
+    };
+    let set_name = |value: &str| [PropertyUpdate::set(1, Some(value.into()))];
+    for_each_backend(|db| {
+        let properties = vec![
+            PropertySchema::new("name", DataType::String, None),
+            PropertySchema::new("version", DataType::Long, None),
+        ];
+        let schemas = vec![
+            IsarSchema::new("Doc", Some("id"), properties.clone(), vec![], false)
+                .with_version_property("version"),
+            IsarSchema::new("Note", Some("id"), properties, vec![], false),
+        ];
+        let isar = db.open(schemas);
+        assert_eq!(isar.get_version_property(0), Ok(Some(2)));
+        assert_eq!(isar.get_version_property(1), Ok(None));
+
+        // written versions are ignored
+        let json = r#"[{"id": 1, "name": "a", "version": 7}, {"id": 2, "name": "b"}]"#;
+        let txn = isar.begin_txn(true).unwrap();
+        let txn = import_json(&isar, txn, 0, json);
+        assert_eq!(version(&isar, &txn, 1), Some(IsarValue::Integer(1)));
+        let txn = import_json(&isar, txn, 0, json);
+        assert_eq!(version(&isar, &txn, 1), Some(IsarValue::Integer(2)));
+        assert_eq!(isar.update_properties(&txn, 0, 1, &set_name("c")), Ok(true));
+        assert_eq!(version(&isar, &txn, 1), Some(IsarValue::Integer(3)));
+
+        assert_eq!(
+            isar.update_if_version(&txn, 0, 1, 3, &set_name("d")),
+            Ok(true)
+        );
+        assert_eq!(
+            isar.update_if_version(&txn, 0, 1, 3, &set_name("e")),
+            Err(IsarError::VersionMismatch {})
+        );
+        assert_eq!(
+            isar.update_if_version(&txn, 0, 3, 1, &set_name("e")),
+            Ok(false)
+        );
+        assert_eq!(
+            isar.update_if(&txn, 0, 1, name_is("e"), &set_name("f")),
+            Err(IsarError::VersionMismatch {})
+        );
+        assert_eq!(
+            isar.update_if(&txn, 0, 1, name_is("d"), &set_name("f")),
+            Ok(true)
+        );
+        assert_eq!(version(&isar, &txn, 1), Some(IsarValue::Integer(5)));
+
+        let updates = [PropertyUpdate::set(2, Some(IsarValue::Integer(100)))];
+        let query = isar.query(0).unwrap().build().unwrap();
+        assert_eq!(
+            isar.query_update_properties(&txn, &query, None, None, &updates),
+            Ok(2)
+        );
+        assert_eq!(version(&isar, &txn, 1), Some(IsarValue::Integer(6)));
+        assert_eq!(version(&isar, &txn, 2), Some(IsarValue::Integer(3)));
+
+        assert_eq!(
+            isar.delete_if_version(&txn, 0, 1, 5),
+            Err(IsarError::VersionMismatch {})
+        );
+        assert_eq!(
+            isar.delete_if(&txn, 0, 2, name_is("a")),
+            Err(IsarError::VersionMismatch {})
+        );
+        assert_eq!(isar.delete_if_version(&txn, 0, 1, 6), Ok(true));
+        assert_eq!(isar.delete_if_version(&txn, 0, 1, 6), Ok(false));
+        assert_eq!(isar.delete_if(&txn, 0, 2, name_is("b")), Ok(true));
+        assert_eq!(isar.count(&txn, 0), Ok(0));
+
+        assert_eq!(
+            isar.update_if_version(&txn, 1, 1, 1, &set_name("a")),
+            Err(IsarError::IllegalArgument {})
+        );
+        isar.abort_txn(txn);
+        AnyInstance::close(isar, true);
+    });
+}
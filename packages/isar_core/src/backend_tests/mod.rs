@@ -0,0 +1,80 @@
+use crate::any::{AnyInstance, AnyTxn, Backend};
+use crate::core::cursor::IsarQueryCursor;
+use crate::core::filter::Filter;
+use crate::core::instance::IsarInstance;
+use crate::core::query_builder::IsarQueryBuilder;
+use crate::core::reader::IsarReader;
+use crate::core::schema::IsarSchema;
+use crate::test_util::{next_instance_id, test_dir};
+
+mod any;
+mod collection;
+mod constraint;
+mod convert;
+mod de;
+mod geo;
+mod instance;
+mod ser;
+mod snapshot;
+mod update;
+mod value;
+
+/// A database of one backend. Opening it again after closing it opens the same database.
+#[derive(Clone, Copy)]
+struct TestDb {
+    backend: Backend,
+    instance_id: u32,
+}
+
+impl TestDb {
+    fn new(backend: Backend) -> Self {
+        TestDb {
+            backend,
+            instance_id: next_instance_id(),
+        }
+    }
+
+    fn open(&self, schemas: Vec<IsarSchema>) -> AnyInstance {
+        let name = format!("test_{}", self.instance_id);
+        AnyInstance::open(
+            self.backend,
+            self.instance_id,
+            &name,
+            &test_dir(),
+            schemas,
+            10,
+            None,
+            None,
+        )
+        .unwrap()
+    }
+}
+
+/// Runs `test` with a new database of every backend.
+fn for_each_backend(test: impl Fn(TestDb)) {
+    for backend in [Backend::Native, Backend::SQLite] {
+        test(TestDb::new(backend));
+    }
+}
+
+/// Returns the ids of the objects of the first collection matching the filter.
+fn query_ids(isar: &AnyInstance, txn: &AnyTxn, filter: Filter) -> Vec<i64> {
+    let mut builder = isar.query(0).unwrap();
+    builder.set_filter(filter);
+    let query = builder.build().unwrap();
+    let mut cursor = isar.query_cursor(txn, &query, None, None).unwrap();
+    let mut ids = vec![];
+    while let Some(reader) = cursor.next() {
+        ids.push(reader.read_id());
+    }
+    ids
+}
+
+/// Imports a JSON array of objects into the collection `collection_index`.
+fn import_json(isar: &AnyInstance, txn: AnyTxn, collection_index: u16, json: &str) -> AnyTxn {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let (txn, _) = isar
+        .import_json(txn, collection_index, &mut deserializer, |_| 0)
+        .unwrap();
+    txn
+}
@@ -0,0 +1,98 @@
+use super::{for_each_backend, import_json};
+use crate::any::AnyInstance;
+use crate::core::data_type::DataType;
+use crate::core::error::IsarError;
+use crate::core::filter::{ConditionType, Filter, FilterCondition};
+use crate::core::instance::{IsarInstance, JsonFormat};
+use crate::core::query_builder::IsarQueryBuilder;
+use crate::core::schema::{IsarSchema, PropertySchema};
+use std::io::{self, Write};
+
+const OBJECTS: &str = r#"[{"id":1,"name":"a","tags":["x"]},{"id":2,"name":null,"tags":[]}]"#;
+const FIRST: &str = r#"{"name":"a","tags":["x"],"id":1}"#;
+const SECOND: &str = r#"{"tags":[],"id":2}"#;
+
+/// Runs `test` with an instance containing `OBJECTS` for every backend.
+fn with_objects(test: impl Fn(&AnyInstance)) {
+    for_each_backend(|db| {
+        let properties = vec![
+            PropertySchema::new("name", DataType::String, None),
+            PropertySchema::new("tags", DataType::StringList, None),
+        ];
+        let schema = IsarSchema::new("User", Some("id"), properties, vec![], false);
+        let isar = db.open(vec![schema]);
+        let txn = isar.begin_txn(true).unwrap();
+        let txn = import_json(&isar, txn, 0, OBJECTS);
+        isar.commit_txn(txn).unwrap();
+        test(&isar);
+        AnyInstance::close(isar, true);
+    });
+}
+
+fn export(isar: &AnyInstance, format: JsonFormat) -> (u32, String) {
+    let txn = isar.begin_txn(false).unwrap();
+    let mut json = vec![];
+    let count = isar
+        .export_collection_json(&txn, 0, format, &mut json)
+        .unwrap();
+    isar.abort_txn(txn);
+    (count, String::from_utf8(json).unwrap())
+}
+
+#[test]
+fn test_export_array() {
+    with_objects(|isar| {
+        let json = format!("[{},{}]", FIRST, SECOND);
+        assert_eq!(export(isar, JsonFormat::Array), (2, json));
+    });
+}
+
+#[test]
+fn test_export_ndjson() {
+    with_objects(|isar| {
+        let json = format!("{}\n{}\n", FIRST, SECOND);
+        assert_eq!(export(isar, JsonFormat::Ndjson), (2, json));
+    });
+}
+
+#[test]
+fn test_export_query() {
+    with_objects(|isar| {
+        let txn = isar.begin_txn(false).unwrap();
+        let mut builder = isar.query(0).unwrap();
+        let condition = FilterCondition::new(1, ConditionType::IsNull, vec![], false);
+        builder.set_filter(Filter::Condition(condition));
+        let query = builder.build().unwrap();
+        let mut json = vec![];
+        let count = isar
+            .export_json(&txn, &query, JsonFormat::Array, &mut json)
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(String::from_utf8(json).unwrap(), format!("[{}]", SECOND));
+        isar.abort_txn(txn);
+    });
+}
+
+struct FailingWriter;
+
+impl Write for FailingWriter {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::other("disk full"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_export_write_error() {
+    with_objects(|isar| {
+        let txn = isar.begin_txn(false).unwrap();
+        for format in [JsonFormat::Array, JsonFormat::Ndjson] {
+            let result = isar.export_collection_json(&txn, 0, format, FailingWriter);
+            assert_eq!(result, Err(IsarError::PathError {}));
+        }
+        isar.abort_txn(txn);
+    });
+}
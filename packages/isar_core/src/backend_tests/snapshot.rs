@@ -0,0 +1,130 @@
+use super::{import_json, TestDb};
+use crate::any::{AnyInstance, AnyTxn, Backend};
+use crate::core::data_type::DataType;
+use crate::core::error::IsarError;
+use crate::core::instance::IsarInstance;
+use crate::core::query_builder::IsarQueryBuilder;
+use crate::core::schema::{
+    CheckSchema, IndexSchema, IsarSchema, LinkSchema, PropertySchema, TtlSchema,
+};
+use crate::core::snapshot::checksum;
+
+fn schemas() -> Vec<IsarSchema> {
+    let name = PropertySchema::new("name", DataType::String, None)
+        .non_null()
+        .with_check(CheckSchema::Length {
+            min: Some(1),
+            max: None,
+        });
+    let user = vec![
+        name,
+        PropertySchema::new("score", DataType::Float, None),
+        PropertySchema::new("bytes", DataType::ByteList, None),
+        PropertySchema::new("address", DataType::Object, Some("Address")),
+        PropertySchema::new("version", DataType::Long, None),
+        PropertySchema::new("expires", DataType::Long, None),
+    ];
+    let index = IndexSchema::new("name_index", vec!["name"], false, false);
+    let item = vec![PropertySchema::new("name", DataType::String, None)];
+    let address = vec![PropertySchema::new("city", DataType::String, None)];
+    vec![
+        IsarSchema::new("User", Some("id"), user, vec![index], false)
+            .with_links(vec![LinkSchema::new("items", "Item", false)])
+            .with_ttl(TtlSchema::new("expires", 1000, false))
+            .with_version_property("version"),
+        IsarSchema::new("Item", Some("id"), item, vec![], false)
+            .with_links(vec![LinkSchema::backlink("users", "User", "items")]),
+        IsarSchema::new("Address", None, address, vec![], true),
+    ]
+}
+
+fn checksums(isar: &AnyInstance, txn: &AnyTxn) -> Vec<(u32, u64)> {
+    (0..2)
+        .map(|index| {
+            let query = isar.query(index).unwrap().build().unwrap();
+            checksum(isar.query_cursor(txn, &query, None, None).unwrap()).unwrap()
+        })
+        .collect()
+}
+
+/// Exports a snapshot of users and items linked to each other.
+fn export_snapshot(isar: &AnyInstance) -> Vec<u8> {
+    let users = r#"[
+        {"id": 1, "name": "a", "score": 0.1, "bytes": [1, 255], "address": {"city": "x"}},
+        {"id": -5, "name": "b", "bytes": [], "address": null}
+    ]"#;
+    let items = r#"[{"id": 1, "name": "i"}, {"id": 2}]"#;
+    let txn = isar.begin_txn(true).unwrap();
+    let txn = import_json(isar, txn, 0, users);
+    let txn = import_json(isar, txn, 1, items);
+    for (id, target_id) in [(1, 1), (1, 2), (-5, 2)] {
+        assert_eq!(isar.link(&txn, 0, 0, id, target_id), Ok(true));
+    }
+    isar.commit_txn(txn).unwrap();
+
+    let txn = isar.begin_txn(false).unwrap();
+    let mut bytes = vec![];
+    assert_eq!(isar.export_snapshot(&txn, &mut bytes), Ok(4));
+    isar.abort_txn(txn);
+    bytes
+}
+
+/// Reads the schemas from the header of a snapshot.
+fn snapshot_schemas(bytes: &[u8]) -> Vec<IsarSchema> {
+    let length = u32::from_le_bytes(bytes[6..10].try_into().unwrap()) as usize;
+    serde_json::from_slice(&bytes[10..10 + length]).unwrap()
+}
+
+#[test]
+fn test_round_trip() {
+    for (source, target) in [
+        (Backend::Native, Backend::SQLite),
+        (Backend::SQLite, Backend::Native),
+    ] {
+        let isar = TestDb::new(source).open(schemas());
+        let bytes = export_snapshot(&isar);
+        let txn = isar.begin_txn(false).unwrap();
+        let expected = checksums(&isar, &txn);
+        isar.abort_txn(txn);
+
+        // the snapshot contains the full schemas
+        let snapshot_schemas = snapshot_schemas(&bytes);
+        assert_eq!(snapshot_schemas, schemas());
+
+        let copy = TestDb::new(target).open(snapshot_schemas);
+        let txn = copy.begin_txn(true).unwrap();
+        let (txn, count) = copy.import_snapshot(txn, bytes.as_slice()).unwrap();
+        assert_eq!(count, 4);
+        assert_eq!(checksums(&copy, &txn), expected);
+        assert_eq!(copy.get_linked_ids(&txn, 0, 0, -5), Ok(vec![2]));
+        let mut users = copy.get_linked_ids(&txn, 1, 0, 2).unwrap();
+        users.sort();
+        assert_eq!(users, vec![-5, 1]);
+        copy.commit_txn(txn).unwrap();
+
+        AnyInstance::close(isar, true);
+        AnyInstance::close(copy, true);
+    }
+}
+
+#[test]
+fn test_import_aborts_txn() {
+    let isar = TestDb::new(Backend::Native).open(schemas());
+    let bytes = export_snapshot(&isar);
+    AnyInstance::close(isar, true);
+
+    for backend in [Backend::Native, Backend::SQLite] {
+        let copy = TestDb::new(backend).open(schemas());
+        // fails while inserting objects and while linking them
+        for length in [bytes.len() / 2, bytes.len() - 4] {
+            let txn = copy.begin_txn(true).unwrap();
+            let result = copy.import_snapshot(txn, &bytes[..length]);
+            assert_eq!(result.err(), Some(IsarError::IllegalArgument {}));
+
+            let txn = copy.begin_txn(false).unwrap();
+            assert_eq!(copy.count(&txn, 0), Ok(0));
+            copy.abort_txn(txn);
+        }
+        AnyInstance::close(copy, true);
+    }
+}
@@ -0,0 +1,246 @@
+use super::for_each_backend;
+use crate::any::AnyInstance;
+use crate::core::collection::{IsarInstanceExt, IsarObject};
+use crate::core::error::IsarError;
+use crate::core::instance::IsarInstance;
+use crate::core::update::{PropertyUpdate, UpdateOperation};
+use crate::core::value::IsarValue;
+use isar_derive::{IsarCollection, IsarEmbedded};
+
+#[derive(IsarEmbedded, Default, Clone, PartialEq, Debug)]
+struct Place {
+    city: String,
+    visits: i32,
+    open: bool,
+}
+
+#[derive(IsarCollection, Clone, PartialEq, Debug)]
+struct Profile {
+    id: i64,
+    points: i64,
+    rating: Option<f64>,
+    tags: Vec<String>,
+    scores: Vec<Option<i32>>,
+    flags: Vec<bool>,
+    home: Option<Place>,
+    work: Option<Place>,
+}
+
+#[test]
+fn test_update_properties() {
+    let place = |city: &str, visits: i32, open: bool| Place {
+        city: city.to_string(),
+        visits,
+        open,
+    };
+    let tags = |tags: &[&str]| tags.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+    let string = |value: &str| Some(IsarValue::String(value.to_string()));
+    let profiles = vec![
+        Profile {
+            id: 1,
+            points: 10,
+            rating: Some(1.5),
+            tags: tags(&["a", "b", "a"]),
+            scores: vec![Some(1), None, Some(3)],
+            flags: vec![true],
+            home: Some(place("Berlin", 1, false)),
+            work: None,
+        },
+        Profile {
+            id: 2,
+            points: 20,
+            rating: None,
+            tags: vec![],
+            scores: vec![],
+            flags: vec![],
+            home: None,
+            work: Some(place("Paris", 1, true)),
+        },
+    ];
+    for_each_backend(|db| {
+        let mut schemas = vec![];
+        Profile::add_schemas(&mut schemas);
+        let isar = db.open(schemas);
+
+        let txn = isar.begin_txn(true).unwrap();
+        let txn = isar.put_all(txn, &profiles).unwrap();
+
+        let updates = [
+            PropertyUpdate::increment(1, IsarValue::Integer(5)),
+            PropertyUpdate::decrement(2, IsarValue::Real(0.5)),
+            PropertyUpdate::append(3, string("c")),
+            PropertyUpdate::remove(3, string("a")),
+            PropertyUpdate::set_at(4, 1, Some(IsarValue::Integer(2))),
+            PropertyUpdate::append(5, Some(IsarValue::Bool(false))),
+            PropertyUpdate::new(vec![6, 1], UpdateOperation::Set(string("Hamburg"))),
+            PropertyUpdate::new(vec![6, 2], UpdateOperation::Increment(2.into())),
+            PropertyUpdate::new(vec![6, 3], UpdateOperation::Set(Some(true.into()))),
+            PropertyUpdate::new(vec![7, 1], UpdateOperation::Set(string("Rome"))),
+        ];
+        assert_eq!(isar.update_properties(&txn, 0, 1, &updates), Ok(true));
+        assert_eq!(isar.update_properties(&txn, 0, 3, &updates), Ok(false));
+        let expected = Profile {
+            id: 1,
+            points: 15,
+            rating: Some(1.0),
+            tags: tags(&["b", "c"]),
+            scores: vec![Some(1), Some(2), Some(3)],
+            flags: vec![true, false],
+            home: Some(place("Hamburg", 3, true)),
+            work: None,
+        };
+        assert_eq!(isar.get_by_id::<Profile>(&txn, 1).unwrap(), Some(expected));
+
+        // null values stay null and out of range indexes are ignored
+        let updates = [
+            PropertyUpdate::increment(2, IsarValue::Real(1.0)),
+            PropertyUpdate::append(3, string("x")),
+            PropertyUpdate::set_at(4, 0, Some(IsarValue::Integer(5))),
+            PropertyUpdate::remove(5, Some(IsarValue::Bool(true))),
+            PropertyUpdate::new(vec![6, 1], UpdateOperation::Set(string("Oslo"))),
+            PropertyUpdate::new(vec![7, 2], UpdateOperation::Increment(1.into())),
+        ];
+        let query = isar
+            .typed_query::<Profile>()
+            .unwrap()
+            .filter(Profile::POINTS.equal_to(20))
+            .build()
+            .unwrap();
+        assert_eq!(
+            isar.query_update_properties(&txn, query.inner(), None, None, &updates),
+            Ok(1)
+        );
+        let expected = Profile {
+            tags: tags(&["x"]),
+            work: Some(place("Paris", 2, true)),
+            ..profiles[1].clone()
+        };
+        assert_eq!(isar.get_by_id::<Profile>(&txn, 2).unwrap(), Some(expected));
+
+        let invalid = [
+            PropertyUpdate::increment(3, IsarValue::Integer(1)),
+            PropertyUpdate::increment(1, IsarValue::Real(1.0)),
+            PropertyUpdate::append(1, Some(IsarValue::Integer(1))),
+            PropertyUpdate::append(4, string("a")),
+            PropertyUpdate::new(vec![3, 1], UpdateOperation::Set(string("a"))),
+            PropertyUpdate::new(vec![6, 4], UpdateOperation::Set(None)),
+        ];
+        for update in invalid {
+            assert_eq!(
+                isar.update_properties(&txn, 0, 1, &[update]),
+                Err(IsarError::IllegalArgument {})
+            );
+        }
+        isar.abort_txn(txn);
+        AnyInstance::close(isar, true);
+    });
+}
+
+#[derive(IsarCollection, Clone, PartialEq, Debug)]
+struct Counter {
+    id: i64,
+    byte: u8,
+    int: i32,
+    long: i64,
+}
+
+#[test]
+fn test_increment_saturates() {
+    let counters = vec![
+        Counter {
+            id: 1,
+            byte: 250,
+            int: i32::MAX - 5,
+            long: i64::MAX - 5,
+        },
+        Counter {
+            id: 2,
+            byte: 5,
+            int: i32::MIN + 6,
+            long: i64::MIN + 6,
+        },
+    ];
+    for_each_backend(|db| {
+        let mut schemas = vec![];
+        Counter::add_schemas(&mut schemas);
+        let isar = db.open(schemas);
+
+        let txn = isar.begin_txn(true).unwrap();
+        let txn = isar.put_all(txn, &counters).unwrap();
+
+        let increment = |value: i64| {
+            [
+                PropertyUpdate::increment(1, IsarValue::Integer(value)),
+                PropertyUpdate::increment(2, IsarValue::Integer(value)),
+                PropertyUpdate::increment(3, IsarValue::Integer(value)),
+            ]
+        };
+        assert_eq!(isar.update_properties(&txn, 0, 1, &increment(10)), Ok(true));
+        assert_eq!(
+            isar.update_properties(&txn, 0, 2, &increment(i64::MIN)),
+            Ok(true)
+        );
+        let expected = Counter {
+            id: 1,
+            byte: 255,
+            int: i32::MAX,
+            long: i64::MAX,
+        };
+        assert_eq!(isar.get_by_id::<Counter>(&txn, 1).unwrap(), Some(expected));
+        let expected = Counter {
+            id: 2,
+            byte: 0,
+            int: i32::MIN + 1,
+            long: i64::MIN + 1,
+        };
+        assert_eq!(isar.get_by_id::<Counter>(&txn, 2).unwrap(), Some(expected));
+
+        isar.abort_txn(txn);
+        AnyInstance::close(isar, true);
+    });
+}
+
+#[test]
+fn test_legacy_update_skips_unknown_properties() {
+    for_each_backend(|db| {
+        let mut schemas = vec![];
+        Counter::add_schemas(&mut schemas);
+        let isar = db.open(schemas);
+
+        let txn = isar.begin_txn(true).unwrap();
+        let counter = Counter {
+            id: 1,
+            byte: 1,
+            int: 2,
+            long: 3,
+        };
+        let txn = isar.put_all(txn, &[counter.clone()]).unwrap();
+
+        let updates = [
+            (2, Some(IsarValue::Integer(7))),
+            (99, Some(IsarValue::Integer(8))),
+        ];
+        assert_eq!(isar.update(&txn, 0, 1, &updates), Ok(true));
+        assert_eq!(isar.update(&txn, 0, 1, &updates[1..]), Ok(true));
+        assert_eq!(isar.update(&txn, 0, 2, &updates), Ok(false));
+
+        let query = isar.typed_query::<Counter>().unwrap().build().unwrap();
+        let updates = [
+            (3, Some(IsarValue::Integer(9))),
+            (99, Some(IsarValue::Integer(8))),
+        ];
+        assert_eq!(
+            isar.query_update(&txn, query.inner(), None, None, &updates),
+            Ok(1)
+        );
+        let expected = Counter {
+            int: 7,
+            long: 9,
+            ..counter
+        };
+        assert_eq!(isar.get_by_id::<Counter>(&txn, 1).unwrap(), Some(expected));
+
+        isar.abort_txn(txn);
+        AnyInstance::close(isar, true);
+    });
+}
@@ -0,0 +1,120 @@
+use super::for_each_backend;
+use crate::any::AnyInstance;
+use crate::core::collection::{IsarInstanceExt, IsarObject};
+use crate::core::filter::Filter;
+use crate::core::instance::{Aggregation, IsarInstance, JsonFormat};
+use crate::core::query_builder::{IsarQueryBuilder, Sort};
+use crate::core::schema::IsarSchema;
+use crate::core::value::{DateTimeOffset, Decimal, IsarValue, Uuid};
+use isar_derive::IsarCollection;
+
+#[derive(IsarCollection, Clone, PartialEq, Debug)]
+struct Payment {
+    id: i64,
+    amount: Decimal,
+    reference: Option<Uuid>,
+    paid_at: DateTimeOffset,
+}
+
+fn schemas() -> Vec<IsarSchema> {
+    let mut schemas = vec![];
+    Payment::add_schemas(&mut schemas);
+    schemas
+}
+
+#[test]
+fn test_decimal_uuid_date_time_offset() {
+    let reference: Uuid = "67e55044-10b1-426f-9247-bb680e5fe0c8".parse().unwrap();
+    let payments = vec![
+        Payment {
+            id: 1,
+            amount: "-12.5".parse().unwrap(),
+            reference: Some(reference),
+            paid_at: "2024-01-01T10:00:00+02:00".parse().unwrap(),
+        },
+        Payment {
+            id: 2,
+            amount: "3.25".parse().unwrap(),
+            reference: None,
+            paid_at: "2024-01-01T09:00:00Z".parse().unwrap(),
+        },
+        Payment {
+            id: 3,
+            amount: Decimal::from(100),
+            reference: Some(Uuid([0; 16])),
+            paid_at: "2023-12-31T23:59:59.5-05:00".parse().unwrap(),
+        },
+    ];
+    for_each_backend(|db| {
+        let isar = db.open(schemas());
+        let txn = isar.begin_txn(true).unwrap();
+        let txn = isar.put_all(txn, &payments).unwrap();
+        isar.commit_txn(txn).unwrap();
+
+        let txn = isar.begin_txn(false).unwrap();
+        assert_eq!(
+            isar.get_by_id::<Payment>(&txn, 1).unwrap().as_ref(),
+            Some(&payments[0])
+        );
+
+        let find_ids = |filter: Filter| {
+            let query = isar
+                .typed_query::<Payment>()
+                .unwrap()
+                .filter(filter)
+                .sort_by(Payment::PAID_AT, Sort::Asc)
+                .build()
+                .unwrap();
+            let payments = isar.find_all(&txn, &query, None, None).unwrap();
+            payments.iter().map(|p| p.id).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            find_ids(Payment::AMOUNT.greater_than(Decimal::from(0))),
+            vec![3, 2]
+        );
+        assert_eq!(
+            find_ids(Payment::AMOUNT.less_or_equal_to("-12.5".parse::<Decimal>().unwrap())),
+            vec![1]
+        );
+        assert_eq!(find_ids(Payment::REFERENCE.is_null()), vec![2]);
+        assert_eq!(find_ids(Payment::REFERENCE.equal_to(reference)), vec![1]);
+        assert_eq!(
+            find_ids(Payment::REFERENCE.less_than(reference)),
+            vec![3, 2]
+        );
+        let start: DateTimeOffset = "2024-01-01T00:00:00+00:00".parse().unwrap();
+        let end: DateTimeOffset = "2024-01-01T08:30:00Z".parse().unwrap();
+        assert_eq!(find_ids(Payment::PAID_AT.between(start, end)), vec![3, 1]);
+
+        let query = isar.query(0).unwrap().build().unwrap();
+        assert_eq!(
+            isar.query_aggregate(&txn, &query, Aggregation::Max, Some(1)),
+            Ok(Some(IsarValue::Decimal(Decimal::from(100))))
+        );
+        assert_eq!(
+            isar.query_aggregate(&txn, &query, Aggregation::Min, Some(3)),
+            Ok(Some(IsarValue::DateTimeOffset(payments[2].paid_at)))
+        );
+
+        let mut json = vec![];
+        isar.export_json(&txn, &query, JsonFormat::Array, &mut json)
+            .unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.contains(r#""amount":"-12.5""#));
+        assert!(json.contains(r#""reference":"67e55044-10b1-426f-9247-bb680e5fe0c8""#));
+        assert!(json.contains(r#""paid_at":"2024-01-01T10:00:00+02:00""#));
+        isar.abort_txn(txn);
+
+        let txn = isar.begin_txn(true).unwrap();
+        isar.query_delete(&txn, &query, None, None).unwrap();
+        let mut deserializer = serde_json::Deserializer::from_str(&json);
+        let (txn, count) = isar.import_json(txn, 0, &mut deserializer, |_| 0).unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(
+            isar.get_by_id::<Payment>(&txn, 3).unwrap().as_ref(),
+            Some(&payments[2])
+        );
+        isar.abort_txn(txn);
+        AnyInstance::close(isar, true);
+    });
+}
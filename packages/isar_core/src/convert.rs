@@ -0,0 +1,349 @@
+use crate::any::AnyInstance;
+use crate::core::cursor::IsarQueryCursor;
+use crate::core::data_type::DataType;
+use crate::core::error::{IsarError, Result};
+use crate::core::insert::IsarInsert;
+use crate::core::instance::IsarInstance;
+use crate::core::query_builder::IsarQueryBuilder;
+use crate::core::reader::IsarReader;
+use crate::core::schema::IsarSchema;
+use crate::core::snapshot::{checksum, read_ids};
+use crate::core::writer::IsarWriter;
+use crate::native::native_instance::NativeInstance;
+use crate::sqlite::sqlite_instance::SQLiteInstance;
+use std::fs;
+use std::path::Path;
+use xxhash_rust::xxh3::xxh3_64;
+
+pub use crate::any::Backend;
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct CollectionSummary {
+    pub name: String,
+    pub count: u32,
+    /// Only depends on the ids and property values so it is equal for both backends.
+    pub checksum: u64,
+}
+
+/// Converts the database `name` in `dir` to the other backend and writes it to `target_dir`.
+/// The target must not exist yet. It is deleted again if the verification of object counts and
+/// checksums fails. The source is left untouched: a copy of it in `target_dir` is opened with the
+/// schemas and deleted afterwards.
+pub fn convert(
+    source: Backend,
+    name: &str,
+    dir: &str,
+    target_dir: &str,
+    schemas: Vec<IsarSchema>,
+    max_size_mib: u32,
+    encryption_key: Option<&str>,
+) -> Result<Vec<CollectionSummary>> {
+    let source_path = Path::new(dir).join(source.file_name(name));
+    let target_path = Path::new(target_dir).join(source.other().file_name(name));
+    let copy_name = format!("{}_source", name);
+    let copy_path = Path::new(target_dir).join(source.file_name(&copy_name));
+    if !source_path.exists() {
+        return Err(IsarError::PathError {});
+    }
+    if target_path.exists() || copy_path.exists() {
+        return Err(IsarError::IllegalArgument {});
+    }
+    let copy_id = instance_id(&copy_path);
+    let target_id = instance_id(&target_path);
+
+    let source_key = encryption_key.filter(|_| source == Backend::SQLite);
+    let target_key = encryption_key.filter(|_| source == Backend::Native);
+    let source_path = source_path.to_str().ok_or(IsarError::PathError {})?;
+    let copy_path = copy_path.to_str().ok_or(IsarError::PathError {})?;
+    let copied = match source {
+        Backend::Native => NativeInstance::copy_file(source_path, copy_path, max_size_mib),
+        Backend::SQLite => SQLiteInstance::copy_file(source_path, copy_path, source_key),
+    };
+    if let Err(err) = copied {
+        let _ = fs::remove_file(copy_path);
+        return Err(err);
+    }
+
+    let source = AnyInstance::open(
+        source,
+        copy_id,
+        &copy_name,
+        target_dir,
+        schemas.clone(),
+        max_size_mib,
+        source_key,
+        None,
+    );
+    let source = match source {
+        Ok(source) => source,
+        Err(err) => {
+            let _ = fs::remove_file(copy_path);
+            return Err(err);
+        }
+    };
+    let target = AnyInstance::open(
+        source.backend().other(),
+        target_id,
+        name,
+        target_dir,
+        schemas,
+        max_size_mib,
+        target_key,
+        None,
+    );
+    let target = match target {
+        Ok(target) => target,
+        Err(err) => {
+            AnyInstance::close(source, true);
+            return Err(err);
+        }
+    };
+
+    let result = copy_instance(&source, &target);
+    AnyInstance::close(source, true);
+    AnyInstance::close(target, result.is_err());
+    result
+}
+
+fn instance_id(path: &Path) -> u32 {
+    xxh3_64(path.to_string_lossy().as_bytes()) as u32
+}
+
+/// Copies all objects from `source` to `target` and verifies the result. Both instances have to
+/// be opened with the same schemas.
+pub fn copy_instance<S: IsarInstance, T: IsarInstance>(
+    source: &S,
+    target: &T,
+) -> Result<Vec<CollectionSummary>> {
+    check_schemas(source, target)?;
+
+    let txn = source.begin_txn(false)?;
+    let result = copy_collections(source, &txn, target);
+    source.abort_txn(txn);
+    result?;
+
+    let summary = summarize(source)?;
+    if summarize(target)? != summary {
+        return Err(IsarError::DbCorrupted {});
+    }
+    Ok(summary)
+}
+
+/// Returns the object count and checksum of all collections.
+pub fn summarize<I: IsarInstance>(instance: &I) -> Result<Vec<CollectionSummary>> {
+    let txn = instance.begin_txn(false)?;
+    let result = summarize_collections(instance, &txn);
+    instance.abort_txn(txn);
+    result
+}
+
+fn collections<I: IsarInstance>(instance: &I) -> Result<Vec<u16>> {
+    let mut collections = vec![];
+    for index in 0..instance.get_collections().count() as u16 {
+        // embedded collections have no id
+        if instance.get_id_name(index)?.is_some() {
+            collections.push(index);
+        }
+    }
+    Ok(collections)
+}
+
+fn check_schemas<S: IsarInstance, T: IsarInstance>(source: &S, target: &T) -> Result<()> {
+    let source_names = source.get_collections().collect::<Vec<_>>();
+    let target_names = target.get_collections().collect::<Vec<_>>();
+    if source_names != target_names {
+        return Err(IsarError::SchemaError {
+            message: "The collections of source and target do not match.".to_string(),
+        });
+    }
+
+    for (index, name) in source_names.iter().enumerate() {
+        let index = index as u16;
+        let source_properties = source.get_properties(index)?.collect::<Vec<_>>();
+        let target_properties = target.get_properties(index)?.collect::<Vec<_>>();
+        let source_links = source.get_links(index)?.collect::<Vec<_>>();
+        let target_links = target.get_links(index)?.collect::<Vec<_>>();
+        if source.get_id_name(index)? != target.get_id_name(index)?
+            || source_properties != target_properties
+            || source_links != target_links
+        {
+            return Err(IsarError::SchemaError {
+                message: format!("The schema of collection {} does not match.", name),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn summarize_collections<I: IsarInstance>(
+    instance: &I,
+    txn: &I::Txn,
+) -> Result<Vec<CollectionSummary>> {
+    let names = instance.get_collections().collect::<Vec<_>>();
+    let mut summary = vec![];
+    for index in collections(instance)? {
+        let query = instance.query(index)?.build()?;
+        let cursor = instance.query_cursor(txn, &query, None, None)?;
+        let (count, checksum) = checksum(cursor)?;
+        summary.push(CollectionSummary {
+            name: names[index as usize].to_string(),
+            count,
+            checksum,
+        });
+    }
+    Ok(summary)
+}
+
+fn copy_collections<S: IsarInstance, T: IsarInstance>(
+    source: &S,
+    txn: &S::Txn,
+    target: &T,
+) -> Result<()> {
+    for index in collections(source)? {
+        let count = source.count(txn, index)?;
+        let query = source.query(index)?.build()?;
+        let cursor = source.query_cursor(txn, &query, None, None)?;
+
+        let target_txn = target.begin_txn(true)?;
+        let insert = target.insert(target_txn, index, count)?;
+        let target_txn = copy_objects(cursor, insert)?;
+        target.commit_txn(target_txn)?;
+    }
+
+    let target_txn = target.begin_txn(true)?;
+    match copy_links(source, txn, target, &target_txn) {
+        Ok(()) => target.commit_txn(target_txn),
+        Err(err) => {
+            target.abort_txn(target_txn);
+            Err(err)
+        }
+    }
+}
+
+/// Copies the edges of all links. Backlinks share the edges of their links so linking again is
+/// a no-op.
+fn copy_links<S: IsarInstance, T: IsarInstance>(
+    source: &S,
+    txn: &S::Txn,
+    target: &T,
+    target_txn: &T::Txn,
+) -> Result<()> {
+    for index in collections(source)? {
+        let link_count = source.get_links(index)?.count() as u16;
+        if link_count == 0 {
+            continue;
+        }
+
+        let query = source.query(index)?.build()?;
+        let cursor = source.query_cursor(txn, &query, None, None)?;
+        let ids = read_ids(cursor);
+
+        for link_index in 0..link_count {
+            for &id in &ids {
+                for target_id in source.get_linked_ids(txn, index, link_index, id)? {
+                    target.link(target_txn, index, link_index, id, target_id)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn copy_objects<'a, W: IsarInsert<'a>>(
+    mut cursor: impl IsarQueryCursor,
+    mut insert: W,
+) -> Result<W::Txn> {
+    while let Some(reader) = cursor.next() {
+        copy_object(&reader, &mut insert);
+        insert.save(reader.read_id())?;
+    }
+    insert.finish()
+}
+
+fn copy_object<'a>(reader: &impl IsarReader, writer: &mut impl IsarWriter<'a>) {
+    let properties = reader.properties().map(|(_, t)| t).collect::<Vec<_>>();
+    for (index, data_type) in properties.into_iter().enumerate() {
+        copy_value(reader, writer, data_type, index as u32 + 1);
+    }
+}
+
+fn copy_value<'a>(
+    reader: &impl IsarReader,
+    writer: &mut impl IsarWriter<'a>,
+    data_type: DataType,
+    index: u32,
+) {
+    if reader.is_null(index) {
+        writer.write_null(index);
+        return;
+    }
+
+    match data_type {
+        DataType::Bool => match reader.read_bool(index) {
+            Some(value) => writer.write_bool(index, value),
+            None => writer.write_null(index),
+        },
+        DataType::Byte => writer.write_byte(index, reader.read_byte(index)),
+        DataType::Int => writer.write_int(index, reader.read_int(index)),
+        DataType::Float => writer.write_float(index, reader.read_float(index)),
+        DataType::Long => writer.write_long(index, reader.read_long(index)),
+        DataType::Double => writer.write_double(index, reader.read_double(index)),
+        DataType::String | DataType::Json => match reader.read_string(index) {
+            Some(value) => writer.write_string(index, value),
+            None => writer.write_null(index),
+        },
+        DataType::Decimal => match reader.read_decimal(index) {
+            Some(value) => writer.write_decimal(index, value),
+            None => writer.write_null(index),
+        },
+        DataType::Uuid => match reader.read_uuid(index) {
+            Some(value) => writer.write_uuid(index, value),
+            None => writer.write_null(index),
+        },
+        DataType::DateTimeOffset => match reader.read_date_time_offset(index) {
+            Some(value) => writer.write_date_time_offset(index, value),
+            None => writer.write_null(index),
+        },
+        DataType::Blob => match reader.read_blob(index) {
+            Some(value) => writer.write_blob(index, &value),
+            None => writer.write_null(index),
+        },
+        DataType::Object => {
+            if let Some(object) = reader.read_object(index) {
+                if let Some(mut object_writer) = writer.begin_object(index) {
+                    copy_object(&object, &mut object_writer);
+                    writer.end_object(object_writer);
+                    return;
+                }
+            }
+            writer.write_null(index);
+        }
+        _ if data_type.is_map() => {
+            let value_type = data_type.value_type().unwrap();
+            if let Some((keys, values)) = reader.read_map(index) {
+                let keys = keys.iter().map(|key| key.as_ref()).collect::<Vec<_>>();
+                if let Some(mut map_writer) = writer.begin_map(index, &keys) {
+                    for i in 0..keys.len() as u32 {
+                        copy_value(&values, &mut map_writer, value_type, i);
+                    }
+                    writer.end_list(map_writer);
+                    return;
+                }
+            }
+            writer.write_null(index);
+        }
+        _ => {
+            let element_type = data_type.element_type().unwrap();
+            if let Some((list, length)) = reader.read_list(index) {
+                if let Some(mut list_writer) = writer.begin_list(index, length) {
+                    for i in 0..length {
+                        copy_value(&list, &mut list_writer, element_type, i);
+                    }
+                    writer.end_list(list_writer);
+                    return;
+                }
+            }
+            writer.write_null(index);
+        }
+    }
+}
@@ -0,0 +1,605 @@
+use super::cursor::{IsarCursor, IsarQueryCursor};
+use super::data_type::DataType;
+use super::error::{IsarError, Result};
+use super::filter::{ConditionType, Filter, FilterCondition, FilterMapEntry, FilterNested};
+use super::insert::IsarInsert;
+use super::instance::IsarInstance;
+use super::query_builder::{IsarQueryBuilder, Sort};
+use super::reader::IsarReader;
+use super::schema::IsarSchema;
+use super::value::{DateTimeOffset, Decimal, IsarValue, Uuid};
+use super::writer::IsarWriter;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::marker::PhantomData;
+
+#[cfg(feature = "derive")]
+pub use isar_derive::{IsarCollection, IsarEmbedded};
+
+/// A Rust type that is stored as the object of a collection or as an embedded object.
+/// Use `#[derive(IsarCollection)]` or `#[derive(IsarEmbedded)]` to implement it.
+pub trait IsarObject: Sized {
+    fn name() -> &'static str;
+
+    fn schema() -> IsarSchema;
+
+    fn add_embedded_schemas(schemas: &mut Vec<IsarSchema>);
+
+    /// Adds the schema of this type and of all embedded types it uses.
+    fn add_schemas(schemas: &mut Vec<IsarSchema>) {
+        if !schemas.iter().any(|schema| schema.name == Self::name()) {
+            schemas.push(Self::schema());
+            Self::add_embedded_schemas(schemas);
+        }
+    }
+
+    fn write<'a>(&self, writer: &mut impl IsarWriter<'a>);
+
+    fn read(reader: &impl IsarReader) -> Self;
+}
+
+pub trait IsarCollection: IsarObject {
+    fn id(&self) -> i64;
+}
+
+/// A Rust type that can be stored in a property. Non-optional properties that are null are read
+/// as the default value.
+pub trait IsarProperty: Sized {
+    const DATA_TYPE: DataType;
+
+    /// The type of values that this property is compared with in filters.
+    type Value: Into<IsarValue>;
+
+    /// The name of the embedded collection of objects.
+    fn target() -> Option<&'static str> {
+        None
+    }
+
+    fn add_schemas(_schemas: &mut Vec<IsarSchema>) {}
+
+    fn write<'a>(&self, writer: &mut impl IsarWriter<'a>, index: u32);
+
+    /// Returns `None` if the value is null.
+    fn read(reader: &impl IsarReader, index: u32) -> Option<Self>;
+}
+
+impl From<Infallible> for IsarValue {
+    fn from(value: Infallible) -> Self {
+        match value {}
+    }
+}
+
+macro_rules! primitive_property {
+    ($type:ty, $data_type:ident, $write:ident, $read:ident) => {
+        impl IsarProperty for $type {
+            const DATA_TYPE: DataType = DataType::$data_type;
+
+            type Value = $type;
+
+            fn write<'a>(&self, writer: &mut impl IsarWriter<'a>, index: u32) {
+                writer.$write(index, *self);
+            }
+
+            fn read(reader: &impl IsarReader, index: u32) -> Option<Self> {
+                if reader.is_null(index) {
+                    None
+                } else {
+                    Some(reader.$read(index))
+                }
+            }
+        }
+    };
+}
+
+primitive_property!(u8, Byte, write_byte, read_byte);
+primitive_property!(i32, Int, write_int, read_int);
+primitive_property!(f32, Float, write_float, read_float);
+primitive_property!(i64, Long, write_long, read_long);
+primitive_property!(f64, Double, write_double, read_double);
+
+impl IsarProperty for bool {
+    const DATA_TYPE: DataType = DataType::Bool;
+
+    type Value = bool;
+
+    fn write<'a>(&self, writer: &mut impl IsarWriter<'a>, index: u32) {
+        writer.write_bool(index, *self);
+    }
+
+    fn read(reader: &impl IsarReader, index: u32) -> Option<Self> {
+        reader.read_bool(index)
+    }
+}
+
+impl IsarProperty for String {
+    const DATA_TYPE: DataType = DataType::String;
+
+    type Value = String;
+
+    fn write<'a>(&self, writer: &mut impl IsarWriter<'a>, index: u32) {
+        writer.write_string(index, self);
+    }
+
+    fn read(reader: &impl IsarReader, index: u32) -> Option<Self> {
+        reader.read_string(index).map(|value| value.to_string())
+    }
+}
+
+macro_rules! fixed_bytes_property {
+    ($type:ty, $data_type:ident, $write:ident, $read:ident) => {
+        impl IsarProperty for $type {
+            const DATA_TYPE: DataType = DataType::$data_type;
+
+            type Value = $type;
+
+            fn write<'a>(&self, writer: &mut impl IsarWriter<'a>, index: u32) {
+                writer.$write(index, *self);
+            }
+
+            fn read(reader: &impl IsarReader, index: u32) -> Option<Self> {
+                reader.$read(index)
+            }
+        }
+    };
+}
+
+fixed_bytes_property!(Decimal, Decimal, write_decimal, read_decimal);
+fixed_bytes_property!(Uuid, Uuid, write_uuid, read_uuid);
+fixed_bytes_property!(
+    DateTimeOffset,
+    DateTimeOffset,
+    write_date_time_offset,
+    read_date_time_offset
+);
+
+/// The payload of a blob property. It is stored outside of its object so large payloads are not
+/// copied when other properties of the object are updated.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Blob(pub Vec<u8>);
+
+impl IsarProperty for Blob {
+    const DATA_TYPE: DataType = DataType::Blob;
+
+    type Value = Infallible;
+
+    fn write<'a>(&self, writer: &mut impl IsarWriter<'a>, index: u32) {
+        writer.write_blob(index, &self.0);
+    }
+
+    fn read(reader: &impl IsarReader, index: u32) -> Option<Self> {
+        reader
+            .read_blob(index)
+            .map(|value| Blob(value.into_owned()))
+    }
+}
+
+impl<T: IsarProperty> IsarProperty for Option<T> {
+    const DATA_TYPE: DataType = T::DATA_TYPE;
+
+    type Value = T::Value;
+
+    fn target() -> Option<&'static str> {
+        T::target()
+    }
+
+    fn add_schemas(schemas: &mut Vec<IsarSchema>) {
+        T::add_schemas(schemas)
+    }
+
+    fn write<'a>(&self, writer: &mut impl IsarWriter<'a>, index: u32) {
+        match self {
+            Some(value) => value.write(writer, index),
+            None => writer.write_null(index),
+        }
+    }
+
+    fn read(reader: &impl IsarReader, index: u32) -> Option<Self> {
+        Some(T::read(reader, index))
+    }
+}
+
+const fn list_type(element_type: DataType) -> DataType {
+    match element_type {
+        DataType::Bool => DataType::BoolList,
+        DataType::Byte => DataType::ByteList,
+        DataType::Int => DataType::IntList,
+        DataType::Float => DataType::FloatList,
+        DataType::Long => DataType::LongList,
+        DataType::Double => DataType::DoubleList,
+        DataType::String => DataType::StringList,
+        DataType::Object => DataType::ObjectList,
+        _ => panic!("Nested lists are not supported."),
+    }
+}
+
+/// Filters of list properties match if any element matches.
+impl<T: IsarProperty + Default> IsarProperty for Vec<T> {
+    const DATA_TYPE: DataType = list_type(T::DATA_TYPE);
+
+    type Value = T::Value;
+
+    fn target() -> Option<&'static str> {
+        T::target()
+    }
+
+    fn add_schemas(schemas: &mut Vec<IsarSchema>) {
+        T::add_schemas(schemas)
+    }
+
+    fn write<'a>(&self, writer: &mut impl IsarWriter<'a>, index: u32) {
+        if let Some(mut list_writer) = writer.begin_list(index, self.len() as u32) {
+            for (i, value) in self.iter().enumerate() {
+                value.write(&mut list_writer, i as u32);
+            }
+            writer.end_list(list_writer);
+        }
+    }
+
+    fn read(reader: &impl IsarReader, index: u32) -> Option<Self> {
+        let (list, length) = reader.read_list(index)?;
+        let values = (0..length)
+            .map(|i| T::read(&list, i).unwrap_or_default())
+            .collect();
+        Some(values)
+    }
+}
+
+/// A map with string keys. Non-optional values that are null are read as the default value.
+pub type StringMap<T> = BTreeMap<String, T>;
+
+const fn map_type(value_type: DataType) -> DataType {
+    match value_type {
+        DataType::Bool => DataType::BoolMap,
+        DataType::Byte => DataType::ByteMap,
+        DataType::Int => DataType::IntMap,
+        DataType::Float => DataType::FloatMap,
+        DataType::Long => DataType::LongMap,
+        DataType::Double => DataType::DoubleMap,
+        DataType::String => DataType::StringMap,
+        DataType::Object => DataType::ObjectMap,
+        _ => panic!("Maps only support primitive and embedded values."),
+    }
+}
+
+/// Filters of map properties target the value of a key, see [`Property::entry`].
+impl<T: IsarProperty + Default> IsarProperty for StringMap<T> {
+    const DATA_TYPE: DataType = map_type(T::DATA_TYPE);
+
+    type Value = T::Value;
+
+    fn target() -> Option<&'static str> {
+        T::target()
+    }
+
+    fn add_schemas(schemas: &mut Vec<IsarSchema>) {
+        T::add_schemas(schemas)
+    }
+
+    fn write<'a>(&self, writer: &mut impl IsarWriter<'a>, index: u32) {
+        let keys = self.keys().map(|key| key.as_str()).collect::<Vec<_>>();
+        if let Some(mut map_writer) = writer.begin_map(index, &keys) {
+            for (i, value) in self.values().enumerate() {
+                value.write(&mut map_writer, i as u32);
+            }
+            writer.end_list(map_writer);
+        }
+    }
+
+    fn read(reader: &impl IsarReader, index: u32) -> Option<Self> {
+        let (keys, values) = reader.read_map(index)?;
+        let map = keys
+            .into_iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let value = T::read(&values, i as u32).unwrap_or_default();
+                (key.into_owned(), value)
+            })
+            .collect();
+        Some(map)
+    }
+}
+
+/// A typed handle for a property of `T` that creates filters.
+pub struct Property<T> {
+    index: u16,
+    case_sensitive: bool,
+    property_type: PhantomData<T>,
+}
+
+impl<T> Clone for Property<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Property<T> {}
+
+impl<T: IsarProperty> Property<T> {
+    pub const fn new(index: u16) -> Self {
+        Property {
+            index,
+            case_sensitive: true,
+            property_type: PhantomData,
+        }
+    }
+
+    pub const fn index(&self) -> u16 {
+        self.index
+    }
+
+    /// Compares strings of the returned property case insensitive.
+    pub const fn case_insensitive(self) -> Self {
+        Property {
+            case_sensitive: false,
+            ..self
+        }
+    }
+
+    fn value(value: impl Into<T::Value>) -> Option<IsarValue> {
+        let value: T::Value = value.into();
+        Some(value.into())
+    }
+
+    fn condition(&self, condition_type: ConditionType, values: Vec<Option<IsarValue>>) -> Filter {
+        Filter::Condition(FilterCondition::new(
+            self.index,
+            condition_type,
+            values,
+            self.case_sensitive,
+        ))
+    }
+
+    pub fn is_null(&self) -> Filter {
+        self.condition(ConditionType::IsNull, vec![])
+    }
+
+    pub fn equal_to(&self, value: impl Into<T::Value>) -> Filter {
+        self.condition(ConditionType::Equal, vec![Self::value(value)])
+    }
+
+    pub fn greater_than(&self, value: impl Into<T::Value>) -> Filter {
+        self.condition(ConditionType::Greater, vec![Self::value(value)])
+    }
+
+    pub fn greater_or_equal_to(&self, value: impl Into<T::Value>) -> Filter {
+        self.condition(ConditionType::GreaterOrEqual, vec![Self::value(value)])
+    }
+
+    pub fn less_than(&self, value: impl Into<T::Value>) -> Filter {
+        self.condition(ConditionType::Less, vec![Self::value(value)])
+    }
+
+    pub fn less_or_equal_to(&self, value: impl Into<T::Value>) -> Filter {
+        self.condition(ConditionType::LessOrEqual, vec![Self::value(value)])
+    }
+
+    pub fn between(&self, lower: impl Into<T::Value>, upper: impl Into<T::Value>) -> Filter {
+        let values = vec![Self::value(lower), Self::value(upper)];
+        self.condition(ConditionType::Between, values)
+    }
+
+    /// Matches objects whose embedded object matches `filter`.
+    pub fn nested(&self, filter: Filter) -> Filter {
+        Filter::Nested(FilterNested::new(self.index, filter))
+    }
+}
+
+impl<T: IsarProperty + Default> Property<StringMap<T>> {
+    /// Creates filters for the value of `key`.
+    pub fn entry(&self, key: &str) -> MapEntry<T> {
+        MapEntry {
+            index: self.index,
+            key: key.to_string(),
+            case_sensitive: self.case_sensitive,
+            value_type: PhantomData,
+        }
+    }
+
+    /// Matches objects whose map has a value for `key` that is not null.
+    pub fn contains_key(&self, key: &str) -> Filter {
+        Filter::Not(Box::new(self.entry(key).is_null()))
+    }
+}
+
+impl<T: IsarProperty<Value = String>> Property<T> {
+    pub fn starts_with(&self, value: &str) -> Filter {
+        self.condition(ConditionType::StringStartsWith, vec![Some(value.into())])
+    }
+
+    pub fn ends_with(&self, value: &str) -> Filter {
+        self.condition(ConditionType::StringEndsWith, vec![Some(value.into())])
+    }
+
+    pub fn contains(&self, value: &str) -> Filter {
+        self.condition(ConditionType::StringContains, vec![Some(value.into())])
+    }
+
+    /// Matches the wildcards `*` and `?`.
+    pub fn matches(&self, pattern: &str) -> Filter {
+        self.condition(ConditionType::StringMatches, vec![Some(pattern.into())])
+    }
+}
+
+/// A typed handle for the value of a key of a map property that creates filters. Missing keys
+/// are null.
+pub struct MapEntry<T> {
+    index: u16,
+    key: String,
+    case_sensitive: bool,
+    value_type: PhantomData<T>,
+}
+
+impl<T: IsarProperty> MapEntry<T> {
+    fn condition(&self, condition_type: ConditionType, values: Vec<Option<IsarValue>>) -> Filter {
+        Filter::MapEntry(FilterMapEntry::new(
+            self.index,
+            &self.key,
+            condition_type,
+            values,
+            self.case_sensitive,
+        ))
+    }
+
+    pub fn is_null(&self) -> Filter {
+        self.condition(ConditionType::IsNull, vec![])
+    }
+
+    pub fn equal_to(&self, value: impl Into<T::Value>) -> Filter {
+        self.condition(ConditionType::Equal, vec![Property::<T>::value(value)])
+    }
+
+    pub fn greater_than(&self, value: impl Into<T::Value>) -> Filter {
+        self.condition(ConditionType::Greater, vec![Property::<T>::value(value)])
+    }
+
+    pub fn greater_or_equal_to(&self, value: impl Into<T::Value>) -> Filter {
+        let values = vec![Property::<T>::value(value)];
+        self.condition(ConditionType::GreaterOrEqual, values)
+    }
+
+    pub fn less_than(&self, value: impl Into<T::Value>) -> Filter {
+        self.condition(ConditionType::Less, vec![Property::<T>::value(value)])
+    }
+
+    pub fn less_or_equal_to(&self, value: impl Into<T::Value>) -> Filter {
+        let values = vec![Property::<T>::value(value)];
+        self.condition(ConditionType::LessOrEqual, values)
+    }
+
+    pub fn between(&self, lower: impl Into<T::Value>, upper: impl Into<T::Value>) -> Filter {
+        let values = vec![Property::<T>::value(lower), Property::<T>::value(upper)];
+        self.condition(ConditionType::Between, values)
+    }
+}
+
+impl<T: IsarProperty<Value = String>> MapEntry<T> {
+    pub fn starts_with(&self, value: &str) -> Filter {
+        self.condition(ConditionType::StringStartsWith, vec![Some(value.into())])
+    }
+
+    pub fn ends_with(&self, value: &str) -> Filter {
+        self.condition(ConditionType::StringEndsWith, vec![Some(value.into())])
+    }
+
+    pub fn contains(&self, value: &str) -> Filter {
+        self.condition(ConditionType::StringContains, vec![Some(value.into())])
+    }
+
+    /// Matches the wildcards `*` and `?`.
+    pub fn matches(&self, pattern: &str) -> Filter {
+        self.condition(ConditionType::StringMatches, vec![Some(pattern.into())])
+    }
+}
+
+pub struct QueryBuilder<B, T> {
+    builder: B,
+    object_type: PhantomData<T>,
+}
+
+impl<B: IsarQueryBuilder, T: IsarCollection> QueryBuilder<B, T> {
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.builder.set_filter(filter);
+        self
+    }
+
+    pub fn sort_by<P>(mut self, property: Property<P>, sort: Sort) -> Self {
+        self.builder
+            .add_sort(property.index, sort, property.case_sensitive);
+        self
+    }
+
+    pub fn distinct_by<P>(mut self, property: Property<P>) -> Self {
+        self.builder
+            .add_distinct(property.index, property.case_sensitive);
+        self
+    }
+
+    pub fn build(self) -> Result<Query<B::Query, T>> {
+        Ok(Query {
+            query: self.builder.build()?,
+            object_type: PhantomData,
+        })
+    }
+}
+
+pub struct Query<Q, T> {
+    query: Q,
+    object_type: PhantomData<T>,
+}
+
+impl<Q, T> Query<Q, T> {
+    /// The untyped query for aggregations, updates and watchers.
+    pub fn inner(&self) -> &Q {
+        &self.query
+    }
+}
+
+/// Typed access to the collections of an instance.
+pub trait IsarInstanceExt: IsarInstance {
+    fn collection_index<T: IsarCollection>(&self) -> Result<u16> {
+        self.get_collections()
+            .position(|name| name == T::name())
+            .map(|index| index as u16)
+            .ok_or_else(|| IsarError::SchemaError {
+                message: format!("Unknown collection {}.", T::name()),
+            })
+    }
+
+    /// Inserts or replaces the objects.
+    fn put_all<T: IsarCollection>(&self, txn: Self::Txn, objects: &[T]) -> Result<Self::Txn> {
+        let collection_index = match self.collection_index::<T>() {
+            Ok(collection_index) => collection_index,
+            Err(err) => {
+                self.abort_txn(txn);
+                return Err(err);
+            }
+        };
+        let mut insert = self.insert(txn, collection_index, objects.len() as u32)?;
+        for object in objects {
+            object.write(&mut insert);
+            insert.save(object.id())?;
+        }
+        insert.finish()
+    }
+
+    fn get_by_id<T: IsarCollection>(&self, txn: &Self::Txn, id: i64) -> Result<Option<T>> {
+        let cursor = self.cursor(txn, self.collection_index::<T>()?)?;
+        Ok(read_by_id(cursor, id))
+    }
+
+    fn delete_by_id<T: IsarCollection>(&self, txn: &Self::Txn, id: i64) -> Result<bool> {
+        self.delete(txn, self.collection_index::<T>()?, id)
+    }
+
+    fn typed_query<T: IsarCollection>(&self) -> Result<QueryBuilder<Self::QueryBuilder<'_>, T>> {
+        Ok(QueryBuilder {
+            builder: self.query(self.collection_index::<T>()?)?,
+            object_type: PhantomData,
+        })
+    }
+
+    fn find_all<T: IsarCollection>(
+        &self,
+        txn: &Self::Txn,
+        query: &Query<Self::Query, T>,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<Vec<T>> {
+        let cursor = self.query_cursor(txn, &query.query, offset, limit)?;
+        Ok(read_all(cursor))
+    }
+}
+
+impl<I: IsarInstance> IsarInstanceExt for I {}
+
+fn read_by_id<T: IsarObject>(mut cursor: impl IsarCursor, id: i64) -> Option<T> {
+    cursor.next(id).map(|reader| T::read(&reader))
+}
+
+fn read_all<T: IsarObject>(mut cursor: impl IsarQueryCursor) -> Vec<T> {
+    let mut objects = vec![];
+    while let Some(reader) = cursor.next() {
+        objects.push(T::read(&reader));
+    }
+    objects
+}
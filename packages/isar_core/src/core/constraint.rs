@@ -0,0 +1,306 @@
+use super::data_type::DataType;
+use super::error::{IsarError, Result};
+use super::regex::Regex;
+use super::schema::{CheckSchema, PropertySchema, PropertyValue};
+use super::update::{PropertyUpdate, UpdateOperation};
+use super::value::IsarValue;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+/// The nullability, default value and checks of a property compiled from its schema.
+pub(crate) struct PropertyConstraint {
+    pub property_index: u16,
+    name: String,
+    nullable: bool,
+    pub default: Option<IsarValue>,
+    checks: Vec<Check>,
+}
+
+enum Check {
+    Range(Option<IsarValue>, Option<IsarValue>),
+    Length(Option<u32>, Option<u32>),
+    Regex(Regex, String),
+    // the length of a list property with a vector index
+    Dimensions(u32),
+}
+
+impl PropertyConstraint {
+    /// Returns `None` if the property has no constraints. Lists of a vector index with
+    /// `dimensions` must have exactly that many elements.
+    pub fn new(
+        property_index: u16,
+        property: &PropertySchema,
+        dimensions: Option<u32>,
+    ) -> Option<Self> {
+        if !property.has_constraints() && dimensions.is_none() {
+            return None;
+        }
+
+        let data_type = property.data_type;
+        let checks = property
+            .checks
+            .iter()
+            .filter_map(|check| match check {
+                CheckSchema::Range { min, max } => Some(Check::Range(
+                    min.as_ref().map(|v| to_isar_value(v, data_type)),
+                    max.as_ref().map(|v| to_isar_value(v, data_type)),
+                )),
+                CheckSchema::Length { min, max } => Some(Check::Length(*min, *max)),
+                CheckSchema::Regex { pattern } => {
+                    Regex::new(pattern, false).map(|regex| Check::Regex(regex, pattern.clone()))
+                }
+            })
+            .chain(dimensions.map(Check::Dimensions))
+            .collect();
+
+        Some(PropertyConstraint {
+            property_index,
+            name: property.name.clone()?,
+            nullable: property.nullable,
+            default: property
+                .default
+                .as_ref()
+                .map(|v| to_isar_value(v, data_type)),
+            checks,
+        })
+    }
+
+    /// Returns the value that is stored instead of `value` or an error if it violates the
+    /// constraint. Lists are passed as their length.
+    pub fn apply(&self, value: Option<IsarValue>) -> Result<Option<IsarValue>> {
+        let value = value.or_else(|| self.default.clone());
+        if let Some(value) = &value {
+            for check in &self.checks {
+                self.check(check, value)?;
+            }
+        } else if !self.nullable {
+            return self.violation("must not be null".to_string());
+        }
+        Ok(value)
+    }
+
+    fn check(&self, check: &Check, value: &IsarValue) -> Result<()> {
+        match check {
+            Check::Range(min, max) => {
+                if let Some(min) = min {
+                    if compare(value, min) == Some(Ordering::Less) {
+                        return self.violation(format!("must be at least {}", format_value(min)));
+                    }
+                }
+                if let Some(max) = max {
+                    if compare(value, max) == Some(Ordering::Greater) {
+                        return self.violation(format!("must be at most {}", format_value(max)));
+                    }
+                }
+            }
+            Check::Length(min, max) => {
+                let length = value.string().map_or(0, |s| s.chars().count() as u32);
+                if min.is_some_and(|min| length < min) {
+                    let message = format!("must have at least {} characters", min.unwrap());
+                    return self.violation(message);
+                }
+                if max.is_some_and(|max| length > max) {
+                    let message = format!("must have at most {} characters", max.unwrap());
+                    return self.violation(message);
+                }
+            }
+            Check::Regex(regex, pattern) => {
+                if !value.string().is_some_and(|s| regex.is_match(s)) {
+                    return self.violation(format!("must match '{}'", pattern));
+                }
+            }
+            Check::Dimensions(dimensions) => {
+                if value.i64() != Some(*dimensions as i64) {
+                    return self.violation(format!("must have {} elements", dimensions));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn violation<T>(&self, message: String) -> Result<T> {
+        Err(IsarError::ConstraintViolation {
+            property: self.name.clone(),
+            message,
+        })
+    }
+}
+
+type Updates<'a> = Cow<'a, [PropertyUpdate]>;
+
+/// Applies the constraints of the updated properties and returns the updates to store. The
+/// result of operations other than sets is not known before they are applied so they are
+/// rejected for properties with checks.
+pub(crate) fn apply_updates<'a>(
+    constraints: &[PropertyConstraint],
+    updates: &'a [PropertyUpdate],
+) -> Result<Updates<'a>> {
+    let constrained = updates.iter().any(|update| {
+        constraints
+            .iter()
+            .any(|c| c.property_index == update.property_index())
+    });
+    if !constrained {
+        return Ok(Cow::Borrowed(updates));
+    }
+
+    let mut applied = Vec::with_capacity(updates.len());
+    for update in updates {
+        let constraint = constraints
+            .iter()
+            .find(|c| c.property_index == update.property_index());
+        match (constraint, &update.operation) {
+            (Some(constraint), UpdateOperation::Set(value)) if !update.is_nested() => {
+                let value = constraint.apply(value.clone())?;
+                applied.push(PropertyUpdate::set(update.property_index(), value));
+            }
+            (Some(constraint), _) if !constraint.checks.is_empty() => {
+                return Err(IsarError::IllegalArgument {});
+            }
+            _ => applied.push(update.clone()),
+        }
+    }
+    Ok(Cow::Owned(applied))
+}
+
+pub(crate) fn to_isar_value(value: &PropertyValue, data_type: DataType) -> IsarValue {
+    match value {
+        PropertyValue::Bool(value) => IsarValue::Bool(*value),
+        PropertyValue::Integer(value) => {
+            if data_type == DataType::Float || data_type == DataType::Double {
+                IsarValue::Real(*value as f64)
+            } else {
+                IsarValue::Integer(*value)
+            }
+        }
+        PropertyValue::Real(value) => IsarValue::Real(*value),
+        PropertyValue::String(value) => IsarValue::String(value.clone()),
+    }
+}
+
+fn compare(value: &IsarValue, bound: &IsarValue) -> Option<Ordering> {
+    match (value, bound) {
+        (IsarValue::Integer(value), IsarValue::Integer(bound)) => Some(value.cmp(bound)),
+        (IsarValue::Real(value), IsarValue::Real(bound)) => value.partial_cmp(bound),
+        _ => None,
+    }
+}
+
+fn format_value(value: &IsarValue) -> String {
+    match value {
+        IsarValue::Bool(value) => value.to_string(),
+        IsarValue::Integer(value) => value.to_string(),
+        IsarValue::Real(value) => value.to_string(),
+        IsarValue::String(value) => value.clone(),
+        IsarValue::Decimal(value) => value.to_string(),
+        IsarValue::Uuid(value) => value.to_string(),
+        IsarValue::DateTimeOffset(value) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constraint(property: PropertySchema) -> PropertyConstraint {
+        PropertyConstraint::new(1, &property, None).unwrap()
+    }
+
+    #[test]
+    fn test_new_without_constraints() {
+        let property = PropertySchema::new("prop", DataType::Int, None);
+        assert!(PropertyConstraint::new(1, &property, None).is_none());
+    }
+
+    #[test]
+    fn test_apply_non_null() {
+        let c = constraint(PropertySchema::new("prop", DataType::Int, None).non_null());
+        assert_eq!(
+            c.apply(Some(IsarValue::Integer(1))),
+            Ok(Some(IsarValue::Integer(1)))
+        );
+        assert_eq!(
+            c.apply(None),
+            Err(IsarError::ConstraintViolation {
+                property: "prop".to_string(),
+                message: "must not be null".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_apply_default() {
+        let c = constraint(
+            PropertySchema::new("prop", DataType::Double, None)
+                .non_null()
+                .with_default(PropertyValue::Integer(5)),
+        );
+        assert_eq!(c.apply(None), Ok(Some(IsarValue::Real(5.0))));
+        assert_eq!(
+            c.apply(Some(IsarValue::Real(1.5))),
+            Ok(Some(IsarValue::Real(1.5)))
+        );
+    }
+
+    #[test]
+    fn test_apply_range() {
+        let c = constraint(
+            PropertySchema::new("prop", DataType::Long, None).with_check(CheckSchema::Range {
+                min: Some(PropertyValue::Integer(0)),
+                max: Some(PropertyValue::Integer(10)),
+            }),
+        );
+        assert!(c.apply(None).is_ok());
+        assert!(c.apply(Some(IsarValue::Integer(0))).is_ok());
+        assert!(c.apply(Some(IsarValue::Integer(10))).is_ok());
+        assert!(c.apply(Some(IsarValue::Integer(-1))).is_err());
+        assert!(c.apply(Some(IsarValue::Integer(11))).is_err());
+    }
+
+    #[test]
+    fn test_apply_length() {
+        let c = constraint(
+            PropertySchema::new("prop", DataType::String, None).with_check(CheckSchema::Length {
+                min: Some(2),
+                max: Some(3),
+            }),
+        );
+        assert!(c.apply(Some(IsarValue::String("a".to_string()))).is_err());
+        assert!(c.apply(Some(IsarValue::String("äöü".to_string()))).is_ok());
+        assert!(c
+            .apply(Some(IsarValue::String("abcd".to_string())))
+            .is_err());
+    }
+
+    #[test]
+    fn test_apply_regex() {
+        let c = constraint(
+            PropertySchema::new("prop", DataType::String, None).with_check(CheckSchema::Regex {
+                pattern: "^[a-z]+@[a-z]+$".to_string(),
+            }),
+        );
+        assert!(c.apply(Some(IsarValue::String("a@b".to_string()))).is_ok());
+        assert_eq!(
+            c.apply(Some(IsarValue::String("a".to_string()))),
+            Err(IsarError::ConstraintViolation {
+                property: "prop".to_string(),
+                message: "must match '^[a-z]+@[a-z]+$'".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_apply_dimensions() {
+        let property = PropertySchema::new("prop", DataType::FloatList, None);
+        let c = PropertyConstraint::new(1, &property, Some(2)).unwrap();
+        assert!(c.apply(None).is_ok());
+        assert!(c.apply(Some(IsarValue::Integer(2))).is_ok());
+        assert_eq!(
+            c.apply(Some(IsarValue::Integer(3))),
+            Err(IsarError::ConstraintViolation {
+                property: "prop".to_string(),
+                message: "must have 2 elements".to_string()
+            })
+        );
+    }
+}
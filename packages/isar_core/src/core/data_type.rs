@@ -12,6 +12,12 @@ pub enum DataType {
     String,
     Object,
     Json,
+    Decimal,
+    Uuid,
+    DateTimeOffset,
+    /// Binary data that is stored outside of the object so large payloads don't have to be
+    /// copied when other properties change.
+    Blob,
     BoolList,
     ByteList,
     IntList,
@@ -21,6 +27,14 @@ pub enum DataType {
     DoubleList,
     StringList,
     ObjectList,
+    BoolMap,
+    ByteMap,
+    IntMap,
+    FloatMap,
+    LongMap,
+    DoubleMap,
+    StringMap,
+    ObjectMap,
 }
 
 impl DataType {
@@ -28,6 +42,40 @@ impl DataType {
         self.element_type().is_some()
     }
 
+    pub const fn is_map(&self) -> bool {
+        self.value_type().is_some()
+    }
+
+    // properties that can have a default value
+    pub const fn is_scalar(&self) -> bool {
+        matches!(
+            self,
+            DataType::Bool
+                | DataType::Byte
+                | DataType::Int
+                | DataType::Float
+                | DataType::Long
+                | DataType::Double
+                | DataType::String
+                | DataType::Json
+        )
+    }
+
+    /// Decimal, UUID and date time values are stored as bytes that preserve their order.
+    pub const fn is_fixed_bytes(&self) -> bool {
+        matches!(
+            self,
+            DataType::Decimal | DataType::Uuid | DataType::DateTimeOffset
+        )
+    }
+
+    pub const fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            DataType::Byte | DataType::Int | DataType::Float | DataType::Long | DataType::Double
+        )
+    }
+
     pub const fn element_type(&self) -> Option<DataType> {
         match self {
             DataType::BoolList => Some(DataType::Bool),
@@ -41,6 +89,21 @@ impl DataType {
             _ => None,
         }
     }
+
+    /// The type of the values of a map with string keys.
+    pub const fn value_type(&self) -> Option<DataType> {
+        match self {
+            DataType::BoolMap => Some(DataType::Bool),
+            DataType::ByteMap => Some(DataType::Byte),
+            DataType::IntMap => Some(DataType::Int),
+            DataType::FloatMap => Some(DataType::Float),
+            DataType::LongMap => Some(DataType::Long),
+            DataType::DoubleMap => Some(DataType::Double),
+            DataType::StringMap => Some(DataType::String),
+            DataType::ObjectMap => Some(DataType::Object),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -58,6 +121,10 @@ mod tests {
         assert!(!DataType::String.is_list());
         assert!(!DataType::Object.is_list());
         assert!(!DataType::Json.is_list());
+        assert!(!DataType::Decimal.is_list());
+        assert!(!DataType::Uuid.is_list());
+        assert!(!DataType::DateTimeOffset.is_list());
+        assert!(!DataType::Blob.is_list());
         assert!(DataType::BoolList.is_list());
         assert!(DataType::ByteList.is_list());
         assert!(DataType::IntList.is_list());
@@ -66,6 +133,40 @@ mod tests {
         assert!(DataType::DoubleList.is_list());
         assert!(DataType::StringList.is_list());
         assert!(DataType::ObjectList.is_list());
+        assert!(!DataType::StringMap.is_list());
+    }
+
+    #[test]
+    fn test_is_map() {
+        assert!(!DataType::String.is_map());
+        assert!(!DataType::Json.is_map());
+        assert!(!DataType::StringList.is_map());
+        assert!(DataType::BoolMap.is_map());
+        assert!(DataType::StringMap.is_map());
+        assert!(DataType::ObjectMap.is_map());
+    }
+
+    #[test]
+    fn test_is_scalar_and_numeric() {
+        assert!(DataType::Bool.is_scalar());
+        assert!(!DataType::Bool.is_numeric());
+        assert!(DataType::Byte.is_numeric());
+        assert!(DataType::Float.is_numeric());
+        assert!(DataType::String.is_scalar());
+        assert!(!DataType::String.is_numeric());
+        assert!(DataType::Json.is_scalar());
+        assert!(!DataType::Object.is_scalar());
+        assert!(!DataType::LongList.is_scalar());
+        assert!(!DataType::LongList.is_numeric());
+    }
+
+    #[test]
+    fn test_is_fixed_bytes() {
+        assert!(DataType::Decimal.is_fixed_bytes());
+        assert!(DataType::Uuid.is_fixed_bytes());
+        assert!(DataType::DateTimeOffset.is_fixed_bytes());
+        assert!(!DataType::String.is_fixed_bytes());
+        assert!(!DataType::ByteList.is_fixed_bytes());
     }
 
     #[test]
@@ -88,4 +189,18 @@ mod tests {
         assert_eq!(DataType::StringList.element_type(), Some(DataType::String));
         assert_eq!(DataType::ObjectList.element_type(), Some(DataType::Object));
     }
+
+    #[test]
+    fn test_value_type() {
+        assert_eq!(DataType::String.value_type(), None);
+        assert_eq!(DataType::IntList.value_type(), None);
+        assert_eq!(DataType::BoolMap.value_type(), Some(DataType::Bool));
+        assert_eq!(DataType::ByteMap.value_type(), Some(DataType::Byte));
+        assert_eq!(DataType::IntMap.value_type(), Some(DataType::Int));
+        assert_eq!(DataType::FloatMap.value_type(), Some(DataType::Float));
+        assert_eq!(DataType::LongMap.value_type(), Some(DataType::Long));
+        assert_eq!(DataType::DoubleMap.value_type(), Some(DataType::Double));
+        assert_eq!(DataType::StringMap.value_type(), Some(DataType::String));
+        assert_eq!(DataType::ObjectMap.value_type(), Some(DataType::Object));
+    }
 }
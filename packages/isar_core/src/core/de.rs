@@ -1,14 +1,21 @@
+use super::cursor::IsarCursor;
 use super::data_type::DataType;
-use super::error::IsarError;
+use super::error::{self, IsarError};
 use super::insert::IsarInsert;
-use super::instance::IsarInstance;
+use super::instance::{IsarInstance, JsonFormat};
+use super::ser::json_error;
+use super::value::{DateTimeOffset, Decimal, Uuid};
 use super::writer::IsarWriter;
-use serde::de::{Error, IgnoredAny, MapAccess, Visitor};
-use serde::Deserializer;
+use base64::{engine::general_purpose, Engine as _};
+use serde::de::{Error, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::value::RawValue;
+use serde_json::{Map, Value};
 use std::borrow::Cow;
 use std::cell::Cell;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt::Formatter;
+use std::io::BufRead;
 
 const BULK_IMPORT_COUNT: usize = 100;
 
@@ -37,14 +44,12 @@ impl<'a, I: IsarInstance, F: Fn(&str) -> i64> IsarJsonImportVisitor<'a, I, F> {
 
         for object in objects {
             let mut deser = serde_json::Deserializer::from_str(object);
-            let visitor = IsarObjectVisitor::new(insert, &self.str_to_id);
-            let (id, writer) =
-                deser
-                    .deserialize_map(visitor)
-                    .map_err(|e| IsarError::JsonError {
-                        message: e.to_string(),
-                    })?;
-            insert = writer;
+            let visitor = IsarObjectVisitor::new(&mut insert, &self.str_to_id);
+            let id = deser
+                .deserialize_map(visitor)
+                .map_err(|e| IsarError::JsonError {
+                    message: e.to_string(),
+                })?;
 
             if let Some(id) = id {
                 insert.save(id)?;
@@ -93,13 +98,13 @@ impl<'a, 'de, I: IsarInstance, F: Fn(&str) -> i64> Visitor<'de>
     }
 }
 
-struct IsarObjectVisitor<'a, W: IsarWriter<'a>, F: Fn(&str) -> i64> {
-    writer: W,
+struct IsarObjectVisitor<'a, 'b, W: IsarWriter<'a>, F: Fn(&str) -> i64> {
+    writer: &'b mut W,
     str_to_id: &'a F,
 }
 
-impl<'a, W: IsarWriter<'a>, F: Fn(&str) -> i64> IsarObjectVisitor<'a, W, F> {
-    pub fn new(writer: W, str_to_id: &'a F) -> Self {
+impl<'a, 'b, W: IsarWriter<'a>, F: Fn(&str) -> i64> IsarObjectVisitor<'a, 'b, W, F> {
+    pub fn new(writer: &'b mut W, str_to_id: &'a F) -> Self {
         IsarObjectVisitor { writer, str_to_id }
     }
 }
@@ -123,6 +128,26 @@ macro_rules! write_list {
     }};
 }
 
+#[macro_export]
+macro_rules! write_map {
+    ($writer:expr, $map:ident, $index:expr, $type:ty, $write:ident) => {{
+        let map = $map.next_value::<Option<BTreeMap<String, Option<$type>>>>()?;
+        if let Some(map) = map {
+            let keys = map.keys().map(|key| key.as_str()).collect::<Vec<_>>();
+            if let Some(mut map_writer) = $writer.begin_map($index as u32, &keys) {
+                for (i, value) in map.values().enumerate() {
+                    if let Some(value) = value {
+                        map_writer.$write(i as u32, *value);
+                    } else {
+                        map_writer.write_null(i as u32);
+                    }
+                }
+                $writer.end_list(map_writer);
+            }
+        }
+    }};
+}
+
 #[macro_export]
 macro_rules! write_scalar {
     ($writer:expr, $map:ident, $index:expr, $type:ty, $write:ident) => {{
@@ -135,16 +160,16 @@ macro_rules! write_scalar {
     }};
 }
 
-impl<'a, 'de: 'a, W: IsarWriter<'a>, F: Fn(&str) -> i64> Visitor<'de>
-    for IsarObjectVisitor<'a, W, F>
+impl<'a, 'b, 'de, W: IsarWriter<'a>, F: Fn(&str) -> i64> Visitor<'de>
+    for IsarObjectVisitor<'a, 'b, W, F>
 {
-    type Value = (Option<i64>, W);
+    type Value = Option<i64>;
 
     fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
         write!(formatter, "a map containing object properties")
     }
 
-    fn visit_map<A>(mut self, mut map: A) -> Result<Self::Value, A::Error>
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
     where
         A: MapAccess<'de>,
     {
@@ -181,18 +206,40 @@ impl<'a, 'de: 'a, W: IsarWriter<'a>, F: Fn(&str) -> i64> Visitor<'de>
                         }
                     }
                     DataType::Json => {
-                        let value = map.next_value::<Option<&RawValue>>()?;
+                        let value = map.next_value::<Option<Box<RawValue>>>()?;
                         if let Some(value) = value {
                             self.writer.write_string(index as u32, value.get());
                         }
                     }
+                    DataType::Decimal => {
+                        write_scalar!(self.writer, map, index, Decimal, write_decimal)
+                    }
+                    DataType::Uuid => write_scalar!(self.writer, map, index, Uuid, write_uuid),
+                    DataType::DateTimeOffset => write_scalar!(
+                        self.writer,
+                        map,
+                        index,
+                        DateTimeOffset,
+                        write_date_time_offset
+                    ),
+                    DataType::Blob => {
+                        let value = map.next_value::<Option<Cow<'_, str>>>()?;
+                        if let Some(value) = value {
+                            let bytes = general_purpose::STANDARD
+                                .decode(value.as_bytes())
+                                .map_err(Error::custom)?;
+                            self.writer.write_blob(index as u32, &bytes);
+                        } else {
+                            self.writer.write_null(index as u32);
+                        }
+                    }
                     DataType::Object => {
-                        let value = map.next_value::<Option<&RawValue>>()?;
+                        let value = map.next_value::<Option<Box<RawValue>>>()?;
                         if let Some(value) = value {
-                            if let Some(object_writer) = self.writer.begin_object(index as u32) {
+                            if let Some(mut object) = self.writer.begin_object(index as u32) {
                                 let mut deser = serde_json::Deserializer::from_str(value.get());
-                                let visitor = IsarObjectVisitor::new(object_writer, self.str_to_id);
-                                let (_, object) = deser
+                                let visitor = IsarObjectVisitor::new(&mut object, self.str_to_id);
+                                deser
                                     .deserialize_map(visitor)
                                     .map_err(|e| Error::custom(e))?;
                                 self.writer.end_object(object);
@@ -223,19 +270,20 @@ impl<'a, 'de: 'a, W: IsarWriter<'a>, F: Fn(&str) -> i64> Visitor<'de>
                         }
                     }
                     DataType::ObjectList => {
-                        let list = map.next_value::<Option<Vec<Option<&RawValue>>>>()?;
+                        let list = map.next_value::<Option<Vec<Option<Box<RawValue>>>>>()?;
                         if let Some(list) = list {
                             if let Some(mut list_writer) =
                                 self.writer.begin_list(index as u32, list.len() as u32)
                             {
                                 for (i, value) in list.iter().enumerate() {
                                     if let Some(value) = value {
-                                        let object = list_writer.begin_object(i as u32).unwrap();
+                                        let mut object =
+                                            list_writer.begin_object(i as u32).unwrap();
                                         let mut deser =
                                             serde_json::Deserializer::from_str(value.get());
                                         let visitor =
-                                            IsarObjectVisitor::new(object, self.str_to_id);
-                                        let (_, object) = deser
+                                            IsarObjectVisitor::new(&mut object, self.str_to_id);
+                                        deser
                                             .deserialize_map(visitor)
                                             .map_err(|e| Error::custom(e))?;
                                         list_writer.end_object(object);
@@ -247,9 +295,56 @@ impl<'a, 'de: 'a, W: IsarWriter<'a>, F: Fn(&str) -> i64> Visitor<'de>
                             }
                         }
                     }
+                    DataType::BoolMap => write_map!(self.writer, map, index, bool, write_bool),
+                    DataType::ByteMap => write_map!(self.writer, map, index, u8, write_byte),
+                    DataType::IntMap => write_map!(self.writer, map, index, i32, write_int),
+                    DataType::FloatMap => write_map!(self.writer, map, index, f32, write_float),
+                    DataType::LongMap => write_map!(self.writer, map, index, i64, write_long),
+                    DataType::DoubleMap => write_map!(self.writer, map, index, f64, write_double),
+                    DataType::StringMap => {
+                        let value = map.next_value::<Option<BTreeMap<String, Option<String>>>>()?;
+                        if let Some(value) = value {
+                            let keys = value.keys().map(|key| key.as_str()).collect::<Vec<_>>();
+                            if let Some(mut map_writer) = self.writer.begin_map(index as u32, &keys)
+                            {
+                                for (i, value) in value.values().enumerate() {
+                                    if let Some(value) = value {
+                                        map_writer.write_string(i as u32, value);
+                                    } else {
+                                        map_writer.write_null(i as u32);
+                                    }
+                                }
+                                self.writer.end_list(map_writer);
+                            }
+                        }
+                    }
+                    DataType::ObjectMap => {
+                        let value =
+                            map.next_value::<Option<BTreeMap<String, Option<Box<RawValue>>>>>()?;
+                        if let Some(value) = value {
+                            let keys = value.keys().map(|key| key.as_str()).collect::<Vec<_>>();
+                            if let Some(mut map_writer) = self.writer.begin_map(index as u32, &keys)
+                            {
+                                for (i, value) in value.values().enumerate() {
+                                    if let Some(value) = value {
+                                        let mut object = map_writer.begin_object(i as u32).unwrap();
+                                        let mut deser =
+                                            serde_json::Deserializer::from_str(value.get());
+                                        let visitor =
+                                            IsarObjectVisitor::new(&mut object, self.str_to_id);
+                                        deser.deserialize_map(visitor).map_err(Error::custom)?;
+                                        map_writer.end_object(object);
+                                    } else {
+                                        map_writer.write_null(i as u32);
+                                    }
+                                }
+                                self.writer.end_list(map_writer);
+                            }
+                        }
+                    }
                 }
             } else if self.writer.id_name() == Some(&key) {
-                id = Some(map.next_value::<i64>()?);
+                id = map.next_value::<Option<i64>>()?;
             } else {
                 map.next_value::<IgnoredAny>()?;
             }
@@ -263,6 +358,330 @@ impl<'a, 'de: 'a, W: IsarWriter<'a>, F: Fn(&str) -> i64> Visitor<'de>
             self.writer.write_null((property_index + 1) as u32);
         }
 
-        Ok((id, self.writer))
+        Ok(id)
+    }
+}
+
+/// Whether imported objects may replace existing objects with the same id.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ImportMode {
+    Upsert,
+    /// Objects with an id that already exists are rejected.
+    InsertOnly,
+}
+
+/// What happens to objects that cannot be imported.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ErrorPolicy {
+    /// Abort the import.
+    Fail,
+    /// Skip the object and add it to the report. Objects that violate a constraint still abort
+    /// the import.
+    Skip,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct ImportOptions {
+    pub format: JsonFormat,
+    pub mode: ImportMode,
+    pub on_error: ErrorPolicy,
+    pub batch_size: u32,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        ImportOptions {
+            format: JsonFormat::Array,
+            mode: ImportMode::Upsert,
+            on_error: ErrorPolicy::Fail,
+            batch_size: BULK_IMPORT_COUNT as u32,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Eq, PartialEq, Default, Debug)]
+pub struct ImportReport {
+    pub imported: u32,
+    pub rejected: Vec<RejectedObject>,
+}
+
+#[derive(Serialize, Clone, Eq, PartialEq, Debug)]
+pub struct RejectedObject {
+    /// The line of the object or its position in the array, starting at 1.
+    pub line: u32,
+    pub message: String,
+}
+
+pub(super) struct JsonStreamImporter<'a, I: IsarInstance, F: Fn(&str) -> i64> {
+    instance: &'a I,
+    txn: Option<I::Txn>,
+    collection_index: u16,
+    options: ImportOptions,
+    str_to_id: F,
+    id_name: &'a str,
+    string_id: bool,
+    pending: Vec<(i64, Map<String, Value>)>,
+    pending_ids: HashSet<i64>,
+    report: ImportReport,
+}
+
+impl<'a, I: IsarInstance, F: Fn(&str) -> i64> JsonStreamImporter<'a, I, F> {
+    pub(super) fn new(
+        instance: &'a I,
+        txn: I::Txn,
+        collection_index: u16,
+        options: ImportOptions,
+        str_to_id: F,
+    ) -> error::Result<Self> {
+        let id = instance.get_id_name(collection_index).and_then(|id_name| {
+            let id_name = id_name.ok_or(IsarError::IllegalArgument {})?;
+            let string_id = instance
+                .get_properties(collection_index)?
+                .any(|(name, data_type, _)| name == id_name && data_type == DataType::String);
+            Ok((id_name, string_id))
+        });
+        let (id_name, string_id) = match id {
+            Ok(id) => id,
+            Err(err) => {
+                instance.abort_txn(txn);
+                return Err(err);
+            }
+        };
+        Ok(JsonStreamImporter {
+            instance,
+            txn: Some(txn),
+            collection_index,
+            options,
+            str_to_id,
+            id_name,
+            string_id,
+            pending: Vec::new(),
+            pending_ids: HashSet::new(),
+            report: ImportReport::default(),
+        })
+    }
+
+    pub(super) fn read(&mut self, reader: impl BufRead) -> error::Result<()> {
+        match self.options.format {
+            JsonFormat::Ndjson => {
+                for (index, line) in reader.lines().enumerate() {
+                    let line = line.map_err(json_error)?;
+                    if !line.trim().is_empty() {
+                        self.push(index as u32 + 1, &line)?;
+                    }
+                }
+            }
+            JsonFormat::Array => {
+                let mut deser = serde_json::Deserializer::from_reader(reader);
+                let mut error = None;
+                let result = deser.deserialize_seq(JsonStreamVisitor {
+                    importer: self,
+                    error: &mut error,
+                });
+                if let Some(error) = error {
+                    return Err(error);
+                }
+                result.and_then(|_| deser.end()).map_err(json_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Aborts the transaction unless the backend lost it while starting or finishing a batch.
+    pub(super) fn abort(self) {
+        if let Some(txn) = self.txn {
+            self.instance.abort_txn(txn);
+        }
+    }
+
+    pub(super) fn finish(self) -> (I::Txn, ImportReport) {
+        (self.txn.unwrap(), self.report)
+    }
+
+    fn push(&mut self, line: u32, object: &str) -> error::Result<()> {
+        let result = match self.validate(object) {
+            Ok((id, _)) if self.options.mode == ImportMode::InsertOnly && self.exists(id)? => {
+                Err(format!("An object with id {} already exists", id))
+            }
+            result => result,
+        };
+
+        match result {
+            Ok((id, object)) => {
+                // objects without id must not get the id of a pending object
+                self.instance
+                    .update_auto_increment(self.collection_index, id);
+                self.pending_ids.insert(id);
+                self.pending.push((id, object));
+                if self.pending.len() >= self.options.batch_size.max(1) as usize {
+                    self.flush()?;
+                }
+            }
+            Err(message) => match self.options.on_error {
+                ErrorPolicy::Fail => {
+                    return Err(IsarError::JsonError {
+                        message: format!("Line {}: {}", line, message),
+                    })
+                }
+                ErrorPolicy::Skip => self.report.rejected.push(RejectedObject { line, message }),
+            },
+        }
+        Ok(())
+    }
+
+    /// Parses the object and checks it against the schema so that writing it cannot fail.
+    fn validate(&self, object: &str) -> Result<(i64, Map<String, Value>), String> {
+        let object =
+            serde_json::from_str::<Map<String, Value>>(object).map_err(|e| e.to_string())?;
+        validate_object(self.instance, self.collection_index, &object)?;
+
+        let id = match object.get(self.id_name) {
+            Some(Value::String(id)) if self.string_id => (self.str_to_id)(id),
+            _ if self.string_id => return Err("Missing id property".to_string()),
+            None | Some(Value::Null) => self.instance.auto_increment(self.collection_index),
+            Some(id) => id.as_i64().ok_or_else(|| format!("Invalid id {}", id))?,
+        };
+        Ok((id, object))
+    }
+
+    fn exists(&self, id: i64) -> error::Result<bool> {
+        if self.pending_ids.contains(&id) {
+            return Ok(true);
+        }
+        let txn = self.txn.as_ref().unwrap();
+        let cursor = self.instance.cursor(txn, self.collection_index)?;
+        Ok(contains_id(cursor, id))
+    }
+
+    pub(super) fn flush(&mut self) -> error::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let pending = std::mem::take(&mut self.pending);
+        let count = pending.len() as u32;
+        self.pending_ids.clear();
+
+        let txn = self.txn.take().unwrap();
+        let mut insert = self.instance.insert(txn, self.collection_index, count)?;
+        for (id, object) in pending {
+            let visitor = IsarObjectVisitor::new(&mut insert, &self.str_to_id);
+            let result = Value::Object(object)
+                .deserialize_map(visitor)
+                .map_err(json_error)
+                .and_then(|_| insert.save(id));
+            if let Err(err) = result {
+                // the batch cannot be completed so the transaction has to be aborted
+                self.txn = Some(insert.cancel());
+                return Err(err);
+            }
+        }
+        self.txn = Some(insert.finish()?);
+
+        self.report.imported += count;
+        Ok(())
+    }
+}
+
+struct JsonStreamVisitor<'a, 'b, I: IsarInstance, F: Fn(&str) -> i64> {
+    importer: &'b mut JsonStreamImporter<'a, I, F>,
+    error: &'b mut Option<IsarError>,
+}
+
+impl<'a, 'b, 'de, I: IsarInstance, F: Fn(&str) -> i64> Visitor<'de>
+    for JsonStreamVisitor<'a, 'b, I, F>
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(formatter, "a list of objects")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut line = 0;
+        while let Some(object) = seq.next_element::<Box<RawValue>>()? {
+            line += 1;
+            if let Err(err) = self.importer.push(line, object.get()) {
+                let message = err.to_string();
+                *self.error = Some(err);
+                return Err(Error::custom(message));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn contains_id(mut cursor: impl IsarCursor, id: i64) -> bool {
+    cursor.next(id).is_some()
+}
+
+fn validate_object<I: IsarInstance>(
+    instance: &I,
+    collection_index: u16,
+    object: &Map<String, Value>,
+) -> Result<(), String> {
+    let properties = instance
+        .get_properties(collection_index)
+        .map_err(|e| e.to_string())?;
+    for (name, data_type, embedded_collection_index) in properties {
+        if let Some(value) = object.get(name) {
+            if !is_valid_value(instance, data_type, embedded_collection_index, value) {
+                return Err(format!("Invalid value for property {}", name));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_valid_value<I: IsarInstance>(
+    instance: &I,
+    data_type: DataType,
+    embedded_collection_index: Option<u16>,
+    value: &Value,
+) -> bool {
+    if value.is_null() {
+        return true;
+    }
+    match data_type {
+        DataType::Bool => value.is_boolean(),
+        DataType::Byte => value.as_u64().is_some_and(|v| v <= u8::MAX as u64),
+        DataType::Int => value.as_i64().is_some_and(|v| i32::try_from(v).is_ok()),
+        DataType::Long => value.as_i64().is_some(),
+        DataType::Float | DataType::Double => value.is_number(),
+        DataType::String => value.is_string(),
+        DataType::Json => true,
+        DataType::Decimal => Decimal::deserialize(value).is_ok(),
+        DataType::Uuid => value.as_str().is_some_and(|v| v.parse::<Uuid>().is_ok()),
+        DataType::DateTimeOffset => value
+            .as_str()
+            .is_some_and(|v| v.parse::<DateTimeOffset>().is_ok()),
+        DataType::Blob => value
+            .as_str()
+            .is_some_and(|v| general_purpose::STANDARD.decode(v).is_ok()),
+        DataType::Object => match (value, embedded_collection_index) {
+            (Value::Object(object), Some(index)) => {
+                validate_object(instance, index, object).is_ok()
+            }
+            _ => false,
+        },
+        _ if data_type.is_map() => {
+            let value_type = data_type.value_type().unwrap();
+            value.as_object().is_some_and(|map| {
+                map.values().all(|value| {
+                    is_valid_value(instance, value_type, embedded_collection_index, value)
+                })
+            })
+        }
+        _ => {
+            let element_type = data_type.element_type().unwrap();
+            value.as_array().is_some_and(|list| {
+                list.iter().all(|value| {
+                    is_valid_value(instance, element_type, embedded_collection_index, value)
+                })
+            })
+        }
     }
 }
@@ -14,9 +14,7 @@ pub enum IsarError {
     WriteTxnRequired {},
 
     #[snafu(display("Schema error: {}", message))]
-    SchemaError {
-        message: String,
-    },
+    SchemaError { message: String },
 
     #[snafu(display("Isar version of the file is too new or too old to be used."))]
     VersionError {},
@@ -54,9 +52,7 @@ pub enum IsarError {
     IllegalArgument {},
 
     #[snafu(display("Json Error: {}", message))]
-    JsonError {
-        message: String,
-    },
+    JsonError { message: String },
 
     #[snafu(display("Encryption Error. Please make sure to use the correct encryption key."))]
     EncryptionError {},
@@ -64,11 +60,18 @@ pub enum IsarError {
     #[snafu(display("The database is full."))]
     DbFull {},
 
+    #[snafu(display("The database is locked by another connection."))]
+    Busy {},
+
+    #[snafu(display("The database is corrupted."))]
     DbCorrupted {},
 
     #[snafu(display("DbError ({}): {}", code, message))]
-    DbError {
-        code: i32,
-        message: String,
-    },
+    DbError { code: i32, message: String },
+
+    #[snafu(display("Constraint of property {} violated: {}", property, message))]
+    ConstraintViolation { property: String, message: String },
+
+    #[snafu(display("The object does not match the expected version or condition."))]
+    VersionMismatch {},
 }
@@ -1,3 +1,5 @@
+use crate::core::error::{IsarError, Result};
+use crate::core::regex::Regex;
 use crate::core::value::IsarValue;
 
 #[derive(PartialEq, Clone, Debug)]
@@ -5,11 +7,58 @@ pub enum Filter {
     Condition(FilterCondition),
     Json(JsonCondition),
     Nested(FilterNested),
+    MapEntry(FilterMapEntry),
+    Link(FilterLink),
     And(Vec<Filter>),
     Or(Vec<Filter>),
     Not(Box<Filter>),
 }
 
+impl Filter {
+    #[cfg(feature = "sqlite")]
+    pub(crate) fn has_links(&self) -> bool {
+        match self {
+            Filter::Link(_) => true,
+            Filter::And(filters) | Filter::Or(filters) => filters.iter().any(|f| f.has_links()),
+            Filter::Not(filter) => filter.has_links(),
+            _ => false,
+        }
+    }
+
+    /// Fails with `IllegalArgument` if a regex condition has an invalid pattern.
+    pub(crate) fn check_patterns(&self) -> Result<()> {
+        let valid = match self {
+            Filter::Condition(condition) => {
+                condition.condition_type != ConditionType::StringRegex
+                    || match condition.values.first() {
+                        Some(Some(IsarValue::String(pattern))) => {
+                            Regex::new(pattern, !condition.case_sensitive).is_some()
+                        }
+                        _ => true,
+                    }
+            }
+            Filter::Json(condition) | Filter::MapEntry(FilterMapEntry { condition, .. }) => {
+                condition.condition_type != ConditionType::StringRegex
+                    || condition.regex.is_some()
+                    || !matches!(condition.values.first(), Some(Some(IsarValue::String(_))))
+            }
+            Filter::Nested(nested) => return nested.filter.check_patterns(),
+            Filter::Link(link) => {
+                return link.filter.as_ref().map_or(Ok(()), |f| f.check_patterns());
+            }
+            Filter::And(filters) | Filter::Or(filters) => {
+                return filters.iter().try_for_each(|f| f.check_patterns());
+            }
+            Filter::Not(filter) => return filter.check_patterns(),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(IsarError::IllegalArgument {})
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum ConditionType {
     IsNull,
@@ -23,6 +72,12 @@ pub enum ConditionType {
     StringEndsWith,
     StringContains,
     StringMatches,
+    // values: pattern
+    StringRegex,
+    // values: min latitude, min longitude, max latitude, max longitude
+    GeoWithinBox,
+    // values: latitude, longitude, radius in meters
+    GeoWithinRadius,
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -57,6 +112,8 @@ pub struct JsonCondition {
     pub values: Vec<Option<IsarValue>>,
     // if false, values are all lowercase
     pub case_sensitive: bool,
+    // compiled pattern of a regex condition
+    pub regex: Option<Regex>,
 }
 
 impl JsonCondition {
@@ -67,6 +124,14 @@ impl JsonCondition {
         values: Vec<Option<IsarValue>>,
         case_sensitive: bool,
     ) -> Self {
+        let regex = if condition_type == ConditionType::StringRegex {
+            match values.first() {
+                Some(Some(IsarValue::String(pattern))) => Regex::new(pattern, !case_sensitive),
+                _ => None,
+            }
+        } else {
+            None
+        };
         let values = if case_sensitive {
             values
         } else {
@@ -89,6 +154,7 @@ impl JsonCondition {
             is_list,
             values,
             case_sensitive,
+            regex,
         }
     }
 }
@@ -107,3 +173,83 @@ impl FilterNested {
         }
     }
 }
+
+/// Matches objects whose map property has a value for `key` that matches the condition. Missing
+/// keys and null values both match `IsNull`, so `Not(IsNull)` checks whether a key exists.
+#[derive(PartialEq, Clone, Debug)]
+pub struct FilterMapEntry {
+    pub property_index: u16,
+    pub key: String,
+    pub condition: JsonCondition,
+}
+
+impl FilterMapEntry {
+    pub fn new(
+        property_index: u16,
+        key: &str,
+        condition_type: ConditionType,
+        values: Vec<Option<IsarValue>>,
+        case_sensitive: bool,
+    ) -> Self {
+        FilterMapEntry {
+            property_index,
+            key: key.to_string(),
+            condition: JsonCondition::new(vec![], condition_type, false, values, case_sensitive),
+        }
+    }
+}
+
+/// Matches objects with at least one linked object that matches the filter. Without a filter,
+/// objects with at least one linked object match.
+#[derive(PartialEq, Clone, Debug)]
+pub struct FilterLink {
+    pub link_index: u16,
+    pub filter: Option<Box<Filter>>,
+}
+
+impl FilterLink {
+    pub fn new(link_index: u16, filter: Option<Filter>) -> Self {
+        FilterLink {
+            link_index,
+            filter: filter.map(Box::new),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regex(pattern: &str) -> FilterCondition {
+        let value = IsarValue::String(pattern.to_string());
+        FilterCondition::new(0, ConditionType::StringRegex, vec![Some(value)], true)
+    }
+
+    #[test]
+    fn test_check_patterns() {
+        assert_eq!(Filter::Condition(regex("^a+$")).check_patterns(), Ok(()));
+        let null = FilterCondition::new(0, ConditionType::StringRegex, vec![None], true);
+        assert_eq!(Filter::Condition(null).check_patterns(), Ok(()));
+
+        let invalid = [
+            Filter::Condition(regex("(a")),
+            Filter::Not(Box::new(Filter::Condition(regex("[a")))),
+            Filter::And(vec![
+                Filter::Condition(regex("a")),
+                Filter::Or(vec![Filter::Condition(regex("a)"))]),
+            ]),
+            Filter::Nested(FilterNested::new(0, Filter::Condition(regex("*a")))),
+            Filter::Link(FilterLink::new(0, Some(Filter::Condition(regex("(a"))))),
+            Filter::MapEntry(FilterMapEntry::new(
+                0,
+                "key",
+                ConditionType::StringRegex,
+                vec![Some(IsarValue::String("(a".to_string()))],
+                false,
+            )),
+        ];
+        for filter in invalid {
+            assert_eq!(filter.check_patterns(), Err(IsarError::IllegalArgument {}));
+        }
+    }
+}
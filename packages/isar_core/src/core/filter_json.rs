@@ -1,5 +1,6 @@
 use super::fast_wild_match::fast_wild_match;
 use super::filter::{ConditionType, JsonCondition};
+use super::regex::Regex;
 use super::value::IsarValue;
 use serde_json::Value;
 
@@ -108,6 +109,15 @@ impl JsonCondition {
                         false
                     }
                 }
+                ConditionType::StringRegex => {
+                    let regex = self.regex.as_ref()?;
+                    if let Value::Array(arr) = value {
+                        arr.iter().any(|value| string_regex(value, regex))
+                    } else {
+                        false
+                    }
+                }
+                ConditionType::GeoWithinBox | ConditionType::GeoWithinRadius => false,
             }
         } else {
             match self.condition_type {
@@ -139,6 +149,8 @@ impl JsonCondition {
                 ConditionType::StringMatches => {
                     string_matches(value, self.values.get(0)?, self.case_sensitive)
                 }
+                ConditionType::StringRegex => string_regex(value, self.regex.as_ref()?),
+                ConditionType::GeoWithinBox | ConditionType::GeoWithinRadius => false,
             }
         };
         Some(result)
@@ -347,3 +359,10 @@ fn string_matches(value: &Value, cond_value: &Option<IsarValue>, case_sensitive:
         _ => false,
     }
 }
+
+fn string_regex(value: &Value, regex: &Regex) -> bool {
+    match value {
+        Value::String(value) => regex.is_match(value),
+        _ => false,
+    }
+}
@@ -0,0 +1,224 @@
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Great-circle distance between two coordinates in meters.
+pub fn distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().min(1.0).asin()
+}
+
+/// Whether a coordinate lies within a bounding box. A box with `min_lon > max_lon` crosses the
+/// antimeridian.
+pub fn within_box(
+    lat: f64,
+    lon: f64,
+    min_lat: f64,
+    min_lon: f64,
+    max_lat: f64,
+    max_lon: f64,
+) -> bool {
+    let within_lon = if min_lon <= max_lon {
+        lon >= min_lon && lon <= max_lon
+    } else {
+        lon >= min_lon || lon <= max_lon
+    };
+    lat >= min_lat && lat <= max_lat && within_lon
+}
+
+/// Bounding box `(min_lat, min_lon, max_lat, max_lon)` containing every coordinate within
+/// `radius` meters of the center.
+pub fn radius_bounds(lat: f64, lon: f64, radius: f64) -> (f64, f64, f64, f64) {
+    let d_lat = (radius / EARTH_RADIUS).to_degrees();
+    let min_lat = lat - d_lat;
+    let max_lat = lat + d_lat;
+    if min_lat <= -90.0 || max_lat >= 90.0 {
+        return (min_lat.max(-90.0), -180.0, max_lat.min(90.0), 180.0);
+    }
+
+    let d_lon = (radius / (EARTH_RADIUS * lat.to_radians().cos()))
+        .min(1.0)
+        .asin()
+        .to_degrees();
+    if d_lon >= 180.0 {
+        return (min_lat, -180.0, max_lat, 180.0);
+    }
+    let mut min_lon = lon - d_lon;
+    let mut max_lon = lon + d_lon;
+    if min_lon < -180.0 {
+        min_lon += 360.0;
+    }
+    if max_lon > 180.0 {
+        max_lon -= 360.0;
+    }
+    (min_lat, min_lon, max_lat, max_lon)
+}
+
+/// Z-order cell of a coordinate. Nearby coordinates share a common prefix.
+pub fn cell_id(lat: f64, lon: f64) -> u64 {
+    let (lat, lon) = grid(lat, lon);
+    spread_bits(lat) << 1 | spread_bits(lon)
+}
+
+// upper bound for the number of ranges a bounding box is split into
+const MAX_CELL_RANGES: usize = 64;
+
+/// Sorted inclusive ranges of cell ids that contain every coordinate within the bounding box.
+/// The ranges may also contain cells outside of the box.
+pub fn cell_ranges(min_lat: f64, min_lon: f64, max_lat: f64, max_lon: f64) -> Vec<(u64, u64)> {
+    if min_lon > max_lon {
+        let mut ranges = cell_ranges(min_lat, min_lon, max_lat, 180.0);
+        ranges.extend(cell_ranges(min_lat, -180.0, max_lat, max_lon));
+        return merge_ranges(ranges);
+    }
+    if min_lat > max_lat {
+        return vec![];
+    }
+
+    let (min_lat, min_lon) = grid(min_lat, min_lon);
+    let (max_lat, max_lon) = grid(max_lat, max_lon);
+    let mut ranges = vec![];
+    // quadtree nodes as (level, lat prefix, lon prefix)
+    let mut nodes = vec![(0u32, 0u32, 0u32)];
+    while !nodes.is_empty() {
+        let refine = ranges.len() + nodes.len() * 4 <= MAX_CELL_RANGES;
+        let mut children = vec![];
+        for (level, lat, lon) in nodes {
+            let shift = 32 - level;
+            let mask = u32::MAX.checked_shr(level).unwrap_or(0);
+            let lat_start = (lat as u64).checked_shl(shift).unwrap_or(0) as u32;
+            let lon_start = (lon as u64).checked_shl(shift).unwrap_or(0) as u32;
+            let (lat_end, lon_end) = (lat_start | mask, lon_start | mask);
+            if lat_end < min_lat || lat_start > max_lat || lon_end < min_lon || lon_start > max_lon
+            {
+                continue;
+            }
+
+            let inside = lat_start >= min_lat
+                && lat_end <= max_lat
+                && lon_start >= min_lon
+                && lon_end <= max_lon;
+            if inside || !refine || level == 32 {
+                let start = spread_bits(lat_start) << 1 | spread_bits(lon_start);
+                let end = spread_bits(lat_end) << 1 | spread_bits(lon_end);
+                ranges.push((start, end));
+            } else {
+                for (lat_bit, lon_bit) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+                    children.push((level + 1, lat << 1 | lat_bit, lon << 1 | lon_bit));
+                }
+            }
+        }
+        nodes = children;
+    }
+    merge_ranges(ranges)
+}
+
+fn merge_ranges(mut ranges: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = vec![];
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+fn grid(lat: f64, lon: f64) -> (u32, u32) {
+    let lat = ((lat.clamp(-90.0, 90.0) + 90.0) / 180.0 * u32::MAX as f64) as u32;
+    let lon = ((lon.clamp(-180.0, 180.0) + 180.0) / 360.0 * u32::MAX as f64) as u32;
+    (lat, lon)
+}
+
+fn spread_bits(value: u32) -> u64 {
+    let mut value = value as u64;
+    value = (value | (value << 16)) & 0x0000_FFFF_0000_FFFF;
+    value = (value | (value << 8)) & 0x00FF_00FF_00FF_00FF;
+    value = (value | (value << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+    value = (value | (value << 2)) & 0x3333_3333_3333_3333;
+    value = (value | (value << 1)) & 0x5555_5555_5555_5555;
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance(52.52, 13.405, 52.52, 13.405), 0.0);
+
+        // Berlin - Paris
+        let d = distance(52.52, 13.405, 48.8566, 2.3522);
+        assert!((d - 877_500.0).abs() < 1_000.0);
+
+        // across the antimeridian
+        let d = distance(0.0, 179.5, 0.0, -179.5);
+        assert!((d - 111_195.0).abs() < 100.0);
+    }
+
+    #[test]
+    fn test_within_box() {
+        assert!(within_box(10.0, 10.0, 0.0, 0.0, 20.0, 20.0));
+        assert!(!within_box(10.0, 30.0, 0.0, 0.0, 20.0, 20.0));
+        assert!(!within_box(-1.0, 10.0, 0.0, 0.0, 20.0, 20.0));
+
+        assert!(within_box(0.0, 179.0, -10.0, 170.0, 10.0, -170.0));
+        assert!(within_box(0.0, -179.0, -10.0, 170.0, 10.0, -170.0));
+        assert!(!within_box(0.0, 0.0, -10.0, 170.0, 10.0, -170.0));
+    }
+
+    #[test]
+    fn test_radius_bounds() {
+        let (min_lat, min_lon, max_lat, max_lon) = radius_bounds(52.52, 13.405, 10_000.0);
+        assert!(min_lat < 52.52 && max_lat > 52.52);
+        assert!(min_lon < 13.405 && max_lon > 13.405);
+        for (lat, lon) in [(min_lat, 13.405), (max_lat, 13.405), (52.52, min_lon)] {
+            assert!(distance(52.52, 13.405, lat, lon) >= 9_999.0);
+        }
+
+        let (_, min_lon, _, max_lon) = radius_bounds(0.0, 179.99, 10_000.0);
+        assert!(min_lon > max_lon);
+
+        assert_eq!(radius_bounds(89.99, 0.0, 10_000.0).1, -180.0);
+    }
+
+    #[test]
+    fn test_cell_id() {
+        assert_eq!(cell_id(-90.0, -180.0), 0);
+        assert_eq!(cell_id(90.0, 180.0), u64::MAX);
+        assert!(cell_id(0.0, 0.0) < cell_id(0.0, 1.0));
+        assert!(cell_id(0.0, 0.0) < cell_id(1.0, 0.0));
+    }
+
+    #[test]
+    fn test_cell_ranges() {
+        let contains = |ranges: &[(u64, u64)], lat: f64, lon: f64| {
+            let cell = cell_id(lat, lon);
+            ranges
+                .iter()
+                .any(|(start, end)| cell >= *start && cell <= *end)
+        };
+
+        assert_eq!(cell_ranges(-90.0, -180.0, 90.0, 180.0), vec![(0, u64::MAX)]);
+        assert_eq!(cell_ranges(10.0, 0.0, 0.0, 10.0), vec![]);
+
+        let ranges = cell_ranges(52.3, 13.0, 52.7, 13.8);
+        assert!(!ranges.is_empty() && ranges.len() <= MAX_CELL_RANGES);
+        assert!(ranges.windows(2).all(|w| w[0].1 < w[1].0));
+        for (lat, lon) in [(52.3, 13.0), (52.52, 13.405), (52.7, 13.8), (52.3, 13.8)] {
+            assert!(contains(&ranges, lat, lon));
+        }
+        for (lat, lon) in [(48.85, 2.35), (52.52, 20.0), (-52.52, 13.405)] {
+            assert!(!contains(&ranges, lat, lon));
+        }
+
+        // crossing the antimeridian
+        let ranges = cell_ranges(-10.0, 170.0, 10.0, -170.0);
+        assert!(contains(&ranges, 0.0, 179.0));
+        assert!(contains(&ranges, 0.0, -179.0));
+        assert!(!contains(&ranges, 0.0, 0.0));
+    }
+}
@@ -4,7 +4,13 @@ use crate::core::error::Result;
 pub trait IsarInsert<'a>: IsarWriter<'a> + Sized {
     type Txn;
 
+    /// Saves the written object. An object that violates a constraint is discarded and the
+    /// next object can be written.
     fn save(&mut self, id: i64) -> Result<()>;
 
     fn finish(self) -> Result<Self::Txn>;
+
+    /// Stops inserting and returns the transaction. Saved objects may or may not have been
+    /// written so the transaction should be aborted.
+    fn cancel(self) -> Self::Txn;
 }
@@ -1,13 +1,19 @@
 use super::cursor::{IsarCursor, IsarQueryCursor};
-use super::de::IsarJsonImportVisitor;
+use super::data_type::DataType;
+use super::de::{ImportOptions, ImportReport, IsarJsonImportVisitor, JsonStreamImporter};
 use super::error::{IsarError, Result};
+use super::filter::{ConditionType, Filter, FilterCondition};
 use super::insert::IsarInsert;
 use super::query_builder::IsarQueryBuilder;
 use super::reader::IsarReader;
 use super::schema::IsarSchema;
+use super::ser::export_json;
+use super::snapshot::{export_snapshot, import_snapshot};
+use super::update::PropertyUpdate;
 use super::value::IsarValue;
 use super::watcher::{WatchHandle, WatcherCallback};
 use serde::Deserializer;
+use std::io::{BufRead, Read, Write};
 
 pub struct CompactCondition {
     pub min_file_size: u32,
@@ -15,6 +21,10 @@ pub struct CompactCondition {
     pub min_ratio: f32,
 }
 
+/// The result of a restore. If the restore fails, the instance is handed back together with the
+/// error unless it could not be reopened.
+pub type RestoreResult<I> = std::result::Result<I, (Option<I>, IsarError)>;
+
 pub trait IsarInstance: Sized {
     type Instance;
 
@@ -50,6 +60,23 @@ pub trait IsarInstance: Sized {
 
     fn get_collections(&self) -> impl Iterator<Item = &str>;
 
+    fn get_id_name(&self, collection_index: u16) -> Result<Option<&str>>;
+
+    /// Returns the schema the collection was opened with.
+    fn get_schema(&self, collection_index: u16) -> Result<&IsarSchema>;
+
+    /// Returns the index of the property that is incremented whenever an object is written.
+    fn get_version_property(&self, collection_index: u16) -> Result<Option<u16>>;
+
+    /// Returns the name, type and embedded collection index of each property.
+    fn get_properties(
+        &self,
+        collection_index: u16,
+    ) -> Result<impl Iterator<Item = (&str, DataType, Option<u16>)>>;
+
+    /// Returns the name, target collection index and whether it is a single link for each link.
+    fn get_links(&self, collection_index: u16) -> Result<impl Iterator<Item = (&str, u16, bool)>>;
+
     fn open_instance(
         instance_id: u32,
         name: &str,
@@ -68,8 +95,17 @@ pub trait IsarInstance: Sized {
 
     fn abort_txn(&self, txn: Self::Txn);
 
+    fn begin_savepoint(&self, txn: &mut Self::Txn) -> Result<()>;
+
+    fn release_savepoint(&self, txn: &mut Self::Txn) -> Result<()>;
+
+    fn rollback_to_savepoint(&self, txn: &mut Self::Txn) -> Result<()>;
+
     fn auto_increment(&self, collection_index: u16) -> i64;
 
+    /// Makes sure `auto_increment` only returns ids greater than `id`.
+    fn update_auto_increment(&self, collection_index: u16, id: i64);
+
     fn cursor<'a>(&'a self, txn: &'a Self::Txn, collection_index: u16) -> Result<Self::Cursor<'a>>;
 
     fn insert(&self, txn: Self::Txn, collection_index: u16, count: u32)
@@ -83,12 +119,131 @@ pub trait IsarInstance: Sized {
         updates: &[(u16, Option<IsarValue>)],
     ) -> Result<bool>;
 
+    /// Applies the updates to the object `id` without reading it. Returns `false` if the object
+    /// does not exist.
+    fn update_properties(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        id: i64,
+        updates: &[PropertyUpdate],
+    ) -> Result<bool>;
+
+    /// Applies the updates to the object `id` only if the stored object matches `condition`.
+    /// Returns `false` if the object does not exist and fails with `VersionMismatch` if it does
+    /// not match.
+    fn update_if(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        id: i64,
+        condition: Filter,
+        updates: &[PropertyUpdate],
+    ) -> Result<bool> {
+        if check_condition(self, txn, collection_index, id, condition)? {
+            self.update_properties(txn, collection_index, id, updates)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Applies the updates to the object `id` only if its stored version is `version`.
+    fn update_if_version(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        id: i64,
+        version: i64,
+        updates: &[PropertyUpdate],
+    ) -> Result<bool> {
+        let condition = version_condition(self, collection_index, version)?;
+        self.update_if(txn, collection_index, id, condition, updates)
+    }
+
     fn delete(&self, txn: &Self::Txn, collection_index: u16, id: i64) -> Result<bool>;
 
+    /// Deletes the object `id` only if the stored object matches `condition`. Returns `false`
+    /// if the object does not exist and fails with `VersionMismatch` if it does not match.
+    fn delete_if(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        id: i64,
+        condition: Filter,
+    ) -> Result<bool> {
+        if check_condition(self, txn, collection_index, id, condition)? {
+            self.delete(txn, collection_index, id)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Deletes the object `id` only if its stored version is `version`.
+    fn delete_if_version(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        id: i64,
+        version: i64,
+    ) -> Result<bool> {
+        let condition = version_condition(self, collection_index, version)?;
+        self.delete_if(txn, collection_index, id, condition)
+    }
+
     fn count(&self, txn: &Self::Txn, collection_index: u16) -> Result<u32>;
 
+    /// Links the object `id` to `target_id`. Single links replace the previous link. Returns
+    /// `false` if one of the objects does not exist.
+    fn link(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+        target_id: i64,
+    ) -> Result<bool>;
+
+    fn unlink(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+        target_id: i64,
+    ) -> Result<bool>;
+
+    /// Removes all links of the object `id` and returns the number of removed links.
+    fn unlink_all(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+    ) -> Result<u32>;
+
+    /// Returns the ids of the objects linked to the object `id`.
+    fn get_linked_ids(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+    ) -> Result<Vec<i64>>;
+
+    fn count_links(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+    ) -> Result<u32>;
+
     fn clear(&self, txn: &Self::Txn, collection_index: u16) -> Result<()>;
 
+    /// Deletes the expired objects of all collections with a TTL and returns the number of
+    /// deleted objects.
+    fn purge_expired(&self, txn: &Self::Txn) -> Result<u32>;
+
     fn get_size(
         &self,
         txn: &Self::Txn,
@@ -123,6 +278,15 @@ pub trait IsarInstance: Sized {
         updates: &[(u16, Option<IsarValue>)],
     ) -> Result<u32>;
 
+    fn query_update_properties(
+        &self,
+        txn: &Self::Txn,
+        query: &Self::Query,
+        offset: Option<u32>,
+        limit: Option<u32>,
+        updates: &[PropertyUpdate],
+    ) -> Result<u32>;
+
     fn query_delete(
         &self,
         txn: &Self::Txn,
@@ -151,6 +315,65 @@ pub trait IsarInstance: Sized {
         Ok((txn, count))
     }
 
+    /// Streams objects from `reader` into the collection. Objects are validated before they are
+    /// written so that rejected objects never leave partial writes behind. The transaction is
+    /// aborted if the import fails.
+    fn import_json_stream(
+        &self,
+        txn: Self::Txn,
+        collection_index: u16,
+        reader: impl BufRead,
+        options: ImportOptions,
+        str_to_id: impl Fn(&str) -> i64,
+    ) -> Result<(Self::Txn, ImportReport)> {
+        let mut importer =
+            JsonStreamImporter::new(self, txn, collection_index, options, str_to_id)?;
+        if let Err(err) = importer.read(reader).and_then(|_| importer.flush()) {
+            importer.abort();
+            return Err(err);
+        }
+        Ok(importer.finish())
+    }
+
+    /// Streams all objects matched by the query to the writer and returns the number of exported
+    /// objects.
+    fn export_json(
+        &self,
+        txn: &Self::Txn,
+        query: &Self::Query,
+        format: JsonFormat,
+        writer: impl Write,
+    ) -> Result<u32> {
+        let cursor = self.query_cursor(txn, query, None, None)?;
+        export_json(cursor, format, writer)
+    }
+
+    /// Streams all objects of the collection to the writer and returns the number of exported
+    /// objects.
+    fn export_collection_json(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        format: JsonFormat,
+        writer: impl Write,
+    ) -> Result<u32> {
+        let query = self.query(collection_index)?.build()?;
+        self.export_json(txn, &query, format, writer)
+    }
+
+    /// Writes the schemas, all objects and their links to `writer` in a compact binary format
+    /// that can be imported by both backends. Returns the number of exported objects.
+    fn export_snapshot(&self, txn: &Self::Txn, writer: impl Write) -> Result<u32> {
+        export_snapshot(self, txn, writer)
+    }
+
+    /// Inserts all objects and links of a snapshot. Collections, properties and links are matched
+    /// by name and properties that are missing or have a different type are skipped. The txn is
+    /// aborted if the import fails.
+    fn import_snapshot(&self, txn: Self::Txn, reader: impl Read) -> Result<(Self::Txn, u32)> {
+        import_snapshot(self, txn, reader)
+    }
+
     fn watch(&self, collection_index: u16, callback: WatcherCallback) -> Result<WatchHandle>;
 
     fn watch_object(
@@ -160,15 +383,86 @@ pub trait IsarInstance: Sized {
         callback: WatcherCallback,
     ) -> Result<WatchHandle>;
 
+    /// Fails with `UnsupportedOperation` if the query filters by links because changes of the
+    /// linked objects are not tracked.
     fn watch_query(&self, query: &Self::Query, callback: WatcherCallback) -> Result<WatchHandle>;
 
     fn copy(&self, path: &str) -> Result<()>;
 
+    /// Creates a backup at `path` while the instance remains usable and verifies the backup
+    /// afterwards. `progress` is called with the number of copied and total pages.
+    fn backup(&self, path: &str, progress: impl FnMut(u64, u64)) -> Result<()>;
+
+    /// Replaces the file of the instance with the backup at `path` and reopens it. Fails if the
+    /// instance is still in use elsewhere.
+    fn restore(
+        instance: Self::Instance,
+        path: &str,
+        schemas: Vec<IsarSchema>,
+        max_size_mib: u32,
+    ) -> RestoreResult<Self::Instance>;
+
     fn verify(&self, txn: &Self::Txn) -> Result<()>;
 
     fn close(instance: Self::Instance, delete: bool) -> bool;
 }
 
+/// Returns `false` if the object `id` does not exist and fails if it does not match
+/// `condition`.
+fn check_condition<I: IsarInstance>(
+    instance: &I,
+    txn: &I::Txn,
+    collection_index: u16,
+    id: i64,
+    condition: Filter,
+) -> Result<bool> {
+    let exists = |filter: Filter| -> Result<bool> {
+        let mut query_builder = instance.query(collection_index)?;
+        query_builder.set_filter(filter);
+        let query = query_builder.build()?;
+        let empty = instance.query_aggregate(txn, &query, Aggregation::IsEmpty, None)?;
+        Ok(empty == Some(IsarValue::Bool(false)))
+    };
+
+    let id_filter = Filter::Condition(FilterCondition::new(
+        0,
+        ConditionType::Equal,
+        vec![Some(IsarValue::Integer(id))],
+        false,
+    ));
+    if !exists(id_filter.clone())? {
+        Ok(false)
+    } else if exists(Filter::And(vec![id_filter, condition]))? {
+        Ok(true)
+    } else {
+        Err(IsarError::VersionMismatch {})
+    }
+}
+
+fn version_condition<I: IsarInstance>(
+    instance: &I,
+    collection_index: u16,
+    version: i64,
+) -> Result<Filter> {
+    let property_index = instance
+        .get_version_property(collection_index)?
+        .ok_or(IsarError::IllegalArgument {})?;
+    Ok(Filter::Condition(FilterCondition::new(
+        property_index,
+        ConditionType::Equal,
+        vec![Some(IsarValue::Integer(version))],
+        false,
+    )))
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum JsonFormat {
+    /// A single JSON array containing all objects.
+    Array,
+    /// One JSON object per line.
+    Ndjson,
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum Aggregation {
     Count,
@@ -1,3 +1,5 @@
+pub mod collection;
+pub(crate) mod constraint;
 pub mod cursor;
 pub mod data_type;
 pub mod de;
@@ -5,12 +7,17 @@ pub mod error;
 pub mod fast_wild_match;
 pub mod filter;
 pub mod filter_json;
+pub mod geo;
 pub mod insert;
 pub mod instance;
 pub mod query_builder;
 pub mod reader;
+pub mod regex;
 pub mod schema;
 mod ser;
+pub(crate) mod snapshot;
+pub mod update;
 pub mod value;
+pub mod vector;
 pub mod watcher;
 pub mod writer;
@@ -1,6 +1,7 @@
+use super::error::Result;
 use super::filter::Filter;
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Sort {
     Asc,
     Desc,
@@ -13,7 +14,17 @@ pub trait IsarQueryBuilder {
 
     fn add_sort(&mut self, property_index: u16, sort: Sort, case_sensitive: bool);
 
+    /// Sorts by the distance to the given coordinate. The property has to be the latitude
+    /// property of a geo index. Nearest-k queries are a distance sort combined with a limit.
+    fn add_distance_sort(&mut self, property_index: u16, latitude: f64, longitude: f64, sort: Sort);
+
+    /// Sorts by the similarity to the query vector, most similar first. The property has to be
+    /// the property of a vector index and only one similarity sort is used per query.
+    fn add_similarity_sort(&mut self, property_index: u16, vector: Vec<f32>);
+
     fn add_distinct(&mut self, property_index: u16, case_sensitive: bool);
 
-    fn build(self) -> Self::Query;
+    /// Fails with `IllegalArgument` if a regex pattern is invalid, a geo condition targets a
+    /// property without a geo index or a distance sort is invalid.
+    fn build(self) -> Result<Self::Query>;
 }
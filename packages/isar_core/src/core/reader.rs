@@ -1,4 +1,5 @@
 use super::error::{IsarError, Result};
+use super::value::{DateTimeOffset, Decimal, Uuid};
 use super::{data_type::DataType, ser::IsarObjectSerialize};
 use serde::Serializer;
 use std::borrow::Cow;
@@ -34,12 +35,41 @@ pub trait IsarReader {
 
     fn read_string(&self, index: u32) -> Option<&str>;
 
+    fn read_decimal(&self, index: u32) -> Option<Decimal>;
+
+    fn read_uuid(&self, index: u32) -> Option<Uuid>;
+
+    fn read_date_time_offset(&self, index: u32) -> Option<DateTimeOffset>;
+
     fn read_blob(&self, index: u32) -> Option<Cow<'_, [u8]>>;
 
+    /// The length of a blob or byte list in bytes.
+    fn read_blob_length(&self, index: u32) -> Option<usize> {
+        self.read_blob(index).map(|blob| blob.len())
+    }
+
+    /// Copies the bytes of a blob or byte list starting at `offset` into `buffer` and returns
+    /// the number of copied bytes.
+    fn read_blob_chunk(&self, index: u32, offset: usize, buffer: &mut [u8]) -> Option<usize> {
+        let blob = self.read_blob(index)?;
+        let chunk = blob.get(offset..).unwrap_or_default();
+        let length = chunk.len().min(buffer.len());
+        buffer[..length].copy_from_slice(&chunk[..length]);
+        Some(length)
+    }
+
     fn read_object(&self, index: u32) -> Option<Self::ObjectReader<'_>>;
 
     fn read_list(&self, index: u32) -> Option<(Self::ListReader<'_>, u32)>;
 
+    /// Returns the keys of a map with string keys and a list reader for the values.
+    fn read_map(&self, index: u32) -> Option<(Vec<Cow<'_, str>>, Self::ListReader<'_>)>;
+
+    /// The similarity score of a query result if the query has a similarity sort.
+    fn read_score(&self) -> Option<f64> {
+        None
+    }
+
     fn serialize(&self, serializer: impl Serializer) -> Result<()>
     where
         Self: Sized,
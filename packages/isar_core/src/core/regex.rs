@@ -0,0 +1,92 @@
+use regex::RegexBuilder;
+
+// bounds the size of patterns like `a{1000}{1000}`
+const SIZE_LIMIT: usize = 1 << 20;
+
+/// Regular expression that is matched in linear time of the text length. Like other regex
+/// engines, the pattern may match anywhere in the text unless it is anchored.
+#[derive(Clone, Debug)]
+pub struct Regex {
+    regex: regex::Regex,
+    case_insensitive: bool,
+}
+
+impl Regex {
+    /// Compiles a pattern. Returns `None` if the pattern is invalid or too large.
+    pub fn new(pattern: &str, case_insensitive: bool) -> Option<Regex> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .size_limit(SIZE_LIMIT)
+            .build()
+            .ok()?;
+        Some(Regex {
+            regex,
+            case_insensitive,
+        })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+}
+
+impl PartialEq for Regex {
+    fn eq(&self, other: &Self) -> bool {
+        self.regex.as_str() == other.regex.as_str()
+            && self.case_insensitive == other.case_insensitive
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_match(pattern: &str, text: &str) -> bool {
+        Regex::new(pattern, false).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn test_is_match() {
+        assert!(is_match("abc", "xxabcxx"));
+        assert!(!is_match("^abc$", "abcd"));
+        assert!(is_match("^(cat|dog)s?$", "cats"));
+        assert!(is_match("^\\d{3}-\\d{4}$", "555-1234"));
+        assert!(is_match("^.$", "€"));
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let regex = Regex::new("^hello [a-c]+$", true).unwrap();
+        assert!(regex.is_match("HeLLo ABC"));
+        assert!(!regex.is_match("HeLLo ABCD"));
+
+        let regex = Regex::new("^ÄB$", true).unwrap();
+        assert!(regex.is_match("äb"));
+
+        let regex = Regex::new("^hello$", false).unwrap();
+        assert!(!regex.is_match("Hello"));
+    }
+
+    #[test]
+    fn test_eq() {
+        let regex = Regex::new("^a+$", false).unwrap();
+        assert_eq!(regex, Regex::new("^a+$", false).unwrap());
+        assert_ne!(regex, Regex::new("^a+$", true).unwrap());
+        assert_ne!(regex, Regex::new("^a*$", false).unwrap());
+    }
+
+    #[test]
+    fn test_invalid() {
+        for pattern in ["(", "a)", "[a", "*a", "a**b(", "[z-a]", "\\1", "a{3,1}"] {
+            assert!(Regex::new(pattern, false).is_none(), "{}", pattern);
+        }
+        assert!(Regex::new("a{1000}{1000}", false).is_none());
+    }
+
+    #[test]
+    fn test_linear_time() {
+        let text = "a".repeat(10_000);
+        assert!(!is_match("^(a*)*b$", &text));
+        assert!(!is_match("(a|aa)+c", &text));
+    }
+}
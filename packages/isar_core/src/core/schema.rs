@@ -1,7 +1,9 @@
 use super::error::Result;
+use super::regex::Regex;
 use super::{data_type::DataType, error::IsarError};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
 
 #[derive(Serialize, Deserialize, Clone, Hash, Debug, PartialEq, Eq)]
 pub struct IsarSchema {
@@ -13,6 +15,16 @@ pub struct IsarSchema {
     pub properties: Vec<PropertySchema>,
     #[serde(default)]
     pub indexes: Vec<IndexSchema>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<LinkSchema>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<TtlSchema>,
+    #[serde(
+        rename = "versionProperty",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub version_property: Option<String>,
     #[serde(default)]
     pub(crate) version: u8,
 }
@@ -31,10 +43,28 @@ impl IsarSchema {
             embedded,
             properties,
             indexes,
+            links: vec![],
+            ttl: None,
+            version_property: None,
             version: 0,
         }
     }
 
+    pub fn with_links(mut self, links: Vec<LinkSchema>) -> IsarSchema {
+        self.links = links;
+        self
+    }
+
+    pub fn with_ttl(mut self, ttl: TtlSchema) -> IsarSchema {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn with_version_property(mut self, property: &str) -> IsarSchema {
+        self.version_property = Some(property.to_string());
+        self
+    }
+
     pub fn from_json(json: &[u8]) -> Result<Vec<Self>> {
         if let Ok(collections) = serde_json::from_slice::<Vec<IsarSchema>>(json) {
             Ok(collections)
@@ -98,10 +128,16 @@ impl IsarSchema {
         for old_index in &old_collection.indexes {
             let index = self.indexes.iter().find(|i| &i.name == &old_index.name);
             if let Some(index) = index {
-                let property_dropped = index.properties.iter().any(|p| drop_properties.contains(p));
+                let property_dropped = index.properties.iter().any(|p| {
+                    let root_property = p.split('.').next().unwrap();
+                    drop_properties.iter().any(|d| d == root_property)
+                });
                 if index.unique != old_index.unique
                     || &index.properties != &old_index.properties
                     || index.hash != old_index.hash
+                    || index.filter != old_index.filter
+                    || index.index_type != old_index.index_type
+                    || index.vector != old_index.vector
                     || property_dropped
                 {
                     add_indexes.push(index);
@@ -125,6 +161,37 @@ impl IsarSchema {
         (add_properties, drop_properties, add_indexes, drop_indexes)
     }
 
+    /// Returns the links that need to be created and the names of the links whose edges need to
+    /// be dropped. Backlinks have no edges of their own.
+    pub fn find_link_changes(
+        &self,
+        old_collection: &IsarSchema,
+    ) -> (Vec<&'_ LinkSchema>, Vec<String>) {
+        let mut add_links = Vec::new();
+        let mut drop_links = Vec::new();
+
+        for old_link in old_collection.links.iter().filter(|l| l.backlink.is_none()) {
+            let link = self.links.iter().find(|l| l.name == old_link.name);
+            let unchanged =
+                link.is_some_and(|l| l.backlink.is_none() && l.target == old_link.target);
+            if !unchanged {
+                drop_links.push(old_link.name.clone());
+            }
+        }
+
+        for link in self.links.iter().filter(|l| l.backlink.is_none()) {
+            let exists = old_collection
+                .links
+                .iter()
+                .any(|l| l.name == link.name && l.backlink.is_none() && l.target == link.target);
+            if !exists {
+                add_links.push(link);
+            }
+        }
+
+        (add_links, drop_links)
+    }
+
     fn verify(&self, collections: &[IsarSchema]) -> Result<()> {
         verify_name(&self.name)?;
 
@@ -137,7 +204,13 @@ impl IsarSchema {
                 verify_name(name)?;
             }
 
-            if property.data_type == DataType::Object || property.data_type == DataType::ObjectList
+            if property.data_type == DataType::Blob && self.embedded {
+                return schema_error("Embedded objects must not have blob properties.");
+            }
+
+            if property.data_type == DataType::Object
+                || property.data_type == DataType::ObjectList
+                || property.data_type == DataType::ObjectMap
             {
                 if let Some(target_col) = &property.collection {
                     if !collections
@@ -163,6 +236,11 @@ impl IsarSchema {
             return schema_error("Duplicate property name")?;
         }
 
+        self.verify_links(collections)?;
+        self.verify_ttl()?;
+        self.verify_version_property()?;
+        self.verify_constraints()?;
+
         let unique_indexes = self.indexes.iter().unique_by(|i| &i.name);
         if unique_indexes.count() != self.indexes.len() {
             return schema_error("Duplicate index name");
@@ -173,15 +251,18 @@ impl IsarSchema {
                 return schema_error("At least one property needs to be added to a valid index");
             }
 
+            if index.index_type == IndexType::Geo {
+                self.verify_geo_index(index)?;
+                continue;
+            } else if index.index_type == IndexType::Vector {
+                self.verify_vector_index(index)?;
+                continue;
+            } else if index.vector.is_some() {
+                return schema_error("Only vector indexes can have vector options.");
+            }
+
             for index_property in &index.properties {
-                let property = self
-                    .properties
-                    .iter()
-                    .find(|p| p.name.as_deref() == Some(&index_property));
-                if property.is_none() {
-                    return schema_error("Index property does not exist");
-                }
-                let property = property.unwrap();
+                let property = self.find_index_property(index_property, collections)?;
 
                 if property.data_type == DataType::Float || property.data_type == DataType::Double {
                     return schema_error("Float properties cannot be indexed.");
@@ -191,6 +272,10 @@ impl IsarSchema {
                     return schema_error("JSON properties cannot be indexed.");
                 } else if property.data_type.is_list() {
                     return schema_error("List properties cannot be indexed.");
+                } else if property.data_type.is_map() {
+                    return schema_error("Map properties cannot be indexed.");
+                } else if property.data_type == DataType::Blob {
+                    return schema_error("Blob properties cannot be indexed.");
                 } else if property.data_type == DataType::String
                     && !index.hash
                     && index.properties.last() != Some(&index_property)
@@ -200,10 +285,273 @@ impl IsarSchema {
                     );
                 }
             }
+
+            if let Some(filter) = &index.filter {
+                self.verify_index_filter(filter)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn verify_ttl(&self) -> Result<()> {
+        if let Some(ttl) = &self.ttl {
+            if self.embedded {
+                return schema_error("Embedded objects must not have a TTL.");
+            }
+            let property = self
+                .properties
+                .iter()
+                .find(|p| p.name.as_ref() == Some(&ttl.property));
+            if property.is_none_or(|p| p.data_type != DataType::Long) {
+                return schema_error("The TTL property has to be a Long property.");
+            }
+            if ttl.duration < 0 {
+                return schema_error("The TTL duration must not be negative.");
+            }
+        }
+        Ok(())
+    }
+
+    fn verify_version_property(&self) -> Result<()> {
+        if let Some(name) = &self.version_property {
+            if self.embedded {
+                return schema_error("Embedded objects must not have a version property.");
+            }
+            let property = self
+                .properties
+                .iter()
+                .find(|p| p.name.as_ref() == Some(name));
+            if property.is_none_or(|p| p.data_type != DataType::Long) {
+                return schema_error("The version property has to be a Long property.");
+            }
+            if property.is_some_and(|p| p.has_constraints()) {
+                return schema_error("The version property must not have constraints.");
+            }
+        }
+        Ok(())
+    }
+
+    fn verify_constraints(&self) -> Result<()> {
+        for property in &self.properties {
+            if !property.has_constraints() {
+                continue;
+            }
+            if self.embedded {
+                return schema_error("Properties of embedded objects must not have constraints.");
+            }
+
+            let data_type = property.data_type;
+            let is_scalar = data_type.is_scalar();
+            if let Some(default) = &property.default {
+                if !is_scalar || !default.matches(data_type) {
+                    return schema_error("The default value does not match the property type.");
+                }
+            }
+
+            for check in &property.checks {
+                let valid = match check {
+                    CheckSchema::Range { min, max } => {
+                        data_type.is_numeric()
+                            && min.iter().chain(max).all(|v| v.matches(data_type))
+                    }
+                    CheckSchema::Length { .. } => data_type == DataType::String,
+                    CheckSchema::Regex { pattern } => {
+                        if Regex::new(pattern, false).is_none() {
+                            return schema_error("Invalid check pattern.");
+                        }
+                        data_type == DataType::String
+                    }
+                };
+                if !valid {
+                    return schema_error("The check does not match the property type.");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn verify_links(&self, collections: &[IsarSchema]) -> Result<()> {
+        if self.embedded && !self.links.is_empty() {
+            return schema_error("Embedded objects must not have links.");
+        }
+
+        for link in &self.links {
+            verify_name(&link.name)?;
+
+            let target = collections
+                .iter()
+                .find(|c| c.name == link.target && !c.embedded);
+            let target = if let Some(target) = target {
+                target
+            } else {
+                return schema_error("Link target collection does not exist.");
+            };
+
+            if let Some(backlink) = &link.backlink {
+                let has_link = target
+                    .links
+                    .iter()
+                    .any(|l| &l.name == backlink && l.backlink.is_none() && l.target == self.name);
+                if !has_link {
+                    return schema_error("Backlinks need a link of the target collection.");
+                }
+            }
+
+            let is_property = self
+                .properties
+                .iter()
+                .any(|p| p.name.as_ref() == Some(&link.name));
+            let is_index = self.indexes.iter().any(|i| i.name == link.name);
+            if is_property || is_index {
+                return schema_error("Link names must differ from property and index names.");
+            }
+        }
+
+        let unique_links = self.links.iter().unique_by(|l| &l.name);
+        if unique_links.count() != self.links.len() {
+            return schema_error("Duplicate link name");
+        }
+
+        Ok(())
+    }
+
+    fn verify_geo_index(&self, index: &IndexSchema) -> Result<()> {
+        if index.properties.len() != 2 {
+            return schema_error("Geo indexes need a latitude and a longitude property.");
+        }
+        if index.unique || index.hash || index.filter.is_some() {
+            return schema_error("Geo indexes cannot be unique, hashed or filtered.");
+        }
+        for index_property in &index.properties {
+            let property = self
+                .properties
+                .iter()
+                .find(|p| p.name.as_ref() == Some(index_property));
+            let is_coordinate = property
+                .is_some_and(|p| p.data_type == DataType::Float || p.data_type == DataType::Double);
+            if !is_coordinate {
+                return schema_error("Geo index properties must be Float or Double properties.");
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the number of dimensions of the vector index of the property.
+    pub(crate) fn vector_dimensions(&self, property_name: &str) -> Option<u32> {
+        let index = self.indexes.iter().find(|index| {
+            index.index_type == IndexType::Vector && index.properties[0] == property_name
+        })?;
+        index.vector.map(|vector| vector.dimensions)
+    }
+
+    fn verify_vector_index(&self, index: &IndexSchema) -> Result<()> {
+        if index.properties.len() != 1 {
+            return schema_error("Vector indexes need exactly one property.");
+        }
+        if index.unique || index.hash || index.filter.is_some() {
+            return schema_error("Vector indexes cannot be unique, hashed or filtered.");
+        }
+        if index.vector.is_none_or(|v| v.dimensions == 0) {
+            return schema_error("Vector indexes need at least one dimension.");
+        }
+        let property = self
+            .properties
+            .iter()
+            .find(|p| p.name.as_ref() == Some(&index.properties[0]));
+        if !property.is_some_and(|p| p.data_type == DataType::FloatList) {
+            return schema_error("Vector index properties must be FloatList properties.");
+        }
+        Ok(())
+    }
+
+    fn verify_index_filter(&self, filter: &IndexFilter) -> Result<()> {
+        let property_name = match filter {
+            IndexFilter::IsNull { property }
+            | IndexFilter::IsNotNull { property }
+            | IndexFilter::Equal { property, .. } => property,
+            IndexFilter::And { filters } => {
+                if filters.is_empty() {
+                    return schema_error("Index filter groups must not be empty.");
+                }
+                for filter in filters {
+                    self.verify_index_filter(filter)?;
+                }
+                return Ok(());
+            }
+        };
+
+        let property = self
+            .properties
+            .iter()
+            .find(|p| p.name.as_ref() == Some(property_name));
+        let data_type = if let Some(property) = property {
+            property.data_type
+        } else {
+            return schema_error("Index filter property does not exist");
+        };
+
+        if data_type == DataType::Object
+            || data_type == DataType::Json
+            || data_type.is_list()
+            || data_type.is_map()
+        {
+            return schema_error("Index filters only support primitive properties.");
+        }
+
+        if let IndexFilter::Equal { value, .. } = filter {
+            let valid_value = match value {
+                IndexFilterValue::Bool(_) => data_type == DataType::Bool,
+                IndexFilterValue::Integer(_) => {
+                    data_type == DataType::Byte
+                        || data_type == DataType::Int
+                        || data_type == DataType::Long
+                }
+                IndexFilterValue::String(_) => data_type == DataType::String,
+            };
+            if !valid_value {
+                return schema_error("Index filter value does not match the property type.");
+            }
         }
 
         Ok(())
     }
+
+    fn find_index_property<'a>(
+        &'a self,
+        path: &str,
+        collections: &'a [IsarSchema],
+    ) -> Result<&'a PropertySchema> {
+        let mut schema = self;
+        let mut parts = path.split('.').peekable();
+        while let Some(part) = parts.next() {
+            let property = schema
+                .properties
+                .iter()
+                .find(|p| p.name.as_deref() == Some(part));
+            let property = if let Some(property) = property {
+                property
+            } else {
+                return schema_error("Index property does not exist");
+            };
+
+            if parts.peek().is_none() {
+                return Ok(property);
+            } else if property.data_type != DataType::Object {
+                return schema_error("Only object properties can be used in index paths.");
+            }
+
+            let target = property.collection.as_deref();
+            schema = if let Some(target) =
+                collections.iter().find(|c| Some(c.name.as_str()) == target)
+            {
+                target
+            } else {
+                return schema_error("Target collection does not exist.");
+            };
+        }
+        schema_error("Index property does not exist")
+    }
 }
 
 fn schema_error<T>(msg: &str) -> Result<T> {
@@ -232,6 +580,13 @@ pub struct PropertySchema {
     #[serde(default)]
     #[serde(rename = "target")]
     pub collection: Option<String>,
+    #[serde(default = "default_nullable", skip_serializing_if = "is_nullable")]
+    pub nullable: bool,
+    // stored instead of null and written to existing objects when the property is added
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<PropertyValue>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checks: Vec<CheckSchema>,
 }
 
 impl PropertySchema {
@@ -240,8 +595,179 @@ impl PropertySchema {
             name: Some(name.to_string()),
             data_type,
             collection: collection.map(|col| col.to_string()),
+            nullable: true,
+            default: None,
+            checks: vec![],
+        }
+    }
+
+    pub fn non_null(mut self) -> PropertySchema {
+        self.nullable = false;
+        self
+    }
+
+    pub fn with_default(mut self, default: PropertyValue) -> PropertySchema {
+        self.default = Some(default);
+        self
+    }
+
+    pub fn with_check(mut self, check: CheckSchema) -> PropertySchema {
+        self.checks.push(check);
+        self
+    }
+
+    pub fn has_constraints(&self) -> bool {
+        !self.nullable || self.default.is_some() || !self.checks.is_empty()
+    }
+}
+
+fn default_nullable() -> bool {
+    true
+}
+
+fn is_nullable(nullable: &bool) -> bool {
+    *nullable
+}
+
+/// Checks are only applied to non-null values.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Hash, Debug)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum CheckSchema {
+    // inclusive bounds of a numeric property
+    Range {
+        #[serde(default)]
+        min: Option<PropertyValue>,
+        #[serde(default)]
+        max: Option<PropertyValue>,
+    },
+    // bounds of the number of characters of a String property
+    Length {
+        #[serde(default)]
+        min: Option<u32>,
+        #[serde(default)]
+        max: Option<u32>,
+    },
+    Regex {
+        pattern: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(untagged)]
+pub enum PropertyValue {
+    Bool(bool),
+    Integer(i64),
+    Real(f64),
+    String(String),
+}
+
+impl PropertyValue {
+    fn matches(&self, data_type: DataType) -> bool {
+        match (self, data_type) {
+            (PropertyValue::Bool(_), DataType::Bool) => true,
+            (PropertyValue::Integer(value), DataType::Byte) => u8::try_from(*value).is_ok(),
+            (PropertyValue::Integer(value), DataType::Int) => {
+                i32::try_from(*value).is_ok_and(|v| v != i32::MIN)
+            }
+            (PropertyValue::Integer(value), DataType::Long) => *value != i64::MIN,
+            (PropertyValue::Integer(_), DataType::Float | DataType::Double) => true,
+            (PropertyValue::Real(value), DataType::Float | DataType::Double) => !value.is_nan(),
+            (PropertyValue::String(_), DataType::String | DataType::Json) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for PropertyValue {}
+
+impl Hash for PropertyValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            PropertyValue::Bool(value) => value.hash(state),
+            PropertyValue::Integer(value) => value.hash(state),
+            PropertyValue::Real(value) => value.to_bits().hash(state),
+            PropertyValue::String(value) => value.hash(state),
+        }
+    }
+}
+
+/// Links store the ids of objects in another collection. A backlink has no edges of its own and
+/// returns the objects of the target collection that link to an object.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct LinkSchema {
+    pub name: String,
+    pub target: String,
+    #[serde(default)]
+    pub single: bool,
+    // name of the link in the target collection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backlink: Option<String>,
+    // deleting an object also deletes the linked objects
+    #[serde(default)]
+    pub cascade: bool,
+}
+
+impl LinkSchema {
+    pub fn new(name: &str, target: &str, single: bool) -> LinkSchema {
+        LinkSchema {
+            name: name.to_string(),
+            target: target.to_string(),
+            single,
+            backlink: None,
+            cascade: false,
+        }
+    }
+
+    pub fn backlink(name: &str, target: &str, link: &str) -> LinkSchema {
+        LinkSchema {
+            backlink: Some(link.to_string()),
+            ..LinkSchema::new(name, target, false)
         }
     }
+
+    pub fn with_cascade(mut self) -> LinkSchema {
+        self.cascade = true;
+        self
+    }
+}
+
+/// Objects expire once the timestamp of the TTL property plus the duration is in the past.
+/// Timestamps and durations are in milliseconds and objects without a timestamp never expire.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Hash, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TtlSchema {
+    pub property: String,
+    #[serde(default)]
+    pub duration: i64,
+    // expired objects are purged when the instance is opened
+    #[serde(default)]
+    pub purge_on_open: bool,
+}
+
+impl TtlSchema {
+    pub fn new(property: &str, duration: i64, purge_on_open: bool) -> TtlSchema {
+        TtlSchema {
+            property: property.to_string(),
+            duration,
+            purge_on_open,
+        }
+    }
+
+    /// Returns the latest timestamp that is expired at `now`.
+    pub fn expired_until(&self, now: i64) -> i64 {
+        now.saturating_sub(self.duration)
+    }
+
+    pub fn expired_until_now(&self) -> i64 {
+        #[cfg(target_arch = "wasm32")]
+        let now = js_sys::Date::now() as i64;
+        #[cfg(not(target_arch = "wasm32"))]
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as i64);
+        self.expired_until(now)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Hash, Debug)]
@@ -250,6 +776,12 @@ pub struct IndexSchema {
     pub properties: Vec<String>,
     pub unique: bool,
     pub hash: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<IndexFilter>,
+    #[serde(rename = "type", default)]
+    pub index_type: IndexType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vector: Option<VectorIndexOptions>,
 }
 
 impl IndexSchema {
@@ -259,8 +791,91 @@ impl IndexSchema {
             properties: properties.iter().map(|p| p.to_string()).collect(),
             unique,
             hash,
+            filter: None,
+            index_type: IndexType::Value,
+            vector: None,
         }
     }
+
+    pub fn geo(name: &str, latitude: &str, longitude: &str) -> IndexSchema {
+        IndexSchema {
+            index_type: IndexType::Geo,
+            ..IndexSchema::new(name, vec![latitude, longitude], false, false)
+        }
+    }
+
+    pub fn vector(
+        name: &str,
+        property: &str,
+        dimensions: u32,
+        metric: VectorMetric,
+    ) -> IndexSchema {
+        IndexSchema {
+            index_type: IndexType::Vector,
+            vector: Some(VectorIndexOptions { dimensions, metric }),
+            ..IndexSchema::new(name, vec![property], false, false)
+        }
+    }
+
+    pub fn with_filter(mut self, filter: IndexFilter) -> IndexSchema {
+        self.filter = Some(filter);
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Hash, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum IndexType {
+    #[default]
+    Value,
+    // latitude and longitude properties
+    Geo,
+    // a single FloatList property with a fixed number of dimensions
+    Vector,
+}
+
+/// Vector indexes are scan-only: no index entries are stored and similarity queries compare the
+/// query vector with the vector of every matching object. Vectors must have exactly `dimensions`
+/// elements.
+#[derive(Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub struct VectorIndexOptions {
+    pub dimensions: u32,
+    pub metric: VectorMetric,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Hash, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum VectorMetric {
+    Cosine,
+    Dot,
+    L2,
+}
+
+/// Only objects matching the filter of a partial index get an index entry.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Hash, Debug)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum IndexFilter {
+    IsNull {
+        property: String,
+    },
+    IsNotNull {
+        property: String,
+    },
+    Equal {
+        property: String,
+        value: IndexFilterValue,
+    },
+    And {
+        filters: Vec<IndexFilter>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Hash, Debug)]
+#[serde(untagged)]
+pub enum IndexFilterValue {
+    Bool(bool),
+    Integer(i64),
+    String(String),
 }
 
 #[cfg(test)]
@@ -511,6 +1126,48 @@ mod test {
         assert!(schema.verify(&[]).is_err());
     }
 
+    #[test]
+    fn test_verify_checks_index_properties_are_not_map() {
+        let schema = IsarSchema::new(
+            "test",
+            None,
+            vec![PropertySchema::new("prop1", DataType::StringMap, None)],
+            vec![IndexSchema::new("index", vec!["prop1"], false, false)],
+            false,
+        );
+        assert!(schema.verify(&[]).is_err());
+    }
+
+    #[test]
+    fn test_verify_checks_blob_properties() {
+        let schema = IsarSchema::new(
+            "test",
+            None,
+            vec![PropertySchema::new("prop1", DataType::Blob, None)],
+            vec![IndexSchema::new("index", vec!["prop1"], false, false)],
+            false,
+        );
+        assert!(schema.verify(&[]).is_err());
+
+        let schema = IsarSchema::new(
+            "test",
+            None,
+            vec![PropertySchema::new("prop1", DataType::Blob, None)],
+            vec![],
+            true,
+        );
+        assert!(schema.verify(&[]).is_err());
+
+        let schema = IsarSchema::new(
+            "test",
+            None,
+            vec![PropertySchema::new("prop1", DataType::Blob, None)],
+            vec![],
+            false,
+        );
+        assert!(schema.verify(&[]).is_ok());
+    }
+
     #[test]
     fn test_verify_checks_index_properties_are_not_string_if_not_last() {
         let schema = IsarSchema::new(
@@ -564,4 +1221,381 @@ mod test {
         );
         assert!(schema.verify(&[]).is_ok());
     }
+
+    #[test]
+    fn test_verify_checks_index_embedded_property_paths() {
+        let embedded = IsarSchema::new(
+            "address",
+            None,
+            vec![
+                PropertySchema::new("city", DataType::String, None),
+                PropertySchema::new("location", DataType::Double, None),
+            ],
+            Vec::new(),
+            true,
+        );
+        let create_schema = |path: &str| {
+            IsarSchema::new(
+                "test",
+                None,
+                vec![
+                    PropertySchema::new("prop1", DataType::Int, None),
+                    PropertySchema::new("address", DataType::Object, Some("address")),
+                ],
+                vec![IndexSchema::new("index", vec![path], false, false)],
+                false,
+            )
+        };
+
+        let schema = create_schema("address.city");
+        assert!(schema.verify(std::slice::from_ref(&embedded)).is_ok());
+
+        let schema = create_schema("address.street");
+        assert!(schema.verify(std::slice::from_ref(&embedded)).is_err());
+
+        let schema = create_schema("address.location");
+        assert!(schema.verify(std::slice::from_ref(&embedded)).is_err());
+
+        let schema = create_schema("prop1.city");
+        assert!(schema.verify(std::slice::from_ref(&embedded)).is_err());
+
+        let schema = create_schema("address");
+        assert!(schema.verify(&[embedded]).is_err());
+    }
+
+    #[test]
+    fn test_find_changes_drops_index_of_dropped_object_property() {
+        let old_schema = IsarSchema::new(
+            "test",
+            None,
+            vec![PropertySchema::new(
+                "address",
+                DataType::Object,
+                Some("address"),
+            )],
+            vec![IndexSchema::new(
+                "index",
+                vec!["address.city"],
+                false,
+                false,
+            )],
+            false,
+        );
+        let schema = IsarSchema::new(
+            "test",
+            None,
+            vec![PropertySchema::new(
+                "address",
+                DataType::Object,
+                Some("address2"),
+            )],
+            vec![IndexSchema::new(
+                "index",
+                vec!["address.city"],
+                false,
+                false,
+            )],
+            false,
+        );
+
+        let (_, _, add_indexes, drop_indexes) = schema.find_changes(&old_schema);
+        assert_eq!(add_indexes, vec![&schema.indexes[0]]);
+        assert_eq!(drop_indexes, vec!["index".to_string()]);
+    }
+
+    #[test]
+    fn test_verify_checks_links() {
+        let create_schemas = |links: Vec<LinkSchema>, backlinks: Vec<LinkSchema>| {
+            vec![
+                IsarSchema::new("user", None, vec![], vec![], false).with_links(links),
+                IsarSchema::new("order", None, vec![], vec![], false).with_links(backlinks),
+                IsarSchema::new("address", None, vec![], vec![], true),
+            ]
+        };
+        let verify = |schemas: Vec<IsarSchema>| schemas.iter().all(|s| s.verify(&schemas).is_ok());
+
+        let links = vec![LinkSchema::new("orders", "order", false)];
+        let backlinks = vec![LinkSchema::backlink("user", "user", "orders")];
+        assert!(verify(create_schemas(links.clone(), backlinks.clone())));
+        assert!(verify(create_schemas(links.clone(), vec![])));
+
+        let backlinks = vec![LinkSchema::backlink("user", "user", "other")];
+        assert!(!verify(create_schemas(links.clone(), backlinks)));
+
+        let backlinks = vec![LinkSchema::backlink("user", "user", "user")];
+        assert!(!verify(create_schemas(vec![], backlinks)));
+
+        let links = vec![LinkSchema::new("address", "address", true)];
+        assert!(!verify(create_schemas(links, vec![])));
+
+        let links = vec![LinkSchema::new("orders", "missing", false)];
+        assert!(!verify(create_schemas(links, vec![])));
+
+        let links = vec![
+            LinkSchema::new("orders", "order", false),
+            LinkSchema::new("orders", "order", true),
+        ];
+        assert!(!verify(create_schemas(links, vec![])));
+
+        let links = vec![LinkSchema::new("_orders", "order", false)];
+        assert!(!verify(create_schemas(links, vec![])));
+
+        let user = IsarSchema::new(
+            "user",
+            None,
+            vec![PropertySchema::new("orders", DataType::Long, None)],
+            vec![],
+            false,
+        )
+        .with_links(vec![LinkSchema::new("orders", "order", false)]);
+        let order = IsarSchema::new("order", None, vec![], vec![], false);
+        assert!(user.verify(&[user.clone(), order]).is_err());
+    }
+
+    #[test]
+    fn test_find_link_changes() {
+        let old = IsarSchema::new("user", None, vec![], vec![], false).with_links(vec![
+            LinkSchema::new("orders", "order", false),
+            LinkSchema::new("best", "order", true),
+            LinkSchema::backlink("friend", "user", "friends"),
+            LinkSchema::new("friends", "user", false),
+        ]);
+        let new = IsarSchema::new("user", None, vec![], vec![], false).with_links(vec![
+            LinkSchema::new("orders", "order", false).with_cascade(),
+            LinkSchema::new("best", "item", true),
+            LinkSchema::backlink("friends", "user", "friend"),
+            LinkSchema::new("friend", "user", false),
+        ]);
+
+        let (add_links, drop_links) = new.find_link_changes(&old);
+        let add_links = add_links.iter().map(|l| l.name.as_str()).collect_vec();
+        assert_eq!(add_links, vec!["best", "friend"]);
+        assert_eq!(drop_links, vec!["best", "friends"]);
+
+        let (add_links, drop_links) = new.find_link_changes(&new);
+        assert!(add_links.is_empty());
+        assert!(drop_links.is_empty());
+    }
+
+    #[test]
+    fn test_verify_checks_ttl() {
+        let create_schema = |data_type: DataType, ttl: TtlSchema| {
+            IsarSchema::new(
+                "test",
+                None,
+                vec![PropertySchema::new("expires", data_type, None)],
+                vec![],
+                false,
+            )
+            .with_ttl(ttl)
+        };
+
+        let schema = create_schema(DataType::Long, TtlSchema::new("expires", 1000, true));
+        assert!(schema.verify(&[]).is_ok());
+
+        let schema = create_schema(DataType::Int, TtlSchema::new("expires", 1000, true));
+        assert!(schema.verify(&[]).is_err());
+
+        let schema = create_schema(DataType::Long, TtlSchema::new("other", 1000, true));
+        assert!(schema.verify(&[]).is_err());
+
+        let schema = create_schema(DataType::Long, TtlSchema::new("expires", -1, true));
+        assert!(schema.verify(&[]).is_err());
+
+        let mut schema = create_schema(DataType::Long, TtlSchema::new("expires", 0, false));
+        schema.embedded = true;
+        assert!(schema.verify(&[]).is_err());
+
+        let ttl: TtlSchema = serde_json::from_str(r#"{"property":"expires"}"#).unwrap();
+        assert_eq!(ttl, TtlSchema::new("expires", 0, false));
+        assert_eq!(TtlSchema::new("expires", 10, false).expired_until(100), 90);
+        assert_eq!(
+            TtlSchema::new("expires", 10, false).expired_until(i64::MIN),
+            i64::MIN
+        );
+    }
+
+    #[test]
+    fn test_verify_checks_version_property() {
+        let create_schema = |property: PropertySchema, version_property: &str| {
+            IsarSchema::new("test", None, vec![property], vec![], false)
+                .with_version_property(version_property)
+        };
+
+        let schema = create_schema(PropertySchema::new("v", DataType::Long, None), "v");
+        assert!(schema.verify(&[]).is_ok());
+
+        let schema = create_schema(PropertySchema::new("v", DataType::Int, None), "v");
+        assert!(schema.verify(&[]).is_err());
+
+        let schema = create_schema(PropertySchema::new("v", DataType::Long, None), "other");
+        assert!(schema.verify(&[]).is_err());
+
+        let property = PropertySchema::new("v", DataType::Long, None).non_null();
+        let schema = create_schema(property, "v");
+        assert!(schema.verify(&[]).is_err());
+
+        let mut schema = create_schema(PropertySchema::new("v", DataType::Long, None), "v");
+        schema.embedded = true;
+        assert!(schema.verify(&[]).is_err());
+
+        let json = r#"[{"name":"test","properties":[],"versionProperty":"v"}]"#;
+        let schemas = IsarSchema::from_json(json.as_bytes()).unwrap();
+        assert_eq!(schemas[0].version_property.as_deref(), Some("v"));
+    }
+
+    #[test]
+    fn test_verify_checks_index_filter() {
+        let create_schema = |filter: IndexFilter| {
+            IsarSchema::new(
+                "test",
+                None,
+                vec![
+                    PropertySchema::new("email", DataType::String, None),
+                    PropertySchema::new("deleted", DataType::Bool, None),
+                    PropertySchema::new("tags", DataType::StringList, None),
+                ],
+                vec![IndexSchema::new("index", vec!["email"], true, false).with_filter(filter)],
+                false,
+            )
+        };
+
+        let schema = create_schema(IndexFilter::IsNotNull {
+            property: "email".to_string(),
+        });
+        assert!(schema.verify(&[]).is_ok());
+
+        let schema = create_schema(IndexFilter::And {
+            filters: vec![
+                IndexFilter::IsNotNull {
+                    property: "email".to_string(),
+                },
+                IndexFilter::Equal {
+                    property: "deleted".to_string(),
+                    value: IndexFilterValue::Bool(false),
+                },
+            ],
+        });
+        assert!(schema.verify(&[]).is_ok());
+
+        let schema = create_schema(IndexFilter::IsNull {
+            property: "name".to_string(),
+        });
+        assert!(schema.verify(&[]).is_err());
+
+        let schema = create_schema(IndexFilter::IsNull {
+            property: "tags".to_string(),
+        });
+        assert!(schema.verify(&[]).is_err());
+
+        let schema = create_schema(IndexFilter::Equal {
+            property: "deleted".to_string(),
+            value: IndexFilterValue::Integer(0),
+        });
+        assert!(schema.verify(&[]).is_err());
+
+        let schema = create_schema(IndexFilter::And { filters: vec![] });
+        assert!(schema.verify(&[]).is_err());
+    }
+
+    #[test]
+    fn test_index_filter_json() {
+        let json = r#"[{"name":"test","properties":[{"name":"deleted","type":"Bool"}],
+            "indexes":[{"name":"index","properties":["deleted"],"unique":false,"hash":false,
+            "filter":{"type":"equal","property":"deleted","value":false}}]}]"#;
+        let schemas = IsarSchema::from_json(json.as_bytes()).unwrap();
+        assert_eq!(
+            schemas[0].indexes[0].filter,
+            Some(IndexFilter::Equal {
+                property: "deleted".to_string(),
+                value: IndexFilterValue::Bool(false),
+            })
+        );
+    }
+
+    #[test]
+    fn test_verify_checks_geo_index() {
+        let create_schema = |index: IndexSchema| {
+            IsarSchema::new(
+                "test",
+                None,
+                vec![
+                    PropertySchema::new("lat", DataType::Double, None),
+                    PropertySchema::new("lon", DataType::Float, None),
+                    PropertySchema::new("name", DataType::String, None),
+                ],
+                vec![index],
+                false,
+            )
+        };
+
+        let schema = create_schema(IndexSchema::geo("index", "lat", "lon"));
+        assert!(schema.verify(&[]).is_ok());
+
+        let schema = create_schema(IndexSchema::geo("index", "lat", "name"));
+        assert!(schema.verify(&[]).is_err());
+
+        let schema = create_schema(IndexSchema::geo("index", "lat", "other"));
+        assert!(schema.verify(&[]).is_err());
+
+        let mut index = IndexSchema::geo("index", "lat", "lon");
+        index.unique = true;
+        let schema = create_schema(index);
+        assert!(schema.verify(&[]).is_err());
+
+        let mut index = IndexSchema::geo("index", "lat", "lon");
+        index.properties.pop();
+        let schema = create_schema(index);
+        assert!(schema.verify(&[]).is_err());
+    }
+
+    #[test]
+    fn test_verify_checks_vector_index() {
+        let create_schema = |index: IndexSchema| {
+            IsarSchema::new(
+                "test",
+                None,
+                vec![
+                    PropertySchema::new("embedding", DataType::FloatList, None),
+                    PropertySchema::new("values", DataType::DoubleList, None),
+                ],
+                vec![index],
+                false,
+            )
+        };
+
+        let schema = create_schema(IndexSchema::vector(
+            "index",
+            "embedding",
+            3,
+            VectorMetric::L2,
+        ));
+        assert!(schema.verify(&[]).is_ok());
+
+        let schema = create_schema(IndexSchema::vector("index", "values", 3, VectorMetric::L2));
+        assert!(schema.verify(&[]).is_err());
+
+        let schema = create_schema(IndexSchema::vector(
+            "index",
+            "embedding",
+            0,
+            VectorMetric::L2,
+        ));
+        assert!(schema.verify(&[]).is_err());
+
+        let mut index = IndexSchema::new("index", vec!["embedding"], false, false);
+        index.vector = IndexSchema::vector("index", "embedding", 3, VectorMetric::Dot).vector;
+        let schema = create_schema(index);
+        assert!(schema.verify(&[]).is_err());
+
+        let index: IndexSchema = serde_json::from_str(
+            r#"{"name":"index","properties":["embedding"],"unique":false,"hash":false,
+            "type":"vector","vector":{"dimensions":3,"metric":"cosine"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            index,
+            IndexSchema::vector("index", "embedding", 3, VectorMetric::Cosine)
+        );
+    }
 }
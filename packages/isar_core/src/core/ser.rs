@@ -1,7 +1,59 @@
+use super::cursor::IsarQueryCursor;
+use super::error::{self, IsarError};
+use super::instance::JsonFormat;
 use super::{data_type::DataType, reader::IsarReader};
+use base64::{engine::general_purpose, Engine as _};
 use serde::ser::{SerializeMap, SerializeSeq};
 use serde::Serialize;
 use serde_json::Value;
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::io::{self, Write};
+
+pub(super) fn json_error(err: impl Display) -> IsarError {
+    IsarError::JsonError {
+        message: err.to_string(),
+    }
+}
+
+fn io_error(_: io::Error) -> IsarError {
+    IsarError::PathError {}
+}
+
+fn write_error(err: serde_json::Error) -> IsarError {
+    if err.is_io() {
+        IsarError::PathError {}
+    } else {
+        json_error(err)
+    }
+}
+
+pub(super) fn export_json(
+    mut cursor: impl IsarQueryCursor,
+    format: JsonFormat,
+    mut writer: impl Write,
+) -> error::Result<u32> {
+    let mut count = 0;
+    if format == JsonFormat::Array {
+        writer.write_all(b"[").map_err(io_error)?;
+    }
+    while let Some(reader) = cursor.next() {
+        if count > 0 && format == JsonFormat::Array {
+            writer.write_all(b",").map_err(io_error)?;
+        }
+        let object = IsarObjectSerialize::new(&reader);
+        serde_json::to_writer(&mut writer, &object).map_err(write_error)?;
+        if format == JsonFormat::Ndjson {
+            writer.write_all(b"\n").map_err(io_error)?;
+        }
+        count += 1;
+    }
+    if format == JsonFormat::Array {
+        writer.write_all(b"]").map_err(io_error)?;
+    }
+    writer.flush().map_err(io_error)?;
+    Ok(count)
+}
 
 pub(super) struct IsarObjectSerialize<'a, R: IsarReader> {
     reader: &'a R,
@@ -75,12 +127,39 @@ impl<'a, R: IsarReader> Serialize for IsarObjectSerialize<'a, R> {
                         }
                     }
                 }
+                DataType::Decimal => {
+                    if let Some(value) = self.reader.read_decimal(index as u32) {
+                        ser.serialize_entry(name, &value)?;
+                    }
+                }
+                DataType::Uuid => {
+                    if let Some(value) = self.reader.read_uuid(index as u32) {
+                        ser.serialize_entry(name, &value)?;
+                    }
+                }
+                DataType::DateTimeOffset => {
+                    if let Some(value) = self.reader.read_date_time_offset(index as u32) {
+                        ser.serialize_entry(name, &value)?;
+                    }
+                }
+                DataType::Blob => {
+                    if let Some(value) = self.reader.read_blob(index as u32) {
+                        ser.serialize_entry(name, &general_purpose::STANDARD.encode(value))?;
+                    }
+                }
                 DataType::Object => {
                     if let Some(object) = self.reader.read_object(index as u32) {
                         let reader = IsarObjectSerialize::new(&object);
                         ser.serialize_entry(name, &reader)?;
                     }
                 }
+                _ if data_type.is_map() => {
+                    if let Some((keys, values)) = self.reader.read_map(index as u32) {
+                        let value_type = data_type.value_type().unwrap();
+                        let reader = IsarMapSerialize::new(value_type, &keys, &values);
+                        ser.serialize_entry(name, &reader)?;
+                    }
+                }
                 _ => {
                     let element_type = data_type.element_type();
                     let list = self.reader.read_list(index as u32);
@@ -202,3 +281,81 @@ impl<'a, R: IsarReader> Serialize for IsarListSerialize<'a, R> {
         ser.end()
     }
 }
+
+struct IsarMapSerialize<'a, R: IsarReader> {
+    value_type: DataType,
+    keys: &'a [Cow<'a, str>],
+    reader: &'a R,
+}
+
+impl<'a, R: IsarReader> IsarMapSerialize<'a, R> {
+    fn new(value_type: DataType, keys: &'a [Cow<'a, str>], reader: &'a R) -> Self {
+        IsarMapSerialize {
+            value_type,
+            keys,
+            reader,
+        }
+    }
+}
+
+impl<'a, R: IsarReader> Serialize for IsarMapSerialize<'a, R> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut ser = serializer.serialize_map(Some(self.keys.len()))?;
+        for (i, key) in self.keys.iter().enumerate() {
+            let value = IsarMapValueSerialize {
+                value_type: self.value_type,
+                reader: self.reader,
+                index: i as u32,
+            };
+            ser.serialize_entry(key, &value)?;
+        }
+        ser.end()
+    }
+}
+
+struct IsarMapValueSerialize<'a, R: IsarReader> {
+    value_type: DataType,
+    reader: &'a R,
+    index: u32,
+}
+
+impl<'a, R: IsarReader> Serialize for IsarMapValueSerialize<'a, R> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let index = self.index;
+        match self.value_type {
+            DataType::Bool => self.reader.read_bool(index).serialize(serializer),
+            DataType::Byte => self.reader.read_byte(index).serialize(serializer),
+            DataType::Int => {
+                let value = self.reader.read_int(index);
+                (value != i32::MIN).then_some(value).serialize(serializer)
+            }
+            DataType::Float => {
+                let value = self.reader.read_float(index);
+                (!value.is_nan()).then_some(value).serialize(serializer)
+            }
+            DataType::Long => {
+                let value = self.reader.read_long(index);
+                (value != i64::MIN).then_some(value).serialize(serializer)
+            }
+            DataType::Double => {
+                let value = self.reader.read_double(index);
+                (!value.is_nan()).then_some(value).serialize(serializer)
+            }
+            DataType::String => self.reader.read_string(index).serialize(serializer),
+            DataType::Object => {
+                if let Some(object) = self.reader.read_object(index) {
+                    IsarObjectSerialize::new(&object).serialize(serializer)
+                } else {
+                    serializer.serialize_none()
+                }
+            }
+            _ => serializer.serialize_none(),
+        }
+    }
+}
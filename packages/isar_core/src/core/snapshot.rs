@@ -0,0 +1,678 @@
+use super::cursor::IsarQueryCursor;
+use super::data_type::DataType;
+use super::error::{IsarError, Result};
+use super::insert::IsarInsert;
+use super::instance::IsarInstance;
+use super::query_builder::IsarQueryBuilder;
+use super::reader::IsarReader;
+use super::schema::IsarSchema;
+use super::value::{DateTimeOffset, Decimal, Uuid};
+use super::writer::IsarWriter;
+use serde_json::Value;
+use std::io::{self, Read, Write};
+#[cfg(all(feature = "native", feature = "sqlite"))]
+use xxhash_rust::xxh3::xxh3_64;
+
+// Layout (little endian):
+// magic, version: u16, schema length: u32, schema json,
+// collection count: u16, then per collection: collection index: u16, object count: u32 and the
+// objects as id: i64 followed by their properties in schema order,
+// link count: u16, then per link: collection index: u16, link index: u16, edge count: u32 and the
+// edges as id: i64 and target id: i64.
+// Every value starts with a u8 that is 0 for null. Strings are prefixed with their length: u32
+// and lists with their element count: u32.
+const MAGIC: &[u8; 4] = b"ISNP";
+const SNAPSHOT_VERSION: u16 = 2;
+
+fn io_error(err: io::Error) -> IsarError {
+    if err.kind() == io::ErrorKind::UnexpectedEof {
+        IsarError::IllegalArgument {}
+    } else {
+        IsarError::PathError {}
+    }
+}
+
+fn instance_schemas<I: IsarInstance>(instance: &I) -> Result<Vec<IsarSchema>> {
+    let count = instance.get_collections().count();
+    (0..count as u16)
+        .map(|index| instance.get_schema(index).cloned())
+        .collect()
+}
+
+pub(super) fn export_snapshot<I: IsarInstance>(
+    instance: &I,
+    txn: &I::Txn,
+    writer: impl Write,
+) -> Result<u32> {
+    let schemas = instance_schemas(instance)?;
+    let mut schema_json = serde_json::to_value(&schemas).unwrap();
+    // the id name is not serialized by default
+    for (schema, json) in schemas.iter().zip(schema_json.as_array_mut().unwrap()) {
+        json["idName"] = schema.id_name.clone().into();
+    }
+    let schema_json = serde_json::to_vec(&schema_json).unwrap();
+
+    let mut out = SnapshotWriter { writer };
+    out.write_bytes(MAGIC)?;
+    out.write_u16(SNAPSHOT_VERSION)?;
+    out.write_u32(schema_json.len() as u32)?;
+    out.write_bytes(&schema_json)?;
+
+    let collections = schemas
+        .iter()
+        .enumerate()
+        .filter(|(_, schema)| !schema.embedded)
+        .map(|(index, _)| index as u16)
+        .collect::<Vec<_>>();
+    out.write_u16(collections.len() as u16)?;
+
+    let mut count = 0;
+    for &collection_index in &collections {
+        let objects = instance.count(txn, collection_index)?;
+        out.write_u16(collection_index)?;
+        out.write_u32(objects)?;
+
+        let query = instance.query(collection_index)?.build()?;
+        let cursor = instance.query_cursor(txn, &query, None, None)?;
+        if out.write_objects(cursor)? != objects {
+            return Err(IsarError::DbCorrupted {});
+        }
+        count += objects;
+    }
+
+    // backlinks share the edges of their link
+    let links = collections
+        .iter()
+        .flat_map(|&index| {
+            let links = schemas[index as usize].links.iter().enumerate();
+            links
+                .filter(|(_, link)| link.backlink.is_none())
+                .map(move |(link_index, _)| (index, link_index as u16))
+        })
+        .collect::<Vec<_>>();
+    out.write_u16(links.len() as u16)?;
+    for (collection_index, link_index) in links {
+        let query = instance.query(collection_index)?.build()?;
+        let cursor = instance.query_cursor(txn, &query, None, None)?;
+        let mut edges = vec![];
+        for id in read_ids(cursor) {
+            for target_id in instance.get_linked_ids(txn, collection_index, link_index, id)? {
+                edges.push((id, target_id));
+            }
+        }
+        out.write_u16(collection_index)?;
+        out.write_u16(link_index)?;
+        out.write_u32(edges.len() as u32)?;
+        for (id, target_id) in edges {
+            out.write_bytes(&id.to_le_bytes())?;
+            out.write_bytes(&target_id.to_le_bytes())?;
+        }
+    }
+    out.writer.flush().map_err(io_error)?;
+
+    Ok(count)
+}
+
+pub(super) fn import_snapshot<I: IsarInstance>(
+    instance: &I,
+    txn: I::Txn,
+    reader: impl Read,
+) -> Result<(I::Txn, u32)> {
+    let mut input = SnapshotReader { reader };
+    let mut txn = Some(txn);
+    match input.import(instance, &mut txn) {
+        Ok(count) => Ok((txn.unwrap(), count)),
+        Err(err) => {
+            // the txn is only lost if finishing an insert failed
+            if let Some(txn) = txn {
+                instance.abort_txn(txn);
+            }
+            Err(err)
+        }
+    }
+}
+
+pub(crate) fn read_ids(mut cursor: impl IsarQueryCursor) -> Vec<i64> {
+    let mut ids = vec![];
+    while let Some(reader) = cursor.next() {
+        ids.push(reader.read_id());
+    }
+    ids
+}
+
+/// Returns the number of objects and a checksum of their ids and values. The checksum does not
+/// depend on the order of the objects or the backend.
+#[cfg(all(feature = "native", feature = "sqlite"))]
+pub(crate) fn checksum(mut cursor: impl IsarQueryCursor) -> Result<(u32, u64)> {
+    let mut out = SnapshotWriter { writer: vec![] };
+    let mut count = 0;
+    let mut checksum = 0u64;
+    while let Some(reader) = cursor.next() {
+        out.writer.clear();
+        out.write_bytes(&reader.read_id().to_le_bytes())?;
+        out.write_object(&reader)?;
+        checksum = checksum.wrapping_add(xxh3_64(&out.writer));
+        count += 1;
+    }
+    Ok((count, checksum))
+}
+
+struct SnapshotWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> SnapshotWriter<W> {
+    fn write_bytes(&mut self, value: &[u8]) -> Result<()> {
+        self.writer.write_all(value).map_err(io_error)
+    }
+
+    fn write_u8(&mut self, value: u8) -> Result<()> {
+        self.write_bytes(&[value])
+    }
+
+    fn write_u16(&mut self, value: u16) -> Result<()> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    fn write_u32(&mut self, value: u32) -> Result<()> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    fn write_objects(&mut self, mut cursor: impl IsarQueryCursor) -> Result<u32> {
+        let mut count = 0;
+        while let Some(reader) = cursor.next() {
+            self.write_bytes(&reader.read_id().to_le_bytes())?;
+            self.write_object(&reader)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn write_object(&mut self, reader: &impl IsarReader) -> Result<()> {
+        for (index, (_, data_type)) in reader.properties().enumerate() {
+            self.write_value(reader, data_type, index as u32 + 1)?;
+        }
+        Ok(())
+    }
+
+    fn write_value(
+        &mut self,
+        reader: &impl IsarReader,
+        data_type: DataType,
+        index: u32,
+    ) -> Result<()> {
+        if reader.is_null(index) {
+            return self.write_u8(0);
+        }
+        match data_type {
+            DataType::Bool => {
+                if let Some(value) = reader.read_bool(index) {
+                    self.write_u8(1)?;
+                    self.write_u8(value as u8)
+                } else {
+                    self.write_u8(0)
+                }
+            }
+            DataType::Byte => {
+                self.write_u8(1)?;
+                self.write_u8(reader.read_byte(index))
+            }
+            DataType::Int => {
+                self.write_u8(1)?;
+                self.write_bytes(&reader.read_int(index).to_le_bytes())
+            }
+            DataType::Float => {
+                self.write_u8(1)?;
+                self.write_bytes(&reader.read_float(index).to_le_bytes())
+            }
+            DataType::Long => {
+                self.write_u8(1)?;
+                self.write_bytes(&reader.read_long(index).to_le_bytes())
+            }
+            DataType::Double => {
+                self.write_u8(1)?;
+                self.write_bytes(&reader.read_double(index).to_le_bytes())
+            }
+            DataType::String | DataType::Json => {
+                if let Some(value) = reader.read_string(index) {
+                    self.write_u8(1)?;
+                    self.write_u32(value.len() as u32)?;
+                    self.write_bytes(value.as_bytes())
+                } else {
+                    self.write_u8(0)
+                }
+            }
+            DataType::Decimal => {
+                if let Some(value) = reader.read_decimal(index) {
+                    self.write_u8(1)?;
+                    self.write_bytes(&value.to_bytes())
+                } else {
+                    self.write_u8(0)
+                }
+            }
+            DataType::Uuid => {
+                if let Some(value) = reader.read_uuid(index) {
+                    self.write_u8(1)?;
+                    self.write_bytes(&value.to_bytes())
+                } else {
+                    self.write_u8(0)
+                }
+            }
+            DataType::DateTimeOffset => {
+                if let Some(value) = reader.read_date_time_offset(index) {
+                    self.write_u8(1)?;
+                    self.write_bytes(&value.to_bytes())
+                } else {
+                    self.write_u8(0)
+                }
+            }
+            DataType::Blob => {
+                if let Some(value) = reader.read_blob(index) {
+                    self.write_u8(1)?;
+                    self.write_u32(value.len() as u32)?;
+                    self.write_bytes(&value)
+                } else {
+                    self.write_u8(0)
+                }
+            }
+            DataType::Object => {
+                if let Some(object) = reader.read_object(index) {
+                    self.write_u8(1)?;
+                    self.write_object(&object)
+                } else {
+                    self.write_u8(0)
+                }
+            }
+            _ if data_type.is_map() => {
+                let value_type = data_type.value_type().unwrap();
+                if let Some((keys, values)) = reader.read_map(index) {
+                    self.write_u8(1)?;
+                    self.write_u32(keys.len() as u32)?;
+                    for key in &keys {
+                        self.write_u32(key.len() as u32)?;
+                        self.write_bytes(key.as_bytes())?;
+                    }
+                    for i in 0..keys.len() as u32 {
+                        self.write_value(&values, value_type, i)?;
+                    }
+                    Ok(())
+                } else {
+                    self.write_u8(0)
+                }
+            }
+            _ => {
+                let element_type = data_type.element_type().unwrap();
+                if let Some((list, length)) = reader.read_list(index) {
+                    self.write_u8(1)?;
+                    self.write_u32(length)?;
+                    for i in 0..length {
+                        self.write_value(&list, element_type, i)?;
+                    }
+                    Ok(())
+                } else {
+                    self.write_u8(0)
+                }
+            }
+        }
+    }
+}
+
+struct SnapshotReader<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> SnapshotReader<R> {
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut bytes = [0; N];
+        self.reader.read_exact(&mut bytes).map_err(io_error)?;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_i64(&mut self) -> Result<i64> {
+        Ok(i64::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_bytes(&mut self) -> Result<Vec<u8>> {
+        let length = self.read_u32()?;
+        let mut bytes = vec![];
+        (&mut self.reader)
+            .take(length as u64)
+            .read_to_end(&mut bytes)
+            .map_err(io_error)?;
+        if bytes.len() != length as usize {
+            return Err(IsarError::IllegalArgument {});
+        }
+        Ok(bytes)
+    }
+
+    fn import<I: IsarInstance>(&mut self, instance: &I, txn: &mut Option<I::Txn>) -> Result<u32> {
+        let schemas = self.read_header()?;
+        let targets = instance.get_collections().collect::<Vec<_>>();
+        let target_index = |schema: &IsarSchema| -> Result<Option<u16>> {
+            let target = targets.iter().position(|name| *name == schema.name);
+            if let Some(target) = target {
+                if !instance.get_schema(target as u16)?.embedded {
+                    return Ok(Some(target as u16));
+                }
+            }
+            Ok(None)
+        };
+
+        let mut count = 0;
+        for _ in 0..self.read_u16()? {
+            let collection_index = self.read_u16()?;
+            let objects = self.read_u32()?;
+            let schema = schemas
+                .get(collection_index as usize)
+                .ok_or(IsarError::IllegalArgument {})?;
+
+            if let Some(target) = target_index(schema)? {
+                let mut insert = instance.insert(txn.take().unwrap(), target, objects)?;
+                let result = (0..objects).try_for_each(|_| {
+                    let id = self.read_i64()?;
+                    self.read_object(&schemas, schema, Some(&mut insert))?;
+                    insert.save(id)
+                });
+                *txn = Some(insert.finish()?);
+                result?;
+                count += objects;
+            } else {
+                // the target has no such collection
+                for _ in 0..objects {
+                    self.read_i64()?;
+                    self.read_object(&schemas, schema, None::<&mut I::Insert<'_>>)?;
+                }
+            }
+        }
+
+        for _ in 0..self.read_u16()? {
+            let collection_index = self.read_u16()?;
+            let link_index = self.read_u16()?;
+            let edges = self.read_u32()?;
+            let link = schemas
+                .get(collection_index as usize)
+                .and_then(|schema| Some((schema, schema.links.get(link_index as usize)?)));
+            let (schema, link) = link.ok_or(IsarError::IllegalArgument {})?;
+
+            // links are matched by name and target collection
+            let target = if let Some(target) = target_index(schema)? {
+                instance
+                    .get_links(target)?
+                    .position(|(name, target_collection_index, _)| {
+                        name == link.name
+                            && targets[target_collection_index as usize] == link.target
+                    })
+                    .map(|target_link| (target, target_link as u16))
+            } else {
+                None
+            };
+            for _ in 0..edges {
+                let id = self.read_i64()?;
+                let target_id = self.read_i64()?;
+                if let Some((target, target_link)) = target {
+                    instance.link(txn.as_ref().unwrap(), target, target_link, id, target_id)?;
+                }
+            }
+        }
+
+        Ok(count)
+    }
+
+    fn read_header(&mut self) -> Result<Vec<IsarSchema>> {
+        if &self.read_array::<4>()? != MAGIC {
+            return Err(IsarError::IllegalArgument {});
+        }
+        if self.read_u16()? != SNAPSHOT_VERSION {
+            return Err(IsarError::VersionError {});
+        }
+
+        let schema_json = self.read_bytes()?;
+        let schemas = serde_json::from_slice::<Vec<Value>>(&schema_json)
+            .and_then(|schemas| {
+                schemas
+                    .into_iter()
+                    .map(serde_json::from_value::<IsarSchema>)
+                    .collect::<serde_json::Result<Vec<_>>>()
+            })
+            .map_err(|e| IsarError::JsonError {
+                message: e.to_string(),
+            })?;
+        Ok(schemas)
+    }
+
+    fn read_object<'a, W: IsarWriter<'a>>(
+        &mut self,
+        schemas: &[IsarSchema],
+        schema: &IsarSchema,
+        mut writer: Option<&mut W>,
+    ) -> Result<()> {
+        let mut written = vec![];
+        for property in &schema.properties {
+            let name = property.name.as_deref().unwrap_or_default();
+            let embedded = property
+                .collection
+                .as_ref()
+                .and_then(|target| schemas.iter().find(|s| &s.name == target));
+
+            // properties are matched by name and type
+            let target = writer.as_ref().and_then(|writer| {
+                writer
+                    .properties()
+                    .position(|(n, t)| n == name && t == property.data_type)
+            });
+            if let Some(target) = target {
+                let index = target as u32 + 1;
+                written.push(index);
+                let writer = writer.as_deref_mut();
+                self.read_value(schemas, property.data_type, embedded, index, writer)?;
+            } else {
+                self.read_value(schemas, property.data_type, embedded, 0, None::<&mut W>)?;
+            }
+        }
+
+        if let Some(writer) = writer {
+            for index in 1..=writer.properties().count() as u32 {
+                if !written.contains(&index) {
+                    writer.write_null(index);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read_value<'a, W: IsarWriter<'a>>(
+        &mut self,
+        schemas: &[IsarSchema],
+        data_type: DataType,
+        embedded: Option<&IsarSchema>,
+        index: u32,
+        writer: Option<&mut W>,
+    ) -> Result<()> {
+        if self.read_u8()? == 0 {
+            if let Some(writer) = writer {
+                writer.write_null(index);
+            }
+            return Ok(());
+        }
+
+        match data_type {
+            DataType::Bool => {
+                let value = self.read_u8()? != 0;
+                if let Some(w) = writer {
+                    w.write_bool(index, value);
+                }
+            }
+            DataType::Byte => {
+                let value = self.read_u8()?;
+                if let Some(w) = writer {
+                    w.write_byte(index, value);
+                }
+            }
+            DataType::Int => {
+                let value = i32::from_le_bytes(self.read_array()?);
+                if let Some(w) = writer {
+                    w.write_int(index, value);
+                }
+            }
+            DataType::Float => {
+                let value = f32::from_le_bytes(self.read_array()?);
+                if let Some(w) = writer {
+                    w.write_float(index, value);
+                }
+            }
+            DataType::Long => {
+                let value = i64::from_le_bytes(self.read_array()?);
+                if let Some(w) = writer {
+                    w.write_long(index, value);
+                }
+            }
+            DataType::Double => {
+                let value = f64::from_le_bytes(self.read_array()?);
+                if let Some(w) = writer {
+                    w.write_double(index, value);
+                }
+            }
+            DataType::String | DataType::Json => {
+                let value = String::from_utf8(self.read_bytes()?)
+                    .map_err(|_| IsarError::IllegalString {})?;
+                if let Some(w) = writer {
+                    w.write_string(index, &value);
+                }
+            }
+            DataType::Decimal => {
+                let value = Decimal::from_bytes(&self.read_array::<16>()?)
+                    .ok_or(IsarError::IllegalArgument {})?;
+                if let Some(w) = writer {
+                    w.write_decimal(index, value);
+                }
+            }
+            DataType::Uuid => {
+                let value = Uuid::from_bytes(&self.read_array::<16>()?)
+                    .ok_or(IsarError::IllegalArgument {})?;
+                if let Some(w) = writer {
+                    w.write_uuid(index, value);
+                }
+            }
+            DataType::DateTimeOffset => {
+                let value = DateTimeOffset::from_bytes(&self.read_array::<10>()?)
+                    .ok_or(IsarError::IllegalArgument {})?;
+                if let Some(w) = writer {
+                    w.write_date_time_offset(index, value);
+                }
+            }
+            DataType::Blob => {
+                let value = self.read_bytes()?;
+                if let Some(w) = writer {
+                    w.write_blob(index, &value);
+                }
+            }
+            DataType::Object => {
+                let schema = embedded.ok_or(IsarError::IllegalArgument {})?;
+                if let Some(writer) = writer {
+                    if let Some(mut object) = writer.begin_object(index) {
+                        self.read_object(schemas, schema, Some(&mut object))?;
+                        writer.end_object(object);
+                        return Ok(());
+                    }
+                }
+                self.read_object(schemas, schema, None::<&mut W::ObjectWriter>)?;
+            }
+            _ if data_type.is_map() => {
+                let value_type = data_type.value_type().unwrap();
+                let length = self.read_u32()?;
+                let mut keys = Vec::with_capacity(length as usize);
+                for _ in 0..length {
+                    let key = String::from_utf8(self.read_bytes()?)
+                        .map_err(|_| IsarError::IllegalString {})?;
+                    keys.push(key);
+                }
+                if let Some(writer) = writer {
+                    let keys = keys.iter().map(|key| key.as_str()).collect::<Vec<_>>();
+                    if let Some(mut map) = writer.begin_map(index, &keys) {
+                        for i in 0..length {
+                            let map_writer = Some(&mut map);
+                            self.read_value(schemas, value_type, embedded, i, map_writer)?;
+                        }
+                        writer.end_list(map);
+                        return Ok(());
+                    }
+                }
+                for i in 0..length {
+                    let map_writer = None::<&mut W::ListWriter>;
+                    self.read_value(schemas, value_type, embedded, i, map_writer)?;
+                }
+            }
+            _ => {
+                let element_type = data_type.element_type().unwrap();
+                let length = self.read_u32()?;
+                if let Some(writer) = writer {
+                    if let Some(mut list) = writer.begin_list(index, length) {
+                        for i in 0..length {
+                            let list_writer = Some(&mut list);
+                            self.read_value(schemas, element_type, embedded, i, list_writer)?;
+                        }
+                        writer.end_list(list);
+                        return Ok(());
+                    }
+                }
+                for i in 0..length {
+                    let list_writer = None::<&mut W::ListWriter>;
+                    self.read_value(schemas, element_type, embedded, i, list_writer)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(magic: &[u8], version: u16, schema_json: &str) -> Vec<u8> {
+        let mut bytes = magic.to_vec();
+        bytes.extend_from_slice(&version.to_le_bytes());
+        bytes.extend_from_slice(&(schema_json.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(schema_json.as_bytes());
+        bytes
+    }
+
+    fn read_header(bytes: &[u8]) -> Result<Vec<IsarSchema>> {
+        SnapshotReader { reader: bytes }.read_header()
+    }
+
+    #[test]
+    fn test_read_header() {
+        let json = r#"[{"name":"col","idName":"id","properties":[{"name":"a","type":"Float"}]}]"#;
+        let schemas = read_header(&header(MAGIC, SNAPSHOT_VERSION, json)).unwrap();
+        assert_eq!(schemas.len(), 1);
+        assert_eq!(schemas[0].id_name.as_deref(), Some("id"));
+        assert_eq!(schemas[0].properties[0].data_type, DataType::Float);
+
+        assert_eq!(
+            read_header(&header(b"ISAR", SNAPSHOT_VERSION, json)),
+            Err(IsarError::IllegalArgument {})
+        );
+        assert_eq!(
+            read_header(&header(MAGIC, SNAPSHOT_VERSION + 1, json)),
+            Err(IsarError::VersionError {})
+        );
+        let bytes = header(MAGIC, SNAPSHOT_VERSION, json);
+        assert_eq!(
+            read_header(&bytes[..bytes.len() - 1]),
+            Err(IsarError::IllegalArgument {})
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use super::data_type::DataType;
+use super::value::IsarValue;
+
+/// A change of a property that is applied without reading and rewriting the whole object.
+#[derive(Clone, PartialEq, Debug)]
+pub enum UpdateOperation {
+    Set(Option<IsarValue>),
+    /// Adds the value to a numeric property. Integers saturate at the bounds of their type and
+    /// null properties stay null.
+    Increment(IsarValue),
+    /// Appends an element to a list. Null lists become a list with the single element.
+    Append(Option<IsarValue>),
+    /// Removes all elements that are equal to the value from a list.
+    Remove(Option<IsarValue>),
+    /// Replaces the element at the given index of a list. Indexes outside of the list are
+    /// ignored.
+    SetAt(u32, Option<IsarValue>),
+}
+
+/// An update of the property at `path`. The path starts with the index of a property of the
+/// collection followed by the indexes of the properties of the embedded objects it passes
+/// through. Updates of embedded objects that are null are ignored.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PropertyUpdate {
+    pub path: Vec<u16>,
+    pub operation: UpdateOperation,
+}
+
+impl PropertyUpdate {
+    pub fn new(path: Vec<u16>, operation: UpdateOperation) -> Self {
+        Self { path, operation }
+    }
+
+    pub fn set(property_index: u16, value: Option<IsarValue>) -> Self {
+        Self::new(vec![property_index], UpdateOperation::Set(value))
+    }
+
+    pub fn increment(property_index: u16, value: IsarValue) -> Self {
+        Self::new(vec![property_index], UpdateOperation::Increment(value))
+    }
+
+    pub fn decrement(property_index: u16, value: IsarValue) -> Self {
+        let value = match value {
+            IsarValue::Integer(value) => IsarValue::Integer(value.wrapping_neg()),
+            IsarValue::Real(value) => IsarValue::Real(-value),
+            value => value,
+        };
+        Self::increment(property_index, value)
+    }
+
+    pub fn append(property_index: u16, value: Option<IsarValue>) -> Self {
+        Self::new(vec![property_index], UpdateOperation::Append(value))
+    }
+
+    pub fn remove(property_index: u16, value: Option<IsarValue>) -> Self {
+        Self::new(vec![property_index], UpdateOperation::Remove(value))
+    }
+
+    pub fn set_at(property_index: u16, index: u32, value: Option<IsarValue>) -> Self {
+        Self::new(vec![property_index], UpdateOperation::SetAt(index, value))
+    }
+
+    /// The index of the updated property of the collection.
+    pub fn property_index(&self) -> u16 {
+        self.path.first().copied().unwrap_or_default()
+    }
+
+    pub fn is_nested(&self) -> bool {
+        self.path.len() > 1
+    }
+}
+
+impl From<(u16, Option<IsarValue>)> for PropertyUpdate {
+    fn from((property_index, value): (u16, Option<IsarValue>)) -> Self {
+        Self::set(property_index, value)
+    }
+}
+
+/// Converts the updates of `IsarInstance::update` which ignores unknown properties.
+pub(crate) fn set_updates(
+    updates: &[(u16, Option<IsarValue>)],
+    has_property: impl Fn(u16) -> bool,
+) -> Vec<PropertyUpdate> {
+    updates
+        .iter()
+        .filter(|(property_index, _)| has_property(*property_index))
+        .cloned()
+        .map(PropertyUpdate::from)
+        .collect()
+}
+
+impl UpdateOperation {
+    /// Whether the operation can be applied to a property of the given type. Sets of properties
+    /// of the collection are not verified to keep the behavior of plain updates.
+    pub(crate) fn is_supported(&self, data_type: DataType, nested: bool) -> bool {
+        match self {
+            UpdateOperation::Set(value) => {
+                !nested || (is_json_scalar(data_type) && value_matches(value, data_type))
+            }
+            UpdateOperation::Increment(value) => match data_type {
+                DataType::Byte | DataType::Int | DataType::Long => {
+                    matches!(value, IsarValue::Integer(_))
+                }
+                DataType::Float | DataType::Double => {
+                    matches!(value, IsarValue::Integer(_) | IsarValue::Real(_))
+                }
+                _ => false,
+            },
+            UpdateOperation::Append(value)
+            | UpdateOperation::Remove(value)
+            | UpdateOperation::SetAt(_, value) => match data_type.element_type() {
+                // byte lists are stored as binary data and object lists have no values
+                Some(DataType::Byte) | Some(DataType::Object) | None => false,
+                Some(element_type) => value_matches(value, element_type),
+            },
+        }
+    }
+}
+
+// types that are stored as a single value in embedded objects
+fn is_json_scalar(data_type: DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Bool
+            | DataType::Byte
+            | DataType::Int
+            | DataType::Float
+            | DataType::Long
+            | DataType::Double
+            | DataType::String
+            | DataType::Decimal
+            | DataType::Uuid
+            | DataType::DateTimeOffset
+    )
+}
+
+fn value_matches(value: &Option<IsarValue>, data_type: DataType) -> bool {
+    matches!(
+        (value, data_type),
+        (None, _)
+            | (Some(IsarValue::Bool(_)), DataType::Bool)
+            | (
+                Some(IsarValue::Integer(_)),
+                DataType::Byte | DataType::Int | DataType::Long
+            )
+            | (Some(IsarValue::Real(_)), DataType::Float | DataType::Double)
+            | (Some(IsarValue::String(_)), DataType::String)
+            | (Some(IsarValue::Decimal(_)), DataType::Decimal)
+            | (Some(IsarValue::Uuid(_)), DataType::Uuid)
+            | (Some(IsarValue::DateTimeOffset(_)), DataType::DateTimeOffset)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decrement() {
+        assert_eq!(
+            PropertyUpdate::decrement(2, IsarValue::Integer(3)),
+            PropertyUpdate::increment(2, IsarValue::Integer(-3))
+        );
+        assert_eq!(
+            PropertyUpdate::decrement(2, IsarValue::Real(1.5)),
+            PropertyUpdate::increment(2, IsarValue::Real(-1.5))
+        );
+    }
+
+    #[test]
+    fn test_is_supported() {
+        let increment = UpdateOperation::Increment(IsarValue::Integer(1));
+        assert!(increment.is_supported(DataType::Int, false));
+        assert!(increment.is_supported(DataType::Double, true));
+        assert!(!increment.is_supported(DataType::String, false));
+        let increment = UpdateOperation::Increment(IsarValue::Real(1.0));
+        assert!(!increment.is_supported(DataType::Long, false));
+
+        let append = UpdateOperation::Append(Some(IsarValue::String("a".to_string())));
+        assert!(append.is_supported(DataType::StringList, false));
+        assert!(!append.is_supported(DataType::IntList, false));
+        assert!(!append.is_supported(DataType::String, false));
+        assert!(UpdateOperation::Remove(None).is_supported(DataType::IntList, false));
+        assert!(!UpdateOperation::Remove(None).is_supported(DataType::ByteList, false));
+        assert!(!UpdateOperation::SetAt(0, None).is_supported(DataType::ObjectList, false));
+
+        let set = UpdateOperation::Set(Some(IsarValue::Bool(true)));
+        assert!(set.is_supported(DataType::String, false));
+        assert!(set.is_supported(DataType::Bool, true));
+        assert!(!set.is_supported(DataType::String, true));
+        assert!(!UpdateOperation::Set(None).is_supported(DataType::StringList, true));
+    }
+}
@@ -1,9 +1,18 @@
+use super::data_type::DataType;
+use serde::de::{Error, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{self, Display, Formatter, Write};
+use std::str::FromStr;
+
 #[derive(PartialEq, Clone, Debug)]
 pub enum IsarValue {
     Bool(bool),
     Integer(i64),
     Real(f64),
     String(String),
+    Decimal(Decimal),
+    Uuid(Uuid),
+    DateTimeOffset(DateTimeOffset),
 }
 
 impl IsarValue {
@@ -48,8 +57,442 @@ impl IsarValue {
             None
         }
     }
+
+    pub fn decimal(&self) -> Option<Decimal> {
+        if let IsarValue::Decimal(value) = self {
+            Some(*value)
+        } else {
+            None
+        }
+    }
+
+    pub fn uuid(&self) -> Option<Uuid> {
+        if let IsarValue::Uuid(value) = self {
+            Some(*value)
+        } else {
+            None
+        }
+    }
+
+    pub fn date_time_offset(&self) -> Option<DateTimeOffset> {
+        if let IsarValue::DateTimeOffset(value) = self {
+            Some(*value)
+        } else {
+            None
+        }
+    }
+
+    /// The bytes that decimal, UUID and date time values are stored as. Comparing the bytes
+    /// compares the values.
+    pub fn to_bytes(&self) -> Option<Vec<u8>> {
+        match self {
+            IsarValue::Decimal(value) => Some(value.to_bytes().to_vec()),
+            IsarValue::Uuid(value) => Some(value.to_bytes().to_vec()),
+            IsarValue::DateTimeOffset(value) => Some(value.to_bytes().to_vec()),
+            _ => None,
+        }
+    }
+
+    pub fn from_bytes(data_type: DataType, bytes: &[u8]) -> Option<IsarValue> {
+        match data_type {
+            DataType::Decimal => Decimal::from_bytes(bytes).map(IsarValue::Decimal),
+            DataType::Uuid => Uuid::from_bytes(bytes).map(IsarValue::Uuid),
+            DataType::DateTimeOffset => {
+                DateTimeOffset::from_bytes(bytes).map(IsarValue::DateTimeOffset)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ParseValueError;
+
+impl Display for ParseValueError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "invalid value")
+    }
+}
+
+/// A fixed-point number with 18 fractional digits.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Decimal(i128);
+
+impl Decimal {
+    pub const SCALE: u32 = 18;
+
+    const ONE: i128 = 10i128.pow(Self::SCALE);
+
+    /// Creates a decimal from its value multiplied by 10^18.
+    pub const fn from_raw(raw: i128) -> Self {
+        Decimal(raw)
+    }
+
+    pub const fn raw(&self) -> i128 {
+        self.0
+    }
+
+    pub fn to_bytes(&self) -> [u8; 16] {
+        (self.0 as u128 ^ 1 << 127).to_be_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let unsigned = u128::from_be_bytes(bytes.try_into().ok()?);
+        Some(Decimal((unsigned ^ 1 << 127) as i128))
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(value: i64) -> Self {
+        Decimal(value as i128 * Self::ONE)
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(s) => (true, s),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (integer, fraction) = s.split_once('.').unwrap_or((s, ""));
+        let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if (integer.is_empty() && fraction.is_empty())
+            || !is_digits(integer)
+            || !is_digits(fraction)
+            || fraction.len() > Self::SCALE as usize
+        {
+            return Err(ParseValueError);
+        }
+
+        let mut raw: u128 = 0;
+        for digit in integer.bytes().chain(fraction.bytes()) {
+            raw = raw
+                .checked_mul(10)
+                .and_then(|raw| raw.checked_add((digit - b'0') as u128))
+                .ok_or(ParseValueError)?;
+        }
+        raw = raw
+            .checked_mul(10u128.pow(Self::SCALE - fraction.len() as u32))
+            .ok_or(ParseValueError)?;
+        if negative && raw <= 1 << 127 {
+            Ok(Decimal((raw as i128).wrapping_neg()))
+        } else {
+            i128::try_from(raw)
+                .map(Decimal)
+                .map_err(|_| ParseValueError)
+        }
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let unsigned = self.0.unsigned_abs();
+        let one = Self::ONE as u128;
+        if self.0 < 0 {
+            f.write_char('-')?;
+        }
+        write!(f, "{}", unsigned / one)?;
+        let fraction = unsigned % one;
+        if fraction != 0 {
+            let digits = format!("{:018}", fraction);
+            write!(f, ".{}", digits.trim_end_matches('0'))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Uuid(pub [u8; 16]);
+
+impl Uuid {
+    pub fn to_bytes(&self) -> [u8; 16] {
+        self.0
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Some(Uuid(bytes.try_into().ok()?))
+    }
+}
+
+impl FromStr for Uuid {
+    type Err = ParseValueError;
+
+    /// Parses the hyphenated or the simple format.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = if s.len() == 36 {
+            let hyphens = [8, 13, 18, 23];
+            if hyphens.iter().any(|i| s.as_bytes()[*i] != b'-') {
+                return Err(ParseValueError);
+            }
+            s.replace('-', "")
+        } else {
+            s.to_string()
+        };
+        // from_str_radix would also accept a sign
+        if hex.len() != 32 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(ParseValueError);
+        }
+
+        let mut bytes = [0; 16];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            let digits = hex.get(i * 2..i * 2 + 2).ok_or(ParseValueError)?;
+            *byte = u8::from_str_radix(digits, 16).map_err(|_| ParseValueError)?;
+        }
+        Ok(Uuid(bytes))
+    }
+}
+
+impl Display for Uuid {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if i == 4 || i == 6 || i == 8 || i == 10 {
+                f.write_char('-')?;
+            }
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// An instant and the UTC offset it was recorded in. Values are ordered by the instant first.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct DateTimeOffset {
+    /// Microseconds since the Unix epoch.
+    pub micros: i64,
+    /// The offset from UTC in minutes.
+    pub offset: i16,
+}
+
+impl DateTimeOffset {
+    const MICROS_PER_SECOND: i64 = 1_000_000;
+
+    const MICROS_PER_DAY: i64 = 86_400 * Self::MICROS_PER_SECOND;
+
+    pub fn new(micros: i64, offset: i16) -> Self {
+        DateTimeOffset { micros, offset }
+    }
+
+    pub fn to_bytes(&self) -> [u8; 10] {
+        let mut bytes = [0; 10];
+        bytes[..8].copy_from_slice(&(self.micros as u64 ^ 1 << 63).to_be_bytes());
+        bytes[8..].copy_from_slice(&(self.offset as u16 ^ 1 << 15).to_be_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 10 {
+            return None;
+        }
+        let micros = u64::from_be_bytes(bytes[..8].try_into().ok()?) ^ 1 << 63;
+        let offset = u16::from_be_bytes(bytes[8..].try_into().ok()?) ^ 1 << 15;
+        Some(DateTimeOffset::new(micros as i64, offset as i16))
+    }
+}
+
+// days since the Unix epoch of a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+impl FromStr for DateTimeOffset {
+    type Err = ParseValueError;
+
+    /// Parses RFC 3339 date times like `2024-05-01T12:30:00.5+02:00`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = |range: std::ops::Range<usize>| -> Result<i64, ParseValueError> {
+            let digits = s.get(range).ok_or(ParseValueError)?;
+            if !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(ParseValueError);
+            }
+            digits.parse().map_err(|_| ParseValueError)
+        };
+        let separators = [(4, b'-'), (7, b'-'), (13, b':'), (16, b':')];
+        if s.len() < 20
+            || !s.is_ascii()
+            || separators.iter().any(|(i, c)| s.as_bytes()[*i] != *c)
+            || !matches!(s.as_bytes()[10], b'T' | b't' | b' ')
+        {
+            return Err(ParseValueError);
+        }
+        let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+        let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+        if !(1..=12).contains(&month)
+            || !(1..=31).contains(&day)
+            || hour > 23
+            || minute > 59
+            || second > 59
+        {
+            return Err(ParseValueError);
+        }
+
+        let mut rest = &s[19..];
+        let mut micros = 0;
+        if let Some(fraction) = rest.strip_prefix('.') {
+            let length = fraction.bytes().take_while(|b| b.is_ascii_digit()).count();
+            if length == 0 {
+                return Err(ParseValueError);
+            }
+            for (i, digit) in fraction.bytes().take(length.min(6)).enumerate() {
+                micros += (digit - b'0') as i64 * 10i64.pow(5 - i as u32);
+            }
+            rest = &fraction[length..];
+        }
+
+        let offset = match rest {
+            "Z" | "z" => 0,
+            _ if rest.len() == 6 && rest.as_bytes()[3] == b':' => {
+                let hours = rest[1..3].parse::<i64>().map_err(|_| ParseValueError)?;
+                let minutes = rest[4..6].parse::<i64>().map_err(|_| ParseValueError)?;
+                if hours > 23 || minutes > 59 {
+                    return Err(ParseValueError);
+                }
+                match rest.as_bytes()[0] {
+                    b'+' => hours * 60 + minutes,
+                    b'-' => -(hours * 60 + minutes),
+                    _ => return Err(ParseValueError),
+                }
+            }
+            _ => return Err(ParseValueError),
+        };
+
+        let local_seconds =
+            days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second;
+        let seconds = local_seconds - offset * 60;
+        Ok(DateTimeOffset::new(
+            seconds * Self::MICROS_PER_SECOND + micros,
+            offset as i16,
+        ))
+    }
+}
+
+impl Display for DateTimeOffset {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let local =
+            self.micros as i128 + self.offset as i128 * 60 * Self::MICROS_PER_SECOND as i128;
+        let days = local.div_euclid(Self::MICROS_PER_DAY as i128) as i64;
+        let time = local.rem_euclid(Self::MICROS_PER_DAY as i128) as i64;
+        let (year, month, day) = civil_from_days(days);
+        let seconds = time / Self::MICROS_PER_SECOND;
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )?;
+        let micros = time % Self::MICROS_PER_SECOND;
+        if micros != 0 {
+            let digits = format!("{:06}", micros);
+            write!(f, ".{}", digits.trim_end_matches('0'))?;
+        }
+        if self.offset == 0 {
+            f.write_char('Z')
+        } else {
+            let sign = if self.offset < 0 { '-' } else { '+' };
+            let offset = self.offset.unsigned_abs();
+            write!(f, "{}{:02}:{:02}", sign, offset / 60, offset % 60)
+        }
+    }
+}
+
+// decimal, UUID and date time values are exchanged as strings in JSON
+macro_rules! string_serde {
+    ($type:ty, $expecting:expr) => {
+        impl Serialize for $type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct ValueVisitor;
+
+                impl<'de> Visitor<'de> for ValueVisitor {
+                    type Value = $type;
+
+                    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+                        formatter.write_str($expecting)
+                    }
+
+                    fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+                        v.parse()
+                            .map_err(|_| E::custom(format!("expected {}", $expecting)))
+                    }
+
+                    fn visit_i64<E: Error>(self, v: i64) -> Result<Self::Value, E> {
+                        self.visit_str(&v.to_string())
+                    }
+
+                    fn visit_u64<E: Error>(self, v: u64) -> Result<Self::Value, E> {
+                        self.visit_str(&v.to_string())
+                    }
+
+                    fn visit_f64<E: Error>(self, v: f64) -> Result<Self::Value, E> {
+                        self.visit_str(&v.to_string())
+                    }
+                }
+
+                deserializer.deserialize_any(ValueVisitor)
+            }
+        }
+    };
+}
+
+string_serde!(Decimal, "a decimal number");
+string_serde!(Uuid, "a UUID");
+string_serde!(DateTimeOffset, "an RFC 3339 date time");
+
+macro_rules! from_value {
+    ($type:ty, $variant:ident, $value:ident => $conversion:expr) => {
+        impl From<$type> for IsarValue {
+            fn from($value: $type) -> Self {
+                IsarValue::$variant($conversion)
+            }
+        }
+    };
 }
 
+from_value!(bool, Bool, value => value);
+from_value!(u8, Integer, value => value as i64);
+from_value!(i32, Integer, value => value as i64);
+from_value!(i64, Integer, value => value);
+from_value!(f32, Real, value => value as f64);
+from_value!(f64, Real, value => value);
+from_value!(String, String, value => value);
+from_value!(&str, String, value => value.to_string());
+from_value!(Decimal, Decimal, value => value);
+from_value!(Uuid, Uuid, value => value);
+from_value!(DateTimeOffset, DateTimeOffset, value => value);
+
 #[cfg(test)]
 impl Eq for IsarValue {}
 
@@ -293,4 +736,120 @@ mod tests {
             );
         }
     }
+
+    mod decimal {
+        use super::super::Decimal;
+
+        #[test]
+        fn test_parse_and_display() {
+            let values = [
+                ("0", "0"),
+                ("-0.0", "0"),
+                ("12.50", "12.5"),
+                ("+3", "3"),
+                ("-0.000000000000000001", "-0.000000000000000001"),
+                (".5", "0.5"),
+            ];
+            for (input, output) in values {
+                let decimal: Decimal = input.parse().unwrap();
+                assert_eq!(decimal.to_string(), output);
+            }
+            assert_eq!(
+                Decimal::from_raw(i128::MIN).to_string().parse(),
+                Ok(Decimal::from_raw(i128::MIN))
+            );
+            for input in [
+                "",
+                ".",
+                "1.2.3",
+                "1e5",
+                "0.0000000000000000001",
+                "1000000000000000000000",
+            ] {
+                assert!(input.parse::<Decimal>().is_err());
+            }
+        }
+
+        #[test]
+        fn test_bytes_preserve_order() {
+            let values = ["-10.5", "-1", "-0.5", "0", "0.25", "1", "100"];
+            for pair in values.windows(2) {
+                let a: Decimal = pair[0].parse().unwrap();
+                let b: Decimal = pair[1].parse().unwrap();
+                assert!(a.to_bytes() < b.to_bytes());
+                assert_eq!(Decimal::from_bytes(&a.to_bytes()), Some(a));
+            }
+        }
+    }
+
+    mod uuid {
+        use super::super::Uuid;
+
+        #[test]
+        fn test_parse_and_display() {
+            let uuid: Uuid = "67E55044-10B1-426F-9247-BB680E5FE0C8".parse().unwrap();
+            assert_eq!(uuid.to_string(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+            assert_eq!("67e5504410b1426f9247bb680e5fe0c8".parse(), Ok(uuid));
+            assert_eq!(Uuid::from_bytes(&uuid.to_bytes()), Some(uuid));
+            for input in [
+                "",
+                "67e55044-10b1-426f-9247",
+                "67e55044_10b1_426f_9247_bb680e5fe0c8",
+                "+7e55044-10b1-426f-9247-bb680e5fe0c8",
+                "+7e5504410b1426f9247bb680e5fe0c8",
+            ] {
+                assert!(input.parse::<Uuid>().is_err());
+            }
+        }
+    }
+
+    mod date_time_offset {
+        use super::super::DateTimeOffset;
+
+        #[test]
+        fn test_parse_and_display() {
+            let value: DateTimeOffset = "2024-02-29T23:30:00.25+02:00".parse().unwrap();
+            assert_eq!(value.micros, 1_709_242_200_250_000);
+            assert_eq!(value.offset, 120);
+            assert_eq!(value.to_string(), "2024-02-29T23:30:00.25+02:00");
+
+            let value: DateTimeOffset = "1969-12-31T23:59:59Z".parse().unwrap();
+            assert_eq!(value, DateTimeOffset::new(-1_000_000, 0));
+            assert_eq!(value.to_string(), "1969-12-31T23:59:59Z");
+
+            let value: DateTimeOffset = "2000-01-01T00:00:00-05:30".parse().unwrap();
+            assert_eq!(value.to_string(), "2000-01-01T00:00:00-05:30");
+
+            for input in [
+                "",
+                "2024-02-29",
+                "2024-13-01T00:00:00Z",
+                "2024-01-01T00:00:00+2:00",
+                "2024-05-01T12:30:00é1:23",
+                "2024-05-01T12:30:00+0é:00",
+            ] {
+                assert!(input.parse::<DateTimeOffset>().is_err());
+            }
+        }
+
+        #[test]
+        fn test_bytes_preserve_order() {
+            let values = [
+                DateTimeOffset::new(i64::MIN, 0),
+                DateTimeOffset::new(-1, 60),
+                DateTimeOffset::new(0, -60),
+                DateTimeOffset::new(0, 0),
+                DateTimeOffset::new(0, 60),
+                DateTimeOffset::new(1, -60),
+            ];
+            for pair in values.windows(2) {
+                assert!(pair[0] < pair[1]);
+                assert!(pair[0].to_bytes() < pair[1].to_bytes());
+                assert_eq!(
+                    DateTimeOffset::from_bytes(&pair[0].to_bytes()),
+                    Some(pair[0])
+                );
+            }
+        }
+    }
 }
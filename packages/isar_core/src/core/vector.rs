@@ -0,0 +1,81 @@
+use super::schema::VectorMetric;
+
+/// Compares a stored vector with a query vector. Cosine and dot scores grow with the similarity,
+/// `L2` returns the euclidean distance. Vectors with a different number of dimensions or missing
+/// elements have no score.
+pub fn score(
+    metric: VectorMetric,
+    query: &[f32],
+    vector: impl ExactSizeIterator<Item = f32>,
+) -> Option<f64> {
+    if vector.len() != query.len() {
+        return None;
+    }
+
+    let mut dot = 0.0;
+    let mut norm_query = 0.0;
+    let mut norm_vector = 0.0;
+    let mut l2 = 0.0;
+    for (q, v) in query.iter().zip(vector) {
+        if v.is_nan() {
+            return None;
+        }
+        let (q, v) = (*q as f64, v as f64);
+        dot += q * v;
+        norm_query += q * q;
+        norm_vector += v * v;
+        l2 += (q - v) * (q - v);
+    }
+
+    match metric {
+        VectorMetric::Cosine => {
+            let norm = (norm_query * norm_vector).sqrt();
+            if norm == 0.0 {
+                None
+            } else {
+                Some(dot / norm)
+            }
+        }
+        VectorMetric::Dot => Some(dot),
+        VectorMetric::L2 => Some(l2.sqrt()),
+    }
+}
+
+/// Sort key of a score where the most similar vectors come first and vectors without a score
+/// come last.
+pub fn sort_key(metric: VectorMetric, score: Option<f64>) -> f64 {
+    match (metric, score) {
+        (_, None) => f64::INFINITY,
+        (VectorMetric::L2, Some(score)) => score,
+        (_, Some(score)) => -score,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        let query = [1.0, 0.0];
+        let score = |metric, vector: &[f32]| score(metric, &query, vector.iter().copied());
+
+        assert_eq!(score(VectorMetric::Cosine, &[2.0, 0.0]), Some(1.0));
+        assert_eq!(score(VectorMetric::Cosine, &[0.0, 3.0]), Some(0.0));
+        assert_eq!(score(VectorMetric::Cosine, &[0.0, 0.0]), None);
+        assert_eq!(score(VectorMetric::Dot, &[2.0, 5.0]), Some(2.0));
+        assert_eq!(score(VectorMetric::L2, &[4.0, 4.0]), Some(5.0));
+
+        assert_eq!(score(VectorMetric::L2, &[1.0]), None);
+        assert_eq!(score(VectorMetric::L2, &[1.0, f32::NAN]), None);
+    }
+
+    #[test]
+    fn test_sort_key() {
+        assert!(
+            sort_key(VectorMetric::Cosine, Some(0.9)) < sort_key(VectorMetric::Cosine, Some(0.1))
+        );
+        assert!(sort_key(VectorMetric::L2, Some(0.1)) < sort_key(VectorMetric::L2, Some(0.9)));
+        assert_eq!(sort_key(VectorMetric::Dot, None), f64::INFINITY);
+    }
+}
@@ -56,6 +56,7 @@ pub(crate) trait QueryMatches: Clone {
     fn matches<'a>(&self, id: i64, object: &Self::Object<'a>) -> bool;
 }
 
+#[derive(Clone)]
 pub(crate) struct ChangeSet {
     changes: IntMap<Arc<Watcher>>,
 }
@@ -97,6 +98,7 @@ impl ChangeSet {
         }
     }
 
+    #[cfg(any(feature = "native", test))]
     pub fn register_all<Q: QueryMatches>(&mut self, cw: &CollectionWatchers<Q>) {
         let w = cw.col_watchers.load();
         self.mark_watchers_changed(&w.watchers);
@@ -205,10 +207,12 @@ impl<Q: QueryMatches + 'static> CollectionWatchers<Q> {
         }))
     }
 
+    #[cfg(feature = "native")]
     pub fn has_query_watchers(&self) -> bool {
         !self.col_watchers.load().query_watchers.is_empty()
     }
 
+    #[cfg(feature = "sqlite")]
     pub fn has_watchers(&self) -> bool {
         let w = self.col_watchers.load();
         !w.watchers.is_empty() || !w.object_watchers.is_empty() || !w.query_watchers.is_empty()
@@ -1,4 +1,5 @@
 use super::data_type::DataType;
+use super::value::{DateTimeOffset, Decimal, Uuid};
 
 pub trait IsarWriter<'a> {
     type ObjectWriter: IsarWriter<'a>;
@@ -25,8 +26,17 @@ pub trait IsarWriter<'a> {
 
     fn write_string(&mut self, index: u32, value: &str);
 
+    fn write_decimal(&mut self, index: u32, value: Decimal);
+
+    fn write_uuid(&mut self, index: u32, value: Uuid);
+
+    fn write_date_time_offset(&mut self, index: u32, value: DateTimeOffset);
+
     fn write_byte_list(&mut self, index: u32, value: &[u8]);
 
+    /// Writes the payload of a blob property. Only properties of collections can be blobs.
+    fn write_blob(&mut self, index: u32, value: &[u8]);
+
     fn begin_object(&mut self, index: u32) -> Option<Self::ObjectWriter>;
 
     fn end_object(&mut self, writer: Self::ObjectWriter);
@@ -34,4 +44,8 @@ pub trait IsarWriter<'a> {
     fn begin_list(&mut self, index: u32, length: u32) -> Option<Self::ListWriter>;
 
     fn end_list(&mut self, writer: Self::ListWriter);
+
+    /// Writes the keys of a map with string keys. The values are written to the returned list
+    /// writer in the order of the keys and the map is finished with `end_list`.
+    fn begin_map(&mut self, index: u32, keys: &[&str]) -> Option<Self::ListWriter>;
 }
@@ -4,6 +4,9 @@
 #[cfg(not(target_endian = "little"))]
 compile_error!("Only little endian systems are supported.");
 
+// allows the derive macros to be used inside this crate
+extern crate self as isar_core;
+
 pub mod core;
 
 pub const SQLITE_MEMORY_DIR: &str = ":memory:";
@@ -13,3 +16,15 @@ pub mod native;
 
 #[cfg(feature = "sqlite")]
 pub mod sqlite;
+
+#[cfg(all(feature = "native", feature = "sqlite"))]
+pub mod any;
+
+#[cfg(all(feature = "native", feature = "sqlite"))]
+pub mod convert;
+
+#[cfg(all(test, feature = "native"))]
+mod test_util;
+
+#[cfg(all(test, feature = "native", feature = "sqlite"))]
+mod backend_tests;
@@ -1,6 +1,6 @@
 use xxhash_rust::xxh3::xxh3_64;
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Debug)]
 pub struct IndexKey {
     bytes: Vec<u8>,
     contains_null: bool,
@@ -103,6 +103,16 @@ impl IndexKey {
         }
     }
 
+    pub fn add_bytes(&mut self, value: Option<&[u8]>) {
+        if let Some(value) = value {
+            self.bytes.push(1);
+            self.bytes.extend_from_slice(value);
+        } else {
+            self.contains_null = true;
+            self.bytes.push(0);
+        }
+    }
+
     pub fn finish(mut self) -> (Vec<u8>, bool) {
         if self.bytes.len() > IndexKey::MAX_INDEX_SIZE {
             let hash = xxh3_64(&self.bytes);
@@ -342,6 +352,22 @@ mod tests {
         assert_eq!(index_key.finish(), (bytes, false));
     }
 
+    #[test]
+    fn test_add_bytes() {
+        let pairs = vec![
+            (None, vec![123, 0], true),
+            (Some(vec![]), vec![123, 1], false),
+            (Some(vec![0, 255]), vec![123, 1, 0, 255], false),
+        ];
+
+        for (val, bytes, contains_null) in pairs {
+            let mut index_key = IndexKey::min();
+            index_key.add_byte(123);
+            index_key.add_bytes(val.as_deref());
+            assert_eq!(index_key.finish(), (bytes, contains_null));
+        }
+    }
+
     #[test]
     fn test_hash() {
         let mut index_key = IndexKey::min();
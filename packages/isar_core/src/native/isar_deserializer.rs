@@ -32,7 +32,7 @@ impl<'a> IsarDeserializer<'a> {
             DataType::Byte => !self.contains_offset(offset),
             DataType::Int => self.read_int(offset) == NULL_INT,
             DataType::Float => self.read_float(offset).is_nan(),
-            DataType::Long => self.read_long(offset) == NULL_LONG,
+            DataType::Long | DataType::Blob => self.read_long(offset) == NULL_LONG,
             DataType::Double => self.read_double(offset).is_nan(),
             _ => self.get_offset_length(offset).is_none(),
         }
@@ -114,6 +114,15 @@ impl<'a> IsarDeserializer<'a> {
         Some((offset as usize + 3, length as usize))
     }
 
+    #[inline]
+    pub fn read_coordinate(&self, offset: u32, data_type: DataType) -> f64 {
+        if data_type == DataType::Float {
+            self.read_float(offset) as f64
+        } else {
+            self.read_double(offset)
+        }
+    }
+
     #[inline]
     pub fn read_dynamic(&self, offset: u32) -> Option<&'a [u8]> {
         let (offset, length) = self.get_offset_length(offset)?;
@@ -145,6 +154,19 @@ impl<'a> IsarDeserializer<'a> {
         Some((nested, length))
     }
 
+    /// Returns the keys, the values and the length of a map with string keys.
+    #[inline]
+    pub fn read_map(
+        &self,
+        offset: u32,
+        value_type: DataType,
+    ) -> Option<(IsarDeserializer<'a>, IsarDeserializer<'a>, u32)> {
+        let map = self.read_nested(offset)?;
+        let (keys, length) = map.read_list(0, DataType::String)?;
+        let (values, _) = map.read_list(3, value_type)?;
+        Some((keys, values, length))
+    }
+
     pub fn hash_property(
         &self,
         offset: u32,
@@ -195,6 +217,14 @@ impl<'a> IsarDeserializer<'a> {
                     xxh3_64_with_seed(&[0], seed)
                 }
             }
+            DataType::Decimal | DataType::Uuid | DataType::DateTimeOffset => {
+                if let Some(bytes) = self.read_dynamic(offset) {
+                    seed = xxh3_64_with_seed(&[1], seed);
+                    xxh3_64_with_seed(bytes, seed)
+                } else {
+                    xxh3_64_with_seed(&[0], seed)
+                }
+            }
             _ => seed,
         }
     }
@@ -1,3 +1,4 @@
+use super::isar_deserializer::IsarDeserializer;
 use super::{FALSE_BOOL, NULL_BOOL, NULL_DOUBLE, NULL_FLOAT, NULL_INT, NULL_LONG, TRUE_BOOL};
 use crate::core::data_type::DataType;
 use byteorder::{ByteOrder, LittleEndian};
@@ -69,7 +70,7 @@ impl IsarSerializer {
             DataType::Byte => self.write_byte(offset, 0),
             DataType::Int => self.write_int(offset, NULL_INT),
             DataType::Float => self.write_float(offset, NULL_FLOAT),
-            DataType::Long => self.write_long(offset, NULL_LONG),
+            DataType::Long | DataType::Blob => self.write_long(offset, NULL_LONG),
             DataType::Double => self.write_double(offset, NULL_DOUBLE),
             _ => self.write_u24_static_checked(offset, 0),
         }
@@ -135,6 +136,47 @@ impl IsarSerializer {
         self.write_dynamic(offset, value);
     }
 
+    /// Appends the nested object and points the property at `offset` to it.
+    pub fn write_nested(&mut self, offset: u32, object: &[u8]) {
+        let buffer_len = self.buffer.get_mut().len() as u32;
+        self.write_u24_static_checked(offset, buffer_len - self.offset);
+        self.buffer.get_mut().extend_from_slice(object);
+    }
+
+    /// Returns the bytes from the start of the nested object at `offset` to the end of the
+    /// buffer or `None` if the nested object is null.
+    pub fn read_nested(&mut self, offset: u32) -> Option<&[u8]> {
+        let nested_offset = self.read_u24(offset);
+        if nested_offset != 0 {
+            let start = (self.offset + nested_offset) as usize;
+            Some(&self.buffer.get_mut()[start..])
+        } else {
+            None
+        }
+    }
+
+    /// Returns a serializer that writes to the existing nested object at `offset` in place.
+    /// Dynamic data of the nested object is appended to the buffer.
+    pub fn edit_nested(&mut self, offset: u32) -> Option<Self> {
+        let nested_offset = self.read_u24(offset);
+        if nested_offset != 0 {
+            let static_size = self.read_u24(nested_offset);
+            Some(Self {
+                buffer: Cell::new(self.buffer.take()),
+                offset: self.offset + nested_offset + 3,
+                static_size,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns a deserializer of the object written so far.
+    pub fn as_deserializer(&mut self) -> IsarDeserializer<'_> {
+        let start = self.offset as usize - 3;
+        IsarDeserializer::from_bytes(&self.buffer.get_mut()[start..])
+    }
+
     pub fn begin_nested(&mut self, offset: u32, static_size: u32) -> Self {
         let nested_offset = self.buffer.get_mut().len() as u32;
         self.write_u24_static_checked(offset, nested_offset - self.offset);
@@ -19,7 +19,7 @@ impl UnboundCursor {
 
     pub fn bind<'txn>(self, txn: &'txn Txn, db: Db) -> Result<Cursor<'txn>> {
         unsafe {
-            mdbx_result(mdbx_sys::mdbx_cursor_bind(txn.txn, self.cursor, db.dbi))?;
+            mdbx_result(mdbx_sys::mdbx_cursor_bind(txn.ptr(), self.cursor, db.dbi))?;
         }
 
         Ok(Cursor {
@@ -94,8 +94,7 @@ impl<'txn> Cursor<'txn> {
         self.op_get(mdbx_sys::MDBX_cursor_op::MDBX_SET_RANGE, Some(key), None)
     }
 
-    #[allow(dead_code)]
-    fn move_to_next_dup(&mut self) -> Result<Option<KeyVal<'txn>>> {
+    pub fn move_to_next_dup(&mut self) -> Result<Option<KeyVal<'txn>>> {
         self.op_get(mdbx_sys::MDBX_cursor_op::MDBX_NEXT_DUP, None, None)
     }
 
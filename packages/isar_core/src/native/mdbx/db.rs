@@ -5,7 +5,7 @@ use std::ffi::CString;
 use std::mem::size_of;
 use std::ptr;
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub(crate) struct Db {
     pub(crate) dbi: mdbx_sys::MDBX_dbi,
     pub dup: bool,
@@ -26,7 +26,7 @@ impl Db {
             let name = CString::new(name.as_bytes()).unwrap();
             unsafe {
                 mdbx_result(mdbx_sys::mdbx_dbi_open(
-                    txn.txn,
+                    txn.ptr(),
                     name.as_ptr(),
                     flags,
                     &mut dbi,
@@ -34,7 +34,7 @@ impl Db {
             }
         } else {
             unsafe {
-                mdbx_result(mdbx_sys::mdbx_dbi_open(txn.txn, ptr::null(), 0, &mut dbi))?;
+                mdbx_result(mdbx_sys::mdbx_dbi_open(txn.ptr(), ptr::null(), 0, &mut dbi))?;
             }
         }
 
@@ -54,7 +54,7 @@ impl Db {
         let stat_ptr = &mut stat as *mut mdbx_sys::MDBX_stat;
         unsafe {
             mdbx_sys::mdbx_dbi_stat(
-                txn.txn,
+                txn.ptr(),
                 self.dbi,
                 stat_ptr,
                 size_of::<mdbx_sys::MDBX_stat>() as mdbx_sys::size_t,
@@ -66,12 +66,12 @@ impl Db {
     }
 
     pub fn clear(&self, txn: &Txn) -> Result<()> {
-        unsafe { mdbx_result(mdbx_sys::mdbx_drop(txn.txn, self.dbi, false)) }?;
+        unsafe { mdbx_result(mdbx_sys::mdbx_drop(txn.ptr(), self.dbi, false)) }?;
         Ok(())
     }
 
     pub fn drop(self, txn: &Txn) -> Result<()> {
-        unsafe { mdbx_result(mdbx_sys::mdbx_drop(txn.txn, self.dbi, true)) }?;
+        unsafe { mdbx_result(mdbx_sys::mdbx_drop(txn.ptr(), self.dbi, true)) }?;
         Ok(())
     }
 }
@@ -102,7 +102,7 @@ mod tests {
             let mut actual_flags: u32 = 0;
             let txn = env.txn(false).unwrap();
             unsafe {
-                mdbx_sys::mdb_dbi_flags(txn.txn, db.dbi, &mut actual_flags);
+                mdbx_sys::mdb_dbi_flags(txn.ptr(), db.dbi, &mut actual_flags);
             }
             txn.abort();
             assert_eq!(*flags, actual_flags);
@@ -66,11 +66,23 @@ impl Env {
 
     pub fn txn(self: &Arc<Self>, write: bool) -> Result<Txn> {
         let flags = if write { 0 } else { mdbx_sys::MDBX_TXN_RDONLY };
+        self.begin_txn(ptr::null_mut(), flags)
+    }
+
+    pub fn nested_txn(self: &Arc<Self>, parent: &Txn) -> Result<Txn> {
+        self.begin_txn(parent.ptr(), 0)
+    }
+
+    fn begin_txn(
+        self: &Arc<Self>,
+        parent: *mut mdbx_sys::MDBX_txn,
+        flags: mdbx_sys::MDBX_txn_flags_t,
+    ) -> Result<Txn> {
         let mut txn: *mut mdbx_sys::MDBX_txn = ptr::null_mut();
         unsafe {
             mdbx_result(mdbx_sys::mdbx_txn_begin_ex(
                 self.env,
-                ptr::null_mut(),
+                parent,
                 flags,
                 &mut txn,
                 ptr::null_mut(),
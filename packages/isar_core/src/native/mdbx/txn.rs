@@ -1,22 +1,46 @@
 use super::{env::Env, mdbx_result};
 use crate::core::error::Result;
 use core::ptr;
+use std::cell::Cell;
 use std::sync::Arc;
 
 pub(crate) struct Txn {
-    pub(crate) txn: *mut mdbx_sys::MDBX_txn,
-    _env: Arc<Env>,
+    // replaced when a nested txn is restarted
+    txn: Cell<*mut mdbx_sys::MDBX_txn>,
+    env: Arc<Env>,
 }
 
 impl Txn {
     pub(crate) fn new(env: Arc<Env>, txn: *mut mdbx_sys::MDBX_txn) -> Self {
-        Txn { txn, _env: env }
+        Txn {
+            txn: Cell::new(txn),
+            env,
+        }
+    }
+
+    pub(crate) fn ptr(&self) -> *mut mdbx_sys::MDBX_txn {
+        self.txn.get()
+    }
+
+    // the parent can't be used until the nested txn is committed or aborted
+    pub fn nested(&self) -> Result<Txn> {
+        self.env.nested_txn(self)
+    }
+
+    /// Aborts this nested txn and begins a new one on the parent. Cursors bound to this txn
+    /// must not be used afterwards.
+    pub fn restart_nested(&self, parent: &Txn) -> Result<()> {
+        unsafe {
+            mdbx_sys::mdbx_txn_abort(self.txn.replace(ptr::null_mut()));
+        }
+        let nested = parent.nested()?;
+        self.txn.set(nested.txn.replace(ptr::null_mut()));
+        Ok(())
     }
 
-    pub fn commit(mut self) -> Result<()> {
-        let result =
-            unsafe { mdbx_result(mdbx_sys::mdbx_txn_commit_ex(self.txn, ptr::null_mut())) };
-        self.txn = ptr::null_mut();
+    pub fn commit(self) -> Result<()> {
+        let txn = self.txn.replace(ptr::null_mut());
+        let result = unsafe { mdbx_result(mdbx_sys::mdbx_txn_commit_ex(txn, ptr::null_mut())) };
         result?;
         Ok(())
     }
@@ -25,18 +49,18 @@ impl Txn {
 
     pub fn mark_broken(&self) {
         unsafe {
-            mdbx_sys::mdbx_txn_break(self.txn);
+            mdbx_sys::mdbx_txn_break(self.txn.get());
         }
     }
 }
 
 impl Drop for Txn {
     fn drop(&mut self) {
-        if !self.txn.is_null() {
+        let txn = self.txn.replace(ptr::null_mut());
+        if !txn.is_null() {
             unsafe {
-                mdbx_sys::mdbx_txn_abort(self.txn);
+                mdbx_sys::mdbx_txn_abort(txn);
             }
-            self.txn = ptr::null_mut();
         }
     }
 }
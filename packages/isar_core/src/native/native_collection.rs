@@ -2,13 +2,18 @@ use super::isar_deserializer::IsarDeserializer;
 use super::isar_serializer::IsarSerializer;
 use super::mdbx::db::Db;
 use super::native_index::NativeIndex;
+use super::native_link::NativeLink;
 use super::native_txn::{NativeTxn, TxnCursor};
 use super::query::NativeQuery;
-use super::{BytesToId, IdToBytes};
+use super::{BytesToId, IdToBytes, NULL_INT, NULL_LONG};
+use crate::core::constraint::PropertyConstraint;
 use crate::core::data_type::DataType;
 use crate::core::error::{IsarError, Result};
+use crate::core::schema::TtlSchema;
+use crate::core::update::{PropertyUpdate, UpdateOperation};
 use crate::core::value::IsarValue;
 use crate::core::watcher::{ChangeSet, CollectionWatchers};
+use byteorder::{ByteOrder, LittleEndian};
 use std::sync::atomic::{self, AtomicI64};
 use std::sync::Arc;
 
@@ -29,16 +34,32 @@ impl NativeProperty {
     }
 }
 
+pub(crate) struct NativeTtl {
+    pub schema: TtlSchema,
+    pub property: NativeProperty,
+    // a value index of the TTL property that is used to find expired objects
+    pub index: Option<usize>,
+}
+
 pub(crate) struct NativeCollection {
     pub collection_index: u16,
     pub name: String,
     pub id_name: Option<String>,
     pub properties: Vec<(String, NativeProperty)>,
     pub indexes: Vec<NativeIndex>,
+    pub links: Vec<NativeLink>,
+    // links of other collections pointing to this collection that have no backlink
+    pub reverse_links: Vec<NativeLink>,
+    pub ttl: Option<NativeTtl>,
+    // index of the Long property that is incremented whenever an object is written
+    pub version_property: Option<u16>,
+    pub constraints: Vec<PropertyConstraint>,
     pub static_size: u32,
     pub watchers: Arc<CollectionWatchers<NativeQuery>>,
     auto_increment: AtomicI64,
     db: Option<Db>,
+    // payloads of blob properties keyed by object id and property offset
+    blob_db: Option<Db>,
 }
 
 impl NativeCollection {
@@ -60,13 +81,49 @@ impl NativeCollection {
             id_name: id_name.map(|s| s.to_string()),
             properties,
             indexes,
+            links: vec![],
+            reverse_links: vec![],
+            ttl: None,
+            version_property: None,
+            constraints: vec![],
             static_size,
             watchers: CollectionWatchers::new(),
             auto_increment: AtomicI64::new(1),
             db,
+            blob_db: None,
         }
     }
 
+    pub fn with_links(mut self, links: Vec<NativeLink>, reverse_links: Vec<NativeLink>) -> Self {
+        self.links = links;
+        self.reverse_links = reverse_links;
+        self
+    }
+
+    pub fn with_ttl(mut self, ttl: Option<NativeTtl>) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn with_version_property(mut self, version_property: Option<u16>) -> Self {
+        self.version_property = version_property;
+        self
+    }
+
+    pub fn with_constraints(mut self, constraints: Vec<PropertyConstraint>) -> Self {
+        self.constraints = constraints;
+        self
+    }
+
+    pub fn with_blob_db(mut self, blob_db: Option<Db>) -> Self {
+        self.blob_db = blob_db;
+        self
+    }
+
+    pub fn has_blobs(&self) -> bool {
+        self.blob_db.is_some()
+    }
+
     pub fn get_cursor<'a>(&self, txn: &'a NativeTxn) -> Result<TxnCursor<'a>> {
         let db = self.db.ok_or(IsarError::UnsupportedOperation {})?;
         txn.get_cursor(db)
@@ -90,9 +147,9 @@ impl NativeCollection {
         self.auto_increment.fetch_add(1, atomic::Ordering::AcqRel)
     }
 
-    fn update_auto_increment(&self, id: i64) {
+    pub fn update_auto_increment(&self, id: i64) {
         self.auto_increment
-            .fetch_max(id + 1, atomic::Ordering::AcqRel);
+            .fetch_max(id.saturating_add(1), atomic::Ordering::AcqRel);
     }
 
     #[inline]
@@ -109,6 +166,9 @@ impl NativeCollection {
     pub fn get_size(&self, txn: &NativeTxn, include_indexes: bool) -> Result<u64> {
         if let Some(db) = self.db {
             let mut size = txn.stat(db)?.1;
+            if let Some(blob_db) = self.blob_db {
+                size += txn.stat(blob_db)?.1;
+            }
             if include_indexes {
                 for index in &self.indexes {
                     size += index.get_size(txn)?;
@@ -120,6 +180,106 @@ impl NativeCollection {
         }
     }
 
+    /// Returns the ids of the objects with a TTL timestamp up to `expired_until`.
+    pub fn get_expired_ids(&self, txn: &NativeTxn, expired_until: i64) -> Result<Vec<i64>> {
+        let ttl = if let Some(ttl) = &self.ttl {
+            ttl
+        } else {
+            return Ok(vec![]);
+        };
+        if let Some(index) = ttl.index {
+            return self.indexes[index].get_long_ids(txn, NULL_LONG + 1, expired_until);
+        }
+
+        let mut ids = vec![];
+        let cursor = self.get_cursor(txn)?;
+        for (id_bytes, bytes) in cursor.iter_between_ids(i64::MIN, i64::MAX, false, false)? {
+            let timestamp = IsarDeserializer::from_bytes(bytes).read_long(ttl.property.offset);
+            if timestamp != NULL_LONG && timestamp <= expired_until {
+                ids.push(id_bytes.to_id());
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Replaces null values of the object with the default value of their property and fails
+    /// if the object violates a constraint.
+    pub fn apply_constraints(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        let mut defaults = vec![];
+        let object = IsarDeserializer::from_bytes(buffer);
+        for constraint in &self.constraints {
+            let p = self.get_property(constraint.property_index).unwrap();
+            if object.is_null(p.offset, p.data_type) {
+                if let Some(default) = constraint.apply(None)? {
+                    defaults.push((constraint.property_index, default));
+                }
+            } else if p.data_type.is_scalar() {
+                constraint.apply(Some(Self::read_value(object, p)))?;
+            } else if let Some(element_type) = p.data_type.element_type() {
+                if let Some((_, length)) = object.read_list(p.offset, element_type) {
+                    constraint.apply(Some(IsarValue::Integer(length as i64)))?;
+                }
+            }
+        }
+
+        if !defaults.is_empty() {
+            let mut object = IsarSerializer::new(std::mem::take(buffer), 0, self.static_size);
+            for (property_index, value) in &defaults {
+                self.write_value(&mut object, *property_index, Some(value))?;
+            }
+            *buffer = object.finish();
+        }
+        Ok(())
+    }
+
+    fn read_value(object: IsarDeserializer, property: &NativeProperty) -> IsarValue {
+        let offset = property.offset;
+        match property.data_type {
+            DataType::Bool => IsarValue::Bool(object.read_bool(offset).unwrap_or_default()),
+            DataType::Byte => IsarValue::Integer(object.read_byte(offset) as i64),
+            DataType::Int => IsarValue::Integer(object.read_int(offset) as i64),
+            DataType::Float => IsarValue::Real(object.read_float(offset) as f64),
+            DataType::Long => IsarValue::Integer(object.read_long(offset)),
+            DataType::Double => IsarValue::Real(object.read_double(offset)),
+            _ => IsarValue::String(object.read_string(offset).unwrap_or_default().to_string()),
+        }
+    }
+
+    /// Writes the default values of the given properties to all objects.
+    pub fn fill_defaults(&self, txn: &NativeTxn, property_names: &[String]) -> Result<()> {
+        let updates = self
+            .constraints
+            .iter()
+            .filter(|c| {
+                let (name, _) = &self.properties[c.property_index as usize - 1];
+                property_names.contains(name)
+            })
+            .filter_map(|c| {
+                Some(PropertyUpdate::set(
+                    c.property_index,
+                    Some(c.default.clone()?),
+                ))
+            })
+            .collect::<Vec<_>>();
+        if updates.is_empty() {
+            return Ok(());
+        }
+
+        let mut ids = vec![];
+        let cursor = self.get_cursor(txn)?;
+        for (id_bytes, _) in cursor.iter_between_ids(i64::MIN, i64::MAX, false, false)? {
+            ids.push(id_bytes.to_id());
+        }
+
+        let change_set = &mut txn.get_change_set();
+        let mut cursor = self.get_cursor(txn)?;
+        for id in ids {
+            // defaults are only set for properties of the collection itself
+            self.update(txn, change_set, &mut cursor, &[], id, &updates)?;
+        }
+        Ok(())
+    }
+
     pub fn count(&self, txn: &NativeTxn) -> Result<u32> {
         if let Some(db) = self.db {
             Ok(txn.stat(db)?.0 as u32)
@@ -134,14 +294,22 @@ impl NativeCollection {
         change_set: &mut ChangeSet,
         cursor: &mut TxnCursor<'a>,
         id: i64,
-        bytes: &[u8],
+        bytes: &mut [u8],
     ) -> Result<()> {
         let id_bytes = id.to_id_bytes();
+        let version_property = self.version_property.and_then(|i| self.get_property(i));
+        let mut version = 1;
 
-        // we only fetch the previous object if there are query watchers or indexes
-        if !self.indexes.is_empty() || self.watchers.has_query_watchers() {
+        // we only fetch the previous object if there are query watchers, indexes or a version
+        if !self.indexes.is_empty()
+            || self.watchers.has_query_watchers()
+            || version_property.is_some()
+        {
             if let Some((_, bytes)) = cursor.move_to(&id_bytes)? {
                 let object = IsarDeserializer::from_bytes(&bytes);
+                if let Some(p) = version_property {
+                    version = object.read_long(p.offset).max(0).saturating_add(1);
+                }
                 // register old object change
                 change_set.register_change(&self.watchers, id, &object);
 
@@ -156,7 +324,12 @@ impl NativeCollection {
             }
         }
 
-        let object = IsarDeserializer::from_bytes(&bytes);
+        // the written version is ignored
+        if let Some(p) = version_property {
+            LittleEndian::write_i64(&mut bytes[3 + p.offset as usize..], version);
+        }
+
+        let object = IsarDeserializer::from_bytes(bytes);
         // register new object change
         change_set.register_change(&self.watchers, id, &object);
 
@@ -198,6 +371,11 @@ impl NativeCollection {
             }
 
             cursor.delete_current()?;
+            for link in self.links.iter().chain(&self.reverse_links) {
+                link.unlink_all(txn, id)?;
+            }
+            // all properties of an empty object are null
+            self.put_blobs(txn, id, &[0; 3], &[])?;
             Ok(true)
         } else {
             Ok(false)
@@ -209,20 +387,27 @@ impl NativeCollection {
         txn: &'a NativeTxn,
         change_set: &mut ChangeSet,
         cursor: &mut TxnCursor<'a>,
+        all_collections: &[NativeCollection],
         id: i64,
-        updates: &[(u16, Option<IsarValue>)],
+        updates: &[PropertyUpdate],
     ) -> Result<bool> {
         if let Some((_, old_object)) = cursor.move_to(&id.to_id_bytes())? {
             let mut buffer = txn.take_buffer();
-            buffer.extend_from_slice(&old_object);
+            self.copy_object(&old_object, &mut buffer);
             let mut new_object = IsarSerializer::new(buffer, 0, self.static_size);
 
-            for (property_index, value) in updates {
-                self.write_value(&mut new_object, *property_index as u16, value.as_ref())?;
+            for update in updates {
+                self.write_update(
+                    all_collections,
+                    &mut new_object,
+                    &update.path,
+                    &update.operation,
+                )?;
             }
 
-            let buffer = new_object.finish();
-            self.put(txn, change_set, cursor, id, &buffer)?;
+            let mut buffer = new_object.finish();
+            self.put(txn, change_set, cursor, id, &mut buffer)?;
+            self.put_blobs(txn, id, &buffer, &[])?;
             txn.put_buffer(buffer);
 
             Ok(true)
@@ -231,6 +416,301 @@ impl NativeCollection {
         }
     }
 
+    fn blob_key(id: i64, offset: u32) -> [u8; 12] {
+        let mut key = [0; 12];
+        key[..8].copy_from_slice(&id.to_id_bytes());
+        key[8..].copy_from_slice(&offset.to_be_bytes());
+        key
+    }
+
+    /// Returns the payload of the blob property at `offset`. The payload is not copied.
+    pub fn get_blob<'a>(
+        &self,
+        txn: &'a NativeTxn,
+        id: i64,
+        offset: u32,
+    ) -> Result<Option<&'a [u8]>> {
+        if let Some(blob_db) = self.blob_db {
+            let mut cursor = txn.get_cursor(blob_db)?;
+            let blob = cursor.move_to(&Self::blob_key(id, offset))?;
+            Ok(blob.map(|(_, bytes)| bytes))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Stores the given blob payloads of an object and deletes the payloads of blob properties
+    /// that are null. The payloads of other blob properties are kept.
+    pub fn put_blobs(
+        &self,
+        txn: &NativeTxn,
+        id: i64,
+        object: &[u8],
+        blobs: &[(u32, Vec<u8>)],
+    ) -> Result<()> {
+        let blob_db = if let Some(blob_db) = self.blob_db {
+            blob_db
+        } else {
+            return Ok(());
+        };
+
+        let object = IsarDeserializer::from_bytes(object);
+        let mut cursor = txn.get_cursor(blob_db)?;
+        for (_, p) in &self.properties {
+            if p.data_type != DataType::Blob {
+                continue;
+            }
+            let key = Self::blob_key(id, p.offset);
+            if object.is_null(p.offset, p.data_type) {
+                if cursor.move_to(&key)?.is_some() {
+                    cursor.delete_current()?;
+                }
+            } else if let Some((_, blob)) = blobs.iter().find(|(offset, _)| *offset == p.offset) {
+                cursor.put(&key, blob)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies the object into `buffer`. Objects that were written before properties were added
+    /// get a larger static section and the added properties are null.
+    fn copy_object(&self, object: &[u8], buffer: &mut Vec<u8>) {
+        let static_size = LittleEndian::read_u24(object);
+        if static_size >= self.static_size {
+            buffer.extend_from_slice(object);
+            return;
+        }
+
+        // offsets of dynamic data are relative to the start of the static section
+        let shift = self.static_size - static_size;
+        buffer.extend_from_slice(&object[..3 + static_size as usize]);
+        buffer.resize(3 + self.static_size as usize, 0);
+        buffer.extend_from_slice(&object[3 + static_size as usize..]);
+
+        let mut new_object = IsarSerializer::new(std::mem::take(buffer), 0, self.static_size);
+        for (_, p) in &self.properties {
+            if p.offset >= static_size {
+                new_object.write_null(p.offset, p.data_type);
+            }
+        }
+        *buffer = new_object.finish();
+
+        for (_, p) in &self.properties {
+            if p.offset < static_size && p.data_type.static_size() == 3 {
+                let position = 3 + p.offset as usize;
+                let offset = LittleEndian::read_u24(&buffer[position..]);
+                if offset != 0 {
+                    LittleEndian::write_u24(&mut buffer[position..], offset + shift);
+                }
+            }
+        }
+    }
+
+    /// Fails if an update targets a property that doesn't exist or that doesn't support the
+    /// operation of the update.
+    pub fn verify_updates(
+        &self,
+        all_collections: &[NativeCollection],
+        updates: &[PropertyUpdate],
+    ) -> Result<()> {
+        for update in updates {
+            let (property_index, parents) = update
+                .path
+                .split_last()
+                .ok_or(IsarError::IllegalArgument {})?;
+            let mut collection = self;
+            for parent_index in parents {
+                let p = collection
+                    .get_property(*parent_index)
+                    .filter(|p| p.data_type == DataType::Object)
+                    .ok_or(IsarError::IllegalArgument {})?;
+                collection = p
+                    .embedded_collection_index
+                    .and_then(|index| all_collections.get(index as usize))
+                    .ok_or(IsarError::IllegalArgument {})?;
+            }
+            let p = collection
+                .get_property(*property_index)
+                .ok_or(IsarError::IllegalArgument {})?;
+            if !update
+                .operation
+                .is_supported(p.data_type, update.is_nested())
+            {
+                return Err(IsarError::IllegalArgument {});
+            }
+        }
+        Ok(())
+    }
+
+    fn write_update(
+        &self,
+        all_collections: &[NativeCollection],
+        object: &mut IsarSerializer,
+        path: &[u16],
+        operation: &UpdateOperation,
+    ) -> Result<()> {
+        match path {
+            [property_index] => self.write_operation(object, *property_index, operation),
+            [property_index, path @ ..] => {
+                let p = self
+                    .get_property(*property_index)
+                    .ok_or(IsarError::IllegalArgument {})?;
+                let embedded = p
+                    .embedded_collection_index
+                    .and_then(|index| all_collections.get(index as usize))
+                    .ok_or(IsarError::IllegalArgument {})?;
+                if let Some(mut nested) = embedded.edit_embedded(object, p.offset) {
+                    let result =
+                        embedded.write_update(all_collections, &mut nested, path, operation);
+                    object.end_nested(nested);
+                    result?;
+                }
+                Ok(())
+            }
+            [] => Err(IsarError::IllegalArgument {}),
+        }
+    }
+
+    /// Returns a serializer for the embedded object at `offset` or `None` if it is null.
+    /// Objects that were written before properties were added are copied first.
+    fn edit_embedded(&self, object: &mut IsarSerializer, offset: u32) -> Option<IsarSerializer> {
+        let nested = object.read_nested(offset)?;
+        if LittleEndian::read_u24(nested) < self.static_size {
+            let mut buffer = vec![];
+            self.copy_object(nested, &mut buffer);
+            object.write_nested(offset, &buffer);
+        }
+        object.edit_nested(offset)
+    }
+
+    fn write_operation(
+        &self,
+        object: &mut IsarSerializer,
+        property_index: u16,
+        operation: &UpdateOperation,
+    ) -> Result<()> {
+        let p = self
+            .get_property(property_index)
+            .ok_or(IsarError::IllegalArgument {})?;
+        match operation {
+            UpdateOperation::Set(value) => {
+                return self.write_value(object, property_index, value.as_ref());
+            }
+            UpdateOperation::Increment(value) => Self::increment(object, p, value),
+            _ => Self::update_list(object, p, operation)?,
+        }
+        Ok(())
+    }
+
+    fn increment(object: &mut IsarSerializer, p: &NativeProperty, value: &IsarValue) {
+        let offset = p.offset;
+        if object.as_deserializer().is_null(offset, p.data_type) {
+            return;
+        }
+        let integer = value.i64().unwrap_or_default();
+        let real = value.real().unwrap_or(integer as f64);
+        match p.data_type {
+            DataType::Byte => {
+                let current = object.as_deserializer().read_byte(offset) as i64;
+                let value = current.saturating_add(integer).clamp(0, u8::MAX as i64);
+                object.write_byte(offset, value as u8);
+            }
+            DataType::Int => {
+                let current = object.as_deserializer().read_int(offset) as i64;
+                let value = current
+                    .saturating_add(integer)
+                    .clamp(NULL_INT as i64 + 1, i32::MAX as i64);
+                object.write_int(offset, value as i32);
+            }
+            DataType::Long => {
+                let current = object.as_deserializer().read_long(offset);
+                object.write_long(offset, current.saturating_add(integer).max(NULL_LONG + 1));
+            }
+            DataType::Float => {
+                let current = object.as_deserializer().read_float(offset);
+                object.write_float(offset, current + real as f32);
+            }
+            DataType::Double => {
+                let current = object.as_deserializer().read_double(offset);
+                object.write_double(offset, current + real);
+            }
+            _ => {}
+        }
+    }
+
+    /// Rewrites a list with the element appended, removed or replaced. Lists that don't change
+    /// are not rewritten.
+    fn update_list(
+        object: &mut IsarSerializer,
+        p: &NativeProperty,
+        operation: &UpdateOperation,
+    ) -> Result<()> {
+        let element_type = p
+            .data_type
+            .element_type()
+            .ok_or(IsarError::IllegalArgument {})?;
+        let size = element_type.static_size() as u32;
+        let elements = object
+            .as_deserializer()
+            .read_list(p.offset, element_type)
+            .map(|(list, length)| {
+                (0..length)
+                    .map(|i| {
+                        let element = NativeProperty::new(element_type, i * size, None);
+                        if list.is_null(element.offset, element_type) {
+                            None
+                        } else {
+                            Some(Self::read_value(list, &element))
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            });
+
+        let elements = match (operation, elements) {
+            (UpdateOperation::Append(value), elements) => {
+                let mut elements = elements.unwrap_or_default();
+                elements.push(value.clone());
+                elements
+            }
+            (UpdateOperation::Remove(value), Some(mut elements)) => {
+                let length = elements.len();
+                elements.retain(|element| element != value);
+                if elements.len() == length {
+                    return Ok(());
+                }
+                elements
+            }
+            (UpdateOperation::SetAt(index, value), Some(mut elements))
+                if (*index as usize) < elements.len() =>
+            {
+                elements[*index as usize] = value.clone();
+                elements
+            }
+            _ => return Ok(()),
+        };
+
+        let mut list = object.begin_nested(p.offset, size * elements.len() as u32);
+        for (i, element) in elements.iter().enumerate() {
+            let offset = i as u32 * size;
+            match (element, element_type) {
+                (None, _) => list.write_null(offset, element_type),
+                (Some(IsarValue::Bool(value)), _) => list.write_bool(offset, *value),
+                (Some(IsarValue::Integer(value)), DataType::Int) => {
+                    list.write_int(offset, *value as i32)
+                }
+                (Some(IsarValue::Integer(value)), _) => list.write_long(offset, *value),
+                (Some(IsarValue::Real(value)), DataType::Float) => {
+                    list.write_float(offset, *value as f32)
+                }
+                (Some(IsarValue::Real(value)), _) => list.write_double(offset, *value),
+                (Some(IsarValue::String(value)), _) => list.write_dynamic(offset, value.as_bytes()),
+                _ => return Err(IsarError::IllegalArgument {}),
+            }
+        }
+        object.end_nested(list);
+        Ok(())
+    }
+
     fn write_value(
         &self,
         object: &mut IsarSerializer,
@@ -262,6 +742,15 @@ impl NativeCollection {
                 | (Some(IsarValue::String(value)), DataType::Json) => {
                     object.update_dynamic(p.offset, value.as_bytes())
                 }
+                (Some(IsarValue::Decimal(value)), DataType::Decimal) => {
+                    object.update_dynamic(p.offset, &value.to_bytes())
+                }
+                (Some(IsarValue::Uuid(value)), DataType::Uuid) => {
+                    object.update_dynamic(p.offset, &value.to_bytes())
+                }
+                (Some(IsarValue::DateTimeOffset(value)), DataType::DateTimeOffset) => {
+                    object.update_dynamic(p.offset, &value.to_bytes())
+                }
                 _ => return Err(IsarError::IllegalArgument {}),
             }
             Ok(())
@@ -278,6 +767,13 @@ impl NativeCollection {
         for index in &self.indexes {
             index.clear(txn)?;
         }
+        if let Some(blob_db) = self.blob_db {
+            txn.clear_db(blob_db)?;
+        }
+        // every edge of a link has an object in this collection
+        for link in self.links.iter().chain(&self.reverse_links) {
+            link.clear(txn)?;
+        }
         Ok(())
     }
 }
@@ -291,7 +787,8 @@ impl DataType {
         match self {
             DataType::Bool | DataType::Byte => 1,
             DataType::Int | DataType::Float => 4,
-            DataType::Long | DataType::Double => 8,
+            // blobs store the length of their payload
+            DataType::Long | DataType::Double | DataType::Blob => 8,
             _ => 3,
         }
     }
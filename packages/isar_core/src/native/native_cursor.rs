@@ -7,6 +7,7 @@ use crate::core::cursor::IsarCursor;
 use crate::core::error::Result;
 
 pub struct NativeCursor<'a> {
+    txn: &'a NativeTxn,
     cursor: TxnCursor<'a>,
     collection: &'a NativeCollection,
     collections: &'a Vec<NativeCollection>,
@@ -20,6 +21,7 @@ impl<'a> NativeCursor<'a> {
     ) -> Result<Self> {
         let cursor = collection.get_cursor(txn)?;
         let nc = Self {
+            txn,
             cursor,
             collection,
             collections,
@@ -35,12 +37,8 @@ impl<'a> IsarCursor for NativeCursor<'a> {
     fn next(&mut self, id: i64) -> Option<Self::Reader<'_>> {
         if let Some((_, bytes)) = self.cursor.move_to(&id.to_id_bytes()).ok()? {
             let object = IsarDeserializer::from_bytes(bytes);
-            Some(NativeReader::new(
-                id,
-                object,
-                self.collection,
-                &self.collections,
-            ))
+            let reader = NativeReader::new(id, object, self.collection, &self.collections);
+            Some(reader.with_txn(self.txn))
         } else {
             None
         }
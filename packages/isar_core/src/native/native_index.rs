@@ -3,16 +3,24 @@ use super::isar_deserializer::IsarDeserializer;
 use super::mdbx::db::Db;
 use super::native_collection::NativeProperty;
 use super::native_txn::NativeTxn;
-use super::{BytesToId, IdToBytes};
+use super::query::native_filter::NativeFilter;
+use super::{BytesToId, IdToBytes, NULL_DOUBLE, NULL_FLOAT, NULL_INT, NULL_LONG};
 use crate::core::data_type::DataType;
 use crate::core::error::Result;
+use crate::core::geo;
+use crate::core::schema::{IndexType, VectorIndexOptions};
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone)]
 pub(crate) struct NativeIndex {
     pub name: String,
-    pub properties: Vec<NativeProperty>,
+    // each property is a path of nested object properties ending with the indexed property
+    pub properties: Vec<Vec<NativeProperty>>,
     pub unique: bool,
     pub hash: bool,
+    pub index_type: IndexType,
+    pub vector: Option<VectorIndexOptions>,
+    // only objects matching the filter are indexed
+    pub filter: Option<NativeFilter>,
     db: Db,
 }
 
@@ -20,30 +28,76 @@ impl NativeIndex {
     pub fn new(
         name: &str,
         db: Db,
-        properties: Vec<NativeProperty>,
+        properties: Vec<Vec<NativeProperty>>,
         unique: bool,
         hash: bool,
+        index_type: IndexType,
+        filter: Option<NativeFilter>,
     ) -> Self {
         NativeIndex {
             name: name.to_string(),
             properties,
             unique,
             hash,
+            index_type,
+            vector: None,
+            filter,
             db,
         }
     }
 
+    pub fn with_vector(mut self, vector: Option<VectorIndexOptions>) -> Self {
+        self.vector = vector;
+        self
+    }
+
+    fn is_indexed(&self, id: i64, object: IsarDeserializer) -> bool {
+        // vector indexes are flat and have no entries
+        self.index_type != IndexType::Vector
+            && self
+                .filter
+                .as_ref()
+                .is_none_or(|filter| filter.evaluate(id, object))
+    }
+
     fn create_key(&self, object: IsarDeserializer, buffer: Vec<u8>) -> (Vec<u8>, bool) {
         let mut key = IndexKey::with_buffer(buffer);
-        for property in &self.properties {
+        if self.index_type == IndexType::Geo {
+            let latitude = &self.properties[0][0];
+            let longitude = &self.properties[1][0];
+            let lat = object.read_coordinate(latitude.offset, latitude.data_type);
+            let lon = object.read_coordinate(longitude.offset, longitude.data_type);
+            if lat.is_nan() || lon.is_nan() {
+                return (key.finish().0, true);
+            }
+            // flip the sign bit so the key bytes keep the order of the cell ids
+            key.add_long((geo::cell_id(lat, lon) ^ 1 << 63) as i64);
+            return (key.finish().0, false);
+        }
+
+        for path in &self.properties {
+            let (property, parents) = path.split_last().unwrap();
+            let mut object = Some(object);
+            for parent in parents {
+                object = object.and_then(|o| o.read_nested(parent.offset));
+            }
+
+            let offset = property.offset;
             match property.data_type {
-                DataType::Bool => key.add_bool(object.read_bool(property.offset)),
-                DataType::Byte => key.add_byte(object.read_byte(property.offset)),
-                DataType::Int => key.add_int(object.read_int(property.offset)),
-                DataType::Float => key.add_float(object.read_float(property.offset)),
-                DataType::Long => key.add_long(object.read_long(property.offset)),
-                DataType::Double => key.add_double(object.read_double(property.offset)),
-                DataType::String => key.add_string(object.read_string(property.offset)),
+                DataType::Bool => key.add_bool(object.and_then(|o| o.read_bool(offset))),
+                DataType::Byte => key.add_byte(object.map_or(0, |o| o.read_byte(offset))),
+                DataType::Int => key.add_int(object.map_or(NULL_INT, |o| o.read_int(offset))),
+                DataType::Float => {
+                    key.add_float(object.map_or(NULL_FLOAT, |o| o.read_float(offset)))
+                }
+                DataType::Long => key.add_long(object.map_or(NULL_LONG, |o| o.read_long(offset))),
+                DataType::Double => {
+                    key.add_double(object.map_or(NULL_DOUBLE, |o| o.read_double(offset)))
+                }
+                DataType::String => key.add_string(object.and_then(|o| o.read_string(offset))),
+                DataType::Decimal | DataType::Uuid | DataType::DateTimeOffset => {
+                    key.add_bytes(object.and_then(|o| o.read_dynamic(offset)))
+                }
                 _ => unreachable!(),
             }
         }
@@ -70,8 +124,15 @@ impl NativeIndex {
     where
         F: FnMut(i64) -> Result<()>,
     {
+        if !self.is_indexed(id, object) {
+            return Ok(buffer);
+        }
+
         let mut cursor = txn.get_cursor(self.db)?;
         let (key, contains_null) = self.create_key(object, buffer);
+        if self.index_type == IndexType::Geo && contains_null {
+            return Ok(key);
+        }
 
         if self.unique && !contains_null {
             if let Some((_, id_bytes)) = cursor.move_to(&key)? {
@@ -90,6 +151,10 @@ impl NativeIndex {
         object: IsarDeserializer,
         buffer: Vec<u8>,
     ) -> Result<Vec<u8>> {
+        if !self.is_indexed(id, object) {
+            return Ok(buffer);
+        }
+
         let mut cursor = txn.get_cursor(self.db)?;
         let key = self.create_key(object, buffer).0;
         if cursor.move_to_key_val(&key, &id.to_id_bytes())?.is_some() {
@@ -106,6 +171,50 @@ impl NativeIndex {
         txn.clear_db(self.db)
     }
 
+    /// Returns the ids of the objects with a value between `lower` and `upper`. Only valid for
+    /// value indexes of a single Long property.
+    pub fn get_long_ids(&self, txn: &NativeTxn, lower: i64, upper: i64) -> Result<Vec<i64>> {
+        if lower > upper {
+            return Ok(vec![]);
+        }
+        let mut lower_key = IndexKey::min();
+        lower_key.add_long(lower);
+        let mut upper_key = IndexKey::min();
+        upper_key.add_long(upper);
+        let cursor = txn.get_cursor(self.db)?;
+        let ids = cursor
+            .iter_between(lower_key.finish().0, upper_key.finish().0, true, false)?
+            .map(|(_, id_bytes)| id_bytes.to_id())
+            .collect();
+        Ok(ids)
+    }
+
+    /// Returns the sorted ids of the objects whose coordinate may lie within the bounding box.
+    /// Only valid for geo indexes; the exact bounds have to be checked by the caller.
+    pub fn get_geo_ids(
+        &self,
+        txn: &NativeTxn,
+        min_lat: f64,
+        min_lon: f64,
+        max_lat: f64,
+        max_lon: f64,
+    ) -> Result<Vec<i64>> {
+        let mut cursor = txn.get_cursor(self.db)?;
+        let mut ids = vec![];
+        for (start, end) in geo::cell_ranges(min_lat, min_lon, max_lat, max_lon) {
+            let mut lower_key = IndexKey::min();
+            lower_key.add_long((start ^ 1 << 63) as i64);
+            let mut upper_key = IndexKey::min();
+            upper_key.add_long((end ^ 1 << 63) as i64);
+            let mut iterator =
+                cursor.iter_between(lower_key.finish().0, upper_key.finish().0, true, false)?;
+            ids.extend(iterator.by_ref().map(|(_, id_bytes)| id_bytes.to_id()));
+            cursor = iterator.close();
+        }
+        ids.sort_unstable();
+        Ok(ids)
+    }
+
     /* pub fn iter_between<'txn, 'env>(
         &self,
         cursors: &IsarCursors<'txn, 'env>,
@@ -1,52 +1,19 @@
 use super::isar_serializer::IsarSerializer;
 use super::native_collection::NativeCollection;
-use super::native_txn::{NativeTxn, TxnCursor};
+use super::native_txn::NativeTxn;
 use super::MAX_OBJ_SIZE;
 use crate::core::error::{IsarError, Result};
 use crate::core::insert::IsarInsert;
-use crate::core::watcher::ChangeSet;
-use ouroboros::self_referencing;
-use std::cell::RefMut;
-
-#[self_referencing]
-struct TxnWithCursor {
-    txn: NativeTxn,
-    #[borrows(txn)]
-    #[covariant]
-    cursor: TxnCursor<'this>,
-    #[borrows(txn)]
-    #[covariant]
-    change_set: RefMut<'this, ChangeSet>,
-}
-
-impl TxnWithCursor {
-    fn open(txn: NativeTxn, collection: &NativeCollection) -> Result<Self> {
-        Self::try_new(
-            txn,
-            |txn| collection.get_cursor(txn),
-            |txn| Ok(txn.get_change_set()),
-        )
-    }
-
-    fn put(&mut self, collection: &NativeCollection, id: i64, bytes: &[u8]) -> Result<()> {
-        self.with_mut(|mut this| {
-            this.txn
-                .guard(|| collection.put(this.txn, &mut this.change_set, this.cursor, id, bytes))
-        })
-    }
-
-    fn close(self) -> NativeTxn {
-        self.into_heads().txn
-    }
-}
 
 pub struct NativeInsert<'a> {
-    txn_cursor: TxnWithCursor,
+    txn: NativeTxn,
     pub(crate) collection: &'a NativeCollection,
     pub(crate) all_collections: &'a Vec<NativeCollection>,
 
     remaining: u32,
     pub(crate) object: IsarSerializer,
+    // the payloads of the blob properties of the current object and their offsets
+    pub(crate) blobs: Vec<(u32, Vec<u8>)>,
 }
 
 impl<'a> NativeInsert<'a> {
@@ -57,13 +24,13 @@ impl<'a> NativeInsert<'a> {
         count: u32,
     ) -> Result<Self> {
         let buffer = txn.take_buffer();
-        let txn_cursor = TxnWithCursor::open(txn, collection)?;
         let insert = Self {
-            txn_cursor,
+            txn,
             collection,
             all_collections,
             remaining: count,
             object: IsarSerializer::new(buffer, 0, collection.static_size),
+            blobs: vec![],
         };
         Ok(insert)
     }
@@ -75,10 +42,26 @@ impl<'a> IsarInsert<'a> for NativeInsert<'a> {
     fn save(&mut self, id: i64) -> Result<()> {
         if self.remaining > 0 {
             let mut buffer = self.object.finish();
+            if let Err(err) = self.collection.apply_constraints(&mut buffer) {
+                // the object is discarded and the next object can be written
+                buffer.clear();
+                self.object = IsarSerializer::new(buffer, 0, self.collection.static_size);
+                self.blobs.clear();
+                return Err(err);
+            }
             if buffer.len() > MAX_OBJ_SIZE as usize {
                 return Result::Err(IsarError::ObjectLimitReached {});
             }
-            self.txn_cursor.put(self.collection, id, &buffer)?;
+            let blobs = std::mem::take(&mut self.blobs);
+            let txn = &self.txn;
+            let collection = self.collection;
+            txn.guard(|| {
+                // the cursor must not outlive the guard which may restart a savepoint
+                let mut cursor = collection.get_cursor(txn)?;
+                let change_set = &mut txn.get_change_set();
+                collection.put(txn, change_set, &mut cursor, id, &mut buffer)?;
+                collection.put_blobs(txn, id, &buffer, &blobs)
+            })?;
 
             self.remaining -= 1;
             buffer.clear();
@@ -93,9 +76,13 @@ impl<'a> IsarInsert<'a> for NativeInsert<'a> {
         if self.remaining > 0 {
             Err(IsarError::InsertIncomplete {})
         } else {
-            let txn = self.txn_cursor.close();
-            txn.put_buffer(self.object.finish());
-            Ok(txn)
+            self.txn.put_buffer(self.object.finish());
+            Ok(self.txn)
         }
     }
+
+    fn cancel(self) -> Self::Txn {
+        self.txn.put_buffer(self.object.finish());
+        self.txn
+    }
 }
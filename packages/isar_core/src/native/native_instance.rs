@@ -2,20 +2,25 @@ use super::mdbx::env::Env;
 use super::native_collection::NativeCollection;
 use super::native_cursor::NativeCursor;
 use super::native_insert::NativeInsert;
+use super::native_link::NativeLink;
 use super::native_open::{get_isar_path, open_native};
 use super::native_query_builder::NativeQueryBuilder;
 use super::native_reader::NativeReader;
 use super::native_txn::NativeTxn;
-use super::native_verify::verify_native;
+use super::native_verify::{verify_native, verify_native_copy};
 use super::query::{NativeQuery, NativeQueryCursor};
+use super::IdToBytes;
+use crate::core::constraint::apply_updates;
+use crate::core::data_type::DataType;
 use crate::core::error::{IsarError, Result};
-use crate::core::instance::{Aggregation, CompactCondition, IsarInstance};
+use crate::core::instance::{Aggregation, CompactCondition, IsarInstance, RestoreResult};
 use crate::core::schema::IsarSchema;
+use crate::core::update::{set_updates, PropertyUpdate};
 use crate::core::value::IsarValue;
-use crate::core::watcher::{WatchHandle, WatcherCallback};
+use crate::core::watcher::{ChangeSet, WatchHandle, WatcherCallback};
 use intmap::IntMap;
 use parking_lot::Mutex;
-use std::fs::remove_file;
+use std::fs::{self, remove_file};
 use std::sync::{Arc, LazyLock};
 
 static INSTANCES: LazyLock<Mutex<IntMap<Arc<NativeInstance>>>> =
@@ -25,6 +30,7 @@ pub struct NativeInstance {
     name: String,
     dir: String,
     instance_id: u32,
+    schemas: Vec<IsarSchema>,
     collections: Vec<NativeCollection>,
     env: Arc<Env>,
 }
@@ -34,6 +40,7 @@ impl NativeInstance {
         name: &str,
         dir: &str,
         instance_id: u32,
+        schemas: Vec<IsarSchema>,
         collections: Vec<NativeCollection>,
         env: Arc<Env>,
     ) -> Self {
@@ -41,11 +48,19 @@ impl NativeInstance {
             name: name.to_string(),
             dir: dir.to_string(),
             instance_id,
+            schemas,
             collections,
             env,
         }
     }
 
+    /// Copies the database at `path` without opening it as an instance so it is not migrated.
+    #[cfg(feature = "sqlite")]
+    pub(crate) fn copy_file(path: &str, copy_path: &str, max_size_mib: u32) -> Result<()> {
+        let env = Env::create(path, 0, max_size_mib)?;
+        env.copy(copy_path)
+    }
+
     pub(crate) fn verify_instance_id(&self, instance_id: u32) -> Result<()> {
         if self.instance_id != instance_id {
             Err(IsarError::InstanceMismatch {})
@@ -61,6 +76,102 @@ impl NativeInstance {
             Err(IsarError::IllegalArgument {})
         }
     }
+
+    fn get_link(&self, collection_index: u16, link_index: u16) -> Result<&NativeLink> {
+        let collection = self.get_collection(collection_index)?;
+        collection
+            .links
+            .get(link_index as usize)
+            .ok_or(IsarError::IllegalArgument {})
+    }
+
+    fn exists(&self, txn: &NativeTxn, collection_index: u16, id: i64) -> Result<bool> {
+        let collection = self.get_collection(collection_index)?;
+        let mut cursor = collection.get_cursor(txn)?;
+        Ok(cursor.move_to(&id.to_id_bytes())?.is_some())
+    }
+
+    /// Deletes the object and the objects of its cascading links.
+    fn delete_cascade(
+        &self,
+        txn: &NativeTxn,
+        change_set: &mut ChangeSet,
+        collection: &NativeCollection,
+        id: i64,
+    ) -> Result<bool> {
+        let mut cascade = vec![];
+        for link in collection.links.iter().filter(|l| l.cascade) {
+            for target_id in link.get_ids(txn, id)? {
+                cascade.push((link.target_collection_index, target_id));
+            }
+        }
+
+        let mut cursor = collection.get_cursor(txn)?;
+        if !collection.delete(txn, change_set, &mut cursor, id)? {
+            return Ok(false);
+        }
+        for (collection_index, target_id) in cascade {
+            let target = &self.collections[collection_index as usize];
+            self.delete_cascade(txn, change_set, target, target_id)?;
+        }
+        Ok(true)
+    }
+
+    fn purge(&self, txn: &NativeTxn, on_open: bool) -> Result<u32> {
+        txn.guard(|| {
+            let change_set = &mut txn.get_change_set();
+            let mut count = 0;
+            for collection in &self.collections {
+                if let Some(ttl) = &collection.ttl {
+                    if on_open && !ttl.schema.purge_on_open {
+                        continue;
+                    }
+                    let expired_until = ttl.schema.expired_until_now();
+                    for id in collection.get_expired_ids(txn, expired_until)? {
+                        if self.delete_cascade(txn, change_set, collection, id)? {
+                            count += 1;
+                        }
+                    }
+                }
+            }
+            Ok(count)
+        })
+    }
+
+    fn purge_on_open(&self) -> Result<()> {
+        let purge = self
+            .collections
+            .iter()
+            .any(|c| c.ttl.as_ref().is_some_and(|t| t.schema.purge_on_open));
+        if purge {
+            let txn = self.begin_txn(true)?;
+            self.purge(&txn, true)?;
+            txn.commit()?;
+        }
+        Ok(())
+    }
+
+    /// Closes the instance or hands it back if it is still in use elsewhere.
+    fn try_close(instance: Arc<Self>, delete: bool) -> std::result::Result<(), Arc<Self>> {
+        // Check whether all other references are gone
+        if Arc::strong_count(&instance) == 2 {
+            let mut lock = INSTANCES.lock();
+            // Check again to make sure there are no new references
+            if Arc::strong_count(&instance) == 2 {
+                lock.remove(instance.instance_id as u64);
+
+                if delete {
+                    let mut path = get_isar_path(&instance.name, &instance.dir);
+                    drop(instance);
+                    let _ = remove_file(&path);
+                    path.push_str(".lock");
+                    let _ = remove_file(&path);
+                }
+                return Ok(());
+            }
+        }
+        Err(instance)
+    }
 }
 
 impl IsarInstance for NativeInstance {
@@ -111,6 +222,39 @@ impl IsarInstance for NativeInstance {
         self.collections.iter().map(|c| c.name.as_str())
     }
 
+    fn get_id_name(&self, collection_index: u16) -> Result<Option<&str>> {
+        Ok(self.get_collection(collection_index)?.id_name.as_deref())
+    }
+
+    fn get_schema(&self, collection_index: u16) -> Result<&IsarSchema> {
+        self.schemas
+            .get(collection_index as usize)
+            .ok_or(IsarError::IllegalArgument {})
+    }
+
+    fn get_version_property(&self, collection_index: u16) -> Result<Option<u16>> {
+        Ok(self.get_collection(collection_index)?.version_property)
+    }
+
+    fn get_properties(
+        &self,
+        collection_index: u16,
+    ) -> Result<impl Iterator<Item = (&str, DataType, Option<u16>)>> {
+        let collection = self.get_collection(collection_index)?;
+        Ok(collection
+            .properties
+            .iter()
+            .map(|(name, p)| (name.as_str(), p.data_type, p.embedded_collection_index)))
+    }
+
+    fn get_links(&self, collection_index: u16) -> Result<impl Iterator<Item = (&str, u16, bool)>> {
+        let collection = self.get_collection(collection_index)?;
+        Ok(collection
+            .links
+            .iter()
+            .map(|l| (l.name.as_str(), l.target_collection_index, l.single)))
+    }
+
     fn open_instance(
         instance_id: u32,
         name: &str,
@@ -135,6 +279,7 @@ impl IsarInstance for NativeInstance {
                 max_size_mib,
                 compact_condition,
             )?;
+            new_instance.purge_on_open()?;
             let new_instance = Arc::new(new_instance);
             lock.insert(instance_id as u64, new_instance.clone());
             Ok(new_instance)
@@ -160,6 +305,21 @@ impl IsarInstance for NativeInstance {
         }
     }
 
+    fn begin_savepoint(&self, txn: &mut Self::Txn) -> Result<()> {
+        self.verify_instance_id(txn.instance_id)?;
+        txn.begin_savepoint()
+    }
+
+    fn release_savepoint(&self, txn: &mut Self::Txn) -> Result<()> {
+        self.verify_instance_id(txn.instance_id)?;
+        txn.release_savepoint()
+    }
+
+    fn rollback_to_savepoint(&self, txn: &mut Self::Txn) -> Result<()> {
+        self.verify_instance_id(txn.instance_id)?;
+        txn.rollback_to_savepoint()
+    }
+
     fn auto_increment(&self, collection_index: u16) -> i64 {
         if let Ok(collection) = self.get_collection(collection_index) {
             collection.auto_increment()
@@ -168,6 +328,12 @@ impl IsarInstance for NativeInstance {
         }
     }
 
+    fn update_auto_increment(&self, collection_index: u16, id: i64) {
+        if let Ok(collection) = self.get_collection(collection_index) {
+            collection.update_auto_increment(id);
+        }
+    }
+
     fn cursor<'a>(&'a self, txn: &'a Self::Txn, collection_index: u16) -> Result<Self::Cursor<'a>> {
         self.verify_instance_id(txn.instance_id)?;
         let collection = self.get_collection(collection_index)?;
@@ -191,18 +357,41 @@ impl IsarInstance for NativeInstance {
         collection_index: u16,
         id: i64,
         updates: &[(u16, Option<IsarValue>)],
+    ) -> Result<bool> {
+        let collection = self.get_collection(collection_index)?;
+        let updates = set_updates(updates, |index| collection.get_property(index).is_some());
+        self.update_properties(txn, collection_index, id, &updates)
+    }
+
+    fn update_properties(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        id: i64,
+        updates: &[PropertyUpdate],
     ) -> Result<bool> {
         self.verify_instance_id(txn.instance_id)?;
         let collection = self.get_collection(collection_index)?;
-        let mut cursor = collection.get_cursor(txn)?;
-        txn.guard(|| collection.update(txn, &mut txn.get_change_set(), &mut cursor, id, updates))
+        collection.verify_updates(&self.collections, updates)?;
+        let updates = apply_updates(&collection.constraints, updates)?;
+        txn.guard(|| {
+            let mut cursor = collection.get_cursor(txn)?;
+            let change_set = &mut txn.get_change_set();
+            collection.update(
+                txn,
+                change_set,
+                &mut cursor,
+                &self.collections,
+                id,
+                &updates,
+            )
+        })
     }
 
     fn delete<'a>(&'a self, txn: &'a Self::Txn, collection_index: u16, id: i64) -> Result<bool> {
         self.verify_instance_id(txn.instance_id)?;
         let collection = self.get_collection(collection_index)?;
-        let mut cursor = collection.get_cursor(txn)?;
-        txn.guard(|| collection.delete(txn, &mut txn.get_change_set(), &mut cursor, id))
+        txn.guard(|| self.delete_cascade(txn, &mut txn.get_change_set(), collection, id))
     }
 
     fn count(&self, txn: &Self::Txn, collection_index: u16) -> Result<u32> {
@@ -211,10 +400,99 @@ impl IsarInstance for NativeInstance {
         collection.count(txn)
     }
 
+    fn link(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+        target_id: i64,
+    ) -> Result<bool> {
+        self.verify_instance_id(txn.instance_id)?;
+        let link = self.get_link(collection_index, link_index)?;
+        txn.guard(|| {
+            if !self.exists(txn, collection_index, id)?
+                || !self.exists(txn, link.target_collection_index, target_id)?
+            {
+                return Ok(false);
+            }
+            link.link(txn, id, target_id)?;
+            Ok(true)
+        })
+    }
+
+    fn unlink(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+        target_id: i64,
+    ) -> Result<bool> {
+        self.verify_instance_id(txn.instance_id)?;
+        let link = self.get_link(collection_index, link_index)?;
+        txn.guard(|| link.unlink(txn, id, target_id))
+    }
+
+    fn unlink_all(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+    ) -> Result<u32> {
+        self.verify_instance_id(txn.instance_id)?;
+        let link = self.get_link(collection_index, link_index)?;
+        txn.guard(|| link.unlink_all(txn, id))
+    }
+
+    fn get_linked_ids(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+    ) -> Result<Vec<i64>> {
+        self.verify_instance_id(txn.instance_id)?;
+        let link = self.get_link(collection_index, link_index)?;
+        link.get_ids(txn, id)
+    }
+
+    fn count_links(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+    ) -> Result<u32> {
+        let ids = self.get_linked_ids(txn, collection_index, link_index, id)?;
+        Ok(ids.len() as u32)
+    }
+
     fn clear(&self, txn: &Self::Txn, collection_index: u16) -> Result<()> {
         self.verify_instance_id(txn.instance_id)?;
         let collection = self.get_collection(collection_index)?;
-        txn.guard(|| collection.clear(txn))
+        txn.guard(|| {
+            let mut cascade = vec![];
+            for link in collection.links.iter().filter(|l| l.cascade) {
+                for target_id in link.get_all_ids(txn)? {
+                    cascade.push((link.target_collection_index, target_id));
+                }
+            }
+            collection.clear(txn)?;
+
+            let change_set = &mut txn.get_change_set();
+            for (collection_index, target_id) in cascade {
+                let target = &self.collections[collection_index as usize];
+                self.delete_cascade(txn, change_set, target, target_id)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn purge_expired(&self, txn: &Self::Txn) -> Result<u32> {
+        self.verify_instance_id(txn.instance_id)?;
+        self.purge(txn, false)
     }
 
     fn get_size(
@@ -246,8 +524,7 @@ impl IsarInstance for NativeInstance {
     ) -> Result<Self::QueryCursor<'a>> {
         self.verify_instance_id(txn.instance_id)?;
         self.verify_instance_id(query.instance_id)?;
-        let result = query.cursor(txn, &self.collections, offset, limit);
-        Ok(result)
+        query.cursor(txn, &self.collections, offset, limit)
     }
 
     fn query_aggregate(
@@ -259,8 +536,7 @@ impl IsarInstance for NativeInstance {
     ) -> Result<Option<IsarValue>> {
         self.verify_instance_id(txn.instance_id)?;
         self.verify_instance_id(query.instance_id)?;
-        let result = query.aggregate(txn, &self.collections, aggregation, property_index);
-        Ok(result)
+        query.aggregate(txn, &self.collections, aggregation, property_index)
     }
 
     fn query_update(
@@ -270,17 +546,39 @@ impl IsarInstance for NativeInstance {
         offset: Option<u32>,
         limit: Option<u32>,
         updates: &[(u16, Option<IsarValue>)],
+    ) -> Result<u32> {
+        let collection = self.get_collection(query.collection_index)?;
+        let updates = set_updates(updates, |index| collection.get_property(index).is_some());
+        self.query_update_properties(txn, query, offset, limit, &updates)
+    }
+
+    fn query_update_properties(
+        &self,
+        txn: &Self::Txn,
+        query: &Self::Query,
+        offset: Option<u32>,
+        limit: Option<u32>,
+        updates: &[PropertyUpdate],
     ) -> Result<u32> {
         self.verify_instance_id(txn.instance_id)?;
         self.verify_instance_id(query.instance_id)?;
         let collection = self.get_collection(query.collection_index)?;
-        let ids = query.get_matching_ids(txn, collection, offset, limit);
+        collection.verify_updates(&self.collections, updates)?;
+        let updates = apply_updates(&collection.constraints, updates)?;
+        let ids = query.get_matching_ids(txn, &self.collections, offset, limit)?;
 
         txn.guard(|| {
             let change_set = &mut txn.get_change_set();
             let mut cursor = collection.get_cursor(txn)?;
             for id in &ids {
-                collection.update(txn, change_set, &mut cursor, *id, updates)?;
+                collection.update(
+                    txn,
+                    change_set,
+                    &mut cursor,
+                    &self.collections,
+                    *id,
+                    &updates,
+                )?;
             }
             Ok(ids.len() as u32)
         })
@@ -296,13 +594,12 @@ impl IsarInstance for NativeInstance {
         self.verify_instance_id(txn.instance_id)?;
         self.verify_instance_id(query.instance_id)?;
         let collection = self.get_collection(query.collection_index)?;
-        let ids = query.get_matching_ids(txn, collection, offset, limit);
+        let ids = query.get_matching_ids(txn, &self.collections, offset, limit)?;
 
         txn.guard(|| {
             let change_set = &mut txn.get_change_set();
-            let mut cursor = collection.get_cursor(txn)?;
             for id in &ids {
-                collection.delete(txn, change_set, &mut cursor, *id)?;
+                self.delete_cascade(txn, change_set, collection, *id)?;
             }
             Ok(ids.len() as u32)
         })
@@ -327,6 +624,9 @@ impl IsarInstance for NativeInstance {
 
     fn watch_query(&self, query: &Self::Query, callback: WatcherCallback) -> Result<WatchHandle> {
         self.verify_instance_id(query.instance_id)?;
+        if query.has_links() {
+            return Err(IsarError::UnsupportedOperation {});
+        }
         let collection = self.get_collection(query.collection_index)?;
         let handle = collection.watchers.watch_query(query, callback);
         Ok(handle)
@@ -336,28 +636,143 @@ impl IsarInstance for NativeInstance {
         self.env.copy(path)
     }
 
+    fn backup(&self, path: &str, mut progress: impl FnMut(u64, u64)) -> Result<()> {
+        // MDBX copies the compacted environment in a single step
+        progress(0, 1);
+        let txn = NativeTxn::new(self.instance_id, &self.env, false)?;
+        let db_names = txn.db_names()?;
+        txn.abort();
+
+        self.env.copy(path)?;
+        if let Err(e) = verify_native_copy(path, Some(db_names)) {
+            let _ = remove_file(path);
+            return Err(e);
+        }
+        progress(1, 1);
+        Ok(())
+    }
+
+    fn restore(
+        instance: Arc<Self>,
+        path: &str,
+        schemas: Vec<IsarSchema>,
+        max_size_mib: u32,
+    ) -> RestoreResult<Arc<Self>> {
+        let isar_path = get_isar_path(&instance.name, &instance.dir);
+        let restore_path = format!("{}.restore", isar_path);
+        let copied = fs::copy(path, &restore_path)
+            .map_err(|_| IsarError::PathError {})
+            .and_then(|_| verify_native_copy(&restore_path, None));
+        if let Err(e) = copied {
+            let _ = remove_file(&restore_path);
+            return Err((Some(instance), e));
+        }
+
+        let instance_id = instance.instance_id;
+        let name = instance.name.clone();
+        let dir = instance.dir.clone();
+        if let Err(instance) = Self::try_close(instance, false) {
+            let _ = remove_file(&restore_path);
+            return Err((Some(instance), IsarError::Busy {}));
+        }
+
+        let open = |schemas| {
+            let _ = remove_file(format!("{}.lock", isar_path));
+            Self::open_instance(instance_id, &name, &dir, schemas, max_size_mib, None, None)
+        };
+        // the old file is kept until the restored one is open so it can be put back on failure
+        let old_path = format!("{}.old", isar_path);
+        let restored = fs::rename(&isar_path, &old_path)
+            .and_then(|_| fs::rename(&restore_path, &isar_path))
+            .map_err(|_| IsarError::PathError {})
+            .and_then(|_| open(schemas.clone()));
+        match restored {
+            Ok(instance) => {
+                let _ = remove_file(&old_path);
+                Ok(instance)
+            }
+            Err(e) => {
+                let _ = remove_file(&restore_path);
+                let _ = fs::rename(&old_path, &isar_path);
+                Err((open(schemas).ok(), e))
+            }
+        }
+    }
+
     fn verify(&self, txn: &Self::Txn) -> Result<()> {
         verify_native(txn, &self.collections)
     }
 
     fn close(instance: Arc<Self>, delete: bool) -> bool {
-        // Check whether all other references are gone
-        if Arc::strong_count(&instance) == 2 {
-            let mut lock = INSTANCES.lock();
-            // Check again to make sure there are no new references
-            if Arc::strong_count(&instance) == 2 {
-                lock.remove(instance.instance_id as u64);
+        Self::try_close(instance, delete).is_ok()
+    }
+}
 
-                if delete {
-                    let mut path = get_isar_path(&instance.name, &instance.dir);
-                    drop(instance);
-                    let _ = remove_file(&path);
-                    path.push_str(".lock");
-                    let _ = remove_file(&path);
-                }
-                return true;
-            }
-        }
-        false
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::schema::PropertySchema;
+    use crate::test_util::{next_instance_id, test_dir};
+
+    #[test]
+    fn test_restore() {
+        let instance_id = next_instance_id();
+        let properties = vec![PropertySchema::new("name", DataType::String, None)];
+        let schemas = vec![IsarSchema::new(
+            "Note",
+            Some("id"),
+            properties,
+            vec![],
+            false,
+        )];
+        let name = format!("restore_{}", instance_id);
+        let isar = NativeInstance::open_instance(
+            instance_id,
+            &name,
+            &test_dir(),
+            schemas.clone(),
+            10,
+            None,
+            None,
+        )
+        .unwrap();
+        let txn = isar.begin_txn(true).unwrap();
+        let json = r#"[{"id": 1, "name": "a"}]"#;
+        let mut deser = serde_json::Deserializer::from_str(json);
+        let (txn, _) = isar.import_json(txn, 0, &mut deser, |_| 0).unwrap();
+        isar.commit_txn(txn).unwrap();
+        let path = format!("{}/{}.bak", test_dir(), name);
+        isar.backup(&path, |_, _| {}).unwrap();
+
+        let txn = isar.begin_txn(true).unwrap();
+        assert_eq!(isar.delete(&txn, 0, 1), Ok(true));
+        isar.commit_txn(txn).unwrap();
+
+        let missing = format!("{}/{}.missing", test_dir(), name);
+        let isar = match NativeInstance::restore(isar, &missing, schemas.clone(), 10) {
+            Err((Some(isar), IsarError::PathError {})) => isar,
+            _ => panic!("restoring a missing backup must hand the instance back"),
+        };
+        let other = isar.clone();
+        let isar = match NativeInstance::restore(isar, &path, schemas.clone(), 10) {
+            Err((Some(isar), IsarError::Busy {})) => isar,
+            _ => panic!("restoring an instance in use must hand it back"),
+        };
+        drop(other);
+        let txn = isar.begin_txn(false).unwrap();
+        assert_eq!(isar.count(&txn, 0), Ok(0));
+        isar.abort_txn(txn);
+
+        let isar = match NativeInstance::restore(isar, &path, schemas, 10) {
+            Ok(isar) => isar,
+            Err((_, e)) => panic!("restore failed: {:?}", e),
+        };
+        let txn = isar.begin_txn(false).unwrap();
+        assert_eq!(isar.count(&txn, 0), Ok(1));
+        isar.abort_txn(txn);
+        let isar_path = get_isar_path(&name, &test_dir());
+        assert!(!std::path::Path::new(&format!("{}.old", isar_path)).exists());
+        let _ = remove_file(&path);
+        NativeInstance::close(isar, true);
     }
 }
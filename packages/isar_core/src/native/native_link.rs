@@ -0,0 +1,171 @@
+use super::mdbx::db::Db;
+use super::native_txn::NativeTxn;
+use super::{BytesToId, IdToBytes};
+use crate::core::error::Result;
+use std::collections::HashSet;
+
+const FORWARD: u8 = 0;
+const BACKWARD: u8 = 1;
+
+/// The edges of a link are stored in both directions in a single dupsort db. The key is the
+/// direction followed by the id and the values are the linked ids. Backlinks use the same db in
+/// the opposite direction.
+#[derive(Clone, Debug)]
+pub(crate) struct NativeLink {
+    pub name: String,
+    pub target_collection_index: u16,
+    pub single: bool,
+    // the link in the opposite direction is single
+    pub reverse_single: bool,
+    pub cascade: bool,
+    backward: bool,
+    db: Db,
+}
+
+impl NativeLink {
+    pub fn new(
+        name: &str,
+        target_collection_index: u16,
+        single: bool,
+        reverse_single: bool,
+        cascade: bool,
+        db: Db,
+    ) -> Self {
+        NativeLink {
+            name: name.to_string(),
+            target_collection_index,
+            single,
+            reverse_single,
+            cascade,
+            backward: false,
+            db,
+        }
+    }
+
+    /// Returns the link in the opposite direction pointing to `collection_index`.
+    pub fn reverse(&self, collection_index: u16) -> Self {
+        NativeLink {
+            name: self.name.clone(),
+            target_collection_index: collection_index,
+            single: self.reverse_single,
+            reverse_single: self.single,
+            cascade: false,
+            backward: !self.backward,
+            db: self.db,
+        }
+    }
+
+    pub fn is_backlink(&self) -> bool {
+        self.backward
+    }
+
+    fn key(&self, id: i64, reverse: bool) -> [u8; 9] {
+        let direction = if self.backward != reverse {
+            BACKWARD
+        } else {
+            FORWARD
+        };
+        let mut key = [direction; 9];
+        key[1..].copy_from_slice(&id.to_id_bytes());
+        key
+    }
+
+    pub fn get_ids(&self, txn: &NativeTxn, id: i64) -> Result<Vec<i64>> {
+        self.read_ids(txn, &self.key(id, false))
+    }
+
+    /// Returns the ids of the objects linking to `id`.
+    pub fn get_reverse_ids(&self, txn: &NativeTxn, id: i64) -> Result<Vec<i64>> {
+        self.read_ids(txn, &self.key(id, true))
+    }
+
+    fn read_ids(&self, txn: &NativeTxn, key: &[u8]) -> Result<Vec<i64>> {
+        let mut cursor = txn.get_cursor(self.db)?;
+        let mut ids = vec![];
+        let mut entry = cursor.move_to(key)?;
+        while let Some((_, value)) = entry {
+            ids.push(value.to_id());
+            entry = cursor.move_to_next_dup()?;
+        }
+        Ok(ids)
+    }
+
+    /// Returns the ids of the objects that link at least one object.
+    pub fn get_linking_ids(&self, txn: &NativeTxn) -> Result<HashSet<i64>> {
+        let cursor = txn.get_cursor(self.db)?;
+        let ids = cursor
+            .iter_between(
+                self.key(i64::MIN, false).to_vec(),
+                self.key(i64::MAX, false).to_vec(),
+                true,
+                true,
+            )?
+            .map(|(key, _)| (&key[1..]).to_id())
+            .collect();
+        Ok(ids)
+    }
+
+    /// Returns the ids linked by any object.
+    pub fn get_all_ids(&self, txn: &NativeTxn) -> Result<Vec<i64>> {
+        let cursor = txn.get_cursor(self.db)?;
+        let ids = cursor
+            .iter_between(
+                self.key(i64::MIN, false).to_vec(),
+                self.key(i64::MAX, false).to_vec(),
+                true,
+                false,
+            )?
+            .map(|(_, value)| value.to_id())
+            .collect();
+        Ok(ids)
+    }
+
+    pub fn link(&self, txn: &NativeTxn, id: i64, target_id: i64) -> Result<()> {
+        if self.single {
+            self.unlink_all(txn, id)?;
+        }
+        if self.reverse_single {
+            for source_id in self.get_reverse_ids(txn, target_id)? {
+                self.unlink(txn, source_id, target_id)?;
+            }
+        }
+        let mut cursor = txn.get_cursor(self.db)?;
+        cursor.put(&self.key(id, false), &target_id.to_id_bytes())?;
+        cursor.put(&self.key(target_id, true), &id.to_id_bytes())
+    }
+
+    pub fn unlink(&self, txn: &NativeTxn, id: i64, target_id: i64) -> Result<bool> {
+        let mut cursor = txn.get_cursor(self.db)?;
+        let key = self.key(id, false);
+        if cursor
+            .move_to_key_val(&key, &target_id.to_id_bytes())?
+            .is_none()
+        {
+            return Ok(false);
+        }
+        cursor.delete_current()?;
+
+        let reverse_key = self.key(target_id, true);
+        if cursor
+            .move_to_key_val(&reverse_key, &id.to_id_bytes())?
+            .is_some()
+        {
+            cursor.delete_current()?;
+        }
+        Ok(true)
+    }
+
+    pub fn unlink_all(&self, txn: &NativeTxn, id: i64) -> Result<u32> {
+        let mut count = 0;
+        for target_id in self.get_ids(txn, id)? {
+            if self.unlink(txn, id, target_id)? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    pub fn clear(&self, txn: &NativeTxn) -> Result<()> {
+        txn.clear_db(self.db)
+    }
+}
@@ -20,22 +20,18 @@ pub(crate) fn open_native(
 ) -> Result<NativeInstance> {
     let path = get_isar_path(name, dir);
 
-    // clone the schema in case we need to compact
-    let compact_schemas = if compact_condition.is_some() {
-        Some(schemas.clone())
-    } else {
-        None
-    };
-
-    // _info + collections + indexes + 1 (to delete old dbs)
+    // _info + collections + indexes + links + blobs + 1 (to delete old dbs)
     let db_count = schemas
         .iter()
         .filter(|c| !c.embedded)
-        .map(|c| c.indexes.len() as u32 + 1)
+        .map(|c| {
+            let links = c.links.iter().filter(|l| l.backlink.is_none()).count();
+            c.indexes.len() as u32 + links as u32 + 2
+        })
         .sum::<u32>()
         + 2;
     let env = Env::create(&path, db_count, max_size_mib)?;
-    let collections = perform_migration(instance_id, &env, schemas)?;
+    let collections = perform_migration(instance_id, &env, schemas.clone())?;
 
     let env_collections = if let Some(compact_condition) = &compact_condition {
         compact_instance(env, collections, &path, compact_condition)?
@@ -44,17 +40,10 @@ pub(crate) fn open_native(
     };
 
     if let Some((env, collections)) = env_collections {
-        let instance = NativeInstance::new(name, dir, instance_id, collections, env);
+        let instance = NativeInstance::new(name, dir, instance_id, schemas, collections, env);
         Ok(instance)
     } else {
-        open_native(
-            name,
-            dir,
-            instance_id,
-            compact_schemas.unwrap(),
-            max_size_mib,
-            None,
-        )
+        open_native(name, dir, instance_id, schemas, max_size_mib, None)
     }
 }
 
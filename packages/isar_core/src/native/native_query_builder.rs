@@ -1,10 +1,14 @@
 use super::native_collection::{NativeCollection, NativeProperty};
 use super::query::native_filter::NativeFilter;
-use super::query::{NativeQuery, QueryIndex};
+use super::query::{NativeQuery, QueryIndex, Similarity, SortProperty};
 use crate::core::data_type::DataType;
+use crate::core::error::{IsarError, Result};
 use crate::core::filter::{ConditionType, Filter, FilterCondition};
+use crate::core::geo;
 use crate::core::query_builder::{IsarQueryBuilder, Sort};
-use crate::core::value::IsarValue;
+use crate::core::regex::Regex;
+use crate::core::schema::IndexType;
+use crate::core::value::{DateTimeOffset, Decimal, IsarValue, Uuid};
 use std::hint::black_box;
 
 pub struct NativeQueryBuilder<'a> {
@@ -12,8 +16,10 @@ pub struct NativeQueryBuilder<'a> {
     collection: &'a NativeCollection,
     all_collections: &'a [NativeCollection],
     filter: Option<Filter>,
-    sort: Vec<(Option<NativeProperty>, Sort, bool)>,
+    sort: Vec<(SortProperty, Sort, bool)>,
     distinct: Vec<(NativeProperty, bool)>,
+    // a distance sort without a geo index or with a non-finite coordinate
+    invalid_sort: bool,
 }
 
 impl<'a> NativeQueryBuilder<'a> {
@@ -29,6 +35,7 @@ impl<'a> NativeQueryBuilder<'a> {
             filter: None,
             sort: Vec::new(),
             distinct: Vec::new(),
+            invalid_sort: false,
         }
     }
 }
@@ -41,8 +48,58 @@ impl<'a> IsarQueryBuilder for NativeQueryBuilder<'a> {
     }
 
     fn add_sort(&mut self, property_index: u16, sort: Sort, case_sensitive: bool) {
-        let property = self.collection.get_property(property_index);
-        self.sort.push((property.copied(), sort, case_sensitive));
+        let property = match self.collection.get_property(property_index) {
+            Some(property) => SortProperty::Property(*property),
+            None => SortProperty::Id,
+        };
+        self.sort.push((property, sort, case_sensitive));
+    }
+
+    fn add_distance_sort(
+        &mut self,
+        property_index: u16,
+        latitude: f64,
+        longitude: f64,
+        sort: Sort,
+    ) {
+        let properties = self
+            .collection
+            .get_property(property_index)
+            .and_then(|p| Some((*p, geo_longitude_property(self.collection, p)?)));
+        match properties {
+            Some((property, lon_property)) if latitude.is_finite() && longitude.is_finite() => {
+                let distance = SortProperty::Distance(property, lon_property, latitude, longitude);
+                self.sort.push((distance, sort, false));
+            }
+            _ => self.invalid_sort = true,
+        }
+    }
+
+    fn add_similarity_sort(&mut self, property_index: u16, vector: Vec<f32>) {
+        let has_similarity = self
+            .sort
+            .iter()
+            .any(|(p, _, _)| matches!(p, SortProperty::Similarity(_)));
+        if has_similarity {
+            return;
+        }
+        if let Some(property) = self.collection.get_property(property_index) {
+            let options = self
+                .collection
+                .indexes
+                .iter()
+                .find(|i| i.index_type == IndexType::Vector && i.properties[0][0] == *property)
+                .and_then(|i| i.vector);
+            if let Some(options) = options {
+                let similarity = Similarity {
+                    property: *property,
+                    metric: options.metric,
+                    vector,
+                };
+                self.sort
+                    .push((SortProperty::Similarity(similarity), Sort::Asc, false));
+            }
+        }
     }
 
     fn add_distinct(&mut self, property_index: u16, case_sensitive: bool) {
@@ -52,30 +109,53 @@ impl<'a> IsarQueryBuilder for NativeQueryBuilder<'a> {
         }
     }
 
-    fn build(self) -> Self::Query {
+    fn build(self) -> Result<Self::Query> {
+        if self.invalid_sort {
+            return Err(IsarError::IllegalArgument {});
+        }
+        if let Some(filter) = &self.filter {
+            filter.check_patterns()?;
+        }
         let filter = self
             .filter
-            .map(|f| filter_to_native(&f, self.collection, self.all_collections))
+            .as_ref()
+            .map(|f| filter_to_native(self.instance_id, f, self.collection, self.all_collections))
+            .transpose()?
             .unwrap_or(NativeFilter::stat(true));
+        let index = self
+            .filter
+            .as_ref()
+            .and_then(|f| geo_query_index(f, self.collection))
+            .unwrap_or(QueryIndex::Primary(i64::MIN, i64::MAX));
 
-        black_box(NativeQuery::new(
+        Ok(black_box(NativeQuery::new(
             self.instance_id,
             self.collection.collection_index,
-            vec![QueryIndex::Primary(i64::MIN, i64::MAX)],
+            vec![index],
             filter,
             self.sort,
             self.distinct,
-        ))
+        )))
     }
 }
 
 fn filter_to_native(
+    instance_id: u32,
     filter: &Filter,
     collection: &NativeCollection,
     all_collections: &[NativeCollection],
-) -> NativeFilter {
-    match filter {
+) -> Result<NativeFilter> {
+    let filter = match filter {
         Filter::Condition(condition) => {
+            if is_geo_condition(condition) {
+                let property = collection.get_property(condition.property_index);
+                if property
+                    .and_then(|p| geo_longitude_property(collection, p))
+                    .is_none()
+                {
+                    return Err(IsarError::IllegalArgument {});
+                }
+            }
             condition_to_native(condition, collection).unwrap_or(NativeFilter::stat(false))
         }
         Filter::Json(_) => todo!(),
@@ -83,32 +163,99 @@ fn filter_to_native(
             if let Some(property) = collection.get_property(nested.property_index) {
                 if let Some(embedded_collection_index) = property.embedded_collection_index {
                     let embedded_collection = &all_collections[embedded_collection_index as usize];
-                    let filter =
-                        filter_to_native(&nested.filter, embedded_collection, all_collections);
-                    return NativeFilter::nested(property, filter);
+                    let filter = filter_to_native(
+                        instance_id,
+                        &nested.filter,
+                        embedded_collection,
+                        all_collections,
+                    )?;
+                    return Ok(NativeFilter::nested(property, filter));
                 }
             }
             NativeFilter::stat(false)
         }
+        Filter::MapEntry(entry) => {
+            if let Some(property) = collection.get_property(entry.property_index) {
+                return Ok(NativeFilter::map_entry(
+                    property,
+                    &entry.key,
+                    entry.condition.clone(),
+                ));
+            }
+            NativeFilter::stat(false)
+        }
+        Filter::Link(link) => {
+            if let Some(native_link) = collection.links.get(link.link_index as usize) {
+                let target = &all_collections[native_link.target_collection_index as usize];
+                let target_query = if let Some(filter) = &link.filter {
+                    let index = geo_query_index(filter, target)
+                        .unwrap_or(QueryIndex::Primary(i64::MIN, i64::MAX));
+                    Some(NativeQuery::new(
+                        instance_id,
+                        target.collection_index,
+                        vec![index],
+                        filter_to_native(instance_id, filter, target, all_collections)?,
+                        vec![],
+                        vec![],
+                    ))
+                } else {
+                    None
+                };
+                return Ok(NativeFilter::link(native_link, target_query));
+            }
+            NativeFilter::stat(false)
+        }
         Filter::And(filters) => {
             let filters = filters
                 .iter()
-                .map(|f| filter_to_native(f, collection, all_collections))
-                .collect();
+                .map(|f| filter_to_native(instance_id, f, collection, all_collections))
+                .collect::<Result<_>>()?;
             NativeFilter::and(filters)
         }
         Filter::Or(filters) => {
             let filters = filters
                 .iter()
-                .map(|f| filter_to_native(f, collection, all_collections))
-                .collect();
+                .map(|f| filter_to_native(instance_id, f, collection, all_collections))
+                .collect::<Result<_>>()?;
             NativeFilter::or(filters)
         }
         Filter::Not(filter) => {
-            let filter = filter_to_native(filter, collection, all_collections);
+            let filter = filter_to_native(instance_id, filter, collection, all_collections)?;
             NativeFilter::not(filter)
         }
-    }
+    };
+    Ok(filter)
+}
+
+fn is_geo_condition(condition: &FilterCondition) -> bool {
+    matches!(
+        condition.condition_type,
+        ConditionType::GeoWithinBox | ConditionType::GeoWithinRadius
+    )
+}
+
+/// Uses the geo index of a top level geo condition to find candidates. The condition itself is
+/// still part of the filter and checks the exact bounds.
+fn geo_query_index(filter: &Filter, collection: &NativeCollection) -> Option<QueryIndex> {
+    let condition = match filter {
+        Filter::Condition(condition) if is_geo_condition(condition) => condition,
+        Filter::And(filters) => {
+            return filters.iter().find_map(|f| geo_query_index(f, collection));
+        }
+        _ => return None,
+    };
+
+    let property = collection.get_property(condition.property_index)?;
+    let index = collection.indexes.iter().position(|index| {
+        index.index_type == IndexType::Geo && index.properties[0][0] == *property
+    })?;
+    let value = |i: usize| condition.values.get(i)?.as_ref()?.real();
+    let bounds = if condition.condition_type == ConditionType::GeoWithinBox {
+        (value(0)?, value(1)?, value(2)?, value(3)?)
+    } else {
+        geo::radius_bounds(value(0)?, value(1)?, value(2)?)
+    };
+    Some(QueryIndex::Geo(index, bounds))
 }
 
 fn condition_to_native(
@@ -191,10 +338,45 @@ fn condition_to_native(
             let value = condition.values.get(0)?.as_ref()?.string()?;
             NativeFilter::string_matches(property?, value, condition.case_sensitive)
         }
+        ConditionType::StringRegex => {
+            let pattern = condition.values.first()?.as_ref()?.string()?;
+            let regex = Regex::new(pattern, !condition.case_sensitive)?;
+            NativeFilter::string_regex(property?, regex)
+        }
+        ConditionType::GeoWithinBox => {
+            let property = property?;
+            let lon_property = geo_longitude_property(collection, property)?;
+            let value = |i: usize| condition.values.get(i)?.as_ref()?.real();
+            NativeFilter::geo_box(
+                property,
+                &lon_property,
+                value(0)?,
+                value(1)?,
+                value(2)?,
+                value(3)?,
+            )
+        }
+        ConditionType::GeoWithinRadius => {
+            let property = property?;
+            let lon_property = geo_longitude_property(collection, property)?;
+            let value = |i: usize| condition.values.get(i)?.as_ref()?.real();
+            NativeFilter::geo_radius(property, &lon_property, value(0)?, value(1)?, value(2)?)
+        }
     };
     Some(filter)
 }
 
+fn geo_longitude_property(
+    collection: &NativeCollection,
+    lat_property: &NativeProperty,
+) -> Option<NativeProperty> {
+    collection
+        .indexes
+        .iter()
+        .find(|index| index.index_type == IndexType::Geo && index.properties[0][0] == *lat_property)
+        .map(|index| index.properties[1][0])
+}
+
 fn native_between_filter(
     property: Option<&NativeProperty>,
     lower: Option<&IsarValue>,
@@ -341,7 +523,44 @@ fn native_between_filter(
 
                 NativeFilter::string(property, lower.as_deref(), upper.as_deref(), case_sensitive)
             }
-            DataType::Object | DataType::ObjectList => return None,
+            DataType::Decimal | DataType::Uuid | DataType::DateTimeOffset => {
+                let lower = if let Some(lower) = lower {
+                    let mut lower = fixed_bytes(property.data_type, lower)?;
+                    if !include_lower && !increment_bytes(&mut lower) {
+                        return None;
+                    }
+                    Some(lower)
+                } else if !include_lower {
+                    Some(vec![])
+                } else {
+                    None
+                };
+
+                let upper = if let Some(upper) = upper {
+                    let mut upper = fixed_bytes(property.data_type, upper)?;
+                    if include_upper || decrement_bytes(&mut upper) {
+                        Some(upper)
+                    } else {
+                        None
+                    }
+                } else if !include_upper {
+                    return None; // cannot exclude
+                } else {
+                    None
+                };
+
+                NativeFilter::fixed_bytes(property, lower, upper)
+            }
+            DataType::Object | DataType::ObjectList | DataType::Blob => return None,
+            // maps are filtered by key
+            DataType::BoolMap
+            | DataType::ByteMap
+            | DataType::IntMap
+            | DataType::FloatMap
+            | DataType::LongMap
+            | DataType::DoubleMap
+            | DataType::StringMap
+            | DataType::ObjectMap => return None,
         }
     } else {
         let mut lower = if let Some(lower) = lower {
@@ -403,6 +622,40 @@ fn upper_real(value: Option<&IsarValue>, include: bool) -> Option<f64> {
     Some(value)
 }
 
+fn fixed_bytes(data_type: DataType, value: &IsarValue) -> Option<Vec<u8>> {
+    let bytes = match (data_type, value) {
+        (DataType::Decimal, IsarValue::Decimal(value)) => value.to_bytes().to_vec(),
+        (DataType::Uuid, IsarValue::Uuid(value)) => value.to_bytes().to_vec(),
+        (DataType::DateTimeOffset, IsarValue::DateTimeOffset(value)) => value.to_bytes().to_vec(),
+        _ => return None,
+    };
+    Some(bytes)
+}
+
+// Returns false if the bytes overflowed.
+fn increment_bytes(bytes: &mut [u8]) -> bool {
+    for byte in bytes.iter_mut().rev() {
+        let (value, overflow) = byte.overflowing_add(1);
+        *byte = value;
+        if !overflow {
+            return true;
+        }
+    }
+    false
+}
+
+// Returns false if the bytes underflowed.
+fn decrement_bytes(bytes: &mut [u8]) -> bool {
+    for byte in bytes.iter_mut().rev() {
+        let (value, overflow) = byte.overflowing_sub(1);
+        *byte = value;
+        if !overflow {
+            return true;
+        }
+    }
+    false
+}
+
 fn get_max(property: Option<&NativeProperty>) -> Option<IsarValue> {
     let value = if let Some(property) = property {
         match property.data_type {
@@ -415,7 +668,20 @@ fn get_max(property: Option<&NativeProperty>) -> Option<IsarValue> {
             DataType::String | DataType::StringList | DataType::Json => {
                 IsarValue::String(IsarValue::MAX_STRING.to_string())
             }
-            DataType::Object | DataType::ObjectList => return None,
+            DataType::Decimal => IsarValue::Decimal(Decimal::from_raw(i128::MAX)),
+            DataType::Uuid => IsarValue::Uuid(Uuid([u8::MAX; 16])),
+            DataType::DateTimeOffset => {
+                IsarValue::DateTimeOffset(DateTimeOffset::new(i64::MAX, i16::MAX))
+            }
+            DataType::Object | DataType::ObjectList | DataType::Blob => return None,
+            DataType::BoolMap
+            | DataType::ByteMap
+            | DataType::IntMap
+            | DataType::FloatMap
+            | DataType::LongMap
+            | DataType::DoubleMap
+            | DataType::StringMap
+            | DataType::ObjectMap => return None,
         }
     } else {
         IsarValue::Integer(i64::MAX)
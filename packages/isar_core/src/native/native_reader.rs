@@ -1,8 +1,10 @@
 use super::isar_deserializer::IsarDeserializer;
 use super::native_collection::{NativeCollection, NativeProperty};
+use super::native_txn::NativeTxn;
 use super::{NULL_DOUBLE, NULL_FLOAT, NULL_INT, NULL_LONG};
 use crate::core::data_type::DataType;
 use crate::core::reader::IsarReader;
+use crate::core::value::{DateTimeOffset, Decimal, Uuid};
 use std::borrow::Cow;
 use std::iter::empty;
 
@@ -11,6 +13,9 @@ pub struct NativeReader<'a> {
     object: IsarDeserializer<'a>,
     collection: &'a NativeCollection,
     all_collections: &'a [NativeCollection],
+    score: Option<f64>,
+    // required to read blobs which are stored outside of the object
+    txn: Option<&'a NativeTxn>,
 }
 
 impl<'a> NativeReader<'a> {
@@ -25,9 +30,21 @@ impl<'a> NativeReader<'a> {
             object,
             collection,
             all_collections,
+            score: None,
+            txn: None,
         }
     }
 
+    pub(crate) fn with_score(mut self, score: Option<f64>) -> Self {
+        self.score = score;
+        self
+    }
+
+    pub(crate) fn with_txn(mut self, txn: &'a NativeTxn) -> Self {
+        self.txn = Some(txn);
+        self
+    }
+
     #[inline]
     fn get_property(&self, index: u32) -> Option<&NativeProperty> {
         self.collection.get_property(index as u16)
@@ -135,10 +152,48 @@ impl<'a> IsarReader for NativeReader<'a> {
         }
     }
 
+    #[inline]
+    fn read_decimal(&self, index: u32) -> Option<Decimal> {
+        let offset = self.get_offset(index, DataType::Decimal)?;
+        Decimal::from_bytes(self.object.read_dynamic(offset)?)
+    }
+
+    #[inline]
+    fn read_uuid(&self, index: u32) -> Option<Uuid> {
+        let offset = self.get_offset(index, DataType::Uuid)?;
+        Uuid::from_bytes(self.object.read_dynamic(offset)?)
+    }
+
+    #[inline]
+    fn read_date_time_offset(&self, index: u32) -> Option<DateTimeOffset> {
+        let offset = self.get_offset(index, DataType::DateTimeOffset)?;
+        DateTimeOffset::from_bytes(self.object.read_dynamic(offset)?)
+    }
+
     #[inline]
     fn read_blob(&self, index: u32) -> Option<Cow<'_, [u8]>> {
-        let offset = self.get_offset(index, DataType::ByteList)?;
-        self.object.read_dynamic(offset).map(Cow::Borrowed)
+        let property = self.get_property(index)?;
+        match property.data_type {
+            DataType::ByteList => self.object.read_dynamic(property.offset).map(Cow::Borrowed),
+            DataType::Blob if !self.object.is_null(property.offset, DataType::Blob) => {
+                let blob = self
+                    .collection
+                    .get_blob(self.txn?, self.id, property.offset)
+                    .ok()??;
+                Some(Cow::Borrowed(blob))
+            }
+            _ => None,
+        }
+    }
+
+    fn read_blob_length(&self, index: u32) -> Option<usize> {
+        let property = self.get_property(index)?;
+        if property.data_type == DataType::Blob {
+            let length = self.object.read_long(property.offset);
+            (length != NULL_LONG).then_some(length as usize)
+        } else {
+            self.read_blob(index).map(|blob| blob.len())
+        }
     }
 
     fn read_object(&self, index: u32) -> Option<Self::ObjectReader<'_>> {
@@ -155,6 +210,8 @@ impl<'a> IsarReader for NativeReader<'a> {
             object,
             collection,
             all_collections: self.all_collections,
+            score: None,
+            txn: None,
         })
     }
 
@@ -171,6 +228,27 @@ impl<'a> IsarReader for NativeReader<'a> {
         };
         Some((reader, length))
     }
+
+    fn read_map(&self, index: u32) -> Option<(Vec<Cow<'_, str>>, Self::ListReader<'_>)> {
+        let property = self.collection.get_property(index as u16)?;
+        let value_type = property.data_type.value_type()?;
+
+        let (keys, values, length) = self.object.read_map(property.offset, value_type)?;
+        let keys = (0..length)
+            .map(|i| Cow::Borrowed(keys.read_string(i * 3).unwrap_or_default()))
+            .collect();
+        let reader = NativeListReader {
+            list: values,
+            data_type: value_type,
+            embedded_collection_index: property.embedded_collection_index,
+            all_collections: self.all_collections,
+        };
+        Some((keys, reader))
+    }
+
+    fn read_score(&self) -> Option<f64> {
+        self.score
+    }
 }
 
 pub struct NativeListReader<'a> {
@@ -273,6 +351,18 @@ impl<'a> IsarReader for NativeListReader<'a> {
         }
     }
 
+    fn read_decimal(&self, _index: u32) -> Option<Decimal> {
+        None // lists of decimals are not supported
+    }
+
+    fn read_uuid(&self, _index: u32) -> Option<Uuid> {
+        None // lists of uuids are not supported
+    }
+
+    fn read_date_time_offset(&self, _index: u32) -> Option<DateTimeOffset> {
+        None // lists of date times are not supported
+    }
+
     fn read_blob(&self, _index: u32) -> Option<Cow<'_, [u8]>> {
         None // nested lists are not supported
     }
@@ -292,12 +382,18 @@ impl<'a> IsarReader for NativeListReader<'a> {
             object,
             collection,
             all_collections: self.all_collections,
+            score: None,
+            txn: None,
         })
     }
 
     fn read_list(&self, _index: u32) -> Option<(Self::ListReader<'_>, u32)> {
         None // nested lists are not supported
     }
+
+    fn read_map(&self, _index: u32) -> Option<(Vec<Cow<'_, str>>, Self::ListReader<'_>)> {
+        None // lists of maps are not supported
+    }
 }
 
 #[cfg(test)]
@@ -5,15 +5,22 @@ use super::mdbx::db::Db;
 use super::mdbx::env::Env;
 use super::mdbx::txn::Txn;
 use super::IdToBytes;
-use crate::core::error::Result;
+use crate::core::error::{IsarError, Result};
 use crate::core::watcher::ChangeSet;
 use std::cell::{Cell, RefCell, RefMut};
+use std::collections::VecDeque;
+use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 
 pub struct NativeTxn {
     pub(crate) instance_id: u32,
+    write: bool,
+    // the innermost savepoint or the root txn
     txn: Txn,
+    // parents of txn with the change set at the start of their savepoint, innermost first so
+    // they are aborted after their children when dropped
+    parents: VecDeque<(Txn, ChangeSet)>,
     buffer: Cell<Option<Vec<u8>>>,
     change_set: RefCell<ChangeSet>,
     unbound_cursors: RefCell<Vec<UnboundCursor>>,
@@ -24,7 +31,9 @@ impl NativeTxn {
         let txn = env.txn(write)?;
         let txn = Self {
             instance_id,
+            write,
             txn,
+            parents: VecDeque::new(),
             buffer: Cell::new(None),
             change_set: RefCell::new(ChangeSet::new()),
             unbound_cursors: RefCell::new(Vec::new()),
@@ -57,7 +66,15 @@ impl NativeTxn {
     {
         let result = job();
         if !result.is_ok() {
-            self.txn.mark_broken();
+            if let Some((parent, change_set)) = self.parents.front() {
+                // only the innermost savepoint is rolled back
+                self.change_set.replace(change_set.clone());
+                if self.txn.restart_nested(parent).is_err() {
+                    parent.mark_broken();
+                }
+            } else {
+                self.txn.mark_broken();
+            }
         }
         result
     }
@@ -89,7 +106,41 @@ impl NativeTxn {
         Ok(names)
     }
 
-    pub(crate) fn commit(self) -> Result<()> {
+    pub(crate) fn begin_savepoint(&mut self) -> Result<()> {
+        if !self.write {
+            return Err(IsarError::WriteTxnRequired {});
+        }
+        let nested = self.txn.nested()?;
+        let parent = mem::replace(&mut self.txn, nested);
+        let change_set = self.change_set.borrow().clone();
+        self.parents.push_front((parent, change_set));
+        Ok(())
+    }
+
+    pub(crate) fn release_savepoint(&mut self) -> Result<()> {
+        let (parent, _) = self
+            .parents
+            .pop_front()
+            .ok_or(IsarError::IllegalArgument {})?;
+        let nested = mem::replace(&mut self.txn, parent);
+        nested.commit()
+    }
+
+    pub(crate) fn rollback_to_savepoint(&mut self) -> Result<()> {
+        let (parent, change_set) = self
+            .parents
+            .pop_front()
+            .ok_or(IsarError::IllegalArgument {})?;
+        let nested = mem::replace(&mut self.txn, parent);
+        nested.abort();
+        self.change_set.replace(change_set);
+        Ok(())
+    }
+
+    pub(crate) fn commit(mut self) -> Result<()> {
+        while !self.parents.is_empty() {
+            self.release_savepoint()?;
+        }
         self.txn.commit()?;
         self.change_set.borrow_mut().notify_watchers();
         Ok(())
@@ -181,3 +232,80 @@ impl<'txn> Drop for TxnCursor<'txn> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::watcher::CollectionWatchers;
+    use crate::native::query::NativeQuery;
+    use crate::native::BytesToId;
+    use crate::test_util::{next_instance_id, test_dir};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn put(txn: &NativeTxn, db: Db, id: i64) -> Result<()> {
+        txn.get_cursor(db)?.put(&id.to_id_bytes(), &[])
+    }
+
+    fn ids(txn: &NativeTxn, db: Db) -> Vec<i64> {
+        let cursor = txn.get_cursor(db).unwrap();
+        cursor
+            .iter_between_ids(i64::MIN, i64::MAX, false, false)
+            .unwrap()
+            .map(|(key, _)| key.to_id())
+            .collect()
+    }
+
+    #[test]
+    fn test_savepoints() {
+        let path = format!("{}/native_txn_{}.isar", test_dir(), next_instance_id());
+        let env = Env::create(&path, 1, 16).unwrap();
+        let mut txn = NativeTxn::new(0, &env, true).unwrap();
+        let db = txn.open_db("test", true, false).unwrap();
+
+        let notified = Arc::new(AtomicU32::new(0));
+        let watchers = CollectionWatchers::<NativeQuery>::new();
+        let notified_clone = notified.clone();
+        let _handle = watchers.watch(Box::new(move || {
+            notified_clone.fetch_add(1, Ordering::SeqCst);
+        }));
+
+        put(&txn, db, 1).unwrap();
+        txn.begin_savepoint().unwrap();
+        put(&txn, db, 2).unwrap();
+        txn.begin_savepoint().unwrap();
+        put(&txn, db, 3).unwrap();
+        txn.get_change_set().register_all(&watchers);
+        txn.rollback_to_savepoint().unwrap();
+        txn.release_savepoint().unwrap();
+        assert!(txn.release_savepoint().is_err());
+
+        // a failing job only rolls back the innermost savepoint
+        txn.begin_savepoint().unwrap();
+        put(&txn, db, 4).unwrap();
+        let result: Result<()> = txn.guard(|| {
+            put(&txn, db, 5)?;
+            txn.get_change_set().register_all(&watchers);
+            Err(IsarError::IllegalArgument {})
+        });
+        assert!(result.is_err());
+        assert_eq!(ids(&txn, db), vec![1, 2]);
+        put(&txn, db, 6).unwrap();
+        txn.release_savepoint().unwrap();
+        txn.commit().unwrap();
+        assert_eq!(notified.load(Ordering::SeqCst), 0);
+
+        let txn = NativeTxn::new(0, &env, false).unwrap();
+        assert_eq!(ids(&txn, db), vec![1, 2, 6]);
+    }
+
+    #[test]
+    fn test_failed_job_without_savepoint_breaks_txn() {
+        let path = format!("{}/native_txn_{}.isar", test_dir(), next_instance_id());
+        let env = Env::create(&path, 1, 16).unwrap();
+        let txn = NativeTxn::new(0, &env, true).unwrap();
+        let db = txn.open_db("test", true, false).unwrap();
+        let result: Result<()> = txn.guard(|| Err(IsarError::IllegalArgument {}));
+        assert!(result.is_err());
+        assert!(put(&txn, db, 1).is_err());
+    }
+}
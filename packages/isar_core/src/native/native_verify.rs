@@ -1,19 +1,29 @@
+use super::mdbx::env::Env;
 use super::native_collection::NativeCollection;
 use super::native_txn::NativeTxn;
 use crate::core::error::{IsarError, Result};
+use std::fs;
 
 pub(crate) fn verify_native(txn: &NativeTxn, collections: &[NativeCollection]) -> Result<()> {
+    let mut actual_db_names = txn.db_names()?;
     let mut db_names = vec![];
     db_names.push("_info".to_string());
     for col in collections {
         if !col.is_embedded() {
             db_names.push(col.name.clone());
+            // the blob db is kept even if all blob properties are removed
+            let blob_db = format!("_{}__blobs", col.name);
+            if col.has_blobs() || actual_db_names.contains(&blob_db) {
+                db_names.push(blob_db);
+            }
             for index in &col.indexes {
                 db_names.push(format!("_{}_{}", col.name, index.name));
             }
+            for link in col.links.iter().filter(|l| !l.is_backlink()) {
+                db_names.push(format!("_{}_{}", col.name, link.name));
+            }
         }
     }
-    let mut actual_db_names = txn.db_names()?;
 
     db_names.sort();
     actual_db_names.sort();
@@ -24,3 +34,30 @@ pub(crate) fn verify_native(txn: &NativeTxn, collections: &[NativeCollection]) -
         Ok(())
     }
 }
+
+/// Opens a copy of an instance and makes sure it can be read. If database names are provided, the
+/// copy has to contain exactly these databases.
+pub(crate) fn verify_native_copy(path: &str, db_names: Option<Vec<String>>) -> Result<()> {
+    let file_size = fs::metadata(path)
+        .map_err(|_| IsarError::PathError {})?
+        .len();
+    let max_size_mib = ((file_size >> 20) + 10).min(u32::MAX as u64) as u32;
+
+    let env = Env::create(path, 0, max_size_mib)?;
+    let txn = NativeTxn::new(0, &env, false)?;
+    let result = txn.db_names().and_then(|mut actual_db_names| {
+        if let Some(mut db_names) = db_names {
+            db_names.sort();
+            actual_db_names.sort();
+            if db_names != actual_db_names {
+                return Err(IsarError::DbCorrupted {});
+            }
+        }
+        Ok(())
+    });
+    txn.abort();
+    drop(env);
+
+    let _ = fs::remove_file(format!("{}.lock", path));
+    result
+}
@@ -4,6 +4,7 @@ use super::isar_serializer::IsarSerializer;
 use super::native_collection::NativeCollection;
 use super::native_insert::NativeInsert;
 use crate::core::data_type::DataType;
+use crate::core::value::{DateTimeOffset, Decimal, Uuid};
 use crate::core::writer::IsarWriter;
 
 pub(crate) trait WriterImpl<'a> {
@@ -26,6 +27,12 @@ pub(crate) trait WriterImpl<'a> {
     fn get_collections(&self) -> &'a [NativeCollection];
 
     fn get_serializer(&mut self) -> &mut IsarSerializer;
+
+    /// Stores the payload of a blob until the object is saved. Only objects of collections can
+    /// have blobs.
+    fn put_blob(&mut self, _offset: u32, _value: &[u8]) -> bool {
+        false
+    }
 }
 
 impl<'a, T: WriterImpl<'a>> IsarWriter<'a> for T {
@@ -100,6 +107,30 @@ impl<'a, T: WriterImpl<'a>> IsarWriter<'a> for T {
         }
     }
 
+    #[inline]
+    fn write_decimal(&mut self, index: u32, value: Decimal) {
+        if let Some(offset) = self.get_offset(index, DataType::Decimal) {
+            self.get_serializer()
+                .write_dynamic(offset, &value.to_bytes());
+        }
+    }
+
+    #[inline]
+    fn write_uuid(&mut self, index: u32, value: Uuid) {
+        if let Some(offset) = self.get_offset(index, DataType::Uuid) {
+            self.get_serializer()
+                .write_dynamic(offset, &value.to_bytes());
+        }
+    }
+
+    #[inline]
+    fn write_date_time_offset(&mut self, index: u32, value: DateTimeOffset) {
+        if let Some(offset) = self.get_offset(index, DataType::DateTimeOffset) {
+            self.get_serializer()
+                .write_dynamic(offset, &value.to_bytes());
+        }
+    }
+
     #[inline]
     fn write_byte_list(&mut self, index: u32, value: &[u8]) {
         if let Some(offset) = self.get_offset(index, DataType::ByteList) {
@@ -107,6 +138,15 @@ impl<'a, T: WriterImpl<'a>> IsarWriter<'a> for T {
         }
     }
 
+    #[inline]
+    fn write_blob(&mut self, index: u32, value: &[u8]) {
+        if let Some(offset) = self.get_offset(index, DataType::Blob) {
+            if self.put_blob(offset, value) {
+                self.get_serializer().write_long(offset, value.len() as i64);
+            }
+        }
+    }
+
     fn begin_object(&mut self, index: u32) -> Option<Self::ObjectWriter> {
         let (data_type, offset, collection_index) = self.get_property(index)?;
         if data_type == DataType::Object {
@@ -150,6 +190,32 @@ impl<'a, T: WriterImpl<'a>> IsarWriter<'a> for T {
     fn end_list(&mut self, writer: Self::ListWriter) {
         self.get_serializer().end_nested(writer.list);
     }
+
+    fn begin_map(&mut self, index: u32, keys: &[&str]) -> Option<Self::ListWriter> {
+        let (data_type, offset, embedded_collection_index) = self.get_property(index)?;
+        if let Some(value_type) = data_type.value_type() {
+            let length = keys.len() as u32;
+            let mut map = self.get_serializer().begin_nested(offset, 6);
+            let mut key_list = map.begin_nested(0, DataType::String.static_size() as u32 * length);
+            for (i, key) in keys.iter().enumerate() {
+                key_list.write_dynamic(i as u32 * 3, key.as_bytes());
+            }
+            map.end_nested(key_list);
+
+            let values = map.begin_nested(3, value_type.static_size() as u32 * length);
+            let writer = NativeListWriter::new(
+                value_type,
+                embedded_collection_index,
+                self.get_collections(),
+                values,
+                length,
+            );
+            Some(writer)
+        } else {
+            self.write_null(index);
+            None
+        }
+    }
 }
 
 impl<'a> WriterImpl<'a> for NativeInsert<'a> {
@@ -183,6 +249,12 @@ impl<'a> WriterImpl<'a> for NativeInsert<'a> {
     fn get_serializer(&mut self) -> &mut IsarSerializer {
         &mut self.object
     }
+
+    fn put_blob(&mut self, offset: u32, value: &[u8]) -> bool {
+        self.blobs.retain(|(o, _)| *o != offset);
+        self.blobs.push((offset, value.to_vec()));
+        true
+    }
 }
 
 pub struct NativeObjectWriter<'a> {
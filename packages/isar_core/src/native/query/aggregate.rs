@@ -228,6 +228,17 @@ pub(crate) fn aggregate_min_max<'a>(
                     None
                 }
             }
+            DataType::Decimal | DataType::Uuid | DataType::DateTimeOffset => {
+                let mut min_max: Option<&[u8]> = None;
+                for (_, reader) in iterator {
+                    if let Some(value) = reader.read_dynamic(property.offset) {
+                        if min_max.is_none_or(|min_max| value.cmp(min_max) == min_max_cmp) {
+                            min_max = Some(value);
+                        }
+                    }
+                }
+                IsarValue::from_bytes(property.data_type, min_max?)
+            }
             _ => None,
         }
     } else {
@@ -3,7 +3,8 @@ use crate::native::isar_deserializer::IsarDeserializer;
 use crate::native::mdbx::cursor_iterator::CursorIterator;
 use crate::native::native_collection::NativeCollection;
 use crate::native::native_txn::{NativeTxn, TxnCursor};
-use crate::native::BytesToId;
+use crate::native::{BytesToId, IdToBytes};
+use std::vec;
 
 pub(crate) struct IndexIterator<'a> {
     txn: &'a NativeTxn,
@@ -12,9 +13,16 @@ pub(crate) struct IndexIterator<'a> {
     // primary index iterator.
     iterator: Option<CursorIterator<'a, TxnCursor<'a>>>,
     primary_cursor: Option<TxnCursor<'a>>,
+    // ids found in a geo index that are looked up using the primary cursor
+    ids: Option<vec::IntoIter<i64>>,
     indexes: Vec<QueryIndex>,
 }
 
+enum IndexSource<'a> {
+    Iterator(CursorIterator<'a, TxnCursor<'a>>, Option<TxnCursor<'a>>),
+    Ids(vec::IntoIter<i64>, TxnCursor<'a>),
+}
+
 impl<'a> IndexIterator<'a> {
     pub fn new(
         txn: &'a NativeTxn,
@@ -23,33 +31,40 @@ impl<'a> IndexIterator<'a> {
     ) -> Self {
         let mut indexes = indexes.to_vec();
         indexes.reverse();
-        if let Some((iterator, primary_cursor)) =
-            Self::next_iterator(txn, collection, None, &mut indexes)
-        {
-            Self {
-                txn,
-                collection,
-                iterator: Some(iterator),
-                primary_cursor,
-                indexes,
+        let mut iterator = Self {
+            txn,
+            collection,
+            iterator: None,
+            primary_cursor: None,
+            ids: None,
+            indexes,
+        };
+        if let Some(source) = Self::next_source(txn, collection, None, &mut iterator.indexes) {
+            iterator.set_source(source);
+        }
+        iterator
+    }
+
+    fn set_source(&mut self, source: IndexSource<'a>) {
+        match source {
+            IndexSource::Iterator(iterator, primary_cursor) => {
+                self.iterator = Some(iterator);
+                self.primary_cursor = primary_cursor;
             }
-        } else {
-            Self {
-                txn,
-                collection,
-                iterator: None,
-                primary_cursor: None,
-                indexes,
+            IndexSource::Ids(ids, primary_cursor) => {
+                self.iterator = None;
+                self.ids = Some(ids);
+                self.primary_cursor = Some(primary_cursor);
             }
         }
     }
 
-    fn next_iterator<'b>(
+    fn next_source<'b>(
         txn: &'b NativeTxn,
         collection: &'b NativeCollection,
         primary_cursor: Option<TxnCursor<'b>>,
         indexes: &mut Vec<QueryIndex>,
-    ) -> Option<(CursorIterator<'b, TxnCursor<'b>>, Option<TxnCursor<'b>>)> {
+    ) -> Option<IndexSource<'b>> {
         let next_index = indexes.pop();
         if let Some(QueryIndex::Primary(start, end)) = next_index {
             let cursor = if let Some(primary_cursor) = primary_cursor {
@@ -58,9 +73,22 @@ impl<'a> IndexIterator<'a> {
                 collection.get_cursor(txn).ok()?
             };
             let iterator = cursor.iter_between_ids(start, end, false, false).ok()?;
-            Some((iterator, None))
+            Some(IndexSource::Iterator(iterator, None))
         } else if let Some(QueryIndex::Secondary(start, end)) = next_index {
             todo!()
+        } else if let Some(QueryIndex::Geo(index, (min_lat, min_lon, max_lat, max_lon))) =
+            next_index
+        {
+            let index = collection.indexes.get(index)?;
+            let ids = index
+                .get_geo_ids(txn, min_lat, min_lon, max_lat, max_lon)
+                .ok()?;
+            let cursor = if let Some(primary_cursor) = primary_cursor {
+                primary_cursor
+            } else {
+                collection.get_cursor(txn).ok()?
+            };
+            Some(IndexSource::Ids(ids.into_iter(), cursor))
         } else {
             None
         }
@@ -72,25 +100,34 @@ impl<'a> Iterator for IndexIterator<'a> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        let next = self.iterator.as_mut()?.next();
-        if let Some((key, value)) = next {
-            if let Some(primary_cursor) = &mut self.primary_cursor {
-                let (id, object) = primary_cursor.move_to(value).ok()??;
-                return Some((id.to_id(), IsarDeserializer::from_bytes(object)));
-            } else {
-                return Some((key.to_id(), IsarDeserializer::from_bytes(value)));
+        if let Some(ids) = &mut self.ids {
+            let primary_cursor = self.primary_cursor.as_mut()?;
+            for id in ids.by_ref() {
+                if let Some((_, object)) = primary_cursor.move_to(&id.to_id_bytes()).ok()? {
+                    return Some((id, IsarDeserializer::from_bytes(object)));
+                }
             }
+            self.ids = None;
         } else {
-            let primary_cursor = if let Some(primary_cursor) = self.primary_cursor.take() {
-                Some(primary_cursor)
-            } else {
-                self.iterator.take().map(|i| i.close())
-            };
-            let (iterator, primary_cursor) =
-                Self::next_iterator(self.txn, self.collection, primary_cursor, &mut self.indexes)?;
-            self.iterator = Some(iterator);
-            self.primary_cursor = primary_cursor;
-            self.next()
+            let next = self.iterator.as_mut()?.next();
+            if let Some((key, value)) = next {
+                if let Some(primary_cursor) = &mut self.primary_cursor {
+                    let (id, object) = primary_cursor.move_to(value).ok()??;
+                    return Some((id.to_id(), IsarDeserializer::from_bytes(object)));
+                } else {
+                    return Some((key.to_id(), IsarDeserializer::from_bytes(value)));
+                }
+            }
         }
+
+        let primary_cursor = if let Some(primary_cursor) = self.primary_cursor.take() {
+            Some(primary_cursor)
+        } else {
+            self.iterator.take().map(|i| i.close())
+        };
+        let source =
+            Self::next_source(self.txn, self.collection, primary_cursor, &mut self.indexes)?;
+        self.set_source(source);
+        self.next()
     }
 }
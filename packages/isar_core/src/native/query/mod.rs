@@ -7,9 +7,13 @@ use super::native_collection::{NativeCollection, NativeProperty};
 use super::native_reader::NativeReader;
 use super::native_txn::NativeTxn;
 use crate::core::cursor::IsarQueryCursor;
+use crate::core::data_type::DataType;
+use crate::core::error::Result;
 use crate::core::instance::Aggregation;
 use crate::core::query_builder::Sort;
+use crate::core::schema::VectorMetric;
 use crate::core::value::IsarValue;
+use crate::core::vector;
 use crate::core::watcher::QueryMatches;
 
 mod aggregate;
@@ -20,19 +24,46 @@ mod sorted_query_iterator;
 mod unsorted_distinct_query_iterator;
 mod unsorted_query_iterator;
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub(crate) enum QueryIndex {
     Primary(i64, i64),
     Secondary(IndexKey, IndexKey),
+    // position of a geo index and the bounding box (min_lat, min_lon, max_lat, max_lon)
+    Geo(usize, (f64, f64, f64, f64)),
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
+pub(crate) enum SortProperty {
+    Id,
+    Property(NativeProperty),
+    // latitude and longitude property of a geo index and the reference coordinate
+    Distance(NativeProperty, NativeProperty, f64, f64),
+    Similarity(Similarity),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Similarity {
+    pub property: NativeProperty,
+    pub metric: VectorMetric,
+    pub vector: Vec<f32>,
+}
+
+impl Similarity {
+    pub fn score(&self, object: IsarDeserializer) -> Option<f64> {
+        let (list, length) = object.read_list(self.property.offset, DataType::Float)?;
+        let elements =
+            (0..length).map(|i| list.read_float(i * DataType::Float.static_size() as u32));
+        vector::score(self.metric, &self.vector, elements)
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct NativeQuery {
     pub(crate) instance_id: u32,
     pub(crate) collection_index: u16,
     pub(self) indexes: Vec<QueryIndex>,
     pub(self) filter: NativeFilter,
-    pub(self) sort: Vec<(Option<NativeProperty>, Sort, bool)>,
+    pub(self) sort: Vec<(SortProperty, Sort, bool)>,
     pub(self) distinct: Vec<(NativeProperty, bool)>,
 }
 
@@ -42,7 +73,7 @@ impl NativeQuery {
         collection_index: u16,
         indexes: Vec<QueryIndex>,
         filter: NativeFilter,
-        sort: Vec<(Option<NativeProperty>, Sort, bool)>,
+        sort: Vec<(SortProperty, Sort, bool)>,
         distinct: Vec<(NativeProperty, bool)>,
     ) -> Self {
         Self {
@@ -61,17 +92,37 @@ impl NativeQuery {
         all_collections: &'a [NativeCollection],
         offset: Option<u32>,
         limit: Option<u32>,
-    ) -> NativeQueryCursor<'_> {
+    ) -> Result<NativeQueryCursor<'_>> {
         let collection = &all_collections[self.collection_index as usize];
         let iterator = QueryIterator::new(
             txn,
             collection,
+            all_collections,
             self,
             false,
             offset.unwrap_or(0),
             limit.unwrap_or(u32::MAX),
-        );
-        NativeQueryCursor::new(iterator, collection, all_collections)
+        )?;
+        Ok(NativeQueryCursor::new(
+            txn,
+            iterator,
+            collection,
+            all_collections,
+            self.similarity(),
+        ))
+    }
+
+    pub(crate) fn has_links(&self) -> bool {
+        self.filter.has_links()
+    }
+
+    fn similarity(&self) -> Option<&Similarity> {
+        self.sort
+            .iter()
+            .find_map(|(property, _, _)| match property {
+                SortProperty::Similarity(similarity) => Some(similarity),
+                _ => None,
+            })
     }
 
     pub(crate) fn aggregate(
@@ -80,7 +131,7 @@ impl NativeQuery {
         all_collections: &[NativeCollection],
         aggregation: Aggregation,
         property_index: Option<u16>,
-    ) -> Option<IsarValue> {
+    ) -> Result<Option<IsarValue>> {
         let collection = &all_collections[self.collection_index as usize];
         let property = if let Some(property_index) = property_index {
             collection.get_property(property_index)
@@ -88,8 +139,9 @@ impl NativeQuery {
             None
         };
 
-        let mut iterator = QueryIterator::new(txn, collection, self, true, 0, u32::MAX);
-        match aggregation {
+        let mut iterator =
+            QueryIterator::new(txn, collection, all_collections, self, true, 0, u32::MAX)?;
+        let result = match aggregation {
             Aggregation::Min | Aggregation::Max => {
                 aggregate_min_max(iterator, property, aggregation == Aggregation::Min)
             }
@@ -98,25 +150,28 @@ impl NativeQuery {
             }
             Aggregation::Count => Some(IsarValue::Integer(iterator.count() as i64)),
             Aggregation::IsEmpty => Some(IsarValue::Bool(iterator.next().is_none())),
-        }
+        };
+        Ok(result)
     }
 
     pub(crate) fn get_matching_ids(
         &self,
         txn: &NativeTxn,
-        collection: &NativeCollection,
+        all_collections: &[NativeCollection],
         offset: Option<u32>,
         limit: Option<u32>,
-    ) -> Vec<i64> {
+    ) -> Result<Vec<i64>> {
+        let collection = &all_collections[self.collection_index as usize];
         let iterator = QueryIterator::new(
             txn,
             collection,
+            all_collections,
             self,
             false,
             offset.unwrap_or(0),
             limit.unwrap_or(u32::MAX),
-        );
-        iterator.map(|(id, _)| id).collect()
+        )?;
+        Ok(iterator.map(|(id, _)| id).collect())
     }
 }
 
@@ -129,21 +184,27 @@ impl QueryMatches for NativeQuery {
 }
 
 pub struct NativeQueryCursor<'a> {
+    txn: &'a NativeTxn,
     iterator: QueryIterator<'a>,
     collection: &'a NativeCollection,
     all_collections: &'a [NativeCollection],
+    similarity: Option<&'a Similarity>,
 }
 
 impl<'a> NativeQueryCursor<'a> {
     pub(crate) fn new(
+        txn: &'a NativeTxn,
         iterator: QueryIterator<'a>,
         collection: &'a NativeCollection,
         all_collections: &'a [NativeCollection],
+        similarity: Option<&'a Similarity>,
     ) -> Self {
         Self {
+            txn,
             iterator,
             collection,
             all_collections,
+            similarity,
         }
     }
 }
@@ -154,11 +215,12 @@ impl<'a> IsarQueryCursor for NativeQueryCursor<'a> {
     #[inline]
     fn next(&mut self) -> Option<Self::Reader<'_>> {
         let (id, object) = self.iterator.next()?;
-        Some(NativeReader::new(
-            id,
-            object,
-            self.collection,
-            self.all_collections,
-        ))
+        let reader =
+            NativeReader::new(id, object, self.collection, self.all_collections).with_txn(self.txn);
+        if let Some(similarity) = self.similarity {
+            Some(reader.with_score(similarity.score(object)))
+        } else {
+            Some(reader)
+        }
     }
 }
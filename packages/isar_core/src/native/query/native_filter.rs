@@ -1,10 +1,20 @@
 use crate::core::data_type::DataType;
+use crate::core::error::Result;
 use crate::core::fast_wild_match::fast_wild_match;
+use crate::core::filter::JsonCondition;
+use crate::core::geo;
+use crate::core::regex::Regex;
 use crate::native::isar_deserializer::IsarDeserializer;
-use crate::native::native_collection::NativeProperty;
+use crate::native::native_collection::{NativeCollection, NativeProperty};
+use crate::native::native_link::NativeLink;
+use crate::native::native_txn::NativeTxn;
+use crate::native::query::NativeQuery;
 use enum_dispatch::enum_dispatch;
 use itertools::Itertools;
 use paste::paste;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::collections::HashSet;
 
 #[macro_export]
 macro_rules! primitive_create {
@@ -144,6 +154,23 @@ impl NativeFilter {
         NativeFilter(filter)
     }
 
+    pub fn fixed_bytes(
+        property: &NativeProperty,
+        lower: Option<Vec<u8>>,
+        upper: Option<Vec<u8>>,
+    ) -> NativeFilter {
+        let filter = if property.data_type.is_fixed_bytes() {
+            Filter::FixedBytesBetween(FixedBytesBetweenCond {
+                offset: property.offset,
+                lower,
+                upper,
+            })
+        } else {
+            Filter::Static(StaticCond { value: false })
+        };
+        NativeFilter(filter)
+    }
+
     pub fn string_ends_with(
         property: &NativeProperty,
         value: &str,
@@ -168,6 +195,59 @@ impl NativeFilter {
         string_filter_create!(Matches, property, value, case_sensitive)
     }
 
+    pub fn string_regex(property: &NativeProperty, regex: Regex) -> NativeFilter {
+        let filter = if property.data_type == DataType::String {
+            Filter::StringRegex(StringRegexCond {
+                offset: property.offset,
+                regex,
+            })
+        } else if property.data_type == DataType::StringList {
+            Filter::AnyStringRegex(AnyStringRegexCond {
+                offset: property.offset,
+                regex,
+            })
+        } else {
+            Filter::Static(StaticCond { value: false })
+        };
+        NativeFilter(filter)
+    }
+
+    pub fn geo_box(
+        latitude: &NativeProperty,
+        longitude: &NativeProperty,
+        min_lat: f64,
+        min_lon: f64,
+        max_lat: f64,
+        max_lon: f64,
+    ) -> NativeFilter {
+        let filter = Filter::GeoBox(GeoBoxCond {
+            latitude: *latitude,
+            longitude: *longitude,
+            min_lat,
+            min_lon,
+            max_lat,
+            max_lon,
+        });
+        NativeFilter(filter)
+    }
+
+    pub fn geo_radius(
+        latitude: &NativeProperty,
+        longitude: &NativeProperty,
+        lat: f64,
+        lon: f64,
+        radius: f64,
+    ) -> NativeFilter {
+        let filter = Filter::GeoRadius(GeoRadiusCond {
+            latitude: *latitude,
+            longitude: *longitude,
+            lat,
+            lon,
+            radius,
+        });
+        NativeFilter(filter)
+    }
+
     pub fn nested(property: &NativeProperty, filter: NativeFilter) -> NativeFilter {
         let filter = if property.data_type == DataType::Object {
             Filter::Nested(NestedCond {
@@ -180,6 +260,32 @@ impl NativeFilter {
         NativeFilter(filter)
     }
 
+    pub fn map_entry(
+        property: &NativeProperty,
+        key: &str,
+        condition: JsonCondition,
+    ) -> NativeFilter {
+        let filter = if let Some(value_type) = property.data_type.value_type() {
+            Filter::MapEntry(MapEntryCond {
+                offset: property.offset,
+                value_type,
+                key: key.to_string(),
+                condition,
+            })
+        } else {
+            Filter::Static(StaticCond { value: false })
+        };
+        NativeFilter(filter)
+    }
+
+    pub fn link(link: &NativeLink, target: Option<NativeQuery>) -> NativeFilter {
+        let filter_cond = Filter::Link(LinkCond {
+            link: link.clone(),
+            target: target.map(Box::new),
+        });
+        NativeFilter(filter_cond)
+    }
+
     pub fn and(filters: Vec<NativeFilter>) -> NativeFilter {
         let filters = filters.into_iter().map(|f| f.0).collect_vec();
         let filter_cond = Filter::And(AndCond { filters });
@@ -207,6 +313,26 @@ impl NativeFilter {
     pub(crate) fn evaluate(&self, id: i64, object: IsarDeserializer) -> bool {
         self.0.evaluate(id, object)
     }
+
+    pub(crate) fn has_links(&self) -> bool {
+        self.0.has_links()
+    }
+
+    /// Link conditions read other collections so they are replaced with the ids of the matching
+    /// objects before a query is executed.
+    pub(crate) fn resolve_links(
+        &self,
+        txn: &NativeTxn,
+        all_collections: &[NativeCollection],
+    ) -> Result<Cow<'_, NativeFilter>> {
+        if self.0.has_links() {
+            Ok(Cow::Owned(NativeFilter(
+                self.0.resolve_links(txn, all_collections)?,
+            )))
+        } else {
+            Ok(Cow::Borrowed(self))
+        }
+    }
 }
 
 #[enum_dispatch]
@@ -226,6 +352,9 @@ enum Filter {
     StringEndsWith(StringEndsWithCond),
     StringContains(StringContainsCond),
     StringMatches(StringMatchesCond),
+    StringRegex(StringRegexCond),
+
+    FixedBytesBetween(FixedBytesBetweenCond),
 
     AnyByteBetween(AnyByteBetweenCond),
     AnyBoolBetween(AnyBoolBetweenCond),
@@ -238,14 +367,64 @@ enum Filter {
     AnyStringEndsWith(AnyStringEndsWithCond),
     AnyStringContains(AnyStringContainsCond),
     AnyStringMatches(AnyStringMatchesCond),
+    AnyStringRegex(AnyStringRegexCond),
+
+    GeoBox(GeoBoxCond),
+    GeoRadius(GeoRadiusCond),
 
     Nested(NestedCond),
+    MapEntry(MapEntryCond),
+    Link(LinkCond),
+    IdIn(IdInCond),
     And(AndCond),
     Or(OrCond),
     Not(NotCond),
     Static(StaticCond),
 }
 
+impl Filter {
+    fn has_links(&self) -> bool {
+        match self {
+            Filter::Link(_) => true,
+            Filter::And(cond) => cond.filters.iter().any(|f| f.has_links()),
+            Filter::Or(cond) => cond.filters.iter().any(|f| f.has_links()),
+            Filter::Not(cond) => cond.filter.has_links(),
+            _ => false,
+        }
+    }
+
+    fn resolve_links(
+        &self,
+        txn: &NativeTxn,
+        all_collections: &[NativeCollection],
+    ) -> Result<Filter> {
+        let filter = match self {
+            Filter::Link(cond) => Filter::IdIn(IdInCond {
+                ids: cond.resolve(txn, all_collections)?,
+            }),
+            Filter::And(cond) => Filter::And(AndCond {
+                filters: cond
+                    .filters
+                    .iter()
+                    .map(|f| f.resolve_links(txn, all_collections))
+                    .try_collect()?,
+            }),
+            Filter::Or(cond) => Filter::Or(OrCond {
+                filters: cond
+                    .filters
+                    .iter()
+                    .map(|f| f.resolve_links(txn, all_collections))
+                    .try_collect()?,
+            }),
+            Filter::Not(cond) => Filter::Not(NotCond {
+                filter: Box::new(cond.filter.resolve_links(txn, all_collections)?),
+            }),
+            _ => self.clone(),
+        };
+        Ok(filter)
+    }
+}
+
 #[enum_dispatch(Filter)]
 trait Condition {
     fn evaluate(&self, id: i64, object: IsarDeserializer) -> bool;
@@ -396,6 +575,26 @@ impl Condition for StringBetweenCond {
     }
 }
 
+#[derive(Clone, Debug)]
+struct FixedBytesBetweenCond {
+    upper: Option<Vec<u8>>,
+    lower: Option<Vec<u8>>,
+    offset: u32,
+}
+
+impl Condition for FixedBytesBetweenCond {
+    #[inline]
+    fn evaluate(&self, _id: i64, object: IsarDeserializer) -> bool {
+        if let Some(value) = object.read_dynamic(self.offset) {
+            let lower = self.lower.as_deref().is_none_or(|lower| lower <= value);
+            let upper = self.upper.as_deref().is_some_and(|upper| upper >= value);
+            lower && upper
+        } else {
+            self.lower.is_none()
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 struct AnyStringBetweenCond {
     upper: Option<Vec<u8>>,
@@ -498,6 +697,84 @@ string_filter!(StringEndsWith);
 string_filter!(StringContains);
 string_filter!(StringMatches);
 
+#[derive(Clone, Debug)]
+struct StringRegexCond {
+    offset: u32,
+    regex: Regex,
+}
+
+impl Condition for StringRegexCond {
+    fn evaluate(&self, _id: i64, object: IsarDeserializer) -> bool {
+        if let Some(value) = object.read_string(self.offset) {
+            self.regex.is_match(value)
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct AnyStringRegexCond {
+    offset: u32,
+    regex: Regex,
+}
+
+impl Condition for AnyStringRegexCond {
+    fn evaluate(&self, _id: i64, object: IsarDeserializer) -> bool {
+        if let Some((list, length)) = object.read_list(self.offset, DataType::String) {
+            for i in 0..length {
+                let value = list.read_string(i * DataType::String.static_size() as u32);
+                if value.is_some_and(|value| self.regex.is_match(value)) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+#[derive(Clone, Debug)]
+struct GeoBoxCond {
+    latitude: NativeProperty,
+    longitude: NativeProperty,
+    min_lat: f64,
+    min_lon: f64,
+    max_lat: f64,
+    max_lon: f64,
+}
+
+impl Condition for GeoBoxCond {
+    fn evaluate(&self, _id: i64, object: IsarDeserializer) -> bool {
+        let lat = object.read_coordinate(self.latitude.offset, self.latitude.data_type);
+        let lon = object.read_coordinate(self.longitude.offset, self.longitude.data_type);
+        geo::within_box(
+            lat,
+            lon,
+            self.min_lat,
+            self.min_lon,
+            self.max_lat,
+            self.max_lon,
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+struct GeoRadiusCond {
+    latitude: NativeProperty,
+    longitude: NativeProperty,
+    lat: f64,
+    lon: f64,
+    radius: f64,
+}
+
+impl Condition for GeoRadiusCond {
+    fn evaluate(&self, _id: i64, object: IsarDeserializer) -> bool {
+        let lat = object.read_coordinate(self.latitude.offset, self.latitude.data_type);
+        let lon = object.read_coordinate(self.longitude.offset, self.longitude.data_type);
+        geo::distance(lat, lon, self.lat, self.lon) <= self.radius
+    }
+}
+
 #[derive(Clone, Debug)]
 struct NestedCond {
     offset: u32,
@@ -514,6 +791,88 @@ impl Condition for NestedCond {
     }
 }
 
+#[derive(Clone, Debug)]
+struct MapEntryCond {
+    offset: u32,
+    value_type: DataType,
+    key: String,
+    condition: JsonCondition,
+}
+
+impl MapEntryCond {
+    fn read_value(&self, values: &IsarDeserializer, index: u32) -> Value {
+        let offset = index * self.value_type.static_size() as u32;
+        if values.is_null(offset, self.value_type) {
+            return Value::Null;
+        }
+        match self.value_type {
+            DataType::Bool => Value::Bool(values.read_bool(offset).unwrap_or_default()),
+            DataType::Byte => Value::from(values.read_byte(offset)),
+            DataType::Int => Value::from(values.read_int(offset)),
+            DataType::Float => Value::from(values.read_float(offset)),
+            DataType::Long => Value::from(values.read_long(offset)),
+            DataType::Double => Value::from(values.read_double(offset)),
+            DataType::String => Value::from(values.read_string(offset).unwrap_or_default()),
+            // only the presence of embedded objects can be filtered
+            _ => Value::Object(Map::new()),
+        }
+    }
+}
+
+impl Condition for MapEntryCond {
+    fn evaluate(&self, _id: i64, object: IsarDeserializer) -> bool {
+        let mut value = Value::Null;
+        if let Some((keys, values, length)) = object.read_map(self.offset, self.value_type) {
+            if let Some(index) = (0..length).find(|i| keys.read_string(i * 3) == Some(&self.key)) {
+                value = self.read_value(&values, index);
+            }
+        }
+        self.condition.matches(value)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct LinkCond {
+    link: NativeLink,
+    // query of the target collection or `None` to match any linked object
+    target: Option<Box<NativeQuery>>,
+}
+
+impl LinkCond {
+    fn resolve(
+        &self,
+        txn: &NativeTxn,
+        all_collections: &[NativeCollection],
+    ) -> Result<HashSet<i64>> {
+        let Some(target) = &self.target else {
+            return self.link.get_linking_ids(txn);
+        };
+        let mut ids = HashSet::new();
+        for target_id in target.get_matching_ids(txn, all_collections, None, None)? {
+            ids.extend(self.link.get_reverse_ids(txn, target_id)?);
+        }
+        Ok(ids)
+    }
+}
+
+impl Condition for LinkCond {
+    fn evaluate(&self, _id: i64, _object: IsarDeserializer) -> bool {
+        unreachable!("link conditions are resolved before a query is executed")
+    }
+}
+
+#[derive(Clone, Debug)]
+struct IdInCond {
+    ids: HashSet<i64>,
+}
+
+impl Condition for IdInCond {
+    #[inline]
+    fn evaluate(&self, id: i64, _object: IsarDeserializer) -> bool {
+        self.ids.contains(&id)
+    }
+}
+
 #[derive(Clone, Debug)]
 struct AndCond {
     filters: Vec<Filter>,
@@ -3,6 +3,7 @@ use super::sorted_query_iterator::SortedQueryIterator;
 use super::unsorted_distinct_query_iterator::UnsortedDistinctQueryIterator;
 use super::unsorted_query_iterator::UnsortedQueryIterator;
 use super::NativeQuery;
+use crate::core::error::Result;
 use crate::native::isar_deserializer::IsarDeserializer;
 use crate::native::native_collection::NativeCollection;
 use crate::native::native_txn::NativeTxn;
@@ -17,17 +18,19 @@ impl<'a> QueryIterator<'a> {
     pub fn new(
         txn: &'a NativeTxn,
         collection: &'a NativeCollection,
+        all_collections: &[NativeCollection],
         query: &'a NativeQuery,
         ignore_sort: bool,
         offset: u32,
         limit: u32,
-    ) -> Self {
+    ) -> Result<Self> {
         let index_iterator = IndexIterator::new(txn, collection, &query.indexes);
-        if !query.sort.is_empty() && !ignore_sort {
+        let filter = query.filter.resolve_links(txn, all_collections)?;
+        let iterator = if !query.sort.is_empty() && !ignore_sort {
             QueryIterator::Sorted(SortedQueryIterator::new(
                 index_iterator,
                 false,
-                &query.filter,
+                &filter,
                 &query.sort,
                 &query.distinct,
                 offset,
@@ -36,7 +39,7 @@ impl<'a> QueryIterator<'a> {
         } else if !query.distinct.is_empty() {
             QueryIterator::UnsortedDistinct(UnsortedDistinctQueryIterator::new(
                 index_iterator,
-                &query.filter,
+                filter,
                 &query.distinct,
                 offset,
                 limit,
@@ -45,11 +48,12 @@ impl<'a> QueryIterator<'a> {
             QueryIterator::Unsorted(UnsortedQueryIterator::new(
                 index_iterator,
                 false,
-                &query.filter,
+                filter,
                 offset,
                 limit,
             ))
-        }
+        };
+        Ok(iterator)
     }
 }
 
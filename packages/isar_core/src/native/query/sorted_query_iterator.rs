@@ -1,7 +1,10 @@
 use super::index_iterator::IndexIterator;
 use super::native_filter::NativeFilter;
+use super::SortProperty;
 use crate::core::data_type::DataType;
+use crate::core::geo;
 use crate::core::query_builder::Sort;
+use crate::core::vector;
 use crate::native::isar_deserializer::IsarDeserializer;
 use crate::native::native_collection::NativeProperty;
 use intmap::IntMap;
@@ -19,7 +22,7 @@ impl<'a> SortedQueryIterator<'a> {
         mut iterator: IndexIterator<'a>,
         has_duplicates: bool,
         filter: &NativeFilter,
-        sort: &[(Option<NativeProperty>, Sort, bool)],
+        sort: &[(SortProperty, Sort, bool)],
         distinct: &[(NativeProperty, bool)],
         offset: u32,
         limit: u32,
@@ -30,6 +33,11 @@ impl<'a> SortedQueryIterator<'a> {
             None
         };
 
+        let similarity = sort.iter().find_map(|(property, _, _)| match property {
+            SortProperty::Similarity(similarity) => Some(similarity),
+            _ => None,
+        });
+
         let mut results = vec![];
         while let Some((id, object)) = iterator.next() {
             if let Some(returned_ids) = &mut returned_ids {
@@ -38,16 +46,26 @@ impl<'a> SortedQueryIterator<'a> {
                 }
             }
             if filter.evaluate(id, object) {
-                results.push((id, object));
+                // the similarity is computed once per object instead of once per comparison
+                let similarity_key =
+                    similarity.map_or(0.0, |s| vector::sort_key(s.metric, s.score(object)));
+                results.push((id, object, similarity_key));
             }
         }
 
-        results.sort_unstable_by(|(id1, o1), (id2, o2)| {
+        results.sort_unstable_by(|(id1, o1, s1), (id2, o2, s2)| {
             for (p, sort, case_sensitive) in sort {
-                let ord = if let Some(p) = p {
-                    Self::compare_property(o1, o2, p.offset, p.data_type, *case_sensitive)
-                } else {
-                    id1.cmp(id2)
+                let ord = match p {
+                    SortProperty::Id => id1.cmp(id2),
+                    SortProperty::Property(p) => {
+                        Self::compare_property(o1, o2, p.offset, p.data_type, *case_sensitive)
+                    }
+                    SortProperty::Distance(lat_p, lon_p, lat, lon) => {
+                        let d1 = Self::distance(o1, lat_p, lon_p, *lat, *lon);
+                        let d2 = Self::distance(o2, lat_p, lon_p, *lat, *lon);
+                        d1.total_cmp(&d2)
+                    }
+                    SortProperty::Similarity(_) => s1.total_cmp(s2),
                 };
                 if ord != Ordering::Equal {
                     return if *sort == Sort::Asc {
@@ -59,6 +77,10 @@ impl<'a> SortedQueryIterator<'a> {
             }
             Ordering::Equal
         });
+        let results = results
+            .into_iter()
+            .map(|(id, object, _)| (id, object))
+            .collect_vec();
 
         if !distinct.is_empty() {
             let mut hashes = IntMap::new();
@@ -91,6 +113,24 @@ impl<'a> SortedQueryIterator<'a> {
         }
     }
 
+    fn distance(
+        object: &IsarDeserializer,
+        lat_property: &NativeProperty,
+        lon_property: &NativeProperty,
+        lat: f64,
+        lon: f64,
+    ) -> f64 {
+        let object_lat = object.read_coordinate(lat_property.offset, lat_property.data_type);
+        let object_lon = object.read_coordinate(lon_property.offset, lon_property.data_type);
+        let distance = geo::distance(object_lat, object_lon, lat, lon);
+        // objects without coordinates are sorted last
+        if distance.is_nan() {
+            f64::INFINITY
+        } else {
+            distance
+        }
+    }
+
     fn compare_property(
         o1: &IsarDeserializer,
         o2: &IsarDeserializer,
@@ -115,6 +155,9 @@ impl<'a> SortedQueryIterator<'a> {
                         .cmp(&s2.map(|s| s.to_lowercase()))
                 }
             }
+            DataType::Decimal | DataType::Uuid | DataType::DateTimeOffset => {
+                o1.read_dynamic(offset).cmp(&o2.read_dynamic(offset))
+            }
             _ => Ordering::Equal,
         }
     }
@@ -3,10 +3,11 @@ use super::native_filter::NativeFilter;
 use crate::native::isar_deserializer::IsarDeserializer;
 use crate::native::native_collection::NativeProperty;
 use intmap::IntMap;
+use std::borrow::Cow;
 
 pub(crate) struct UnsortedDistinctQueryIterator<'a> {
     iterator: IndexIterator<'a>,
-    filter: &'a NativeFilter,
+    filter: Cow<'a, NativeFilter>,
     properties: &'a [(NativeProperty, bool)],
     hashes: IntMap<()>,
     skip: u32,
@@ -16,7 +17,7 @@ pub(crate) struct UnsortedDistinctQueryIterator<'a> {
 impl<'a> UnsortedDistinctQueryIterator<'a> {
     pub fn new(
         iterator: IndexIterator<'a>,
-        filter: &'a NativeFilter,
+        filter: Cow<'a, NativeFilter>,
         properties: &'a [(NativeProperty, bool)],
         offset: u32,
         limit: u32,
@@ -1,11 +1,12 @@
 use super::{index_iterator::IndexIterator, native_filter::NativeFilter};
 use crate::native::isar_deserializer::IsarDeserializer;
 use intmap::IntMap;
+use std::borrow::Cow;
 
 pub(crate) struct UnsortedQueryIterator<'a> {
     iterator: IndexIterator<'a>,
     returned_ids: Option<IntMap<()>>,
-    filter: &'a NativeFilter,
+    filter: Cow<'a, NativeFilter>,
     skip: u32,
     take: u32,
 }
@@ -14,7 +15,7 @@ impl<'a> UnsortedQueryIterator<'a> {
     pub fn new(
         iterator: IndexIterator<'a>,
         has_duplicates: bool,
-        filter: &'a NativeFilter,
+        filter: Cow<'a, NativeFilter>,
         offset: u32,
         limit: u32,
     ) -> UnsortedQueryIterator<'a> {
@@ -1,10 +1,15 @@
 use super::mdbx::db::Db;
 use super::mdbx::env::Env;
-use super::native_collection::{NativeCollection, NativeProperty};
+use super::native_collection::{NativeCollection, NativeProperty, NativeTtl};
 use super::native_index::NativeIndex;
+use super::native_link::NativeLink;
 use super::native_txn::NativeTxn;
+use super::query::native_filter::NativeFilter;
+use crate::core::constraint::PropertyConstraint;
+use crate::core::data_type::DataType;
 use crate::core::error::{IsarError, Result};
-use crate::core::schema::{IsarSchema, PropertySchema};
+use crate::core::schema::{IndexFilter, IndexFilterValue, IndexType, IsarSchema, PropertySchema};
+use byteorder::{BigEndian, ByteOrder};
 use itertools::Itertools;
 use std::borrow::Cow;
 use std::sync::Arc;
@@ -19,19 +24,22 @@ pub(crate) fn perform_migration(
     let txn = NativeTxn::new(instance_id, env, true)?;
     let info_db = open_info_db(&txn)?;
     let existing_schemas = get_schemas(&txn, info_db)?;
-    txn.commit()?;
 
     let schema_names = schemas.iter().map(|c| c.name.to_string()).collect_vec();
 
-    let mut collections = vec![];
+    let mut collection_properties = vec![];
+    let mut collection_dbs = vec![];
+    let mut collection_defaults = vec![];
     for schema in schemas.iter_mut() {
         let existing_schema_index = existing_schemas.iter().position(|c| c.name == schema.name);
 
-        let txn = NativeTxn::new(instance_id, env, true)?;
+        let mut fill_defaults = vec![];
         let merged_properties = if let Some(existing_schema_index) = existing_schema_index {
             let existing_schema = &existing_schemas[existing_schema_index];
 
-            let merged_properties = migrate_collection(&txn, &schema, existing_schema)?;
+            let (merged_properties, added_defaults) =
+                migrate_collection(&txn, &schema, existing_schema)?;
+            fill_defaults = added_defaults;
             Cow::Owned(merged_properties)
         } else {
             Cow::Borrowed(&schema.properties)
@@ -59,23 +67,41 @@ pub(crate) fn perform_migration(
             None
         };
 
+        collection_properties.push(properties);
+        collection_dbs.push(db);
+        collection_defaults.push(fill_defaults);
+    }
+
+    let mut collection_links = get_links(&txn, &schemas)?;
+
+    // indexes may reference properties of embedded collections so they are created once the
+    // properties of all collections are known
+    let mut collections = vec![];
+    for (schema, db) in schemas.iter().zip(collection_dbs) {
+        let properties = &collection_properties[collections.len()];
+
         let mut indexes = vec![];
         for index in &schema.indexes {
             let index_db = open_index_db(&txn, &schema.name, &index.name)?;
             let properties = index
                 .properties
                 .iter()
-                .map(|p| {
-                    properties
-                        .iter()
-                        .find(|(name, _)| name == p)
-                        .unwrap()
-                        .1
-                        .clone()
-                })
-                .collect_vec();
-            let index =
-                NativeIndex::new(&index.name, index_db, properties, index.unique, index.hash);
+                .map(|p| get_property_path(p, properties, &collection_properties))
+                .try_collect()?;
+            let filter = index
+                .filter
+                .as_ref()
+                .map(|f| get_index_filter(f, &collection_properties[collections.len()]));
+            let index = NativeIndex::new(
+                &index.name,
+                index_db,
+                properties,
+                index.unique,
+                index.hash,
+                index.index_type,
+                filter,
+            )
+            .with_vector(index.vector);
             indexes.push(index);
         }
 
@@ -83,20 +109,31 @@ pub(crate) fn perform_migration(
             collections.len() as u16,
             &schema.name,
             schema.id_name.as_deref(),
-            properties,
+            properties.clone(),
             indexes,
             db,
         );
+        let blob_db = if has_blobs(&schema.properties) {
+            Some(open_blob_db(&txn, &schema.name)?)
+        } else {
+            None
+        };
+        let (links, reverse_links) = collection_links.remove(0);
+        let col = col
+            .with_links(links, reverse_links)
+            .with_blob_db(blob_db)
+            .with_ttl(get_ttl(schema, properties))
+            .with_version_property(get_version_property(schema, properties))
+            .with_constraints(get_constraints(schema, properties));
 
         if !col.is_embedded() {
             col.init_auto_increment(&txn)?;
+            col.fill_defaults(&txn, &collection_defaults[collections.len()])?;
         }
-        txn.commit()?;
 
         collections.push(col);
     }
 
-    let txn = NativeTxn::new(instance_id, env, true)?;
     for existing_schema in existing_schemas {
         if !schemas.iter().any(|c| c.name == existing_schema.name) {
             delete_collection(&txn, info_db, &existing_schema)?;
@@ -138,6 +175,142 @@ fn open_index_db(txn: &NativeTxn, col_name: &str, index_name: &str) -> Result<Db
     txn.open_db(&db_name, false, true)
 }
 
+fn open_link_db(txn: &NativeTxn, col_name: &str, link_name: &str) -> Result<Db> {
+    let db_name = format!("_{}_{}", col_name, link_name);
+    txn.open_db(&db_name, false, true)
+}
+
+// link names must not begin with an underscore so the name can't clash with a link db
+fn open_blob_db(txn: &NativeTxn, col_name: &str) -> Result<Db> {
+    let db_name = format!("_{}__blobs", col_name);
+    txn.open_db(&db_name, false, false)
+}
+
+fn has_blobs(properties: &[PropertySchema]) -> bool {
+    properties.iter().any(|p| p.data_type == DataType::Blob)
+}
+
+/// Deletes the payloads of the blob properties at the given offsets.
+fn delete_blobs(txn: &NativeTxn, col_name: &str, offsets: &[u32]) -> Result<()> {
+    let blob_db = open_blob_db(txn, col_name)?;
+    let keys = txn
+        .get_cursor(blob_db)?
+        .iter()?
+        .filter(|(key, _)| offsets.contains(&BigEndian::read_u32(&key[8..])))
+        .map(|(key, _)| key.to_vec())
+        .collect_vec();
+    let mut cursor = txn.get_cursor(blob_db)?;
+    for key in keys {
+        if cursor.move_to(&key)?.is_some() {
+            cursor.delete_current()?;
+        }
+    }
+    Ok(())
+}
+
+fn get_constraints(
+    schema: &IsarSchema,
+    properties: &[(String, NativeProperty)],
+) -> Vec<PropertyConstraint> {
+    schema
+        .properties
+        .iter()
+        .filter_map(|p| {
+            let name = p.name.as_ref()?;
+            let index = properties.iter().position(|(n, _)| n == name)?;
+            PropertyConstraint::new(index as u16 + 1, p, schema.vector_dimensions(name))
+        })
+        .collect()
+}
+
+fn get_ttl(schema: &IsarSchema, properties: &[(String, NativeProperty)]) -> Option<NativeTtl> {
+    let ttl = schema.ttl.as_ref()?;
+    let (_, property) = properties.iter().find(|(name, _)| name == &ttl.property)?;
+    let index = schema.indexes.iter().position(|i| {
+        i.index_type == IndexType::Value
+            && !i.hash
+            && i.filter.is_none()
+            && i.properties == [ttl.property.as_str()]
+    });
+    Some(NativeTtl {
+        schema: ttl.clone(),
+        property: *property,
+        index,
+    })
+}
+
+fn get_version_property(
+    schema: &IsarSchema,
+    properties: &[(String, NativeProperty)],
+) -> Option<u16> {
+    let name = schema.version_property.as_ref()?;
+    let index = properties.iter().position(|(n, _)| n == name)?;
+    Some(index as u16 + 1)
+}
+
+/// Returns the links and the reverse links of undeclared backlinks for each collection.
+fn get_links(
+    txn: &NativeTxn,
+    schemas: &[IsarSchema],
+) -> Result<Vec<(Vec<NativeLink>, Vec<NativeLink>)>> {
+    let collection_index = |name: &str| schemas.iter().position(|c| c.name == name).unwrap() as u16;
+
+    let mut forward_links = vec![];
+    for (index, schema) in schemas.iter().enumerate() {
+        for link in schema.links.iter().filter(|l| l.backlink.is_none()) {
+            let target_index = collection_index(&link.target);
+            let backlink = schemas[target_index as usize]
+                .links
+                .iter()
+                .find(|l| l.backlink.as_ref() == Some(&link.name) && l.target == schema.name);
+            let db = open_link_db(txn, &schema.name, &link.name)?;
+            let native_link = NativeLink::new(
+                &link.name,
+                target_index,
+                link.single,
+                backlink.is_some_and(|b| b.single),
+                link.cascade,
+                db,
+            );
+            forward_links.push((index as u16, native_link, backlink.is_some()));
+        }
+    }
+
+    let mut collection_links = vec![];
+    for (index, schema) in schemas.iter().enumerate() {
+        let index = index as u16;
+        let mut links = vec![];
+        for link in &schema.links {
+            let native_link = if let Some(backlink) = &link.backlink {
+                let target_index = collection_index(&link.target);
+                let (_, forward_link, _) = forward_links
+                    .iter()
+                    .find(|(i, l, _)| *i == target_index && &l.name == backlink)
+                    .unwrap();
+                let mut native_link = forward_link.reverse(target_index);
+                native_link.name = link.name.clone();
+                native_link.cascade = link.cascade;
+                native_link
+            } else {
+                let (_, forward_link, _) = forward_links
+                    .iter()
+                    .find(|(i, l, _)| *i == index && l.name == link.name)
+                    .unwrap();
+                forward_link.clone()
+            };
+            links.push(native_link);
+        }
+
+        let reverse_links = forward_links
+            .iter()
+            .filter(|(_, l, has_backlink)| l.target_collection_index == index && !has_backlink)
+            .map(|(i, l, _)| l.reverse(*i))
+            .collect();
+        collection_links.push((links, reverse_links));
+    }
+    Ok(collection_links)
+}
+
 fn delete_collection(txn: &NativeTxn, info_db: Db, schema: &IsarSchema) -> Result<()> {
     let db = txn.open_db(&schema.name, true, false)?;
     txn.drop_db(db)?;
@@ -145,6 +318,14 @@ fn delete_collection(txn: &NativeTxn, info_db: Db, schema: &IsarSchema) -> Resul
         let index_db = open_index_db(txn, &schema.name, &index.name)?;
         txn.drop_db(index_db)?;
     }
+    for link in schema.links.iter().filter(|l| l.backlink.is_none()) {
+        let link_db = open_link_db(txn, &schema.name, &link.name)?;
+        txn.drop_db(link_db)?;
+    }
+    if has_blobs(&schema.properties) {
+        let blob_db = open_blob_db(txn, &schema.name)?;
+        txn.drop_db(blob_db)?;
+    }
 
     let mut info_cursor = txn.get_cursor(info_db)?;
     if info_cursor.move_to(&schema.name.as_bytes())?.is_some() {
@@ -157,7 +338,7 @@ fn migrate_collection(
     txn: &NativeTxn,
     schema: &IsarSchema,
     existing_schema: &IsarSchema,
-) -> Result<Vec<PropertySchema>> {
+) -> Result<(Vec<PropertySchema>, Vec<String>)> {
     if existing_schema.version != ISAR_FILE_VERSION {
         return Err(IsarError::VersionError {});
     }
@@ -170,6 +351,28 @@ fn migrate_collection(
         txn.drop_db(index_db)?;
     }
 
+    let (_, drop_links) = schema.find_link_changes(existing_schema);
+    for link in &drop_links {
+        let link_db = open_link_db(txn, &schema.name, link)?;
+        txn.drop_db(link_db)?;
+    }
+
+    let mut offset = 0;
+    let mut dropped_blob_offsets = vec![];
+    for property in &existing_schema.properties {
+        let dropped = property
+            .name
+            .as_ref()
+            .is_some_and(|name| drop_properties.contains(name));
+        if dropped && property.data_type == DataType::Blob {
+            dropped_blob_offsets.push(offset);
+        }
+        offset += property.data_type.static_size() as u32;
+    }
+    if !dropped_blob_offsets.is_empty() {
+        delete_blobs(txn, &schema.name, &dropped_blob_offsets)?;
+    }
+
     let mut merged_properties = existing_schema.properties.clone();
 
     for property in &drop_properties {
@@ -181,11 +384,23 @@ fn migrate_collection(
             .take();
     }
 
+    // the constraints of existing properties may have changed
+    for property in &mut merged_properties {
+        let schema_property = schema.properties.iter().find(|p| p.name == property.name);
+        if let (Some(_), Some(schema_property)) = (&property.name, schema_property) {
+            *property = schema_property.clone();
+        }
+    }
+
+    let mut added_defaults = vec![];
     for property in add_properties {
+        if property.default.is_some() {
+            added_defaults.push(property.name.clone().unwrap());
+        }
         merged_properties.push(property.clone());
     }
 
-    Ok(merged_properties)
+    Ok((merged_properties, added_defaults))
 }
 
 fn get_properties(
@@ -210,3 +425,76 @@ fn get_properties(
     }
     properties
 }
+
+fn get_property_path(
+    path: &str,
+    properties: &[(String, NativeProperty)],
+    collection_properties: &[Vec<(String, NativeProperty)>],
+) -> Result<Vec<NativeProperty>> {
+    let mut properties = properties;
+    let mut property_path = vec![];
+    for part in path.split('.') {
+        let (_, property) = properties
+            .iter()
+            .find(|(name, _)| name == part)
+            .ok_or_else(|| IsarError::SchemaError {
+                message: format!("Index property {} does not exist.", path),
+            })?;
+        if let Some(embedded_collection_index) = property.embedded_collection_index {
+            properties = &collection_properties[embedded_collection_index as usize];
+        }
+        property_path.push(*property);
+    }
+    Ok(property_path)
+}
+
+fn get_index_filter(filter: &IndexFilter, properties: &[(String, NativeProperty)]) -> NativeFilter {
+    let get_property = |name: &str| properties.iter().find(|(n, _)| n == name).map(|(_, p)| p);
+    match filter {
+        IndexFilter::IsNull { property } => {
+            get_property(property).map_or(NativeFilter::stat(false), NativeFilter::is_null)
+        }
+        IndexFilter::IsNotNull { property } => get_property(property)
+            .map_or(NativeFilter::stat(false), |p| {
+                NativeFilter::not(NativeFilter::is_null(p))
+            }),
+        IndexFilter::Equal { property, value } => {
+            if let Some(property) = get_property(property) {
+                match (property.data_type, value) {
+                    (DataType::Bool, IndexFilterValue::Bool(value)) => {
+                        NativeFilter::bool(property, Some(*value), Some(*value))
+                    }
+                    (DataType::Byte, IndexFilterValue::Integer(value)) => {
+                        if let Ok(value) = (*value).try_into() {
+                            NativeFilter::byte(property, value, value)
+                        } else {
+                            NativeFilter::stat(false)
+                        }
+                    }
+                    (DataType::Int, IndexFilterValue::Integer(value)) => {
+                        if let Ok(value) = (*value).try_into() {
+                            NativeFilter::int(property, value, value)
+                        } else {
+                            NativeFilter::stat(false)
+                        }
+                    }
+                    (DataType::Long, IndexFilterValue::Integer(value)) => {
+                        NativeFilter::long(property, *value, *value)
+                    }
+                    (DataType::String, IndexFilterValue::String(value)) => {
+                        NativeFilter::string(property, Some(value), Some(value), true)
+                    }
+                    _ => NativeFilter::stat(false),
+                }
+            } else {
+                NativeFilter::stat(false)
+            }
+        }
+        IndexFilter::And { filters } => NativeFilter::and(
+            filters
+                .iter()
+                .map(|f| get_index_filter(f, properties))
+                .collect_vec(),
+        ),
+    }
+}
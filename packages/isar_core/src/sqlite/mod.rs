@@ -5,6 +5,7 @@ mod sqlite_collection;
 mod sqlite_cursor;
 mod sqlite_insert;
 pub mod sqlite_instance;
+mod sqlite_link;
 mod sqlite_open;
 mod sqlite_query;
 mod sqlite_query_builder;
@@ -1,11 +1,16 @@
 use super::sql::{
-    add_column_sql, create_index_sql, create_table_sql, drop_column_sql, drop_index_sql,
-    sql_data_type,
+    add_column_sql, create_blob_table_sql, create_blob_triggers_sql, create_geo_index_sql,
+    create_index_sql, create_link_table_sql, create_link_triggers_sql, create_table_sql,
+    delete_blobs_sql, drop_blob_triggers_sql, drop_column_sql, drop_geo_index_sql, drop_index_sql,
+    drop_link_table_sql, fill_column_sql, sql_data_type, BLOB_TABLE,
 };
 use super::sqlite3::SQLite3;
+use super::sqlite_link::SQLiteLink;
 use super::sqlite_txn::SQLiteTxn;
+use crate::core::constraint::to_isar_value;
+use crate::core::data_type::DataType;
 use crate::core::error::{IsarError, Result};
-use crate::core::schema::{IndexSchema, IsarSchema, PropertySchema};
+use crate::core::schema::{IndexSchema, IndexType, IsarSchema, PropertySchema};
 use crate::sqlite::sqlite_collection::SQLiteProperty;
 use itertools::Itertools;
 
@@ -14,6 +19,13 @@ pub(crate) fn perform_migration(txn: &SQLiteTxn, schemas: &[IsarSchema]) -> Resu
         let sqlite = txn.get_sqlite(true)?;
         let table_names = sqlite.get_table_names()?;
 
+        let has_blobs = schemas
+            .iter()
+            .any(|c| c.properties.iter().any(|p| p.data_type == DataType::Blob));
+        if has_blobs {
+            sqlite.prepare(&create_blob_table_sql())?.step()?;
+        }
+
         for collection in schemas {
             if !collection.embedded {
                 if table_names.contains(&collection.name) {
@@ -22,27 +34,87 @@ pub(crate) fn perform_migration(txn: &SQLiteTxn, schemas: &[IsarSchema]) -> Resu
                     let sql = create_table_sql(collection);
                     sqlite.prepare(&sql)?.step()?;
                     for index in &collection.indexes {
-                        let sql = create_index_sql(&collection.name, index);
-                        sqlite.prepare(&sql)?.step()?;
+                        create_index(sqlite, &collection.name, index)?;
                     }
                 }
+                for sql in create_blob_triggers_sql(collection) {
+                    sqlite.prepare(&sql)?.step()?;
+                }
             }
         }
 
-        for table in table_names {
-            if !schemas.iter().any(|c| c.name == table && !c.embedded) {
+        for table in &table_names {
+            if table.starts_with('_') {
+                // link tables are migrated below
+                continue;
+            }
+            if !schemas.iter().any(|c| &c.name == table && !c.embedded) {
+                // the triggers are dropped with the table but the R*Tree tables are not
+                for (rtree, _) in sqlite.get_table_geo_indexes(table)? {
+                    sqlite.prepare(&format!("DROP TABLE {}", rtree))?.step()?;
+                }
+                for (column, sql_type) in sqlite.get_table_columns(table)? {
+                    if sql_data_type(&sql_type).0 == DataType::Blob {
+                        sqlite.prepare(&delete_blobs_sql(table, &column))?.step()?;
+                    }
+                }
                 let sql = format!("DROP TABLE {}", table);
                 sqlite.prepare(&sql)?.step()?;
             }
         }
 
-        Ok(())
+        migrate_links(sqlite, schemas, &table_names)
     })
 }
 
-fn read_col_schema(sqlite: &SQLite3, name: &str) -> Result<IsarSchema> {
+fn migrate_links(sqlite: &SQLite3, schemas: &[IsarSchema], table_names: &[String]) -> Result<()> {
+    let mut link_tables = vec![];
+    for collection in schemas {
+        for link in collection.links.iter().filter(|l| l.backlink.is_none()) {
+            let table = SQLiteLink::table_name(&collection.name, &link.name);
+            link_tables.push((table, &collection.name, &link.target));
+        }
+    }
+
+    for table in table_names
+        .iter()
+        .filter(|t| t.starts_with('_') && *t != BLOB_TABLE)
+    {
+        // the target trigger is missing if the target table was dropped
+        let trigger = format!("{}_delete_{}", table, SQLiteLink::TARGET);
+        let target = sqlite.get_trigger_table(&trigger)?;
+        let unchanged = link_tables
+            .iter()
+            .any(|(t, _, target_table)| t == table && target.as_ref() == Some(*target_table));
+        if !unchanged {
+            for sql in drop_link_table_sql(table) {
+                sqlite.prepare(&sql)?.step()?;
+            }
+        }
+    }
+
+    let table_names = sqlite.get_table_names()?;
+    for (table, source_table, target_table) in link_tables {
+        if !table_names.contains(&table) {
+            for sql in create_link_table_sql(&table) {
+                sqlite.prepare(&sql)?.step()?;
+            }
+        }
+        for sql in create_link_triggers_sql(&table, source_table, target_table) {
+            sqlite.prepare(&sql)?.step()?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads the existing schema of the table of `collection`. Expressions and filters of indexes are
+/// not read back so an index is returned as defined in `collection` if SQLite stored the same
+/// definition.
+fn read_col_schema(sqlite: &SQLite3, collection: &IsarSchema) -> Result<IsarSchema> {
+    let name = collection.name.as_str();
     let columns = sqlite.get_table_columns(name)?;
     let indexes = sqlite.get_table_indexes(name)?;
+    let geo_indexes = sqlite.get_table_geo_indexes(name)?;
 
     let mut properties = columns
         .iter()
@@ -63,29 +135,67 @@ fn read_col_schema(sqlite: &SQLite3, name: &str) -> Result<IsarSchema> {
         });
     }
 
-    let indexes = indexes
+    let mut indexes = indexes
         .iter()
-        .map(|(name, unique, cols)| {
-            let name = name.split('_').last().unwrap();
-            let cols = cols.iter().map(|c| c.as_str()).collect();
-            IndexSchema::new(name, cols, *unique, false)
+        .map(|(index_name, unique, cols, sql)| {
+            let index_name = index_name.split('_').last().unwrap();
+            let defined = collection
+                .indexes
+                .iter()
+                .find(|i| i.name == index_name && create_index_sql(name, i) == *sql);
+            if let Some(index) = defined {
+                index.clone()
+            } else {
+                let cols = cols.iter().map(|c| c.as_str()).collect();
+                IndexSchema::new(index_name, cols, *unique, false)
+            }
         })
-        .collect();
+        .collect_vec();
+
+    for (rtree, cols) in &geo_indexes {
+        // the columns are id, lat_min, lat_max, lon_min, lon_max
+        let name = rtree.rsplit('_').next().unwrap();
+        let lat = cols[1].strip_suffix("_min").unwrap();
+        let lon = cols[3].strip_suffix("_min").unwrap();
+        indexes.push(IndexSchema::geo(name, lat, lon));
+    }
 
     Ok(IsarSchema::new(name, None, properties, indexes, false))
 }
 
 fn update_table(sqlite: &SQLite3, collection: &IsarSchema) -> Result<()> {
-    let existing_schema = read_col_schema(sqlite, &collection.name)?;
+    let existing_schema = read_col_schema(sqlite, collection)?;
     let (add_properties, drop_properties, add_indexes, drop_indexes) =
         collection.find_changes(&existing_schema);
 
     for index in drop_indexes {
-        let sql = drop_index_sql(&collection.name, &index);
-        sqlite.prepare(&sql)?.step()?;
+        let is_geo = existing_schema
+            .indexes
+            .iter()
+            .any(|i| i.name == index && i.index_type == IndexType::Geo);
+        if is_geo {
+            for sql in drop_geo_index_sql(&collection.name, &index) {
+                sqlite.prepare(&sql)?.step()?;
+            }
+        } else {
+            let sql = drop_index_sql(&collection.name, &index);
+            sqlite.prepare(&sql)?.step()?;
+        }
     }
 
+    // columns that are referenced by triggers can't be dropped
+    for sql in drop_blob_triggers_sql(&collection.name) {
+        sqlite.prepare(&sql)?.step()?;
+    }
     for property in &drop_properties {
+        let is_blob = existing_schema
+            .properties
+            .iter()
+            .any(|p| p.name.as_ref() == Some(property) && p.data_type == DataType::Blob);
+        if is_blob {
+            let sql = delete_blobs_sql(&collection.name, property);
+            sqlite.prepare(&sql)?.step()?;
+        }
         let sql = drop_column_sql(collection, property);
         sqlite.prepare(&sql)?.step()?;
     }
@@ -93,12 +203,32 @@ fn update_table(sqlite: &SQLite3, collection: &IsarSchema) -> Result<()> {
     for property in &add_properties {
         let sql = add_column_sql(collection, property);
         sqlite.prepare(&sql)?.step()?;
+
+        if let Some(default) = &property.default {
+            let mut stmt = sqlite.prepare(&fill_column_sql(collection, property))?;
+            stmt.bind_value(0, &to_isar_value(default, property.data_type))?;
+            stmt.step()?;
+        }
     }
 
     for index in &add_indexes {
-        let sql = create_index_sql(&collection.name, index);
-        sqlite.prepare(&sql)?.step()?;
+        create_index(sqlite, &collection.name, index)?;
     }
 
     Ok(())
 }
+
+fn create_index(sqlite: &SQLite3, table_name: &str, index: &IndexSchema) -> Result<()> {
+    if index.index_type == IndexType::Vector {
+        // vector indexes are flat and only used to find the metric of similarity queries
+        return Ok(());
+    } else if index.index_type == IndexType::Geo {
+        for sql in create_geo_index_sql(table_name, index) {
+            sqlite.prepare(&sql)?.step()?;
+        }
+    } else {
+        let sql = create_index_sql(table_name, index);
+        sqlite.prepare(&sql)?.step()?;
+    }
+    Ok(())
+}
@@ -1,11 +1,18 @@
 use super::sqlite3::{SQLite3, SQLiteFnContext};
 use super::sqlite_collection::{SQLiteCollection, SQLiteProperty};
+use super::sqlite_link::SQLiteLink;
 use super::sqlite_query::QueryParam;
 use crate::core::data_type::DataType;
-use crate::core::error::Result;
+use crate::core::error::{IsarError, Result};
 use crate::core::filter::{ConditionType, Filter, FilterCondition, JsonCondition};
-use crate::core::schema::{IndexSchema, IsarSchema, PropertySchema};
+use crate::core::geo;
+use crate::core::regex::Regex;
+use crate::core::schema::{
+    IndexFilter, IndexFilterValue, IndexSchema, IndexType, IsarSchema, PropertySchema, VectorMetric,
+};
+use crate::core::update::{PropertyUpdate, UpdateOperation};
 use crate::core::value::IsarValue;
+use crate::core::vector;
 use itertools::Itertools;
 use serde_json::Value;
 use std::borrow::Cow;
@@ -40,6 +47,14 @@ pub(crate) fn add_column_sql(collection: &IsarSchema, property: &PropertySchema)
     )
 }
 
+pub(crate) fn fill_column_sql(collection: &IsarSchema, property: &PropertySchema) -> String {
+    format!(
+        "UPDATE {} SET {} = ?",
+        collection.name,
+        property.name.as_ref().unwrap()
+    )
+}
+
 pub(crate) fn drop_column_sql(collection: &IsarSchema, property_name: &str) -> String {
     format!(
         "ALTER TABLE {} DROP COLUMN {}",
@@ -48,20 +63,228 @@ pub(crate) fn drop_column_sql(collection: &IsarSchema, property_name: &str) -> S
 }
 
 pub(crate) fn create_index_sql(table_name: &str, index: &IndexSchema) -> String {
-    format!(
-        "CREATE {} INDEX {}_{} ON {} ({})",
-        if index.unique { "UNIQUE" } else { "" },
+    let mut sql = format!(
+        "CREATE {}INDEX {}_{} ON {} ({})",
+        if index.unique { "UNIQUE " } else { "" },
         table_name,
         index.name,
         table_name,
-        index.properties.join(", ")
-    )
+        index
+            .properties
+            .iter()
+            .map(|p| index_property_sql(p))
+            .join(", ")
+    );
+    if let Some(filter) = &index.filter {
+        sql.push_str(" WHERE ");
+        sql.push_str(&index_filter_sql(filter));
+    }
+    sql
+}
+
+pub(crate) fn index_property_sql(property: &str) -> Cow<'_, str> {
+    if let Some((column, path)) = property.split_once('.') {
+        Cow::Owned(format!("json_extract({}, '$.{}')", column, path))
+    } else {
+        Cow::Borrowed(property)
+    }
+}
+
+// values are inlined because index definitions cannot use parameters
+fn index_filter_sql(filter: &IndexFilter) -> String {
+    match filter {
+        IndexFilter::IsNull { property } => format!("{} IS NULL", property),
+        IndexFilter::IsNotNull { property } => format!("{} IS NOT NULL", property),
+        IndexFilter::Equal { property, value } => {
+            let value = match value {
+                IndexFilterValue::Bool(true) => "TRUE".to_string(),
+                IndexFilterValue::Bool(false) => "FALSE".to_string(),
+                IndexFilterValue::Integer(value) => value.to_string(),
+                IndexFilterValue::String(value) => format!("'{}'", value.replace('\'', "''")),
+            };
+            format!("{} = {}", property, value)
+        }
+        IndexFilter::And { filters } => {
+            format!("({})", filters.iter().map(index_filter_sql).join(" AND "))
+        }
+    }
 }
 
 pub(crate) fn drop_index_sql(table_name: &str, index_name: &str) -> String {
     format!("DROP INDEX {}_{}", table_name, index_name)
 }
 
+/// Geo indexes are R*Tree tables that are kept in sync with the indexed table using triggers.
+pub(crate) fn create_geo_index_sql(table_name: &str, index: &IndexSchema) -> Vec<String> {
+    let rtree = format!("{}_{}", table_name, index.name);
+    let lat = &index.properties[0];
+    let lon = &index.properties[1];
+    let insert_rtree = |row: &str| {
+        format!(
+            "INSERT INTO {rtree} SELECT {row}.{id}, {row}.{lat}, {row}.{lat}, {row}.{lon}, \
+            {row}.{lon} WHERE {row}.{lat} IS NOT NULL AND {row}.{lon} IS NOT NULL;",
+            id = SQLiteProperty::ID_NAME
+        )
+    };
+    vec![
+        format!("CREATE VIRTUAL TABLE {rtree} USING rtree(id, {lat}_min, {lat}_max, {lon}_min, {lon}_max)"),
+        format!(
+            "INSERT INTO {rtree} SELECT {id}, {lat}, {lat}, {lon}, {lon} FROM {table_name} \
+            WHERE {lat} IS NOT NULL AND {lon} IS NOT NULL",
+            id = SQLiteProperty::ID_NAME
+        ),
+        // INSERT OR REPLACE does not fire delete triggers so stale entries are removed here
+        format!(
+            "CREATE TRIGGER {rtree}_insert AFTER INSERT ON {table_name} BEGIN \
+            DELETE FROM {rtree} WHERE id = NEW.{id}; {} END",
+            insert_rtree("NEW"),
+            id = SQLiteProperty::ID_NAME
+        ),
+        format!(
+            "CREATE TRIGGER {rtree}_update AFTER UPDATE OF {lat}, {lon} ON {table_name} BEGIN \
+            DELETE FROM {rtree} WHERE id = OLD.{id}; {} END",
+            insert_rtree("NEW"),
+            id = SQLiteProperty::ID_NAME
+        ),
+        format!(
+            "CREATE TRIGGER {rtree}_delete AFTER DELETE ON {table_name} BEGIN \
+            DELETE FROM {rtree} WHERE id = OLD.{id}; END",
+            id = SQLiteProperty::ID_NAME
+        ),
+    ]
+}
+
+pub(crate) fn drop_geo_index_sql(table_name: &str, index_name: &str) -> Vec<String> {
+    let rtree = format!("{}_{}", table_name, index_name);
+    vec![
+        format!("DROP TRIGGER IF EXISTS {rtree}_insert"),
+        format!("DROP TRIGGER IF EXISTS {rtree}_update"),
+        format!("DROP TRIGGER IF EXISTS {rtree}_delete"),
+        format!("DROP TABLE {rtree}"),
+    ]
+}
+
+/// The edges of a link are stored in a join table. Triggers remove the edges of deleted objects.
+pub(crate) fn create_link_table_sql(table: &str) -> Vec<String> {
+    vec![
+        format!(
+            "CREATE TABLE {table} ({source} INTEGER NOT NULL, {target} INTEGER NOT NULL, \
+            PRIMARY KEY ({source}, {target})) WITHOUT ROWID",
+            source = SQLiteLink::SOURCE,
+            target = SQLiteLink::TARGET
+        ),
+        format!(
+            "CREATE INDEX {table}_index ON {table} ({}, {})",
+            SQLiteLink::TARGET,
+            SQLiteLink::SOURCE
+        ),
+    ]
+}
+
+/// The triggers are dropped with the tables they are defined on so they are recreated when the
+/// database is opened.
+pub(crate) fn create_link_triggers_sql(
+    table: &str,
+    source_table: &str,
+    target_table: &str,
+) -> Vec<String> {
+    let mut sql = drop_link_triggers_sql(table);
+    for (column, trigger_table) in [
+        (SQLiteLink::SOURCE, source_table),
+        (SQLiteLink::TARGET, target_table),
+    ] {
+        sql.push(format!(
+            "CREATE TRIGGER {table}_delete_{column} AFTER DELETE ON {trigger_table} BEGIN \
+            DELETE FROM {table} WHERE {column} = OLD.{id}; END",
+            id = SQLiteProperty::ID_NAME
+        ));
+    }
+    sql
+}
+
+fn drop_link_triggers_sql(table: &str) -> Vec<String> {
+    vec![
+        format!(
+            "DROP TRIGGER IF EXISTS {table}_delete_{}",
+            SQLiteLink::SOURCE
+        ),
+        format!(
+            "DROP TRIGGER IF EXISTS {table}_delete_{}",
+            SQLiteLink::TARGET
+        ),
+    ]
+}
+
+pub(crate) fn drop_link_table_sql(table: &str) -> Vec<String> {
+    let mut sql = drop_link_triggers_sql(table);
+    sql.push(format!("DROP TABLE {table}"));
+    sql
+}
+
+/// Blob payloads are stored in a separate table so they are not copied when other properties of
+/// their object change. Blob columns contain the rowid of their payload.
+pub(crate) const BLOB_TABLE: &str = "_blobs";
+
+pub(crate) fn create_blob_table_sql() -> String {
+    format!("CREATE TABLE IF NOT EXISTS {BLOB_TABLE} (data BLOB NOT NULL)")
+}
+
+pub(crate) fn insert_blob_sql() -> String {
+    format!("INSERT INTO {BLOB_TABLE} (data) VALUES (?)")
+}
+
+/// Deletes the payloads of all blobs in a column before it is dropped.
+pub(crate) fn delete_blobs_sql(table: &str, column: &str) -> String {
+    format!("DELETE FROM {BLOB_TABLE} WHERE rowid IN (SELECT {column} FROM {table})")
+}
+
+/// The triggers delete the payloads of blobs that are replaced, set to null or whose object is
+/// deleted. Like link triggers they are recreated when the database is opened.
+pub(crate) fn create_blob_triggers_sql(collection: &IsarSchema) -> Vec<String> {
+    let table = &collection.name;
+    let mut sql = drop_blob_triggers_sql(table);
+    let columns = collection
+        .properties
+        .iter()
+        .filter(|p| p.data_type == DataType::Blob)
+        .filter_map(|p| p.name.as_deref())
+        .collect_vec();
+    if columns.is_empty() {
+        return sql;
+    }
+
+    let id = SQLiteProperty::ID_NAME;
+    let delete_blobs = |row: &dyn Fn(&str) -> String| {
+        columns
+            .iter()
+            .map(|c| format!("DELETE FROM {BLOB_TABLE} WHERE {};", row(c)))
+            .join(" ")
+    };
+    // INSERT OR REPLACE does not fire delete triggers so replaced blobs are deleted here
+    sql.push(format!(
+        "CREATE TRIGGER {table}_blobs_insert BEFORE INSERT ON {table} BEGIN {} END",
+        delete_blobs(&|c| format!("rowid = (SELECT {c} FROM {table} WHERE {id} = NEW.{id})"))
+    ));
+    sql.push(format!(
+        "CREATE TRIGGER {table}_blobs_update AFTER UPDATE OF {} ON {table} BEGIN {} END",
+        columns.join(", "),
+        delete_blobs(&|c| format!("rowid = OLD.{c} AND OLD.{c} IS NOT NEW.{c}"))
+    ));
+    sql.push(format!(
+        "CREATE TRIGGER {table}_blobs_delete AFTER DELETE ON {table} BEGIN {} END",
+        delete_blobs(&|c| format!("rowid = OLD.{c}"))
+    ));
+    sql
+}
+
+pub(crate) fn drop_blob_triggers_sql(table: &str) -> Vec<String> {
+    vec![
+        format!("DROP TRIGGER IF EXISTS {table}_blobs_insert"),
+        format!("DROP TRIGGER IF EXISTS {table}_blobs_update"),
+        format!("DROP TRIGGER IF EXISTS {table}_blobs_delete"),
+    ]
+}
+
 pub(crate) fn select_properties_sql(collection: &SQLiteCollection) -> String {
     let mut sql = String::new();
     sql.push_str(SQLiteProperty::ID_NAME);
@@ -72,10 +295,13 @@ pub(crate) fn select_properties_sql(collection: &SQLiteCollection) -> String {
     sql
 }
 
-pub(crate) fn insert_sql(name: &str, properties: &[SQLiteProperty], count: u32) -> (u32, String) {
+/// The placeholder of the version property is bound to the id of the object to read the
+/// stored version.
+pub(crate) fn insert_sql(collection: &SQLiteCollection, count: u32) -> (u32, String) {
+    let properties = &collection.properties;
     let mut sql = String::new();
     sql.push_str("INSERT OR REPLACE INTO ");
-    sql.push_str(name);
+    sql.push_str(&collection.name);
     sql.push_str(" (");
     sql.push_str(SQLiteProperty::ID_NAME);
 
@@ -88,8 +314,18 @@ pub(crate) fn insert_sql(name: &str, properties: &[SQLiteProperty], count: u32)
 
     let mut batch = String::new();
     batch.push_str("(?");
-    for _ in 0..properties.len() {
-        batch.push_str(",?");
+    for index in 1..=properties.len() as u16 {
+        if collection.version_property == Some(index) {
+            let stored = format!(
+                "SELECT {} FROM {} WHERE {} = ?",
+                collection.get_property_name(index),
+                collection.name,
+                SQLiteProperty::ID_NAME
+            );
+            batch.push_str(&format!(",{}", next_version_sql(&format!("({})", stored))));
+        } else {
+            batch.push_str(",?");
+        }
     }
     batch.push_str(")");
 
@@ -106,27 +342,215 @@ pub(crate) fn insert_sql(name: &str, properties: &[SQLiteProperty], count: u32)
     (batch_size, sql)
 }
 
+/// An SQL expression and the parameters of its placeholders.
+#[derive(Clone, Default)]
+struct SqlExpr {
+    sql: String,
+    params: Vec<QueryParam>,
+}
+
+impl SqlExpr {
+    fn push(mut self, sql: &str) -> Self {
+        self.sql.push_str(sql);
+        self
+    }
+
+    fn push_expr(mut self, expr: &SqlExpr) -> Self {
+        self.sql.push_str(&expr.sql);
+        self.params.extend(expr.params.iter().cloned());
+        self
+    }
+
+    fn push_param(mut self, value: IsarValue) -> Self {
+        self.sql.push('?');
+        self.params.push(QueryParam::Value(value));
+        self
+    }
+
+    // values in JSON are bound as JSON text so booleans don't become integers
+    fn push_json(self, value: &Option<IsarValue>) -> Self {
+        let value = match value {
+            None => Value::Null,
+            Some(IsarValue::Bool(value)) => Value::Bool(*value),
+            Some(IsarValue::Integer(value)) => Value::from(*value),
+            Some(IsarValue::Real(value)) => Value::from(*value),
+            Some(IsarValue::String(value)) => Value::String(value.clone()),
+            Some(IsarValue::Decimal(value)) => Value::String(value.to_string()),
+            Some(IsarValue::Uuid(value)) => Value::String(value.to_string()),
+            Some(IsarValue::DateTimeOffset(value)) => Value::String(value.to_string()),
+        };
+        self.push("json(")
+            .push_param(IsarValue::String(value.to_string()))
+            .push(")")
+    }
+}
+
 pub(crate) fn update_properties_sql(
     collection: &SQLiteCollection,
-    updates: &[(u16, Option<IsarValue>)],
-) -> (String, Vec<QueryParam>) {
+    all_collections: &[SQLiteCollection],
+    updates: &[PropertyUpdate],
+) -> Result<(String, Vec<QueryParam>)> {
+    // only the last assignment of a column is applied so updates of the same column are nested
+    let mut columns: Vec<(&str, SqlExpr)> = vec![];
+    for update in updates {
+        let (property_index, path) = update
+            .path
+            .split_first()
+            .ok_or(IsarError::IllegalArgument {})?;
+        let property = collection
+            .get_property(*property_index)
+            .ok_or(IsarError::IllegalArgument {})?;
+        let column = if let Some(column) = columns.iter().position(|(c, _)| *c == property.name) {
+            column
+        } else {
+            let current = SqlExpr::default().push(&property.name);
+            columns.push((&property.name, current));
+            columns.len() - 1
+        };
+
+        let current = &columns[column].1;
+        let expr = if path.is_empty() {
+            // the payload of a blob can only be written by inserting its object
+            let writes_blob = property.data_type == DataType::Blob
+                && update.operation != UpdateOperation::Set(None);
+            if writes_blob || !update.operation.is_supported(property.data_type, false) {
+                return Err(IsarError::IllegalArgument {});
+            }
+            update_value_sql(current, &update.operation, property.data_type, false)
+        } else {
+            let mut property = property;
+            let mut parent_path = String::new();
+            let mut json_path = "$".to_string();
+            for nested_index in path {
+                property = property
+                    .collection_index
+                    .filter(|_| property.data_type == DataType::Object)
+                    .and_then(|index| all_collections.get(index as usize))
+                    .and_then(|embedded| embedded.get_property(*nested_index))
+                    .ok_or(IsarError::IllegalArgument {})?;
+                parent_path = json_path.clone();
+                json_path.push('.');
+                json_path.push_str(&property.name);
+            }
+            if !update.operation.is_supported(property.data_type, true) {
+                return Err(IsarError::IllegalArgument {});
+            }
+
+            let target = SqlExpr::default()
+                .push("json_extract(")
+                .push_expr(current)
+                .push(&format!(", '{}')", json_path));
+            let value = update_value_sql(&target, &update.operation, property.data_type, true);
+            // embedded objects that are null are not updated
+            SqlExpr::default()
+                .push("CASE WHEN json_type(")
+                .push_expr(current)
+                .push(&format!(", '{}') = 'object' THEN json_set(", parent_path))
+                .push_expr(current)
+                .push(&format!(", '{}', ", json_path))
+                .push_expr(&value)
+                .push(") ELSE ")
+                .push_expr(current)
+                .push(" END")
+        };
+        columns[column].1 = expr;
+    }
+
+    // the written version is ignored
+    if let Some(version_property) = collection.version_property {
+        let name = collection.get_property_name(version_property);
+        columns.retain(|(column, _)| *column != name);
+        columns.push((name, SqlExpr::default().push(&next_version_sql(name))));
+    }
+
     let mut sql = String::new();
     let mut params = vec![];
-    for (prop, change) in updates.iter() {
-        if let Some(property) = collection.get_property(*prop) {
-            if !sql.is_empty() {
-                sql.push(',');
-            }
-            sql.push_str(&property.name);
-            if let Some(value) = change {
-                sql.push_str("=?");
-                params.push(QueryParam::Value(value.clone()));
+    for (name, expr) in columns {
+        if !sql.is_empty() {
+            sql.push(',');
+        }
+        sql.push_str(name);
+        sql.push('=');
+        sql.push_str(&expr.sql);
+        params.extend(expr.params);
+    }
+    if sql.is_empty() {
+        // the matching objects are still counted
+        sql.push_str("_rowid_=_rowid_");
+    }
+    Ok((sql, params))
+}
+
+fn next_version_sql(version: &str) -> String {
+    format!("COALESCE(MAX({}, 0), 0) + 1", version)
+}
+
+// the smallest and largest value of an integer type that is not null
+fn integer_bounds(data_type: DataType) -> Option<(i64, i64)> {
+    match data_type {
+        DataType::Byte => Some((0, u8::MAX as i64)),
+        DataType::Int => Some((i32::MIN as i64 + 1, i32::MAX as i64)),
+        DataType::Long => Some((i64::MIN + 1, i64::MAX)),
+        _ => None,
+    }
+}
+
+fn update_value_sql(
+    target: &SqlExpr,
+    operation: &UpdateOperation,
+    data_type: DataType,
+    nested: bool,
+) -> SqlExpr {
+    let expr = SqlExpr::default();
+    match operation {
+        UpdateOperation::Set(None) => expr.push("NULL"),
+        UpdateOperation::Set(Some(value)) if nested => expr.push_json(&Some(value.clone())),
+        UpdateOperation::Set(Some(value)) => expr.push_param(value.clone()),
+        UpdateOperation::Increment(value) => {
+            let sum = SqlExpr::default()
+                .push("(")
+                .push_expr(target)
+                .push(" + ")
+                .push_param(value.clone())
+                .push(")");
+            // integers saturate like in native instances, overflowing sums become reals
+            if let Some((min, max)) = integer_bounds(data_type) {
+                expr.push("MIN(MAX(")
+                    .push_expr(&sum)
+                    .push(&format!(", {}), {})", min, max))
             } else {
-                sql.push_str("=NULL");
+                sum
             }
         }
+        UpdateOperation::Append(value) => expr
+            .push("json_insert(COALESCE(")
+            .push_expr(target)
+            .push(", '[]'), '$[#]', ")
+            .push_json(value)
+            .push(")"),
+        UpdateOperation::Remove(value) => {
+            let expr = expr
+                .push("json(CASE WHEN ")
+                .push_expr(target)
+                .push(" IS NULL THEN NULL ELSE (SELECT json_group_array(CASE WHEN type IN ")
+                .push("('true', 'false') THEN json(type) ELSE value END) FROM (SELECT value, ")
+                .push("type FROM json_each(")
+                .push_expr(target)
+                .push(") ORDER BY key) WHERE value IS NOT ");
+            let expr = if let Some(value) = value {
+                expr.push_param(value.clone())
+            } else {
+                expr.push("NULL")
+            };
+            expr.push(") END)")
+        }
+        UpdateOperation::SetAt(index, value) => expr
+            .push("json_replace(")
+            .push_expr(target)
+            .push(&format!(", '$[{}]', ", index))
+            .push_json(value)
+            .push(")"),
     }
-    (sql, params)
 }
 
 pub(crate) fn offset_limit_sql(offset: Option<u32>, limit: Option<u32>) -> String {
@@ -143,24 +567,56 @@ pub(crate) fn filter_sql(
     collection_index: u16,
     all_collections: &[SQLiteCollection],
     filter: Filter,
-) -> (String, Vec<QueryParam>) {
-    filter_sql_path(collection_index, all_collections, filter, vec![])
+) -> Result<(String, Vec<QueryParam>)> {
+    let root_collection = &all_collections[collection_index as usize];
+    filter_sql_path(
+        collection_index,
+        all_collections,
+        root_collection,
+        filter,
+        vec![],
+    )
 }
 
 fn filter_sql_path(
     collection_index: u16,
     all_collections: &[SQLiteCollection],
+    root_collection: &SQLiteCollection,
     filter: Filter,
     mut path: Vec<String>,
-) -> (String, Vec<QueryParam>) {
+) -> Result<(String, Vec<QueryParam>)> {
     let collection = &all_collections[collection_index as usize];
-    match filter {
+    let sql = match filter {
+        Filter::Condition(condition)
+            if matches!(
+                condition.condition_type,
+                ConditionType::GeoWithinBox | ConditionType::GeoWithinRadius
+            ) =>
+        {
+            let lat = collection.get_property_name(condition.property_index);
+            let has_index = collection
+                .indexes
+                .iter()
+                .any(|i| i.index_type == IndexType::Geo && i.properties[0] == lat);
+            if !path.is_empty() || !has_index {
+                return Err(IsarError::IllegalArgument {});
+            }
+            geo_condition_sql(collection, &condition).unwrap_or(("FALSE".to_string(), vec![]))
+        }
         Filter::Condition(condition) => {
-            let is_list = collection
-                .get_property(condition.property_index)
-                .map_or(false, |p| p.data_type.is_list());
+            let property = collection.get_property(condition.property_index);
+            let is_list = property.is_some_and(|p| p.data_type.is_list());
             let property_name = collection.get_property_name(condition.property_index);
-            if !path.is_empty() {
+            let index_path = if !path.is_empty() && property.is_some() && !is_list {
+                Some(format!("{}.{}", path.join("."), property_name))
+            } else {
+                None
+            };
+            if let Some(index_path) = index_path.filter(|p| root_collection.is_indexed(p)) {
+                // use the same expression as the index so SQLite can use it
+                let property_sql = index_property_sql(&index_path);
+                condition_sql(&property_sql, &condition).unwrap_or(("FALSE".to_string(), vec![]))
+            } else if !path.is_empty() {
                 let first_path_part = path.remove(0);
                 path.push(property_name.to_string());
                 let sql = format!("{}({}, ?)", FN_FILTER_JSON_NAME, first_path_part);
@@ -183,7 +639,7 @@ fn filter_sql_path(
                 );
                 (sql, vec![QueryParam::JsonCondition(condition)])
             } else {
-                condition_sql(collection, &condition).unwrap_or(("FALSE".to_string(), vec![]))
+                condition_sql(property_name, &condition).unwrap_or(("FALSE".to_string(), vec![]))
             }
         }
         Filter::Json(_) => todo!(),
@@ -194,6 +650,7 @@ fn filter_sql_path(
                     return filter_sql_path(
                         property.collection_index.unwrap(),
                         all_collections,
+                        root_collection,
                         *nested.filter,
                         path,
                     );
@@ -201,6 +658,54 @@ fn filter_sql_path(
             }
             ("FALSE".to_string(), vec![])
         }
+        Filter::MapEntry(entry) => {
+            let property_name = collection.get_property_name(entry.property_index);
+            let mut condition = entry.condition;
+            let sql = if path.is_empty() {
+                condition.path = vec![entry.key];
+                // a null map has no keys
+                format!(
+                    "{}(IFNULL({}, '{{}}'), ?)",
+                    FN_FILTER_JSON_NAME, property_name
+                )
+            } else {
+                let first_path_part = path.remove(0);
+                path.push(property_name.to_string());
+                path.push(entry.key);
+                condition.path = path;
+                format!("{}({}, ?)", FN_FILTER_JSON_NAME, first_path_part)
+            };
+            (sql, vec![QueryParam::JsonCondition(condition)])
+        }
+        Filter::Link(link_filter) => {
+            let link = collection.links.get(link_filter.link_index as usize);
+            if let Some(link) = link.filter(|_| path.is_empty()) {
+                let target = &all_collections[link.target_collection_index as usize];
+                let (filter_sql, params) = if let Some(filter) = link_filter.filter {
+                    let (sql, params) = filter_sql_path(
+                        link.target_collection_index,
+                        all_collections,
+                        target,
+                        *filter,
+                        vec![],
+                    )?;
+                    (format!(" WHERE {}", sql), params)
+                } else {
+                    (String::new(), vec![])
+                };
+                let (from, to) = link.columns();
+                let sql = format!(
+                    "{id} IN (SELECT {from} FROM {} WHERE {to} IN (SELECT {id} FROM {}{}))",
+                    link.table,
+                    target.name,
+                    filter_sql,
+                    id = SQLiteProperty::ID_NAME
+                );
+                (sql, params)
+            } else {
+                ("FALSE".to_string(), vec![])
+            }
+        }
         Filter::And(filters) => {
             let mut sql = String::new();
             let mut params = vec![];
@@ -208,8 +713,13 @@ fn filter_sql_path(
                 if !sql.is_empty() {
                     sql.push_str(" AND ");
                 }
-                let (filter_sql, filter_params) =
-                    filter_sql_path(collection_index, all_collections, filter, path.clone());
+                let (filter_sql, filter_params) = filter_sql_path(
+                    collection_index,
+                    all_collections,
+                    root_collection,
+                    filter,
+                    path.clone(),
+                )?;
                 sql.push_str(&filter_sql);
                 params.extend(filter_params.into_iter());
             }
@@ -222,25 +732,92 @@ fn filter_sql_path(
                 if !sql.is_empty() {
                     sql.push_str(" OR ");
                 }
-                let (filter_sql, filter_params) =
-                    filter_sql_path(collection_index, all_collections, filter, path.clone());
+                let (filter_sql, filter_params) = filter_sql_path(
+                    collection_index,
+                    all_collections,
+                    root_collection,
+                    filter,
+                    path.clone(),
+                )?;
                 sql.push_str(&filter_sql);
                 params.extend(filter_params.into_iter());
             }
             (format!("({})", sql), params)
         }
         Filter::Not(filter) => {
-            let (sql, params) = filter_sql_path(collection_index, all_collections, *filter, path);
+            let (sql, params) = filter_sql_path(
+                collection_index,
+                all_collections,
+                root_collection,
+                *filter,
+                path,
+            )?;
             (format!("NOT ({})", sql), params)
         }
-    }
+    };
+    Ok(sql)
 }
 
-fn condition_sql(
+fn geo_condition_sql(
     collection: &SQLiteCollection,
     condition: &FilterCondition,
 ) -> Option<(String, Vec<QueryParam>)> {
-    let property_name = collection.get_property_name(condition.property_index);
+    let lat = collection.get_property_name(condition.property_index);
+    let index = collection
+        .indexes
+        .iter()
+        .find(|i| i.index_type == IndexType::Geo && i.properties[0] == lat)?;
+    let lon = &index.properties[1];
+    let value = |i: usize| condition.values.get(i)?.as_ref()?.real();
+
+    let (bounds, exact_sql, exact_values) = match condition.condition_type {
+        ConditionType::GeoWithinBox => {
+            let bounds = (value(0)?, value(1)?, value(2)?, value(3)?);
+            let (min_lat, min_lon, max_lat, max_lon) = bounds;
+            let sql = if min_lon <= max_lon {
+                format!("{lat} BETWEEN ? AND ? AND {lon} BETWEEN ? AND ?")
+            } else {
+                format!("{lat} BETWEEN ? AND ? AND ({lon} >= ? OR {lon} <= ?)")
+            };
+            (bounds, sql, vec![min_lat, max_lat, min_lon, max_lon])
+        }
+        ConditionType::GeoWithinRadius => {
+            let (center_lat, center_lon, radius) = (value(0)?, value(1)?, value(2)?);
+            let bounds = geo::radius_bounds(center_lat, center_lon, radius);
+            let sql = format!("{FN_GEO_DISTANCE_NAME}({lat}, {lon}, ?, ?) <= ?");
+            (bounds, sql, vec![center_lat, center_lon, radius])
+        }
+        _ => return None,
+    };
+
+    // R*Tree constraints cannot express boxes crossing the antimeridian
+    let (min_lat, min_lon, max_lat, max_lon) = bounds;
+    let (sql, values) = if min_lon <= max_lon {
+        let sql = format!(
+            "({} IN (SELECT id FROM {}_{} WHERE {lat}_max >= ? AND {lat}_min <= ? \
+            AND {lon}_max >= ? AND {lon}_min <= ?) AND {})",
+            SQLiteProperty::ID_NAME,
+            collection.name,
+            index.name,
+            exact_sql
+        );
+        let mut values = vec![min_lat, max_lat, min_lon, max_lon];
+        values.extend(exact_values);
+        (sql, values)
+    } else {
+        (format!("({})", exact_sql), exact_values)
+    };
+    let params = values
+        .into_iter()
+        .map(|v| QueryParam::Value(IsarValue::Real(v)))
+        .collect();
+    Some((sql, params))
+}
+
+fn condition_sql(
+    property_name: &str,
+    condition: &FilterCondition,
+) -> Option<(String, Vec<QueryParam>)> {
     let collate = if condition.case_sensitive {
         ""
     } else {
@@ -370,6 +947,16 @@ fn condition_sql(
                 "FALSE".to_string()
             }
         }
+        ConditionType::StringRegex => {
+            if let Some(IsarValue::String(pattern)) = condition.values.first()? {
+                if let Some(regex) = Regex::new(pattern, !condition.case_sensitive) {
+                    let sql = format!("{}({}, ?)", FN_FILTER_REGEX_NAME, property_name);
+                    return Some((sql, vec![QueryParam::Regex(regex)]));
+                }
+            }
+            "FALSE".to_string()
+        }
+        ConditionType::GeoWithinBox | ConditionType::GeoWithinRadius => return None,
     };
 
     let params = values.into_iter().map(|v| QueryParam::Value(v)).collect();
@@ -393,6 +980,10 @@ pub(crate) fn data_type_sql(property: &PropertySchema) -> Cow<str> {
         DataType::Double => Cow::Borrowed("f64"),
         DataType::String => Cow::Borrowed("str"),
         DataType::Json => Cow::Borrowed("json"),
+        DataType::Decimal => Cow::Borrowed("decimal"),
+        DataType::Uuid => Cow::Borrowed("uuid"),
+        DataType::DateTimeOffset => Cow::Borrowed("datetimeoffset"),
+        DataType::Blob => Cow::Borrowed("blob"),
         DataType::Object => Cow::Borrowed(property.collection.as_ref().unwrap()),
         DataType::BoolList => Cow::Borrowed("bool[]"),
         DataType::ByteList => Cow::Borrowed("u8[]"),
@@ -405,6 +996,17 @@ pub(crate) fn data_type_sql(property: &PropertySchema) -> Cow<str> {
             let target_collection = property.collection.as_ref().unwrap();
             Cow::Owned(format!("{target_collection}[]"))
         }
+        DataType::BoolMap => Cow::Borrowed("map[bool]"),
+        DataType::ByteMap => Cow::Borrowed("map[u8]"),
+        DataType::IntMap => Cow::Borrowed("map[i32]"),
+        DataType::FloatMap => Cow::Borrowed("map[f32]"),
+        DataType::LongMap => Cow::Borrowed("map[i64]"),
+        DataType::DoubleMap => Cow::Borrowed("map[f64]"),
+        DataType::StringMap => Cow::Borrowed("map[str]"),
+        DataType::ObjectMap => {
+            let target_collection = property.collection.as_ref().unwrap();
+            Cow::Owned(format!("map[{target_collection}]"))
+        }
     }
 }
 
@@ -418,6 +1020,10 @@ pub(crate) fn sql_data_type(sqlite_type: &str) -> (DataType, Option<&str>) {
         "f64" => (DataType::Double, None),
         "str" => (DataType::String, None),
         "json" => (DataType::Json, None),
+        "decimal" => (DataType::Decimal, None),
+        "uuid" => (DataType::Uuid, None),
+        "datetimeoffset" => (DataType::DateTimeOffset, None),
+        "blob" => (DataType::Blob, None),
         "bool[]" => (DataType::BoolList, None),
         "u8[]" => (DataType::ByteList, None),
         "i32[]" => (DataType::IntList, None),
@@ -425,9 +1031,21 @@ pub(crate) fn sql_data_type(sqlite_type: &str) -> (DataType, Option<&str>) {
         "i64[]" => (DataType::LongList, None),
         "f64[]" => (DataType::DoubleList, None),
         "str[]" => (DataType::StringList, None),
+        "map[bool]" => (DataType::BoolMap, None),
+        "map[u8]" => (DataType::ByteMap, None),
+        "map[i32]" => (DataType::IntMap, None),
+        "map[f32]" => (DataType::FloatMap, None),
+        "map[i64]" => (DataType::LongMap, None),
+        "map[f64]" => (DataType::DoubleMap, None),
+        "map[str]" => (DataType::StringMap, None),
         _ => {
             if let Some(target_collection) = sqlite_type.strip_suffix("[]") {
                 (DataType::ObjectList, Some(target_collection))
+            } else if let Some(target_collection) = sqlite_type
+                .strip_prefix("map[")
+                .and_then(|t| t.strip_suffix(']'))
+            {
+                (DataType::ObjectMap, Some(target_collection))
             } else {
                 (DataType::Object, Some(sqlite_type))
             }
@@ -450,3 +1068,147 @@ pub(crate) fn sql_fn_filter_json(ctx: &mut SQLiteFnContext) -> Result<()> {
 
     Ok(())
 }
+
+pub(crate) const FN_FILTER_REGEX_NAME: &str = "isar_filter_regex";
+pub(crate) const FN_FILTER_REGEX_PTR_TYPE: &[u8] = b"regex_ptr\0";
+pub(crate) fn sql_fn_filter_regex(ctx: &mut SQLiteFnContext) -> Result<()> {
+    if ctx.is_null(0) {
+        return Ok(());
+    }
+
+    let value = ctx.get_str(0);
+    let regex = ctx.get_object::<Regex>(1, FN_FILTER_REGEX_PTR_TYPE);
+    if let Some(regex) = regex {
+        let result = regex.is_match(value);
+        ctx.set_int_result(if result { 1 } else { 0 });
+    }
+
+    Ok(())
+}
+
+pub(crate) const FN_GEO_DISTANCE_NAME: &str = "isar_geo_distance";
+pub(crate) fn sql_fn_geo_distance(ctx: &mut SQLiteFnContext) -> Result<()> {
+    if (0..4).all(|i| !ctx.is_null(i)) {
+        let distance = geo::distance(
+            ctx.get_double(0),
+            ctx.get_double(1),
+            ctx.get_double(2),
+            ctx.get_double(3),
+        );
+        ctx.set_double_result(distance);
+    }
+    Ok(())
+}
+
+pub(crate) const FN_VECTOR_SCORE_NAME: &str = "isar_vector_score";
+
+// the query vector is bound as a blob parameter
+pub(crate) fn vector_score_sql(property_name: &str, metric: VectorMetric) -> String {
+    let metric = match metric {
+        VectorMetric::Cosine => 0,
+        VectorMetric::Dot => 1,
+        VectorMetric::L2 => 2,
+    };
+    format!("{}({}, ?, {})", FN_VECTOR_SCORE_NAME, property_name, metric)
+}
+
+pub(crate) fn vector_param(vector: &[f32]) -> QueryParam {
+    QueryParam::Blob(vector.iter().flat_map(|v| v.to_le_bytes()).collect())
+}
+
+pub(crate) fn sql_fn_vector_score(ctx: &mut SQLiteFnContext) -> Result<()> {
+    if ctx.is_null(0) {
+        return Ok(());
+    }
+    let metric = match ctx.get_int(2) {
+        0 => VectorMetric::Cosine,
+        1 => VectorMetric::Dot,
+        _ => VectorMetric::L2,
+    };
+    let query = ctx
+        .get_blob(1)
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+        .collect_vec();
+    let list = serde_json::from_str::<Vec<Option<f32>>>(ctx.get_str(0)).unwrap_or_default();
+    let elements = list.into_iter().map(|v| v.unwrap_or(f32::NAN));
+    if let Some(score) = vector::score(metric, &query, elements) {
+        ctx.set_double_result(score);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_create_index_sql_nested_property() {
+        let index = IndexSchema::new("index", vec!["prop1", "address.city"], true, false);
+        let sql = create_index_sql("col", &index);
+        assert_eq!(
+            sql,
+            "CREATE UNIQUE INDEX col_index ON col (prop1, json_extract(address, '$.city'))"
+        );
+    }
+
+    #[test]
+    fn test_create_index_sql_filter() {
+        let filter = IndexFilter::And {
+            filters: vec![
+                IndexFilter::IsNotNull {
+                    property: "email".to_string(),
+                },
+                IndexFilter::IsNull {
+                    property: "deletedAt".to_string(),
+                },
+                IndexFilter::Equal {
+                    property: "active".to_string(),
+                    value: IndexFilterValue::Bool(true),
+                },
+                IndexFilter::Equal {
+                    property: "level".to_string(),
+                    value: IndexFilterValue::Integer(-5),
+                },
+                IndexFilter::Equal {
+                    property: "name".to_string(),
+                    value: IndexFilterValue::String("it's (a) test".to_string()),
+                },
+            ],
+        };
+        let index = IndexSchema::new("index", vec!["email"], true, false).with_filter(filter);
+        let sql = create_index_sql("col", &index);
+        assert_eq!(
+            sql,
+            "CREATE UNIQUE INDEX col_index ON col (email) WHERE (email IS NOT NULL AND \
+            deletedAt IS NULL AND active = TRUE AND level = -5 AND name = 'it''s (a) test')"
+        );
+    }
+
+    #[test]
+    fn test_create_geo_index_sql() {
+        let index = IndexSchema::geo("loc", "lat", "lon");
+        let sql = create_geo_index_sql("col", &index);
+        assert_eq!(
+            sql[0],
+            "CREATE VIRTUAL TABLE col_loc USING rtree(id, lat_min, lat_max, lon_min, lon_max)"
+        );
+        assert_eq!(sql.len(), 5);
+        assert_eq!(
+            drop_geo_index_sql("col", "loc").last().unwrap(),
+            "DROP TABLE col_loc"
+        );
+    }
+
+    #[test]
+    fn test_vector_score_sql() {
+        assert_eq!(
+            vector_score_sql("embedding", VectorMetric::L2),
+            "isar_vector_score(embedding, ?, 2)"
+        );
+        assert_eq!(
+            vector_param(&[1.0, -2.0]),
+            QueryParam::Blob(vec![0, 0, 0x80, 0x3F, 0, 0, 0, 0xC0])
+        );
+    }
+}
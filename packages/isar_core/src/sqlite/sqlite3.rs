@@ -1,11 +1,15 @@
 use crate::core::error::{IsarError, Result};
 use ffi::sqlite3_busy_timeout;
 use libsqlite3_sys as ffi;
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::ffi::{c_char, c_int, c_void, CStr, CString};
 use std::{ptr, slice};
 
-use super::sql::{sql_fn_filter_json, FN_FILTER_JSON_NAME};
+use super::sql::{
+    sql_fn_filter_json, sql_fn_filter_regex, sql_fn_geo_distance, sql_fn_vector_score,
+    FN_FILTER_JSON_NAME, FN_FILTER_REGEX_NAME, FN_GEO_DISTANCE_NAME, FN_VECTOR_SCORE_NAME,
+};
+use crate::core::value::IsarValue;
 
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
@@ -24,9 +28,33 @@ extern "C" {
     fn opfs_access(path: &str, flags: i32) -> i32;
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct BusyPolicy {
+    // how long SQLite waits for a lock before failing with SQLITE_BUSY
+    pub timeout_ms: u32,
+    // how often beginning a transaction is retried after it failed with SQLITE_BUSY
+    pub max_retries: u32,
+}
+
+impl Default for BusyPolicy {
+    fn default() -> Self {
+        BusyPolicy {
+            timeout_ms: 5000,
+            max_retries: 3,
+        }
+    }
+}
+
+// name, unique, columns, filter
+// name, unique, columns and the `CREATE INDEX` statement stored by SQLite
+pub(crate) type SQLiteIndexInfo = (String, bool, Vec<String>, String);
+
 pub(crate) struct SQLite3 {
     db: *mut ffi::sqlite3,
     free_update_hook: Cell<Option<unsafe extern "C" fn(*mut std::os::raw::c_void)>>,
+    // statements that are currently not in use, least recently used first
+    statement_cache: RefCell<Vec<(String, *mut ffi::sqlite3_stmt)>>,
+    busy_policy: Cell<BusyPolicy>,
     fd: i32,
 }
 
@@ -34,8 +62,15 @@ unsafe impl Send for SQLite3 {}
 
 impl SQLite3 {
     pub(crate) const MAX_PARAM_COUNT: u32 = 999;
+    const STATEMENT_CACHE_SIZE: usize = 32;
+    const BACKUP_STEP_PAGES: c_int = 64;
+    const BACKUP_BUSY_SLEEP_MS: u32 = 10;
 
-    pub fn open(path: &str, encryption_key: Option<&str>) -> Result<SQLite3> {
+    pub fn open(
+        path: &str,
+        encryption_key: Option<&str>,
+        busy_policy: BusyPolicy,
+    ) -> Result<SQLite3> {
         #[cfg(target_arch = "wasm32")]
         {
             let fd = opfs_open(path, ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE);
@@ -48,6 +83,8 @@ impl SQLite3 {
             Ok(SQLite3 {
                 db: std::ptr::null_mut(),
                 free_update_hook: Cell::new(None),
+                statement_cache: RefCell::new(vec![]),
+                busy_policy: Cell::new(busy_policy),
                 fd,
             })
         }
@@ -64,13 +101,15 @@ impl SQLite3 {
                     let sqlite = SQLite3 {
                         db,
                         free_update_hook: Cell::new(None),
+                        statement_cache: RefCell::new(vec![]),
+                        busy_policy: Cell::new(busy_policy),
                     };
                     if let Some(encryption_key) = encryption_key {
                         sqlite
                             .key(encryption_key)
                             .map_err(|_| IsarError::EncryptionError {})?;
                     }
-                    sqlite.initialize()?;
+                    sqlite.initialize(busy_policy)?;
                     Ok(sqlite)
                 } else {
                     let err = sqlite_err(db, r);
@@ -97,15 +136,95 @@ impl SQLite3 {
         Ok(())
     }
 
-    fn initialize(&self) -> Result<()> {
+    /// Copies the database into `dest` using the online backup API so other connections can keep
+    /// using the database. `progress` is called after each step with the copied and total pages.
+    /// A step that keeps failing because the database is locked is retried for as long as the
+    /// busy policy allows before the backup fails with `Busy`.
+    pub fn backup(&self, dest: &SQLite3, mut progress: impl FnMut(u64, u64)) -> Result<()> {
+        let main = c"main".as_ptr();
+        let busy_policy = self.busy_policy.get();
+        let max_busy_steps = (busy_policy.timeout_ms / Self::BACKUP_BUSY_SLEEP_MS + 1)
+            * (busy_policy.max_retries + 1);
+        let mut busy_steps = 0;
         unsafe {
-            sqlite3_busy_timeout(self.db, 5000);
+            let backup = ffi::sqlite3_backup_init(dest.db, main, self.db, main);
+            if backup.is_null() {
+                return Err(sqlite_err(dest.db, ffi::sqlite3_errcode(dest.db)));
+            }
+            loop {
+                let r = ffi::sqlite3_backup_step(backup, Self::BACKUP_STEP_PAGES);
+                match r {
+                    ffi::SQLITE_OK | ffi::SQLITE_DONE => {
+                        busy_steps = 0;
+                        let total = ffi::sqlite3_backup_pagecount(backup) as u64;
+                        let remaining = ffi::sqlite3_backup_remaining(backup) as u64;
+                        progress(total.saturating_sub(remaining), total);
+                        if r == ffi::SQLITE_DONE {
+                            break;
+                        }
+                    }
+                    ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED if busy_steps < max_busy_steps => {
+                        busy_steps += 1;
+                        ffi::sqlite3_sleep(Self::BACKUP_BUSY_SLEEP_MS as c_int);
+                    }
+                    ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED => {
+                        ffi::sqlite3_backup_finish(backup);
+                        return Err(IsarError::Busy {});
+                    }
+                    _ => {
+                        ffi::sqlite3_backup_finish(backup);
+                        return Err(sqlite_err(dest.db, r));
+                    }
+                }
+            }
+            let r = ffi::sqlite3_backup_finish(backup);
+            if r != ffi::SQLITE_OK {
+                return Err(sqlite_err(dest.db, r));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn integrity_check(&self) -> Result<()> {
+        let mut stmt = self.prepare("PRAGMA integrity_check")?;
+        if stmt.step()? && stmt.get_text(0) == "ok" {
+            Ok(())
+        } else {
+            Err(IsarError::DbCorrupted {})
         }
+    }
+
+    fn initialize(&self, busy_policy: BusyPolicy) -> Result<()> {
+        self.set_busy_policy(busy_policy);
         self.prepare("PRAGMA case_sensitive_like = true")?.step()?;
         self.create_function(FN_FILTER_JSON_NAME, 2, sql_fn_filter_json)?;
+        self.create_function(FN_FILTER_REGEX_NAME, 2, sql_fn_filter_regex)?;
+        self.create_function(FN_GEO_DISTANCE_NAME, 4, sql_fn_geo_distance)?;
+        self.create_function(FN_VECTOR_SCORE_NAME, 3, sql_fn_vector_score)?;
         Ok(())
     }
 
+    pub fn set_busy_policy(&self, busy_policy: BusyPolicy) {
+        unsafe {
+            sqlite3_busy_timeout(self.db, busy_policy.timeout_ms.min(i32::MAX as u32) as i32);
+        }
+        self.busy_policy.replace(busy_policy);
+    }
+
+    /// Runs the job again if it failed because the database is locked by another connection.
+    /// Only use it for jobs that can safely be repeated like beginning a transaction.
+    pub fn retry_busy<T>(&self, mut job: impl FnMut() -> Result<T>) -> Result<T> {
+        let mut retries = 0;
+        loop {
+            match job() {
+                Err(IsarError::Busy {}) if retries < self.busy_policy.get().max_retries => {
+                    retries += 1
+                }
+                result => return result,
+            }
+        }
+    }
+
     pub fn prepare(&self, sql: &str) -> Result<SQLiteStatement> {
         let mut stmt: *mut ffi::sqlite3_stmt = ptr::null_mut();
         let mut c_tail = ptr::null();
@@ -118,13 +237,57 @@ impl SQLite3 {
                 &mut c_tail as *mut *const c_char,
             );
             if r == ffi::SQLITE_OK {
-                Ok(SQLiteStatement { stmt, sqlite: self })
+                Ok(SQLiteStatement {
+                    stmt,
+                    sqlite: self,
+                    cache_key: None,
+                })
             } else {
                 Err(sqlite_err(self.db, r))
             }
         }
     }
 
+    /// Prepares a statement or reuses a cached statement with the same SQL. The statement is
+    /// reset and returned to the cache of this connection when it is dropped.
+    pub fn prepare_cached(&self, sql: &str) -> Result<SQLiteStatement<'_>> {
+        let cached = {
+            let mut cache = self.statement_cache.borrow_mut();
+            let index = cache.iter().rposition(|(cached_sql, _)| cached_sql == sql);
+            index.map(|index| cache.remove(index).1)
+        };
+        let mut stmt = if let Some(stmt) = cached {
+            SQLiteStatement {
+                stmt,
+                sqlite: self,
+                cache_key: None,
+            }
+        } else {
+            self.prepare(sql)?
+        };
+        stmt.cache_key = Some(sql.to_string());
+        Ok(stmt)
+    }
+
+    fn cache_statement(&self, sql: String, stmt: *mut ffi::sqlite3_stmt) {
+        unsafe {
+            // also releases bound objects
+            ffi::sqlite3_reset(stmt);
+            ffi::sqlite3_clear_bindings(stmt);
+        }
+
+        let mut cache = self.statement_cache.borrow_mut();
+        if cache.iter().any(|(cached_sql, _)| *cached_sql == sql) {
+            unsafe { ffi::sqlite3_finalize(stmt) };
+            return;
+        }
+        cache.push((sql, stmt));
+        if cache.len() > Self::STATEMENT_CACHE_SIZE {
+            let (_, evicted) = cache.remove(0);
+            unsafe { ffi::sqlite3_finalize(evicted) };
+        }
+    }
+
     pub fn get_table_names(&self) -> Result<Vec<String>> {
         let mut stmt = self.prepare("PRAGMA table_list")?;
         let mut names = vec![];
@@ -151,7 +314,7 @@ impl SQLite3 {
         Ok(cols)
     }
 
-    pub fn get_table_indexes(&self, table_name: &str) -> Result<Vec<(String, bool, Vec<String>)>> {
+    pub fn get_table_indexes(&self, table_name: &str) -> Result<Vec<SQLiteIndexInfo>> {
         let mut stmt = self.prepare(&format!("PRAGMA index_list({})", table_name))?;
         let mut index_names_unique = vec![];
         while stmt.step()? {
@@ -166,17 +329,91 @@ impl SQLite3 {
             let mut stmt = self.prepare(&format!("PRAGMA index_info({})", index_name))?;
             let mut cols = vec![];
             while stmt.step()? {
+                // expression columns have no name
                 cols.push(stmt.get_text(2).to_string());
             }
-            indexes.push((index_name, unique, cols));
+            let sql = self.get_index_sql(&index_name)?;
+            indexes.push((index_name, unique, cols, sql));
         }
         Ok(indexes)
     }
 
+    fn get_index_sql(&self, index_name: &str) -> Result<String> {
+        let mut stmt =
+            self.prepare("SELECT sql FROM sqlite_master WHERE type = 'index' AND name = ?")?;
+        stmt.bind_text(0, index_name)?;
+        stmt.step()?;
+        Ok(stmt.get_text(0).to_string())
+    }
+
+    /// Returns the R*Tree tables of the geo indexes of a table and their columns.
+    pub fn get_table_geo_indexes(&self, table_name: &str) -> Result<Vec<(String, Vec<String>)>> {
+        // every geo index is an R*Tree table with an insert trigger on the indexed table
+        let mut stmt = self.prepare(
+            "SELECT r.name FROM sqlite_master r JOIN sqlite_master t \
+            ON t.type = 'trigger' AND t.tbl_name = ? AND t.name = r.name || '_insert' \
+            WHERE r.type = 'table' AND r.sql LIKE '%USING rtree%'",
+        )?;
+        stmt.bind_text(0, table_name)?;
+        let mut names = vec![];
+        while stmt.step()? {
+            names.push(stmt.get_text(0).to_string());
+        }
+        let mut indexes = vec![];
+        for name in names {
+            let cols = self
+                .get_table_columns(&name)?
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect();
+            indexes.push((name, cols));
+        }
+        Ok(indexes)
+    }
+
+    /// Returns the table a trigger is defined on.
+    pub fn get_trigger_table(&self, trigger_name: &str) -> Result<Option<String>> {
+        let mut stmt =
+            self.prepare("SELECT tbl_name FROM sqlite_master WHERE type = 'trigger' AND name = ?")?;
+        stmt.bind_text(0, trigger_name)?;
+        if stmt.step()? {
+            Ok(Some(stmt.get_text(0).to_string()))
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn count_changes(&self) -> i32 {
         unsafe { ffi::sqlite3_changes(self.db) }
     }
 
+    pub fn last_insert_rowid(&self) -> i64 {
+        unsafe { ffi::sqlite3_last_insert_rowid(self.db) }
+    }
+
+    /// Opens a blob for incremental reads without loading it into memory.
+    pub fn open_blob(&self, table: &str, column: &str, rowid: i64) -> Result<SQLiteBlob<'_>> {
+        let table = CString::new(table).unwrap();
+        let column = CString::new(column).unwrap();
+        let mut blob: *mut ffi::sqlite3_blob = ptr::null_mut();
+        unsafe {
+            let r = ffi::sqlite3_blob_open(
+                self.db,
+                c"main".as_ptr(),
+                table.as_ptr(),
+                column.as_ptr(),
+                rowid,
+                0,
+                &mut blob,
+            );
+            if r == ffi::SQLITE_OK {
+                Ok(SQLiteBlob { blob, sqlite: self })
+            } else {
+                Err(sqlite_err(self.db, r))
+            }
+        }
+    }
+
     pub fn create_function<F>(&self, name: &str, args: u32, func: F) -> Result<()>
     where
         F: FnMut(&mut SQLiteFnContext<'_>) -> Result<()> + Send + 'static,
@@ -265,6 +502,9 @@ impl SQLite3 {
 impl Drop for SQLite3 {
     fn drop(&mut self) {
         unsafe {
+            for (_, stmt) in self.statement_cache.get_mut().drain(..) {
+                ffi::sqlite3_finalize(stmt);
+            }
             ffi::sqlite3_close(self.db);
         }
     }
@@ -281,6 +521,10 @@ pub(crate) struct SQLiteFnContext<'a> {
 
 #[allow(dead_code)]
 impl<'a> SQLiteFnContext<'a> {
+    pub fn is_null(&self, index: usize) -> bool {
+        unsafe { ffi::sqlite3_value_type(self.args[index]) == ffi::SQLITE_NULL }
+    }
+
     pub fn get_int(&self, index: usize) -> i64 {
         unsafe { ffi::sqlite3_value_int64(self.args[index]) }
     }
@@ -292,6 +536,9 @@ impl<'a> SQLiteFnContext<'a> {
     pub fn get_str(&self, index: usize) -> &'static str {
         unsafe {
             let text = ffi::sqlite3_value_text(self.args[index]);
+            if text.is_null() {
+                return "";
+            }
             let num = ffi::sqlite3_value_bytes(self.args[index]);
             let bytes = std::slice::from_raw_parts(text as *const u8, num as usize);
             std::str::from_utf8_unchecked(bytes)
@@ -301,6 +548,9 @@ impl<'a> SQLiteFnContext<'a> {
     pub fn get_blob(&self, index: usize) -> &'static [u8] {
         unsafe {
             let blob = ffi::sqlite3_value_blob(self.args[index]);
+            if blob.is_null() {
+                return &[];
+            }
             let num = ffi::sqlite3_value_bytes(self.args[index]);
             std::slice::from_raw_parts(blob as *const u8, num as usize)
         }
@@ -363,9 +613,14 @@ impl<'a> SQLiteFnContext<'a> {
 pub(crate) struct SQLiteStatement<'sqlite> {
     stmt: *mut ffi::sqlite3_stmt,
     sqlite: &'sqlite SQLite3,
+    cache_key: Option<String>,
 }
 
 impl<'sqlite> SQLiteStatement<'sqlite> {
+    pub fn sqlite(&self) -> &'sqlite SQLite3 {
+        self.sqlite
+    }
+
     pub fn step(&mut self) -> Result<bool> {
         unsafe {
             let r = ffi::sqlite3_step(self.stmt);
@@ -419,6 +674,9 @@ impl<'sqlite> SQLiteStatement<'sqlite> {
     pub fn get_blob(&self, col: u32) -> &[u8] {
         unsafe {
             let blob = ffi::sqlite3_column_blob(self.stmt, col as i32);
+            if blob.is_null() {
+                return &[];
+            }
             let num = ffi::sqlite3_column_bytes(self.stmt, col as i32);
             std::slice::from_raw_parts(blob as *const u8, num as usize)
         }
@@ -502,6 +760,18 @@ impl<'sqlite> SQLiteStatement<'sqlite> {
         }
     }
 
+    pub fn bind_value(&mut self, col: u32, value: &IsarValue) -> Result<()> {
+        match value {
+            IsarValue::Bool(value) => self.bind_int(col, *value as i32),
+            IsarValue::Integer(value) => self.bind_long(col, *value),
+            IsarValue::Real(value) => self.bind_double(col, *value),
+            IsarValue::String(value) => self.bind_text(col, value),
+            IsarValue::Decimal(value) => self.bind_blob(col, &value.to_bytes()),
+            IsarValue::Uuid(value) => self.bind_blob(col, &value.to_bytes()),
+            IsarValue::DateTimeOffset(value) => self.bind_blob(col, &value.to_bytes()),
+        }
+    }
+
     pub fn bind_object<T>(&mut self, col: u32, value: T, value_type: &'static [u8]) -> Result<()> {
         let ptr = Box::into_raw(Box::new(value));
         unsafe {
@@ -522,9 +792,51 @@ impl<'sqlite> SQLiteStatement<'sqlite> {
 }
 
 impl Drop for SQLiteStatement<'_> {
+    fn drop(&mut self) {
+        if let Some(sql) = self.cache_key.take() {
+            self.sqlite.cache_statement(sql, self.stmt);
+        } else {
+            unsafe {
+                ffi::sqlite3_finalize(self.stmt);
+            }
+        }
+    }
+}
+
+pub(crate) struct SQLiteBlob<'sqlite> {
+    blob: *mut ffi::sqlite3_blob,
+    sqlite: &'sqlite SQLite3,
+}
+
+impl SQLiteBlob<'_> {
+    pub fn size(&self) -> usize {
+        unsafe { ffi::sqlite3_blob_bytes(self.blob) as usize }
+    }
+
+    /// Reads the bytes starting at `offset` into `buffer` and returns the number of read bytes.
+    pub fn read(&self, offset: usize, buffer: &mut [u8]) -> Result<usize> {
+        let length = self.size().saturating_sub(offset).min(buffer.len());
+        if length > 0 {
+            let r = unsafe {
+                ffi::sqlite3_blob_read(
+                    self.blob,
+                    buffer.as_mut_ptr() as *mut c_void,
+                    length as c_int,
+                    offset as c_int,
+                )
+            };
+            if r != ffi::SQLITE_OK {
+                return Err(sqlite_err(self.sqlite.db, r));
+            }
+        }
+        Ok(length)
+    }
+}
+
+impl Drop for SQLiteBlob<'_> {
     fn drop(&mut self) {
         unsafe {
-            ffi::sqlite3_finalize(self.stmt);
+            ffi::sqlite3_blob_close(self.blob);
         }
     }
 }
@@ -533,9 +845,13 @@ pub fn sqlite_err(db: *mut ffi::sqlite3, code: i32) -> IsarError {
     unsafe {
         let c_slice = CStr::from_ptr(ffi::sqlite3_errmsg(db)).to_bytes();
         let msg = String::from_utf8_lossy(c_slice).into_owned();
-        IsarError::DbError {
-            code: code,
-            message: msg,
+        if code & 0xff == ffi::SQLITE_BUSY {
+            IsarError::Busy {}
+        } else {
+            IsarError::DbError {
+                code: code,
+                message: msg,
+            }
         }
     }
 }
@@ -547,3 +863,221 @@ impl<'a> ToOwned for SQLiteStatement<'a> {
         panic!("SQLiteStatement can't be cloned")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_cached() {
+        let sqlite = SQLite3::open(":memory:", None, BusyPolicy::default()).unwrap();
+        let sql = "SELECT ?";
+
+        let mut stmt = sqlite.prepare_cached(sql).unwrap();
+        stmt.bind_long(0, 42).unwrap();
+        assert!(stmt.step().unwrap());
+        assert_eq!(stmt.get_long(0), 42);
+        let raw_stmt = stmt.stmt;
+        drop(stmt);
+
+        let mut stmt = sqlite.prepare_cached(sql).unwrap();
+        assert_eq!(stmt.stmt, raw_stmt);
+        assert!(stmt.step().unwrap());
+        assert!(stmt.is_null(0));
+
+        // the cached statement is in use so a new one is prepared
+        let stmt2 = sqlite.prepare_cached(sql).unwrap();
+        assert_ne!(stmt2.stmt, raw_stmt);
+        drop(stmt);
+        drop(stmt2);
+        assert_eq!(sqlite.statement_cache.borrow().len(), 1);
+    }
+
+    #[test]
+    fn test_prepare_cached_evicts_least_recently_used() {
+        let sqlite = SQLite3::open(":memory:", None, BusyPolicy::default()).unwrap();
+        for i in 0..=SQLite3::STATEMENT_CACHE_SIZE {
+            sqlite.prepare_cached(&format!("SELECT {}", i)).unwrap();
+        }
+
+        let cache = sqlite.statement_cache.borrow();
+        assert_eq!(cache.len(), SQLite3::STATEMENT_CACHE_SIZE);
+        assert_eq!(cache[0].0, "SELECT 1");
+    }
+
+    #[test]
+    fn test_busy() {
+        let path = std::env::temp_dir().join("isar_test_busy.sqlite");
+        let path = path.to_str().unwrap();
+        let busy_policy = BusyPolicy {
+            timeout_ms: 0,
+            max_retries: 2,
+        };
+        let sqlite1 = SQLite3::open(path, None, busy_policy).unwrap();
+        let sqlite2 = SQLite3::open(path, None, busy_policy).unwrap();
+        sqlite1.prepare("BEGIN IMMEDIATE").unwrap().step().unwrap();
+
+        let mut attempts = 0;
+        let result = sqlite2.retry_busy(|| {
+            attempts += 1;
+            sqlite2.prepare("BEGIN IMMEDIATE")?.step()
+        });
+        assert_eq!(result, Err(IsarError::Busy {}));
+        assert_eq!(attempts, 3);
+
+        sqlite1.prepare("COMMIT").unwrap().step().unwrap();
+        assert!(sqlite2.prepare("BEGIN IMMEDIATE").unwrap().step().is_ok());
+        drop(sqlite1);
+        drop(sqlite2);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_backup() {
+        let path = std::env::temp_dir().join("isar_test_backup.sqlite");
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        let sqlite = SQLite3::open(":memory:", None, BusyPolicy::default()).unwrap();
+        sqlite
+            .prepare("CREATE TABLE t (v TEXT)")
+            .unwrap()
+            .step()
+            .unwrap();
+        for _ in 0..500 {
+            let mut stmt = sqlite.prepare("INSERT INTO t VALUES (?)").unwrap();
+            stmt.bind_text(0, &"a".repeat(1000)).unwrap();
+            stmt.step().unwrap();
+        }
+
+        let backup = SQLite3::open(path, None, BusyPolicy::default()).unwrap();
+        let mut steps = vec![];
+        sqlite
+            .backup(&backup, |copied, total| steps.push((copied, total)))
+            .unwrap();
+        assert!(steps.len() > 1);
+        let (copied, total) = *steps.last().unwrap();
+        assert_eq!(copied, total);
+
+        backup.integrity_check().unwrap();
+        let mut stmt = backup.prepare("SELECT count(*) FROM t").unwrap();
+        assert!(stmt.step().unwrap());
+        assert_eq!(stmt.get_long(0), 500);
+        drop(stmt);
+        drop(backup);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_backup_busy() {
+        let path = std::env::temp_dir().join("isar_test_backup_busy.sqlite");
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+        let busy_policy = BusyPolicy {
+            timeout_ms: 20,
+            max_retries: 1,
+        };
+        let sqlite1 = SQLite3::open(path, None, busy_policy).unwrap();
+        let sqlite2 = SQLite3::open(path, None, busy_policy).unwrap();
+        sqlite1
+            .prepare("CREATE TABLE t (v TEXT)")
+            .unwrap()
+            .step()
+            .unwrap();
+        sqlite2.prepare("BEGIN EXCLUSIVE").unwrap().step().unwrap();
+
+        let backup = SQLite3::open(":memory:", None, BusyPolicy::default()).unwrap();
+        assert_eq!(sqlite1.backup(&backup, |_, _| {}), Err(IsarError::Busy {}));
+
+        sqlite2.prepare("COMMIT").unwrap().step().unwrap();
+        assert_eq!(sqlite1.backup(&backup, |_, _| {}), Ok(()));
+        drop(sqlite1);
+        drop(sqlite2);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_get_table_geo_indexes() {
+        use crate::core::data_type::DataType;
+        use crate::core::schema::{IndexSchema, IsarSchema, PropertySchema};
+        use crate::sqlite::sql::{
+            create_blob_table_sql, create_blob_triggers_sql, create_geo_index_sql,
+        };
+
+        let sqlite = SQLite3::open(":memory:", None, BusyPolicy::default()).unwrap();
+        let index = IndexSchema::geo("loc", "lat", "lon");
+        let schema = IsarSchema::new(
+            "col",
+            None,
+            vec![
+                PropertySchema::new("lat", DataType::Double, None),
+                PropertySchema::new("lon", DataType::Double, None),
+                PropertySchema::new("data", DataType::Blob, None),
+            ],
+            vec![index.clone()],
+            false,
+        );
+        let mut sql = vec![
+            "CREATE TABLE col (_rowid_ INTEGER PRIMARY KEY, lat REAL, lon REAL, data INTEGER)"
+                .to_string(),
+            create_blob_table_sql(),
+        ];
+        sql.extend(create_geo_index_sql("col", &index));
+        sql.extend(create_blob_triggers_sql(&schema));
+        for sql in sql {
+            sqlite.prepare(&sql).unwrap().step().unwrap();
+        }
+
+        // the blob insert trigger must not be mistaken for a geo index
+        let cols = ["id", "lat_min", "lat_max", "lon_min", "lon_max"].map(String::from);
+        assert_eq!(
+            sqlite.get_table_geo_indexes("col").unwrap(),
+            vec![("col_loc".to_string(), cols.to_vec())]
+        );
+    }
+
+    #[test]
+    fn test_get_table_indexes() {
+        use crate::core::schema::{IndexFilter, IndexSchema};
+        use crate::sqlite::sql::create_index_sql;
+
+        let sqlite = SQLite3::open(":memory:", None, BusyPolicy::default()).unwrap();
+        let filter = IndexFilter::IsNotNull {
+            property: "name".to_string(),
+        };
+        let indexes = [
+            IndexSchema::new("name", vec!["name"], true, false).with_filter(filter),
+            IndexSchema::new("city", vec!["name", "address.city"], false, false),
+        ];
+        sqlite
+            .prepare("CREATE TABLE col (_rowid_ INTEGER PRIMARY KEY, name TEXT, address TEXT)")
+            .unwrap()
+            .step()
+            .unwrap();
+        for index in &indexes {
+            let sql = create_index_sql("col", index);
+            sqlite.prepare(&sql).unwrap().step().unwrap();
+        }
+
+        // the stored definitions can be compared with the definitions of the schema
+        let mut table_indexes = sqlite.get_table_indexes("col").unwrap();
+        table_indexes.sort();
+        assert_eq!(
+            table_indexes,
+            vec![
+                (
+                    "col_city".to_string(),
+                    false,
+                    vec!["name".to_string(), "".to_string()],
+                    create_index_sql("col", &indexes[1])
+                ),
+                (
+                    "col_name".to_string(),
+                    true,
+                    vec!["name".to_string()],
+                    create_index_sql("col", &indexes[0])
+                ),
+            ]
+        );
+    }
+}
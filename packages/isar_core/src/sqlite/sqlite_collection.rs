@@ -1,11 +1,13 @@
 use std::sync::atomic::{self, AtomicI64};
 use std::sync::Arc;
 
+use super::sqlite_link::SQLiteLink;
 use super::sqlite_query::SQLiteQuery;
 use super::sqlite_txn::SQLiteTxn;
+use crate::core::constraint::PropertyConstraint;
 use crate::core::data_type::DataType;
 use crate::core::error::Result;
-use crate::core::schema::IndexSchema;
+use crate::core::schema::{IndexSchema, TtlSchema};
 use crate::core::watcher::CollectionWatchers;
 
 #[derive(Debug)]
@@ -35,8 +37,13 @@ pub(crate) struct SQLiteCollection {
     pub watchers: Arc<CollectionWatchers<SQLiteQuery>>,
     auto_increment: AtomicI64,
 
-    // these are only used for verification
+    // these are used for verification and to pick indexed expressions in queries
     pub indexes: Vec<IndexSchema>,
+    pub links: Vec<SQLiteLink>,
+    pub ttl: Option<TtlSchema>,
+    // index of the Long property that is incremented whenever an object is written
+    pub version_property: Option<u16>,
+    pub constraints: Vec<PropertyConstraint>,
 }
 
 impl SQLiteCollection {
@@ -53,9 +60,33 @@ impl SQLiteCollection {
             watchers: CollectionWatchers::new(),
             auto_increment: AtomicI64::new(0),
             indexes,
+            links: vec![],
+            ttl: None,
+            version_property: None,
+            constraints: vec![],
         }
     }
 
+    pub fn with_links(mut self, links: Vec<SQLiteLink>) -> Self {
+        self.links = links;
+        self
+    }
+
+    pub fn with_ttl(mut self, ttl: Option<TtlSchema>) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn with_version_property(mut self, version_property: Option<u16>) -> Self {
+        self.version_property = version_property;
+        self
+    }
+
+    pub fn with_constraints(mut self, constraints: Vec<PropertyConstraint>) -> Self {
+        self.constraints = constraints;
+        self
+    }
+
     pub fn is_embedded(&self) -> bool {
         self.id_name.is_none()
     }
@@ -80,7 +111,7 @@ impl SQLiteCollection {
 
     pub fn update_auto_increment(&self, id: i64) {
         self.auto_increment
-            .fetch_max(id + 1, atomic::Ordering::AcqRel);
+            .fetch_max(id.saturating_add(1), atomic::Ordering::AcqRel);
     }
 
     pub fn get_property(&self, property_index: u16) -> Option<&SQLiteProperty> {
@@ -91,6 +122,12 @@ impl SQLiteCollection {
         }
     }
 
+    pub fn is_indexed(&self, property: &str) -> bool {
+        self.indexes
+            .iter()
+            .any(|index| index.properties.iter().any(|p| p == property))
+    }
+
     pub fn get_property_name(&self, property_index: u16) -> &str {
         if let Some(property) = self.get_property(property_index) {
             &property.name
@@ -25,7 +25,7 @@ impl<'a> SQLiteCursor<'a> {
             collection.name,
             SQLiteProperty::ID_NAME,
         );
-        let stmt = txn.get_sqlite(false)?.prepare(&sql)?;
+        let stmt = txn.get_sqlite(false)?.prepare_cached(&sql)?;
         let cursor = Self {
             stmt,
             collection,
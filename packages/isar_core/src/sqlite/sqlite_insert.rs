@@ -4,6 +4,7 @@ use super::sqlite_collection::SQLiteCollection;
 use super::sqlite_txn::SQLiteTxn;
 use crate::core::error::{IsarError, Result};
 use crate::core::insert::IsarInsert;
+use crate::core::value::IsarValue;
 use ouroboros::self_referencing;
 use std::cell::Cell;
 
@@ -21,19 +22,20 @@ impl TxnWithStatement {
         collection: &SQLiteCollection,
         count: u32,
     ) -> Result<(u32, TxnWithStatement)> {
-        let (batch_size, sql) = insert_sql(&collection.name, &collection.properties, count);
+        let (batch_size, sql) = insert_sql(collection, count);
         let txn_stmt = Self::try_new(txn, |txn| {
-            Ok(Cell::new(txn.get_sqlite(true)?.prepare(&sql)?))
+            Ok(Cell::new(txn.get_sqlite(true)?.prepare_cached(&sql)?))
         })?;
         Ok((batch_size, txn_stmt))
     }
 
     fn next(&mut self, collection: &SQLiteCollection, count: u32) -> Result<u32> {
-        let (batch_size, sql) = insert_sql(&collection.name, &collection.properties, count);
+        let (batch_size, sql) = insert_sql(collection, count);
 
         self.with_mut(|s| {
             s.txn.guard(|| s.statement.get_mut().step())?;
-            s.statement.replace(s.txn.get_sqlite(true)?.prepare(&sql)?);
+            s.statement
+                .replace(s.txn.get_sqlite(true)?.prepare_cached(&sql)?);
             Ok(())
         })?;
 
@@ -44,6 +46,10 @@ impl TxnWithStatement {
         self.with_mut(|s| s.txn.guard(|| s.statement.get_mut().step()))?;
         Ok(self.into_heads().txn)
     }
+
+    fn cancel(self) -> SQLiteTxn {
+        self.into_heads().txn
+    }
 }
 
 pub struct SQLiteInsert<'a> {
@@ -55,6 +61,8 @@ pub struct SQLiteInsert<'a> {
     remaining: u32,
     pub(crate) batch_size: u32,
     pub(crate) batch_remaining: u32,
+    // values of the constrained properties of the current object
+    pub(crate) values: Vec<Option<IsarValue>>,
 }
 
 impl<'a> SQLiteInsert<'a> {
@@ -72,10 +80,25 @@ impl<'a> SQLiteInsert<'a> {
             remaining: count - batch_size,
             batch_size,
             batch_remaining: batch_size,
+            values: vec![None; collection.constraints.len()],
         };
         Ok(insert)
     }
 
+    /// Binds the default values of null properties and fails if the object violates a
+    /// constraint.
+    fn apply_constraints(&mut self) -> Result<()> {
+        for (i, constraint) in self.collection.constraints.iter().enumerate() {
+            let value = self.values[i].take();
+            let is_null = value.is_none();
+            if let Some(default) = constraint.apply(value)?.filter(|_| is_null) {
+                let col = self.property_index(constraint.property_index as u32);
+                self.with_stmt(|stmt| stmt.bind_value(col, &default))?;
+            }
+        }
+        Ok(())
+    }
+
     #[inline]
     pub(crate) fn with_stmt<T>(&mut self, callback: impl FnOnce(&mut SQLiteStatement) -> T) -> T {
         self.txn_stmt
@@ -88,11 +111,19 @@ impl<'a> IsarInsert<'a> for SQLiteInsert<'a> {
 
     fn save(&mut self, id: i64) -> Result<()> {
         if self.batch_remaining > 0 {
+            let result = self.apply_constraints();
+            self.values.fill(None);
+            result?;
+
             self.collection.update_auto_increment(id);
 
             let id_property = (self.batch_size - self.batch_remaining)
                 * (self.collection.properties.len() as u32 + 1);
             self.with_stmt(|stmt| stmt.bind_long(id_property, id))?;
+            if let Some(version_property) = self.collection.version_property {
+                let col = self.property_index(version_property as u32);
+                self.with_stmt(|stmt| stmt.bind_long(col, id))?;
+            }
 
             self.batch_remaining -= 1;
             if self.batch_remaining == 0 && self.remaining > 0 {
@@ -111,4 +142,8 @@ impl<'a> IsarInsert<'a> for SQLiteInsert<'a> {
     fn finish(self) -> Result<Self::Txn> {
         self.txn_stmt.finish()
     }
+
+    fn cancel(self) -> Self::Txn {
+        self.txn_stmt.cancel()
+    }
 }
@@ -1,22 +1,29 @@
-use super::sqlite3::SQLite3;
-use super::sqlite_collection::SQLiteCollection;
+use super::sql::update_properties_sql;
+use super::sqlite3::{BusyPolicy, SQLite3};
+use super::sqlite_collection::{SQLiteCollection, SQLiteProperty};
 use super::sqlite_cursor::SQLiteCursor;
 use super::sqlite_insert::SQLiteInsert;
-use super::sqlite_open::{close_instance, get_instance, open_instance};
+use super::sqlite_link::SQLiteLink;
+use super::sqlite_open::{close_instance, get_instance, open_instance, try_close_instance};
 use super::sqlite_query::{SQLiteQuery, SQLiteQueryCursor};
 use super::sqlite_query_builder::SQLiteQueryBuilder;
 use super::sqlite_reader::SQLiteReader;
 use super::sqlite_txn::SQLiteTxn;
 use super::sqlite_verify::verify_sqlite;
+use crate::core::constraint::apply_updates;
+use crate::core::data_type::DataType;
 use crate::core::error::{IsarError, Result};
 use crate::core::filter::{ConditionType, Filter, FilterCondition};
-use crate::core::instance::{Aggregation, CompactCondition, IsarInstance};
+use crate::core::instance::{Aggregation, CompactCondition, IsarInstance, RestoreResult};
 use crate::core::query_builder::IsarQueryBuilder;
 use crate::core::schema::IsarSchema;
+use crate::core::update::{set_updates, PropertyUpdate};
 use crate::core::value::IsarValue;
 use crate::core::watcher::{WatchHandle, WatcherCallback};
+use crate::SQLITE_MEMORY_DIR;
 use parking_lot::lock_api::RawMutex;
 use std::cell::Cell;
+use std::fs;
 use std::rc::Rc;
 use std::sync::Arc;
 use std::vec;
@@ -39,8 +46,10 @@ pub(crate) struct SQLiteInstanceInfo {
     pub(crate) path: String,
     pub(crate) encryption_key: Option<String>,
 
+    schemas: Vec<IsarSchema>,
     collections: Vec<SQLiteCollection>,
     write_mutex: parking_lot::RawMutex,
+    pub(crate) busy_policy: parking_lot::Mutex<BusyPolicy>,
 }
 
 impl SQLiteInstanceInfo {
@@ -50,6 +59,7 @@ impl SQLiteInstanceInfo {
         dir: &str,
         path: &str,
         encryption_key: Option<&str>,
+        schemas: Vec<IsarSchema>,
         collections: Vec<SQLiteCollection>,
     ) -> Self {
         Self {
@@ -58,8 +68,10 @@ impl SQLiteInstanceInfo {
             dir: dir.to_string(),
             path: path.to_string(),
             encryption_key: encryption_key.map(|s| s.to_string()),
+            schemas,
             collections,
             write_mutex: RawMutex::INIT,
+            busy_policy: parking_lot::Mutex::new(BusyPolicy::default()),
         }
     }
 }
@@ -71,6 +83,29 @@ pub struct SQLiteInstance {
 }
 
 impl SQLiteInstance {
+    /// Copies the database at `path` without opening it as an instance so it is not migrated.
+    #[cfg(feature = "native")]
+    pub(crate) fn copy_file(
+        path: &str,
+        copy_path: &str,
+        encryption_key: Option<&str>,
+    ) -> Result<()> {
+        let sqlite = SQLite3::open(path, encryption_key, BusyPolicy::default())?;
+        let copy = SQLite3::open(copy_path, encryption_key, BusyPolicy::default())?;
+        sqlite.backup(&copy, |_, _| {})
+    }
+
+    /// Sets how long connections of this instance wait for locks held by other connections and
+    /// how often beginning a transaction is retried afterwards.
+    pub fn set_busy_policy(&self, timeout_ms: u32, max_retries: u32) {
+        let busy_policy = BusyPolicy {
+            timeout_ms,
+            max_retries,
+        };
+        *self.info.busy_policy.lock() = busy_policy;
+        self.sqlite.set_busy_policy(busy_policy);
+    }
+
     fn get_collection(&self, collection_index: u16) -> Result<&SQLiteCollection> {
         if let Some(collection) = self.info.collections.get(collection_index as usize) {
             Ok(collection)
@@ -79,6 +114,75 @@ impl SQLiteInstance {
         }
     }
 
+    fn get_link(&self, collection_index: u16, link_index: u16) -> Result<&SQLiteLink> {
+        let collection = self.get_collection(collection_index)?;
+        collection
+            .links
+            .get(link_index as usize)
+            .ok_or(IsarError::IllegalArgument {})
+    }
+
+    fn exists(&self, txn: &SQLiteTxn, collection_index: u16, id: i64) -> Result<bool> {
+        let collection = self.get_collection(collection_index)?;
+        let sql = format!(
+            "SELECT 1 FROM {} WHERE {} = ?",
+            collection.name,
+            SQLiteProperty::ID_NAME
+        );
+        let sqlite = txn.get_sqlite(false)?;
+        let mut stmt = sqlite.prepare_cached(&sql)?;
+        stmt.bind_long(0, id)?;
+        stmt.step()
+    }
+
+    fn purge(&self, txn: &SQLiteTxn, on_open: bool) -> Result<u32> {
+        let mut count = 0;
+        for (collection_index, collection) in self.info.collections.iter().enumerate() {
+            if let Some(ttl) = &collection.ttl {
+                if on_open && !ttl.purge_on_open {
+                    continue;
+                }
+                let property_index = collection
+                    .properties
+                    .iter()
+                    .position(|p| p.name == ttl.property)
+                    .ok_or(IsarError::IllegalArgument {})?;
+                let mut qb = self.query(collection_index as u16)?;
+                qb.set_filter(Filter::Condition(FilterCondition::new(
+                    property_index as u16 + 1,
+                    ConditionType::Between,
+                    vec![
+                        Some(IsarValue::Integer(i64::MIN + 1)),
+                        Some(IsarValue::Integer(ttl.expired_until_now())),
+                    ],
+                    false,
+                )));
+                let q = qb.build()?;
+                count += self.query_delete(txn, &q, None, None)?;
+            }
+        }
+        Ok(count)
+    }
+
+    fn purge_on_open(&self) -> Result<()> {
+        let purge = self
+            .info
+            .collections
+            .iter()
+            .any(|c| c.ttl.as_ref().is_some_and(|t| t.purge_on_open));
+        if purge {
+            let txn = self.begin_txn(true)?;
+            match self.purge(&txn, true) {
+                Ok(_) => self.commit_txn(txn)?,
+                Err(err) => {
+                    self.abort_txn(txn);
+                    return Err(err);
+                }
+            }
+        }
+        Ok(())
+    }
+
     #[cfg(target_arch = "wasm32")]
     fn init_opfs() -> Result<()> {
         let window = web_sys::window().expect("no global `window` exists");
@@ -137,6 +241,40 @@ impl IsarInstance for SQLiteInstance {
         self.info.collections.iter().map(|c| c.name.as_str())
     }
 
+    fn get_id_name(&self, collection_index: u16) -> Result<Option<&str>> {
+        Ok(self.get_collection(collection_index)?.id_name.as_deref())
+    }
+
+    fn get_schema(&self, collection_index: u16) -> Result<&IsarSchema> {
+        self.info
+            .schemas
+            .get(collection_index as usize)
+            .ok_or(IsarError::IllegalArgument {})
+    }
+
+    fn get_version_property(&self, collection_index: u16) -> Result<Option<u16>> {
+        Ok(self.get_collection(collection_index)?.version_property)
+    }
+
+    fn get_properties(
+        &self,
+        collection_index: u16,
+    ) -> Result<impl Iterator<Item = (&str, DataType, Option<u16>)>> {
+        let collection = self.get_collection(collection_index)?;
+        Ok(collection
+            .properties
+            .iter()
+            .map(|p| (p.name.as_str(), p.data_type, p.collection_index)))
+    }
+
+    fn get_links(&self, collection_index: u16) -> Result<impl Iterator<Item = (&str, u16, bool)>> {
+        let collection = self.get_collection(collection_index)?;
+        Ok(collection
+            .links
+            .iter()
+            .map(|l| (l.name.as_str(), l.target_collection_index, l.single)))
+    }
+
     fn get_instance(instance_id: u32) -> Option<Self::Instance> {
         let (info, sqlite) = get_instance(instance_id)?;
         Some(Self {
@@ -174,11 +312,16 @@ impl IsarInstance for SQLiteInstance {
             max_size_mib,
             encryption_key,
         )?;
-        Ok(Self {
+        let instance = Self {
             info,
             sqlite: Rc::new(sqlite),
             txn_active: Cell::new(false),
-        })
+        };
+        if let Err(err) = instance.purge_on_open() {
+            Self::close(instance, false);
+            return Err(err);
+        }
+        Ok(instance)
     }
 
     fn change_encryption_key(&self, encryption_key: Option<&str>) -> Result<()> {
@@ -196,11 +339,19 @@ impl IsarInstance for SQLiteInstance {
         if write {
             self.info.write_mutex.lock();
         }
-        if self.txn_active.replace(true) {
+        let txn = if self.txn_active.replace(true) {
             Err(IsarError::TransactionActive {})
         } else {
-            SQLiteTxn::new(self.sqlite.clone(), write)
+            let txn = SQLiteTxn::new(self.sqlite.clone(), write);
+            if txn.is_err() {
+                self.txn_active.replace(false);
+            }
+            txn
+        };
+        if write && txn.is_err() {
+            unsafe { self.info.write_mutex.unlock() };
         }
+        txn
     }
 
     fn commit_txn(&self, txn: SQLiteTxn) -> Result<()> {
@@ -222,6 +373,18 @@ impl IsarInstance for SQLiteInstance {
         }
     }
 
+    fn begin_savepoint(&self, txn: &mut SQLiteTxn) -> Result<()> {
+        txn.begin_savepoint()
+    }
+
+    fn release_savepoint(&self, txn: &mut SQLiteTxn) -> Result<()> {
+        txn.release_savepoint()
+    }
+
+    fn rollback_to_savepoint(&self, txn: &mut SQLiteTxn) -> Result<()> {
+        txn.rollback_to_savepoint()
+    }
+
     fn auto_increment(&self, collection_index: u16) -> i64 {
         if let Ok(collection) = self.get_collection(collection_index) {
             collection.auto_increment()
@@ -230,6 +393,12 @@ impl IsarInstance for SQLiteInstance {
         }
     }
 
+    fn update_auto_increment(&self, collection_index: u16, id: i64) {
+        if let Ok(collection) = self.get_collection(collection_index) {
+            collection.update_auto_increment(id);
+        }
+    }
+
     fn cursor<'a>(&'a self, txn: &'a Self::Txn, collection_index: u16) -> Result<Self::Cursor<'a>> {
         let collection = self.get_collection(collection_index)?;
         SQLiteCursor::new(txn, collection, &self.info.collections)
@@ -253,6 +422,18 @@ impl IsarInstance for SQLiteInstance {
         collection_index: u16,
         id: i64,
         updates: &[(u16, Option<IsarValue>)],
+    ) -> Result<bool> {
+        let collection = self.get_collection(collection_index)?;
+        let updates = set_updates(updates, |index| collection.get_property(index).is_some());
+        self.update_properties(txn, collection_index, id, &updates)
+    }
+
+    fn update_properties(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        id: i64,
+        updates: &[PropertyUpdate],
     ) -> Result<bool> {
         let mut qb = self.query(collection_index)?;
         qb.set_filter(Filter::Condition(FilterCondition::new(
@@ -261,8 +442,8 @@ impl IsarInstance for SQLiteInstance {
             vec![Some(IsarValue::Integer(id))],
             false,
         )));
-        let q = qb.build();
-        let count = self.query_update(txn, &q, None, None, updates)?;
+        let q = qb.build()?;
+        let count = self.query_update_properties(txn, &q, None, None, updates)?;
         Ok(count > 0)
     }
 
@@ -274,13 +455,13 @@ impl IsarInstance for SQLiteInstance {
             vec![Some(IsarValue::Integer(id))],
             false,
         )));
-        let q = qb.build();
+        let q = qb.build()?;
         let count = self.query_delete(txn, &q, None, None)?;
         Ok(count > 0)
     }
 
     fn count(&self, txn: &Self::Txn, collection_index: u16) -> Result<u32> {
-        let q = self.query(collection_index)?.build();
+        let q = self.query(collection_index)?.build()?;
         let result = self.query_aggregate(txn, &q, Aggregation::Count, None)?;
         if let Some(IsarValue::Integer(count)) = result {
             Ok(count as u32)
@@ -289,12 +470,81 @@ impl IsarInstance for SQLiteInstance {
         }
     }
 
+    fn link(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+        target_id: i64,
+    ) -> Result<bool> {
+        let link = self.get_link(collection_index, link_index)?;
+        txn.guard(|| {
+            if !self.exists(txn, collection_index, id)?
+                || !self.exists(txn, link.target_collection_index, target_id)?
+            {
+                return Ok(false);
+            }
+            link.link(txn, id, target_id)?;
+            Ok(true)
+        })
+    }
+
+    fn unlink(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+        target_id: i64,
+    ) -> Result<bool> {
+        let link = self.get_link(collection_index, link_index)?;
+        txn.guard(|| link.unlink(txn, id, target_id))
+    }
+
+    fn unlink_all(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+    ) -> Result<u32> {
+        let link = self.get_link(collection_index, link_index)?;
+        txn.guard(|| link.unlink_all(txn, id))
+    }
+
+    fn get_linked_ids(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+    ) -> Result<Vec<i64>> {
+        let link = self.get_link(collection_index, link_index)?;
+        link.get_ids(txn, id)
+    }
+
+    fn count_links(
+        &self,
+        txn: &Self::Txn,
+        collection_index: u16,
+        link_index: u16,
+        id: i64,
+    ) -> Result<u32> {
+        let ids = self.get_linked_ids(txn, collection_index, link_index, id)?;
+        Ok(ids.len() as u32)
+    }
+
     fn clear(&self, txn: &Self::Txn, collection_index: u16) -> Result<()> {
-        let q = self.query(collection_index)?.build();
+        let q = self.query(collection_index)?.build()?;
         self.query_delete(txn, &q, None, None)?;
         Ok(())
     }
 
+    fn purge_expired(&self, txn: &Self::Txn) -> Result<u32> {
+        self.purge(txn, false)
+    }
+
     fn get_size(
         &self,
         _txn: &Self::Txn,
@@ -341,9 +591,25 @@ impl IsarInstance for SQLiteInstance {
         updates: &[(u16, Option<IsarValue>)],
     ) -> Result<u32> {
         let collection = self.get_collection(query.collection_index)?;
+        let updates = set_updates(updates, |index| collection.get_property(index).is_some());
+        self.query_update_properties(txn, query, offset, limit, &updates)
+    }
+
+    fn query_update_properties(
+        &self,
+        txn: &Self::Txn,
+        query: &Self::Query,
+        offset: Option<u32>,
+        limit: Option<u32>,
+        updates: &[PropertyUpdate],
+    ) -> Result<u32> {
+        let collection = self.get_collection(query.collection_index)?;
+        let updates = apply_updates(&collection.constraints, updates)?;
+        let all_collections = &self.info.collections;
+        let (update_sql, params) = update_properties_sql(collection, all_collections, &updates)?;
         txn.monitor_changes(&collection.watchers);
         let result =
-            txn.guard(|| query.update(txn, &self.info.collections, offset, limit, updates))?;
+            txn.guard(|| query.update(txn, all_collections, offset, limit, &update_sql, &params))?;
         txn.stop_monitor_changes();
         Ok(result)
     }
@@ -356,9 +622,14 @@ impl IsarInstance for SQLiteInstance {
         limit: Option<u32>,
     ) -> Result<u32> {
         let collection = self.get_collection(query.collection_index)?;
+        let cascade_ids =
+            txn.guard(|| query.cascade_ids(txn, &self.info.collections, offset, limit))?;
         txn.monitor_changes(&collection.watchers);
         let result = txn.guard(|| query.delete(txn, &self.info.collections, offset, limit))?;
         txn.stop_monitor_changes();
+        for (collection_index, id) in cascade_ids {
+            self.delete(txn, collection_index, id)?;
+        }
         Ok(result)
     }
 
@@ -380,6 +651,9 @@ impl IsarInstance for SQLiteInstance {
     }
 
     fn watch_query(&self, query: &Self::Query, callback: WatcherCallback) -> Result<WatchHandle> {
+        if query.has_links() {
+            return Err(IsarError::UnsupportedOperation {});
+        }
         let collection = self.get_collection(query.collection_index)?;
         let handle = collection.watchers.watch_query(query, callback);
         Ok(handle)
@@ -395,6 +669,109 @@ impl IsarInstance for SQLiteInstance {
         Ok(())
     }
 
+    fn backup(&self, path: &str, progress: impl FnMut(u64, u64)) -> Result<()> {
+        let encryption_key = self.info.encryption_key.as_deref();
+        let backup = SQLite3::open(path, encryption_key, BusyPolicy::default())?;
+        let result = self
+            .sqlite
+            .backup(&backup, progress)
+            .and_then(|_| backup.integrity_check())
+            .and_then(|_| {
+                let mut table_names = self.sqlite.get_table_names()?;
+                let mut backup_table_names = backup.get_table_names()?;
+                table_names.sort();
+                backup_table_names.sort();
+                if table_names != backup_table_names {
+                    return Err(IsarError::DbCorrupted {});
+                }
+                Ok(())
+            });
+        drop(backup);
+        if result.is_err() {
+            let _ = fs::remove_file(path);
+        }
+        result
+    }
+
+    fn restore(
+        instance: Self,
+        path: &str,
+        schemas: Vec<IsarSchema>,
+        max_size_mib: u32,
+    ) -> RestoreResult<Self> {
+        if cfg!(target_arch = "wasm32") || instance.info.dir == SQLITE_MEMORY_DIR {
+            return Err((Some(instance), IsarError::UnsupportedOperation {}));
+        }
+
+        let instance_id = instance.info.instance_id;
+        let name = instance.info.name.clone();
+        let dir = instance.info.dir.clone();
+        let db_path = instance.info.path.clone();
+        let encryption_key = instance.info.encryption_key.clone();
+
+        let restore_path = format!("{}.restore", db_path);
+        let verified = fs::copy(path, &restore_path)
+            .map_err(|_| IsarError::PathError {})
+            .and_then(|_| {
+                SQLite3::open(
+                    &restore_path,
+                    encryption_key.as_deref(),
+                    BusyPolicy::default(),
+                )
+            })
+            .and_then(|sqlite| sqlite.integrity_check());
+        if let Err(e) = verified {
+            let _ = fs::remove_file(&restore_path);
+            return Err((Some(instance), e));
+        }
+
+        let SQLiteInstance {
+            info,
+            sqlite,
+            txn_active,
+        } = instance;
+        if let Err((info, sqlite)) = try_close_instance(info, sqlite, false) {
+            let _ = fs::remove_file(&restore_path);
+            let instance = SQLiteInstance {
+                info,
+                sqlite,
+                txn_active,
+            };
+            return Err((Some(instance), IsarError::Busy {}));
+        }
+
+        let open = |schemas| {
+            let _ = fs::remove_file(format!("{}-wal", db_path));
+            let _ = fs::remove_file(format!("{}-shm", db_path));
+            Self::open_instance(
+                instance_id,
+                &name,
+                &dir,
+                schemas,
+                max_size_mib,
+                encryption_key.as_deref(),
+                None,
+            )
+        };
+        // the old file is kept until the restored one is open so it can be put back on failure
+        let old_path = format!("{}.old", db_path);
+        let restored = fs::rename(&db_path, &old_path)
+            .and_then(|_| fs::rename(&restore_path, &db_path))
+            .map_err(|_| IsarError::PathError {})
+            .and_then(|_| open(schemas.clone()));
+        match restored {
+            Ok(instance) => {
+                let _ = fs::remove_file(&old_path);
+                Ok(instance)
+            }
+            Err(e) => {
+                let _ = fs::remove_file(&restore_path);
+                let _ = fs::rename(&old_path, &db_path);
+                Err((open(schemas).ok(), e))
+            }
+        }
+    }
+
     fn verify(&self, _txn: &Self::Txn) -> Result<()> {
         verify_sqlite(&self.sqlite, &self.info.collections)
     }
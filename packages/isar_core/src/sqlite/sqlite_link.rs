@@ -0,0 +1,107 @@
+use super::sqlite_collection::SQLiteProperty;
+use super::sqlite_txn::SQLiteTxn;
+use crate::core::error::Result;
+
+/// The edges of a link are stored in a join table with a `source` and a `target` column.
+/// Backlinks use the join table of the link in the opposite direction.
+#[derive(Debug)]
+pub(crate) struct SQLiteLink {
+    pub name: String,
+    pub target_collection_index: u16,
+    pub single: bool,
+    // the link in the opposite direction is single
+    pub reverse_single: bool,
+    pub cascade: bool,
+    pub table: String,
+    // the table of the target collection
+    pub target_table: String,
+    pub backlink: bool,
+}
+
+impl SQLiteLink {
+    pub const SOURCE: &'static str = "source";
+    pub const TARGET: &'static str = "target";
+
+    pub fn table_name(collection: &str, link: &str) -> String {
+        format!("_{}_{}", collection, link)
+    }
+
+    /// Returns the column of this side and the column of the linked side.
+    pub fn columns(&self) -> (&'static str, &'static str) {
+        if self.backlink {
+            (Self::TARGET, Self::SOURCE)
+        } else {
+            (Self::SOURCE, Self::TARGET)
+        }
+    }
+
+    /// Returns the SQL selecting the ids linked to the ids selected by `ids_sql`. Edges of
+    /// objects that were replaced without running the delete triggers are skipped.
+    pub fn linked_ids_sql(&self, ids_sql: &str) -> String {
+        let (from, to) = self.columns();
+        format!(
+            "SELECT {to} FROM {} WHERE {from} IN ({ids_sql}) AND {to} IN (SELECT {} FROM {})",
+            self.table,
+            SQLiteProperty::ID_NAME,
+            self.target_table
+        )
+    }
+
+    pub fn get_ids(&self, txn: &SQLiteTxn, id: i64) -> Result<Vec<i64>> {
+        let sqlite = txn.get_sqlite(false)?;
+        let mut stmt = sqlite.prepare_cached(&self.linked_ids_sql("?"))?;
+        stmt.bind_long(0, id)?;
+        let mut ids = vec![];
+        while stmt.step()? {
+            ids.push(stmt.get_long(0));
+        }
+        Ok(ids)
+    }
+
+    pub fn link(&self, txn: &SQLiteTxn, id: i64, target_id: i64) -> Result<()> {
+        let sqlite = txn.get_sqlite(true)?;
+        let (from, to) = self.columns();
+        if self.single {
+            let sql = format!("DELETE FROM {} WHERE {from} = ?", self.table);
+            let mut stmt = sqlite.prepare_cached(&sql)?;
+            stmt.bind_long(0, id)?;
+            stmt.step()?;
+        }
+        if self.reverse_single {
+            let sql = format!("DELETE FROM {} WHERE {to} = ?", self.table);
+            let mut stmt = sqlite.prepare_cached(&sql)?;
+            stmt.bind_long(0, target_id)?;
+            stmt.step()?;
+        }
+        let sql = format!(
+            "INSERT OR IGNORE INTO {} ({from}, {to}) VALUES (?, ?)",
+            self.table
+        );
+        let mut stmt = sqlite.prepare_cached(&sql)?;
+        stmt.bind_long(0, id)?;
+        stmt.bind_long(1, target_id)?;
+        stmt.step()?;
+        Ok(())
+    }
+
+    pub fn unlink(&self, txn: &SQLiteTxn, id: i64, target_id: i64) -> Result<bool> {
+        let sqlite = txn.get_sqlite(true)?;
+        let (from, to) = self.columns();
+        let sql = format!("DELETE FROM {} WHERE {from} = ? AND {to} = ?", self.table);
+        let mut stmt = sqlite.prepare_cached(&sql)?;
+        stmt.bind_long(0, id)?;
+        stmt.bind_long(1, target_id)?;
+        stmt.step()?;
+        Ok(sqlite.count_changes() > 0)
+    }
+
+    pub fn unlink_all(&self, txn: &SQLiteTxn, id: i64) -> Result<u32> {
+        let sqlite = txn.get_sqlite(true)?;
+        let (from, _) = self.columns();
+        let sql = format!("DELETE FROM {} WHERE {from} = ?", self.table);
+        let mut stmt = sqlite.prepare_cached(&sql)?;
+        stmt.bind_long(0, id)?;
+        stmt.step()?;
+        Ok(sqlite.count_changes() as u32)
+    }
+}
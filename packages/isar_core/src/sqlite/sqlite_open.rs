@@ -1,8 +1,10 @@
 use super::schema_manager::perform_migration;
-use super::sqlite3::SQLite3;
+use super::sqlite3::{BusyPolicy, SQLite3};
 use super::sqlite_collection::{SQLiteCollection, SQLiteProperty};
 use super::sqlite_instance::SQLiteInstanceInfo;
+use super::sqlite_link::SQLiteLink;
 use super::sqlite_txn::SQLiteTxn;
+use crate::core::constraint::PropertyConstraint;
 use crate::core::error::Result;
 use crate::core::schema::IsarSchema;
 use crate::SQLITE_MEMORY_DIR;
@@ -21,15 +23,22 @@ const MIB: usize = 1 << 20;
 
 struct Connections {
     info: Arc<SQLiteInstanceInfo>,
+    // idle connections keep their prepared statement cache
     sqlite: Vec<SQLite3>,
 }
 
 impl Connections {
     fn get_sqlite(&mut self) -> Result<SQLite3> {
         if let Some(sqlite) = self.sqlite.pop() {
+            sqlite.set_busy_policy(*self.info.busy_policy.lock());
             Ok(sqlite)
         } else {
-            SQLite3::open(&self.info.path, self.info.encryption_key.as_deref())
+            let busy_policy = *self.info.busy_policy.lock();
+            SQLite3::open(
+                &self.info.path,
+                self.info.encryption_key.as_deref(),
+                busy_policy,
+            )
         }
     }
 }
@@ -54,7 +63,7 @@ pub(crate) fn open_sqlite(
         path_buf.as_path().to_str().unwrap().to_string()
     };
 
-    let sqlite = SQLite3::open(&path, encryption_key)?;
+    let sqlite = SQLite3::open(&path, encryption_key, BusyPolicy::default())?;
 
     let max_size = (max_size_mib as usize).saturating_mul(MIB);
     sqlite
@@ -84,6 +93,7 @@ pub(crate) fn open_sqlite(
         dir,
         &path,
         encryption_key,
+        schemas,
         collections,
     );
 
@@ -91,6 +101,19 @@ pub(crate) fn open_sqlite(
     Ok((instance_info, sqlite))
 }
 
+fn get_constraints(schema: &IsarSchema) -> Vec<PropertyConstraint> {
+    schema
+        .properties
+        .iter()
+        .filter(|p| p.name.is_some())
+        .enumerate()
+        .filter_map(|(index, p)| {
+            let dimensions = schema.vector_dimensions(p.name.as_ref()?);
+            PropertyConstraint::new(index as u16 + 1, p, dimensions)
+        })
+        .collect()
+}
+
 fn get_collections(schemas: &[IsarSchema]) -> Vec<SQLiteCollection> {
     let mut collections = Vec::new();
     for collection_schema in schemas {
@@ -110,17 +133,75 @@ fn get_collections(schemas: &[IsarSchema]) -> Vec<SQLiteCollection> {
                 }
             })
             .collect_vec();
+        let version_property = collection_schema
+            .version_property
+            .as_ref()
+            .and_then(|name| {
+                let index = properties.iter().position(|p| &p.name == name)?;
+                Some(index as u16 + 1)
+            });
         let collection = SQLiteCollection::new(
             collection_schema.name.clone(),
             collection_schema.id_name.clone(),
             properties,
             collection_schema.indexes.clone(),
-        );
+        )
+        .with_links(get_links(schemas, collection_schema))
+        .with_ttl(collection_schema.ttl.clone())
+        .with_version_property(version_property)
+        .with_constraints(get_constraints(collection_schema));
         collections.push(collection);
     }
     collections
 }
 
+fn get_links(schemas: &[IsarSchema], collection_schema: &IsarSchema) -> Vec<SQLiteLink> {
+    let find_collection = |name: &str| {
+        let index = schemas.iter().position(|c| c.name == name).unwrap();
+        (index as u16, &schemas[index])
+    };
+    collection_schema
+        .links
+        .iter()
+        .map(|link| {
+            let (target_index, target_schema) = find_collection(&link.target);
+            if let Some(backlink) = &link.backlink {
+                let forward = target_schema
+                    .links
+                    .iter()
+                    .find(|l| &l.name == backlink)
+                    .unwrap();
+                SQLiteLink {
+                    name: link.name.clone(),
+                    target_collection_index: target_index,
+                    single: link.single,
+                    reverse_single: forward.single,
+                    cascade: link.cascade,
+                    table: SQLiteLink::table_name(&target_schema.name, &forward.name),
+                    target_table: target_schema.name.clone(),
+                    backlink: true,
+                }
+            } else {
+                let reverse_single = target_schema.links.iter().any(|l| {
+                    l.backlink.as_ref() == Some(&link.name)
+                        && l.target == collection_schema.name
+                        && l.single
+                });
+                SQLiteLink {
+                    name: link.name.clone(),
+                    target_collection_index: target_index,
+                    single: link.single,
+                    reverse_single,
+                    cascade: link.cascade,
+                    table: SQLiteLink::table_name(&collection_schema.name, &link.name),
+                    target_table: target_schema.name.clone(),
+                    backlink: false,
+                }
+            }
+        })
+        .collect()
+}
+
 pub(crate) fn get_instance(instance_id: u32) -> Option<(Arc<SQLiteInstanceInfo>, SQLite3)> {
     let mut lock = INSTANCES.lock();
     if let Some(connections) = lock.get_mut(instance_id as u64) {
@@ -166,6 +247,26 @@ pub(crate) fn close_instance(
     sqlite: Rc<SQLite3>,
     delete: bool,
 ) -> bool {
+    match try_close_instance(info, sqlite, delete) {
+        Ok(()) => true,
+        Err((info, sqlite)) => {
+            // Return connection to pool
+            if let Some(sqlite) = Rc::into_inner(sqlite) {
+                let mut lock = INSTANCES.lock();
+                let connections = lock.get_mut(info.instance_id as u64).unwrap();
+                connections.sqlite.push(sqlite);
+            }
+            false
+        }
+    }
+}
+
+/// Closes the instance or hands its info and connection back if it is still in use elsewhere.
+pub(crate) fn try_close_instance(
+    info: Arc<SQLiteInstanceInfo>,
+    sqlite: Rc<SQLite3>,
+    delete: bool,
+) -> std::result::Result<(), (Arc<SQLiteInstanceInfo>, Rc<SQLite3>)> {
     // Check whether all other references are gone
     if Arc::strong_count(&info) == 2 {
         let mut lock = INSTANCES.lock();
@@ -180,16 +281,8 @@ pub(crate) fn close_instance(
                 let _ = remove_file(&format!("{}-wal", path));
                 let _ = remove_file(&format!("{}-shm", path));
             }
-            return true;
+            return Ok(());
         }
     }
-
-    // Return connection to pool
-    if let Some(sqlite) = Rc::into_inner(sqlite) {
-        let mut lock = INSTANCES.lock();
-        let connections = lock.get_mut(info.instance_id as u64).unwrap();
-        connections.sqlite.push(sqlite);
-    }
-
-    false
+    Err((info, sqlite))
 }
@@ -1,5 +1,5 @@
 use super::sql::{
-    offset_limit_sql, select_properties_sql, update_properties_sql, FN_FILTER_JSON_COND_PTR_TYPE,
+    offset_limit_sql, select_properties_sql, FN_FILTER_JSON_COND_PTR_TYPE, FN_FILTER_REGEX_PTR_TYPE,
 };
 use super::sqlite3::SQLiteStatement;
 use super::sqlite_collection::{SQLiteCollection, SQLiteProperty};
@@ -10,6 +10,7 @@ use crate::core::data_type::DataType;
 use crate::core::error::Result;
 use crate::core::filter::JsonCondition;
 use crate::core::instance::Aggregation;
+use crate::core::regex::Regex;
 use crate::core::value::IsarValue;
 use crate::core::watcher::QueryMatches;
 use std::borrow::Cow;
@@ -18,6 +19,8 @@ use std::borrow::Cow;
 pub(crate) enum QueryParam {
     Value(IsarValue),
     JsonCondition(JsonCondition),
+    Regex(Regex),
+    Blob(Vec<u8>),
 }
 
 #[cfg(test)]
@@ -29,6 +32,9 @@ pub struct SQLiteQuery {
     sql: String,
     has_sort_distinct: bool,
     params: Vec<QueryParam>,
+    // selected after the properties if the query has a similarity sort
+    score: Option<(String, QueryParam)>,
+    has_links: bool,
 }
 
 impl SQLiteQuery {
@@ -37,15 +43,23 @@ impl SQLiteQuery {
         sql: String,
         has_sort_distinct: bool,
         params: Vec<QueryParam>,
+        score: Option<(String, QueryParam)>,
+        has_links: bool,
     ) -> Self {
         Self {
             collection_index,
             sql,
             has_sort_distinct,
             params,
+            score,
+            has_links,
         }
     }
 
+    pub(crate) fn has_links(&self) -> bool {
+        self.has_links
+    }
+
     pub(crate) fn cursor<'a>(
         &'a self,
         txn: &'a SQLiteTxn,
@@ -54,20 +68,34 @@ impl SQLiteQuery {
         limit: Option<u32>,
     ) -> Result<SQLiteQueryCursor<'a>> {
         let collection = &all_collections[self.collection_index as usize];
+        let mut select_sql = select_properties_sql(collection);
+        let score_column = if let Some((score_sql, _)) = &self.score {
+            select_sql.push(',');
+            select_sql.push_str(score_sql);
+            Some(collection.properties.len() as u32 + 1)
+        } else {
+            None
+        };
         let sql = format!(
             "SELECT {} FROM {} {} {}",
-            select_properties_sql(collection),
+            select_sql,
             collection.name,
             self.sql,
             offset_limit_sql(offset, limit)
         );
-        let mut stmt = txn.get_sqlite(false)?.prepare(&sql)?;
-        Self::bind_params(&mut stmt, &self.params, 0)?;
+        let mut stmt = txn.get_sqlite(false)?.prepare_cached(&sql)?;
+        if let Some((_, score_param)) = &self.score {
+            Self::bind_params(&mut stmt, std::slice::from_ref(score_param), 0)?;
+            Self::bind_params(&mut stmt, &self.params, 1)?;
+        } else {
+            Self::bind_params(&mut stmt, &self.params, 0)?;
+        }
 
         Ok(SQLiteQueryCursor {
             stmt,
             collection,
             all_collections,
+            score_column,
         })
     }
 
@@ -104,7 +132,7 @@ impl SQLiteQuery {
             "SELECT {} FROM {} {}",
             aggregation_sql, collection.name, self.sql
         );
-        let mut stmt = txn.get_sqlite(false)?.prepare(&sql)?;
+        let mut stmt = txn.get_sqlite(false)?.prepare_cached(&sql)?;
         Self::bind_params(&mut stmt, &self.params, 0)?;
 
         let has_next = stmt.step()?;
@@ -119,6 +147,9 @@ impl SQLiteQuery {
                         }
                         DataType::Float | DataType::Double => IsarValue::Real(stmt.get_double(0)),
                         DataType::String => IsarValue::String(stmt.get_text(0).to_string()),
+                        DataType::Decimal | DataType::Uuid | DataType::DateTimeOffset => {
+                            return Ok(IsarValue::from_bytes(property_type, stmt.get_blob(0)))
+                        }
                         _ => return Ok(None),
                     }
                 } else {
@@ -142,10 +173,10 @@ impl SQLiteQuery {
         all_collections: &[SQLiteCollection],
         offset: Option<u32>,
         limit: Option<u32>,
-        updates: &[(u16, Option<IsarValue>)],
+        update_sql: &str,
+        update_params: &[QueryParam],
     ) -> Result<u32> {
         let collection: &SQLiteCollection = &all_collections[self.collection_index as usize];
-        let (update_sql, update_params) = update_properties_sql(collection, updates);
         let sql = if offset.is_some() || limit.is_some() || self.has_sort_distinct {
             format!(
                 "UPDATE {} SET {} WHERE {} IN (SELECT {} FROM {} {} {})",
@@ -161,8 +192,8 @@ impl SQLiteQuery {
             format!("UPDATE {} SET {} {}", collection.name, update_sql, self.sql)
         };
         let sqlite = txn.get_sqlite(true)?;
-        let mut stmt = sqlite.prepare(&sql)?;
-        Self::bind_params(&mut stmt, &update_params, 0)?;
+        let mut stmt = sqlite.prepare_cached(&sql)?;
+        Self::bind_params(&mut stmt, update_params, 0)?;
         Self::bind_params(&mut stmt, &self.params, update_params.len())?;
         stmt.step()?;
         let count = sqlite.count_changes();
@@ -191,27 +222,54 @@ impl SQLiteQuery {
             format!("DELETE FROM {} {}", collection.name, self.sql)
         };
         let sqlite = txn.get_sqlite(true)?;
-        let mut stmt = sqlite.prepare(&sql)?;
+        let mut stmt = sqlite.prepare_cached(&sql)?;
         Self::bind_params(&mut stmt, &self.params, 0)?;
         stmt.step()?;
         let count = sqlite.count_changes();
         Ok(count as u32)
     }
 
+    /// Returns the collection index and id of the objects linked by cascading links of the
+    /// objects matched by the query.
+    pub(crate) fn cascade_ids(
+        &self,
+        txn: &SQLiteTxn,
+        all_collections: &[SQLiteCollection],
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<Vec<(u16, i64)>> {
+        let collection = &all_collections[self.collection_index as usize];
+        let mut ids = vec![];
+        for link in collection.links.iter().filter(|l| l.cascade) {
+            let ids_sql = format!(
+                "SELECT {} FROM {} {} {}",
+                SQLiteProperty::ID_NAME,
+                collection.name,
+                self.sql,
+                offset_limit_sql(offset, limit)
+            );
+            let sqlite = txn.get_sqlite(false)?;
+            let mut stmt = sqlite.prepare_cached(&link.linked_ids_sql(&ids_sql))?;
+            Self::bind_params(&mut stmt, &self.params, 0)?;
+            while stmt.step()? {
+                ids.push((link.target_collection_index, stmt.get_long(0)));
+            }
+        }
+        Ok(ids)
+    }
+
     fn bind_params(stmt: &mut SQLiteStatement, params: &[QueryParam], offset: usize) -> Result<()> {
         for (i, params) in params.iter().enumerate() {
             let col = (i + offset) as u32;
             match params {
-                QueryParam::Value(IsarValue::Bool(value)) => {
-                    let value = if *value { 1 } else { 0 };
-                    stmt.bind_int(col, value)?;
-                }
-                QueryParam::Value(IsarValue::Integer(value)) => stmt.bind_long(col, *value)?,
-                QueryParam::Value(IsarValue::Real(value)) => stmt.bind_double(col, *value)?,
-                QueryParam::Value(IsarValue::String(value)) => stmt.bind_text(col, value)?,
+                QueryParam::Value(value) => stmt.bind_value(col, value)?,
                 QueryParam::JsonCondition(cond) => {
                     stmt.bind_object(col, cond, FN_FILTER_JSON_COND_PTR_TYPE)?
                 }
+                QueryParam::Regex(regex) => {
+                    stmt.bind_object(col, regex, FN_FILTER_REGEX_PTR_TYPE)?
+                }
+                QueryParam::Blob(blob) => stmt.bind_blob(col, blob)?,
             }
         }
         Ok(())
@@ -230,6 +288,7 @@ pub struct SQLiteQueryCursor<'a> {
     stmt: SQLiteStatement<'a>,
     collection: &'a SQLiteCollection,
     all_collections: &'a [SQLiteCollection],
+    score_column: Option<u32>,
 }
 
 impl<'a> IsarQueryCursor for SQLiteQueryCursor<'a> {
@@ -243,7 +302,7 @@ impl<'a> IsarQueryCursor for SQLiteQueryCursor<'a> {
                 self.collection,
                 self.all_collections,
             );
-            Some(reader)
+            Some(reader.with_score_column(self.score_column))
         } else {
             None
         }
@@ -1,18 +1,25 @@
+use std::borrow::Cow;
 use std::vec;
 
-use super::sql::filter_sql;
+use super::sql::{filter_sql, vector_param, vector_score_sql, FN_GEO_DISTANCE_NAME};
 use super::sqlite_collection::SQLiteCollection;
 use super::sqlite_query::{QueryParam, SQLiteQuery};
+use crate::core::error::{IsarError, Result};
 use crate::core::filter::Filter;
 use crate::core::query_builder::{IsarQueryBuilder, Sort};
+use crate::core::schema::{IndexType, VectorMetric};
 use itertools::Itertools;
 
 pub struct SQLiteQueryBuilder<'a> {
     all_collections: &'a [SQLiteCollection],
     collection_index: u16,
     filter: Option<Filter>,
-    sort: Vec<(&'a str, Sort, bool)>,
+    sort: Vec<(Cow<'a, str>, Sort, bool)>,
     distinct: Vec<(&'a str, bool)>,
+    // the score of a similarity sort and the query vector
+    score: Option<(String, QueryParam)>,
+    // a distance sort without a geo index or with a non-finite coordinate
+    invalid_sort: bool,
 }
 
 impl SQLiteQueryBuilder<'_> {
@@ -26,23 +33,31 @@ impl SQLiteQueryBuilder<'_> {
             filter: None,
             sort: Vec::new(),
             distinct: Vec::new(),
+            score: None,
+            invalid_sort: false,
         }
     }
 }
 
 impl<'a> SQLiteQueryBuilder<'a> {
-    fn build_query(self) -> (String, Vec<QueryParam>) {
+    fn build_query(self) -> Result<(String, Vec<QueryParam>)> {
+        if self.invalid_sort {
+            return Err(IsarError::IllegalArgument {});
+        }
         let mut filter_params = vec![];
 
         let mut sql = String::new();
         if let Some(filter) = self.filter {
+            filter.check_patterns()?;
             sql.push_str(" WHERE ");
             let (filter_sql, params) =
-                filter_sql(self.collection_index, self.all_collections, filter);
+                filter_sql(self.collection_index, self.all_collections, filter)?;
             sql.push_str(&filter_sql);
             filter_params = params;
         }
         if !self.sort.is_empty() {
+            // the query vector of a similarity sort follows the filter parameters
+            filter_params.extend(self.score.map(|(_, param)| param));
             sql.push_str(" ORDER BY ");
             sql.push_str(
                 &self
@@ -76,7 +91,7 @@ impl<'a> SQLiteQueryBuilder<'a> {
             );
         }
 
-        (sql, filter_params)
+        Ok((sql, filter_params))
     }
 }
 
@@ -89,12 +104,65 @@ impl<'a> IsarQueryBuilder for SQLiteQueryBuilder<'a> {
 
     fn add_sort(&mut self, property_index: u16, sort: Sort, case_sensitive: bool) {
         self.sort.push((
-            self.all_collections[self.collection_index as usize].get_property_name(property_index),
+            Cow::Borrowed(
+                self.all_collections[self.collection_index as usize]
+                    .get_property_name(property_index),
+            ),
             sort,
             case_sensitive,
         ));
     }
 
+    fn add_distance_sort(
+        &mut self,
+        property_index: u16,
+        latitude: f64,
+        longitude: f64,
+        sort: Sort,
+    ) {
+        let collection = &self.all_collections[self.collection_index as usize];
+        let lat = collection.get_property_name(property_index);
+        let index = collection
+            .indexes
+            .iter()
+            .find(|i| i.index_type == IndexType::Geo && i.properties[0] == lat);
+        match index {
+            Some(index) if latitude.is_finite() && longitude.is_finite() => {
+                // objects without coordinates are sorted last
+                let distance = format!(
+                    "IFNULL({}({}, {}, {:?}, {:?}), 9e999)",
+                    FN_GEO_DISTANCE_NAME, lat, index.properties[1], latitude, longitude
+                );
+                self.sort.push((Cow::Owned(distance), sort, false));
+            }
+            _ => self.invalid_sort = true,
+        }
+    }
+
+    fn add_similarity_sort(&mut self, property_index: u16, vector: Vec<f32>) {
+        if self.score.is_some() {
+            return;
+        }
+        let collection = &self.all_collections[self.collection_index as usize];
+        let property_name = collection.get_property_name(property_index);
+        let options = collection
+            .indexes
+            .iter()
+            .find(|i| i.index_type == IndexType::Vector && i.properties[0] == property_name)
+            .and_then(|i| i.vector);
+        if let Some(options) = options {
+            let score_sql = vector_score_sql(property_name, options.metric);
+            // most similar first and objects without a score last
+            let sort_sql = if options.metric == VectorMetric::L2 {
+                format!("IFNULL({}, 9e999)", score_sql)
+            } else {
+                format!("IFNULL(-{}, 9e999)", score_sql)
+            };
+            self.sort.push((Cow::Owned(sort_sql), Sort::Asc, false));
+            self.score = Some((score_sql, vector_param(&vector)));
+        }
+    }
+
     fn add_distinct(&mut self, property_index: u16, case_sensitive: bool) {
         self.distinct.push((
             self.all_collections[self.collection_index as usize].get_property_name(property_index),
@@ -102,11 +170,20 @@ impl<'a> IsarQueryBuilder for SQLiteQueryBuilder<'a> {
         ));
     }
 
-    fn build(self) -> Self::Query {
+    fn build(self) -> Result<Self::Query> {
         let collection_index = self.collection_index;
         let has_sort_distinct = !self.sort.is_empty() || !self.distinct.is_empty();
-        let (sql, filter_params) = self.build_query();
-        SQLiteQuery::new(collection_index, sql, has_sort_distinct, filter_params)
+        let score = self.score.clone();
+        let has_links = self.filter.as_ref().is_some_and(|f| f.has_links());
+        let (sql, filter_params) = self.build_query()?;
+        Ok(SQLiteQuery::new(
+            collection_index,
+            sql,
+            has_sort_distinct,
+            filter_params,
+            score,
+            has_links,
+        ))
     }
 }
 
@@ -114,7 +191,9 @@ impl<'a> IsarQueryBuilder for SQLiteQueryBuilder<'a> {
 mod test {
     use super::*;
     use crate::core::data_type::DataType;
-    use crate::core::filter::{ConditionType::*, Filter::*, FilterCondition};
+    use crate::core::filter::{ConditionType::*, Filter::*, FilterCondition, FilterNested};
+    use crate::core::regex::Regex;
+    use crate::core::schema::IndexSchema;
     use crate::core::value::IsarValue;
     use crate::sqlite::sqlite_collection::SQLiteProperty;
 
@@ -134,13 +213,13 @@ mod test {
         let cols = vec![debug_col()];
         let mut qb = SQLiteQueryBuilder::new(&cols, 0);
         qb.set_filter(filter);
-        qb.build_query()
+        qb.build_query().unwrap()
     }
 
     #[test]
     fn test_build_empty() {
         let qb = SQLiteQueryBuilder::new(&[], 0);
-        let (sql, params) = qb.build_query();
+        let (sql, params) = qb.build_query().unwrap();
         assert_eq!(sql, "");
         assert_eq!(params.is_empty(), true);
     }
@@ -151,13 +230,13 @@ mod test {
 
         let mut qb = SQLiteQueryBuilder::new(&cols, 0);
         qb.add_sort(0, Sort::Asc, false);
-        let (sql, params) = qb.build_query();
+        let (sql, params) = qb.build_query().unwrap();
         assert_eq!(sql.trim(), "ORDER BY _rowid_ COLLATE BINARY");
         assert_eq!(params.is_empty(), true);
 
         let mut qb = SQLiteQueryBuilder::new(&cols, 0);
         qb.add_sort(2, Sort::Desc, true);
-        let (sql, params) = qb.build_query();
+        let (sql, params) = qb.build_query().unwrap();
         assert_eq!(sql.trim(), "ORDER BY prop2 COLLATE NOCASE DESC");
         assert_eq!(params.is_empty(), true);
     }
@@ -170,7 +249,7 @@ mod test {
         qb.add_sort(0, Sort::Asc, false);
         qb.add_sort(1, Sort::Desc, false);
         qb.add_sort(2, Sort::Asc, true);
-        let (sql, params) = qb.build_query();
+        let (sql, params) = qb.build_query().unwrap();
         assert_eq!(
             sql.trim(),
             "ORDER BY _rowid_ COLLATE BINARY, prop1 COLLATE BINARY DESC, prop2 COLLATE NOCASE"
@@ -184,13 +263,13 @@ mod test {
 
         let mut qb = SQLiteQueryBuilder::new(&cols, 0);
         qb.add_distinct(0, false);
-        let (sql, params) = qb.build_query();
+        let (sql, params) = qb.build_query().unwrap();
         assert_eq!(sql.trim(), "GROUP BY _rowid_ COLLATE BINARY");
         assert_eq!(params.is_empty(), true);
 
         let mut qb = SQLiteQueryBuilder::new(&cols, 0);
         qb.add_distinct(2, true);
-        let (sql, params) = qb.build_query();
+        let (sql, params) = qb.build_query().unwrap();
         assert_eq!(sql.trim(), "GROUP BY prop2 COLLATE NOCASE");
         assert_eq!(params.is_empty(), true);
     }
@@ -203,7 +282,7 @@ mod test {
         qb.add_distinct(0, false);
         qb.add_distinct(1, false);
         qb.add_distinct(2, true);
-        let (sql, params) = qb.build_query();
+        let (sql, params) = qb.build_query().unwrap();
         assert_eq!(
             sql.trim(),
             "GROUP BY _rowid_ COLLATE BINARY, prop1 COLLATE BINARY, prop2 COLLATE NOCASE"
@@ -511,6 +590,33 @@ mod test {
         assert_eq!(params.is_empty(), true);
     }
 
+    #[test]
+    fn test_filter_string_regex() {
+        let value = IsarValue::String("^a+b$".to_string());
+        let cond = FilterCondition::new(1, StringRegex, vec![Some(value.clone())], false);
+
+        let (sql, params) = qb_filter(Condition(cond));
+        assert_eq!(sql.trim(), "WHERE isar_filter_regex(prop1, ?)");
+        assert_eq!(
+            params,
+            vec![QueryParam::Regex(Regex::new("^a+b$", true).unwrap())]
+        );
+    }
+
+    #[test]
+    fn test_filter_string_regex_invalid() {
+        let value = IsarValue::String("(a".to_string());
+        let cond = FilterCondition::new(1, StringRegex, vec![Some(value.clone())], true);
+
+        let cols = vec![debug_col()];
+        let mut qb = SQLiteQueryBuilder::new(&cols, 0);
+        qb.set_filter(Not(Box::new(Condition(cond))));
+        assert!(matches!(
+            qb.build_query(),
+            Err(IsarError::IllegalArgument {})
+        ));
+    }
+
     #[test]
     fn test_filter_and() {
         let cond1 = FilterCondition::new(1, IsNull, vec![], true);
@@ -566,4 +672,182 @@ mod test {
         );
         assert_eq!(params.is_empty(), true);
     }
+
+    #[test]
+    fn test_filter_nested_indexed() {
+        let col = SQLiteCollection::new(
+            "col".to_string(),
+            Some("id".to_string()),
+            vec![
+                SQLiteProperty::new("address", DataType::Object, Some(1)),
+                SQLiteProperty::new("address2", DataType::Object, Some(1)),
+            ],
+            vec![IndexSchema::new("city", vec!["address.city"], false, false)],
+        );
+        let embedded_col = SQLiteCollection::new(
+            "address".to_string(),
+            None,
+            vec![SQLiteProperty::new("city", DataType::String, None)],
+            vec![],
+        );
+        let cols = vec![col, embedded_col];
+
+        let value = IsarValue::String("Berlin".to_string());
+        let cond = FilterCondition::new(1, Equal, vec![Some(value.clone())], true);
+
+        let mut qb = SQLiteQueryBuilder::new(&cols, 0);
+        qb.set_filter(Nested(FilterNested::new(1, Condition(cond.clone()))));
+        let (sql, params) = qb.build_query().unwrap();
+        assert_eq!(sql.trim(), "WHERE json_extract(address, '$.city') = ?");
+        assert_eq!(params, vec![QueryParam::Value(value)]);
+
+        let mut qb = SQLiteQueryBuilder::new(&cols, 0);
+        qb.set_filter(Nested(FilterNested::new(2, Condition(cond))));
+        let (sql, params) = qb.build_query().unwrap();
+        assert_eq!(sql.trim(), "WHERE isar_filter_json(address2, ?)");
+        assert_eq!(params.len(), 1);
+    }
+
+    #[test]
+    fn test_filter_geo() {
+        let col = SQLiteCollection::new(
+            "col".to_string(),
+            Some("id".to_string()),
+            vec![
+                SQLiteProperty::new("lat", DataType::Double, None),
+                SQLiteProperty::new("lon", DataType::Double, None),
+            ],
+            vec![IndexSchema::geo("loc", "lat", "lon")],
+        );
+        let cols = vec![col];
+        let reals = |values: &[f64]| {
+            values
+                .iter()
+                .map(|v| Some(IsarValue::Real(*v)))
+                .collect::<Vec<_>>()
+        };
+
+        let cond = FilterCondition::new(1, GeoWithinBox, reals(&[0.0, 1.0, 2.0, 3.0]), false);
+        let mut qb = SQLiteQueryBuilder::new(&cols, 0);
+        qb.set_filter(Condition(cond));
+        let (sql, params) = qb.build_query().unwrap();
+        assert_eq!(
+            sql.trim(),
+            "WHERE (_rowid_ IN (SELECT id FROM col_loc WHERE lat_max >= ? AND lat_min <= ? \
+            AND lon_max >= ? AND lon_min <= ?) AND lat BETWEEN ? AND ? AND lon BETWEEN ? AND ?)"
+        );
+        assert_eq!(params.len(), 8);
+
+        // crossing the antimeridian
+        let cond = FilterCondition::new(1, GeoWithinBox, reals(&[0.0, 170.0, 2.0, -170.0]), false);
+        let mut qb = SQLiteQueryBuilder::new(&cols, 0);
+        qb.set_filter(Condition(cond));
+        let (sql, params) = qb.build_query().unwrap();
+        assert_eq!(
+            sql.trim(),
+            "WHERE (lat BETWEEN ? AND ? AND (lon >= ? OR lon <= ?))"
+        );
+        assert_eq!(params.len(), 4);
+
+        let cond = FilterCondition::new(1, GeoWithinRadius, reals(&[1.0, 2.0, 100.0]), false);
+        let mut qb = SQLiteQueryBuilder::new(&cols, 0);
+        qb.set_filter(Condition(cond));
+        let (sql, params) = qb.build_query().unwrap();
+        assert!(sql.ends_with("AND isar_geo_distance(lat, lon, ?, ?) <= ?)"));
+        assert_eq!(params.len(), 7);
+
+        // not a geo index
+        let cond = FilterCondition::new(2, GeoWithinRadius, reals(&[1.0, 2.0, 100.0]), false);
+        let mut qb = SQLiteQueryBuilder::new(&cols, 0);
+        qb.set_filter(Or(vec![Condition(cond)]));
+        assert!(matches!(
+            qb.build_query(),
+            Err(IsarError::IllegalArgument {})
+        ));
+    }
+
+    #[test]
+    fn test_build_distance_sort() {
+        let col = SQLiteCollection::new(
+            "col".to_string(),
+            Some("id".to_string()),
+            vec![
+                SQLiteProperty::new("lat", DataType::Double, None),
+                SQLiteProperty::new("lon", DataType::Double, None),
+            ],
+            vec![IndexSchema::geo("loc", "lat", "lon")],
+        );
+        let cols = vec![col];
+
+        let mut qb = SQLiteQueryBuilder::new(&cols, 0);
+        qb.add_distance_sort(1, 52.5, 13.4, Sort::Asc);
+        let (sql, params) = qb.build_query().unwrap();
+        assert_eq!(
+            sql.trim(),
+            "ORDER BY IFNULL(isar_geo_distance(lat, lon, 52.5, 13.4), 9e999) COLLATE BINARY"
+        );
+        assert_eq!(params.is_empty(), true);
+
+        // not a geo index
+        let mut qb = SQLiteQueryBuilder::new(&cols, 0);
+        qb.add_distance_sort(2, 52.5, 13.4, Sort::Asc);
+        assert!(matches!(
+            qb.build_query(),
+            Err(IsarError::IllegalArgument {})
+        ));
+
+        let mut qb = SQLiteQueryBuilder::new(&cols, 0);
+        qb.add_distance_sort(1, f64::NAN, 13.4, Sort::Asc);
+        assert!(matches!(
+            qb.build_query(),
+            Err(IsarError::IllegalArgument {})
+        ));
+    }
+
+    #[test]
+    fn test_build_similarity_sort() {
+        let col = SQLiteCollection::new(
+            "col".to_string(),
+            Some("id".to_string()),
+            vec![SQLiteProperty::new("embedding", DataType::FloatList, None)],
+            vec![IndexSchema::vector(
+                "embedding",
+                "embedding",
+                2,
+                VectorMetric::Cosine,
+            )],
+        );
+        let cols = vec![col];
+
+        let mut qb = SQLiteQueryBuilder::new(&cols, 0);
+        qb.add_similarity_sort(1, vec![1.0, 0.0]);
+        qb.add_similarity_sort(1, vec![0.0, 1.0]);
+        let vector = vector_param(&[1.0, 0.0]);
+        assert_eq!(
+            qb.score,
+            Some((
+                "isar_vector_score(embedding, ?, 0)".to_string(),
+                vector.clone()
+            ))
+        );
+        let (sql, params) = qb.build_query().unwrap();
+        assert_eq!(
+            sql.trim(),
+            "ORDER BY IFNULL(-isar_vector_score(embedding, ?, 0), 9e999) COLLATE BINARY"
+        );
+        assert_eq!(params, vec![vector.clone()]);
+
+        // the filter parameters come first
+        let mut qb = SQLiteQueryBuilder::new(&cols, 0);
+        qb.set_filter(Condition(FilterCondition::new(1, IsNull, vec![], false)));
+        qb.add_similarity_sort(1, vec![1.0, 0.0]);
+        let (sql, params) = qb.build_query().unwrap();
+        assert_eq!(
+            sql.trim(),
+            "WHERE isar_filter_json(embedding, ?) \
+            ORDER BY IFNULL(-isar_vector_score(embedding, ?, 0), 9e999) COLLATE BINARY"
+        );
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[1], vector);
+    }
 }
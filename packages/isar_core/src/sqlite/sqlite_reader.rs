@@ -1,5 +1,7 @@
-use super::sqlite3::SQLiteStatement;
+use super::sql::BLOB_TABLE;
+use super::sqlite3::{SQLiteBlob, SQLiteStatement};
 use super::sqlite_collection::SQLiteCollection;
+use crate::core::value::{DateTimeOffset, Decimal, Uuid};
 use crate::core::{data_type::DataType, reader::IsarReader};
 use base64::{engine::general_purpose, Engine as _};
 use serde_json::{Map, Value};
@@ -10,6 +12,7 @@ pub struct SQLiteReader<'a> {
     stmt: Cow<'a, SQLiteStatement<'a>>,
     collection: &'a SQLiteCollection,
     all_collections: &'a [SQLiteCollection],
+    score_column: Option<u32>,
 }
 
 impl<'a> SQLiteReader<'a> {
@@ -22,8 +25,23 @@ impl<'a> SQLiteReader<'a> {
             stmt,
             collection,
             all_collections,
+            score_column: None,
         }
     }
+
+    pub(crate) fn with_score_column(mut self, score_column: Option<u32>) -> Self {
+        self.score_column = score_column;
+        self
+    }
+
+    fn open_blob(&self, index: u32) -> Option<SQLiteBlob<'a>> {
+        let property = self.collection.get_property(index as u16)?;
+        if property.data_type != DataType::Blob || self.is_null(index) {
+            return None;
+        }
+        let rowid = self.stmt.get_long(index);
+        self.stmt.sqlite().open_blob(BLOB_TABLE, "data", rowid).ok()
+    }
 }
 
 impl<'a> IsarReader for SQLiteReader<'a> {
@@ -101,15 +119,63 @@ impl<'a> IsarReader for SQLiteReader<'a> {
         }
     }
 
+    fn read_decimal(&self, index: u32) -> Option<Decimal> {
+        if self.is_null(index) {
+            None
+        } else {
+            Decimal::from_bytes(self.stmt.get_blob(index))
+        }
+    }
+
+    fn read_uuid(&self, index: u32) -> Option<Uuid> {
+        if self.is_null(index) {
+            None
+        } else {
+            Uuid::from_bytes(self.stmt.get_blob(index))
+        }
+    }
+
+    fn read_date_time_offset(&self, index: u32) -> Option<DateTimeOffset> {
+        if self.is_null(index) {
+            None
+        } else {
+            DateTimeOffset::from_bytes(self.stmt.get_blob(index))
+        }
+    }
+
     fn read_blob(&self, index: u32) -> Option<Cow<[u8]>> {
         if self.is_null(index) {
             None
+        } else if let Some(blob) = self.open_blob(index) {
+            let mut bytes = vec![0; blob.size()];
+            blob.read(0, &mut bytes).ok()?;
+            Some(Cow::Owned(bytes))
         } else {
             let bytes = self.stmt.get_blob(index);
             Some(Cow::Borrowed(bytes))
         }
     }
 
+    fn read_blob_length(&self, index: u32) -> Option<usize> {
+        if let Some(blob) = self.open_blob(index) {
+            Some(blob.size())
+        } else {
+            self.read_blob(index).map(|blob| blob.len())
+        }
+    }
+
+    fn read_blob_chunk(&self, index: u32, offset: usize, buffer: &mut [u8]) -> Option<usize> {
+        if let Some(blob) = self.open_blob(index) {
+            blob.read(offset, buffer).ok()
+        } else {
+            let blob = self.read_blob(index)?;
+            let chunk = blob.get(offset..).unwrap_or_default();
+            let length = chunk.len().min(buffer.len());
+            buffer[..length].copy_from_slice(&chunk[..length]);
+            Some(length)
+        }
+    }
+
     fn read_object(&self, index: u32) -> Option<Self::ObjectReader<'a>> {
         if let Some(property) = self.collection.get_property(index as u16) {
             if let Some(collection_index) = property.collection_index {
@@ -143,6 +209,30 @@ impl<'a> IsarReader for SQLiteReader<'a> {
         }
         None
     }
+
+    fn read_map(&self, index: u32) -> Option<(Vec<Cow<'_, str>>, Self::ListReader<'_>)> {
+        let property = self.collection.get_property(index as u16)?;
+        let text = self.stmt.get_text(index);
+        if let Ok(Value::Object(map)) = serde_json::from_str(text) {
+            let (keys, values) = map.into_iter().map(|(k, v)| (Cow::Owned(k), v)).unzip();
+            let list_reader = SQLiteListReader {
+                list: Cow::Owned(values),
+                collection_index: property.collection_index,
+                all_collections: self.all_collections,
+            };
+            return Some((keys, list_reader));
+        }
+        None
+    }
+
+    fn read_score(&self) -> Option<f64> {
+        let column = self.score_column?;
+        if self.stmt.is_null(column) {
+            None
+        } else {
+            Some(self.stmt.get_double(column))
+        }
+    }
 }
 
 pub struct SQLiteObjectReader<'a> {
@@ -239,6 +329,18 @@ impl<'a> IsarReader for SQLiteObjectReader<'a> {
         None
     }
 
+    fn read_decimal(&self, index: u32) -> Option<Decimal> {
+        self.read_string(index)?.parse().ok()
+    }
+
+    fn read_uuid(&self, index: u32) -> Option<Uuid> {
+        self.read_string(index)?.parse().ok()
+    }
+
+    fn read_date_time_offset(&self, index: u32) -> Option<DateTimeOffset> {
+        self.read_string(index)?.parse().ok()
+    }
+
     fn read_blob(&self, index: u32) -> Option<Cow<'a, [u8]>> {
         if let Some(property) = self.collection.get_property(index as u16) {
             if let Some(Value::String(val)) = self.object.get(&property.name) {
@@ -279,6 +381,23 @@ impl<'a> IsarReader for SQLiteObjectReader<'a> {
         }
         None
     }
+
+    fn read_map(&self, index: u32) -> Option<(Vec<Cow<'_, str>>, Self::ListReader<'_>)> {
+        let property = self.collection.get_property(index as u16)?;
+        if let Some(Value::Object(map)) = self.object.get(&property.name) {
+            let (keys, values) = map
+                .iter()
+                .map(|(k, v)| (Cow::Borrowed(k.as_str()), v.clone()))
+                .unzip();
+            let list_reader = SQLiteListReader {
+                list: Cow::Owned(values),
+                collection_index: property.collection_index,
+                all_collections: self.all_collections,
+            };
+            return Some((keys, list_reader));
+        }
+        None
+    }
 }
 
 pub struct SQLiteListReader<'a> {
@@ -361,6 +480,18 @@ impl<'a> IsarReader for SQLiteListReader<'a> {
         }
     }
 
+    fn read_decimal(&self, _index: u32) -> Option<Decimal> {
+        None
+    }
+
+    fn read_uuid(&self, _index: u32) -> Option<Uuid> {
+        None
+    }
+
+    fn read_date_time_offset(&self, _index: u32) -> Option<DateTimeOffset> {
+        None
+    }
+
     fn read_blob(&self, _index: u32) -> Option<Cow<'a, [u8]>> {
         None
     }
@@ -382,4 +513,8 @@ impl<'a> IsarReader for SQLiteListReader<'a> {
     fn read_list(&self, _: u32) -> Option<(Self::ListReader<'_>, u32)> {
         None
     }
+
+    fn read_map(&self, _: u32) -> Option<(Vec<Cow<'_, str>>, Self::ListReader<'_>)> {
+        None
+    }
 }
@@ -11,17 +11,24 @@ pub struct SQLiteTxn {
     write: bool,
     sqlite: Rc<SQLite3>,
     active: Cell<bool>,
+    savepoints: u32,
     change_set: Rc<RefCell<ChangeSet>>,
+    // the change set at the start of each savepoint
+    savepoint_change_sets: Vec<ChangeSet>,
 }
 
 impl SQLiteTxn {
     pub(crate) fn new(sqlite: Rc<SQLite3>, write: bool) -> Result<SQLiteTxn> {
-        sqlite.prepare("BEGIN")?.step()?;
+        // write transactions take the write lock immediately so they can't fail to upgrade later
+        let sql = if write { "BEGIN IMMEDIATE" } else { "BEGIN" };
+        sqlite.retry_busy(|| sqlite.prepare_cached(sql)?.step())?;
         let txn = SQLiteTxn {
             write,
             sqlite: sqlite,
             active: Cell::new(true),
+            savepoints: 0,
             change_set: Rc::new(RefCell::new(ChangeSet::new())),
+            savepoint_change_sets: vec![],
         };
         Ok(txn)
     }
@@ -49,12 +56,65 @@ impl SQLiteTxn {
         }
         let result = job();
         if !result.is_ok() {
-            self.sqlite.prepare("ROLLBACK")?.step()?;
-            self.active.replace(false);
+            if let Some(change_set) = self.savepoint_change_sets.last() {
+                // only the innermost savepoint is lost
+                let sql = format!("ROLLBACK TO {}", Self::savepoint_name(self.savepoints));
+                self.sqlite.prepare_cached(&sql)?.step()?;
+                self.change_set.replace(change_set.clone());
+            } else {
+                self.sqlite.prepare_cached("ROLLBACK")?.step()?;
+                self.active.replace(false);
+            }
         }
         result
     }
 
+    fn savepoint_name(savepoint: u32) -> String {
+        format!("isar_savepoint_{}", savepoint)
+    }
+
+    pub(crate) fn begin_savepoint(&mut self) -> Result<()> {
+        self.get_sqlite(true)?;
+        let sql = format!("SAVEPOINT {}", Self::savepoint_name(self.savepoints + 1));
+        self.sqlite.prepare_cached(&sql)?.step()?;
+        self.savepoints += 1;
+        self.savepoint_change_sets
+            .push(self.change_set.borrow().clone());
+        Ok(())
+    }
+
+    pub(crate) fn release_savepoint(&mut self) -> Result<()> {
+        self.get_sqlite(true)?;
+        if self.savepoints == 0 {
+            return Err(IsarError::IllegalArgument {});
+        }
+        let sql = format!("RELEASE {}", Self::savepoint_name(self.savepoints));
+        self.sqlite.prepare_cached(&sql)?.step()?;
+        self.savepoints -= 1;
+        self.savepoint_change_sets.pop();
+        Ok(())
+    }
+
+    pub(crate) fn rollback_to_savepoint(&mut self) -> Result<()> {
+        self.get_sqlite(true)?;
+        if self.savepoints == 0 {
+            return Err(IsarError::IllegalArgument {});
+        }
+        let name = Self::savepoint_name(self.savepoints);
+        // ROLLBACK TO keeps the savepoint open
+        self.sqlite
+            .prepare_cached(&format!("ROLLBACK TO {}", name))?
+            .step()?;
+        self.sqlite
+            .prepare_cached(&format!("RELEASE {}", name))?
+            .step()?;
+        self.savepoints -= 1;
+        if let Some(change_set) = self.savepoint_change_sets.pop() {
+            self.change_set.replace(change_set);
+        }
+        Ok(())
+    }
+
     pub(crate) fn monitor_changes(&self, watchers: &Arc<CollectionWatchers<SQLiteQuery>>) {
         if watchers.has_watchers() {
             let change_set = self.change_set.clone();
@@ -75,7 +135,7 @@ impl SQLiteTxn {
         if !self.active.get() {
             return Err(IsarError::TransactionClosed {});
         }
-        self.sqlite.prepare("COMMIT")?.step()?;
+        self.sqlite.prepare_cached("COMMIT")?.step()?;
         self.sqlite.clear_update_hook();
         self.change_set.borrow_mut().notify_watchers();
         Ok(())
@@ -84,10 +144,80 @@ impl SQLiteTxn {
     pub(crate) fn abort(&self) {
         if self.active.get() {
             self.sqlite.clear_update_hook();
-            let stmt = self.sqlite.prepare("ROLLBACK");
+            let stmt = self.sqlite.prepare_cached("ROLLBACK");
             if let Ok(mut stmt) = stmt {
                 let _ = stmt.step();
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlite::sqlite3::BusyPolicy;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn ids(sqlite: &SQLite3) -> Vec<i64> {
+        let mut stmt = sqlite.prepare("SELECT id FROM test ORDER BY id").unwrap();
+        let mut ids = vec![];
+        while stmt.step().unwrap() {
+            ids.push(stmt.get_long(0));
+        }
+        ids
+    }
+
+    #[test]
+    fn test_savepoints() {
+        let sqlite = SQLite3::open(":memory:", None, BusyPolicy::default()).unwrap();
+        let sqlite = Rc::new(sqlite);
+        let insert = |id: i64| {
+            let sql = format!("INSERT INTO test VALUES ({})", id);
+            sqlite.prepare(&sql)?.step()
+        };
+        sqlite
+            .prepare("CREATE TABLE test (id INTEGER PRIMARY KEY)")
+            .unwrap()
+            .step()
+            .unwrap();
+
+        let notified = Arc::new(AtomicU32::new(0));
+        let watchers = CollectionWatchers::<SQLiteQuery>::new();
+        let notified_clone = notified.clone();
+        let _handle = watchers.watch(Box::new(move || {
+            notified_clone.fetch_add(1, Ordering::SeqCst);
+        }));
+
+        let mut txn = SQLiteTxn::new(sqlite.clone(), true).unwrap();
+        insert(1).unwrap();
+        txn.begin_savepoint().unwrap();
+        insert(2).unwrap();
+        txn.begin_savepoint().unwrap();
+        insert(3).unwrap();
+        txn.change_set.borrow_mut().register_all(&watchers);
+        txn.rollback_to_savepoint().unwrap();
+        txn.release_savepoint().unwrap();
+        assert!(txn.release_savepoint().is_err());
+
+        // a failing job only rolls back the innermost savepoint
+        txn.begin_savepoint().unwrap();
+        let result = txn.guard(|| {
+            insert(4)?;
+            txn.change_set.borrow_mut().register_all(&watchers);
+            insert(1)
+        });
+        assert!(result.is_err());
+        txn.release_savepoint().unwrap();
+        txn.commit().unwrap();
+        assert_eq!(notified.load(Ordering::SeqCst), 0);
+
+        assert_eq!(ids(&sqlite), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_savepoint_requires_write_txn() {
+        let sqlite = SQLite3::open(":memory:", None, BusyPolicy::default()).unwrap();
+        let mut txn = SQLiteTxn::new(Rc::new(sqlite), false).unwrap();
+        assert_eq!(txn.begin_savepoint(), Err(IsarError::WriteTxnRequired {}));
+    }
+}
@@ -1,6 +1,9 @@
+use super::sql::{create_index_sql, sql_data_type, BLOB_TABLE};
+use super::sqlite3::SQLite3;
 use super::sqlite_collection::{SQLiteCollection, SQLiteProperty};
-use super::{sql::sql_data_type, sqlite3::SQLite3};
+use crate::core::data_type::DataType;
 use crate::core::error::{IsarError, Result};
+use crate::core::schema::IndexType;
 
 pub(crate) fn verify_sqlite(sqlite: &SQLite3, cols: &[SQLiteCollection]) -> Result<()> {
     let mut table_names = vec![];
@@ -10,12 +13,26 @@ pub(crate) fn verify_sqlite(sqlite: &SQLite3, cols: &[SQLiteCollection]) -> Resu
             table_names.push(col.name.clone());
         }
     }
+    let mut link_table_names = vec![];
+    for col in cols {
+        for link in col.links.iter().filter(|l| !l.backlink) {
+            link_table_names.push(link.table.clone());
+        }
+    }
     let mut actual_table_names = sqlite.get_table_names()?;
 
-    table_names.sort();
+    let mut all_table_names = [table_names.clone(), link_table_names].concat();
+    // the blob table is kept even if all blob properties are removed
+    let has_blobs = cols
+        .iter()
+        .any(|c| c.properties.iter().any(|p| p.data_type == DataType::Blob));
+    if has_blobs || actual_table_names.iter().any(|t| t == BLOB_TABLE) {
+        all_table_names.push(BLOB_TABLE.to_string());
+    }
+    all_table_names.sort();
     actual_table_names.sort();
 
-    if table_names != actual_table_names {
+    if all_table_names != actual_table_names {
         return Err(IsarError::DbCorrupted {});
     }
 
@@ -57,14 +74,37 @@ pub(crate) fn verify_sqlite(sqlite: &SQLite3, cols: &[SQLiteCollection]) -> Resu
         }
 
         let indexes = sqlite.get_table_indexes(&table)?;
-        if indexes.len() != collection.indexes.len() {
+        let geo_indexes = sqlite.get_table_geo_indexes(&table)?;
+        let vector_indexes = collection
+            .indexes
+            .iter()
+            .filter(|i| i.index_type == IndexType::Vector)
+            .count();
+        if indexes.len() + geo_indexes.len() + vector_indexes != collection.indexes.len() {
             return Err(IsarError::DbCorrupted {});
         }
 
-        for (index, unique, cols) in indexes {
+        for (rtree, cols) in geo_indexes {
+            let name = rtree.strip_prefix(&format!("{}_", table));
+            let index = collection.indexes.iter().find(|i| {
+                Some(i.name.as_str()) == name
+                    && i.index_type == IndexType::Geo
+                    && cols.len() == 5
+                    && cols[1] == format!("{}_min", i.properties[0])
+                    && cols[3] == format!("{}_min", i.properties[1])
+            });
+
+            if index.is_none() {
+                return Err(IsarError::DbCorrupted {});
+            }
+        }
+
+        for (index, _, _, sql) in indexes {
             let name = index.strip_prefix(&format!("{}_", table));
             let index = collection.indexes.iter().find(|i| {
-                Some(i.name.as_str()) == name && i.unique == unique && i.properties == cols
+                Some(i.name.as_str()) == name
+                    && i.index_type == IndexType::Value
+                    && create_index_sql(&table, i) == sql
             });
 
             if index.is_none() {
@@ -1,15 +1,28 @@
+use super::sql::insert_blob_sql;
 use super::sqlite_collection::SQLiteCollection;
 use super::sqlite_insert::SQLiteInsert;
+use crate::core::value::{DateTimeOffset, Decimal, IsarValue, Uuid};
 use crate::core::{data_type::DataType, writer::IsarWriter};
 use base64::{engine::general_purpose, Engine};
 use serde_json::{Map, Number, Value};
 use std::iter::empty;
 
 impl<'a> SQLiteInsert<'a> {
-    fn property_index(&self, index: u32) -> u32 {
+    pub(crate) fn property_index(&self, index: u32) -> u32 {
         (self.batch_size - self.batch_remaining) * (self.collection.properties.len() as u32 + 1)
             + index
     }
+
+    #[inline]
+    fn set_value(&mut self, index: u32, value: impl FnOnce() -> Option<IsarValue>) {
+        let constraints = &self.collection.constraints;
+        if let Some(i) = constraints
+            .iter()
+            .position(|c| c.property_index as u32 == index)
+        {
+            self.values[i] = value();
+        }
+    }
 }
 
 impl<'a> IsarWriter<'a> for SQLiteInsert<'a> {
@@ -29,21 +42,27 @@ impl<'a> IsarWriter<'a> for SQLiteInsert<'a> {
     }
 
     fn write_null(&mut self, index: u32) {
+        self.set_value(index, || None);
         let col = self.property_index(index);
         let _ = self.with_stmt(|stmt| stmt.bind_null(col));
     }
 
     fn write_bool(&mut self, index: u32, value: bool) {
+        self.set_value(index, || Some(IsarValue::Bool(value)));
         let col = self.property_index(index);
         let _ = self.with_stmt(|stmt| stmt.bind_int(col, value as i32));
     }
 
     fn write_byte(&mut self, index: u32, value: u8) {
+        self.set_value(index, || Some(IsarValue::Integer(value as i64)));
         let col = self.property_index(index);
         let _ = self.with_stmt(|stmt| stmt.bind_int(col, value as i32));
     }
 
     fn write_int(&mut self, index: u32, value: i32) {
+        self.set_value(index, || {
+            (value != i32::MIN).then_some(IsarValue::Integer(value as i64))
+        });
         let col = self.property_index(index);
         let _ = self.with_stmt(|stmt| {
             if value != i32::MIN {
@@ -55,6 +74,9 @@ impl<'a> IsarWriter<'a> for SQLiteInsert<'a> {
     }
 
     fn write_float(&mut self, index: u32, value: f32) {
+        self.set_value(index, || {
+            (!value.is_nan()).then_some(IsarValue::Real(value as f64))
+        });
         let col = self.property_index(index);
         let _ = self.with_stmt(|stmt| {
             if !value.is_nan() {
@@ -66,6 +88,9 @@ impl<'a> IsarWriter<'a> for SQLiteInsert<'a> {
     }
 
     fn write_long(&mut self, index: u32, value: i64) {
+        self.set_value(index, || {
+            (value != i64::MIN).then_some(IsarValue::Integer(value))
+        });
         let col = self.property_index(index);
         let _ = self.with_stmt(|stmt| {
             if value != i64::MIN {
@@ -77,6 +102,9 @@ impl<'a> IsarWriter<'a> for SQLiteInsert<'a> {
     }
 
     fn write_double(&mut self, index: u32, value: f64) {
+        self.set_value(index, || {
+            (!value.is_nan()).then_some(IsarValue::Real(value))
+        });
         let col = self.property_index(index);
         let _ = self.with_stmt(|stmt| {
             if !value.is_nan() {
@@ -88,15 +116,56 @@ impl<'a> IsarWriter<'a> for SQLiteInsert<'a> {
     }
 
     fn write_string(&mut self, index: u32, value: &str) {
+        self.set_value(index, || Some(IsarValue::String(value.to_string())));
         let col = self.property_index(index);
         let _ = self.with_stmt(|stmt| stmt.bind_text(col, value));
     }
 
+    fn write_decimal(&mut self, index: u32, value: Decimal) {
+        self.set_value(index, || Some(IsarValue::Decimal(value)));
+        let col = self.property_index(index);
+        let _ = self.with_stmt(|stmt| stmt.bind_blob(col, &value.to_bytes()));
+    }
+
+    fn write_uuid(&mut self, index: u32, value: Uuid) {
+        self.set_value(index, || Some(IsarValue::Uuid(value)));
+        let col = self.property_index(index);
+        let _ = self.with_stmt(|stmt| stmt.bind_blob(col, &value.to_bytes()));
+    }
+
+    fn write_date_time_offset(&mut self, index: u32, value: DateTimeOffset) {
+        self.set_value(index, || Some(IsarValue::DateTimeOffset(value)));
+        let col = self.property_index(index);
+        let _ = self.with_stmt(|stmt| stmt.bind_blob(col, &value.to_bytes()));
+    }
+
     fn write_byte_list(&mut self, index: u32, value: &[u8]) {
+        // only the presence of byte lists is constrained
+        self.set_value(index, || Some(IsarValue::Bool(true)));
         let col = self.property_index(index);
         let _ = self.with_stmt(|stmt| stmt.bind_blob(col, value));
     }
 
+    fn write_blob(&mut self, index: u32, value: &[u8]) {
+        let is_blob = self
+            .collection
+            .get_property(index as u16)
+            .is_some_and(|p| p.data_type == DataType::Blob);
+        if !is_blob {
+            return;
+        }
+
+        self.set_value(index, || Some(IsarValue::Bool(true)));
+        let col = self.property_index(index);
+        let _ = self.with_stmt(|stmt| {
+            let sqlite = stmt.sqlite();
+            let mut insert = sqlite.prepare_cached(&insert_blob_sql())?;
+            insert.bind_blob(0, value)?;
+            insert.step()?;
+            stmt.bind_long(col, sqlite.last_insert_rowid())
+        });
+    }
+
     fn begin_object<'b>(&mut self, index: u32) -> Option<Self::ObjectWriter> {
         let property = self.collection.get_property(index as u16)?;
         if let Some(collection_index) = property.collection_index {
@@ -129,12 +198,22 @@ impl<'a> IsarWriter<'a> for SQLiteInsert<'a> {
     }
 
     fn end_list<'b>(&'b mut self, writer: Self::ListWriter) {
-        if let Ok(json) = serde_json::to_string(&writer.list) {
-            self.write_string(writer.property_index as u32, &json);
+        let property_index = writer.property_index as u32;
+        let value = writer.into_value();
+        if let Ok(json) = serde_json::to_string(&value) {
+            self.write_string(property_index, &json);
+            // lists are constrained by their length
+            let length = value.as_array().map_or(0, |list| list.len());
+            self.set_value(property_index, || Some(IsarValue::Integer(length as i64)));
         } else {
-            self.write_null(writer.property_index as u32);
+            self.write_null(property_index);
         }
     }
+
+    fn begin_map(&mut self, index: u32, keys: &[&str]) -> Option<Self::ListWriter> {
+        let writer = self.begin_list(index, keys.len() as u32)?;
+        Some(writer.with_keys(keys))
+    }
 }
 
 pub struct SQLiteObjectWriter<'a> {
@@ -239,12 +318,26 @@ impl<'a> IsarWriter<'a> for SQLiteObjectWriter<'a> {
         }
     }
 
+    fn write_decimal(&mut self, index: u32, value: Decimal) {
+        self.write_string(index, &value.to_string());
+    }
+
+    fn write_uuid(&mut self, index: u32, value: Uuid) {
+        self.write_string(index, &value.to_string());
+    }
+
+    fn write_date_time_offset(&mut self, index: u32, value: DateTimeOffset) {
+        self.write_string(index, &value.to_string());
+    }
+
     fn write_byte_list(&mut self, index: u32, value: &[u8]) {
         let mut string = String::new();
         let _ = general_purpose::STANDARD_NO_PAD.encode_string(value, &mut string);
         self.write_string(index, &string);
     }
 
+    fn write_blob(&mut self, _index: u32, _value: &[u8]) {}
+
     fn begin_object(&mut self, index: u32) -> Option<Self::ObjectWriter> {
         if let Some(property) = self.collection.get_property(index as u16) {
             if let Some(collection_index) = property.collection_index {
@@ -280,10 +373,14 @@ impl<'a> IsarWriter<'a> for SQLiteObjectWriter<'a> {
 
     fn end_list<'b>(&'b mut self, writer: Self::ListWriter) {
         if let Some(property) = self.collection.get_property(writer.property_index) {
-            self.map
-                .insert(property.name.clone(), Value::Array(writer.list));
+            self.map.insert(property.name.clone(), writer.into_value());
         }
     }
+
+    fn begin_map(&mut self, index: u32, keys: &[&str]) -> Option<Self::ListWriter> {
+        let writer = self.begin_list(index, keys.len() as u32)?;
+        Some(writer.with_keys(keys))
+    }
 }
 
 pub struct SQLiteListWriter<'a> {
@@ -291,6 +388,7 @@ pub struct SQLiteListWriter<'a> {
     all_collections: &'a Vec<SQLiteCollection>,
     property_index: u16,
     list: Vec<Value>,
+    keys: Option<Vec<String>>,
 }
 
 impl<'a> SQLiteListWriter<'a> {
@@ -305,6 +403,21 @@ impl<'a> SQLiteListWriter<'a> {
             all_collections,
             property_index,
             list: vec![Value::Null; length as usize],
+            keys: None,
+        }
+    }
+
+    fn with_keys(mut self, keys: &[&str]) -> Self {
+        self.keys = Some(keys.iter().map(|key| key.to_string()).collect());
+        self
+    }
+
+    /// The JSON array of the list or the JSON object of the map.
+    fn into_value(self) -> Value {
+        if let Some(keys) = self.keys {
+            Value::Object(keys.into_iter().zip(self.list).collect())
+        } else {
+            Value::Array(self.list)
         }
     }
 }
@@ -398,8 +511,16 @@ impl<'a> IsarWriter<'a> for SQLiteListWriter<'a> {
         }
     }
 
+    fn write_decimal(&mut self, _index: u32, _value: Decimal) {}
+
+    fn write_uuid(&mut self, _index: u32, _value: Uuid) {}
+
+    fn write_date_time_offset(&mut self, _index: u32, _value: DateTimeOffset) {}
+
     fn write_byte_list(&mut self, _index: u32, _value: &[u8]) {}
 
+    fn write_blob(&mut self, _index: u32, _value: &[u8]) {}
+
     fn begin_object(&mut self, index: u32) -> Option<Self::ObjectWriter> {
         if let Some(collection_index) = self.collection_index {
             let target_collection = &self.all_collections[collection_index as usize];
@@ -423,4 +544,8 @@ impl<'a> IsarWriter<'a> for SQLiteListWriter<'a> {
     }
 
     fn end_list<'b>(&'b mut self, _writer: Self::ListWriter) {}
+
+    fn begin_map(&mut self, _index: u32, _keys: &[&str]) -> Option<Self::ListWriter> {
+        None
+    }
 }
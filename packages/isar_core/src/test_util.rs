@@ -0,0 +1,16 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+// instances are registered by id for the whole process so tests running in parallel must never
+// share an id
+static NEXT_INSTANCE_ID: AtomicU32 = AtomicU32::new(1);
+
+pub(crate) fn next_instance_id() -> u32 {
+    NEXT_INSTANCE_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Returns a directory for the files of this test process.
+pub(crate) fn test_dir() -> String {
+    let dir = std::env::temp_dir().join(format!("isar_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.to_str().unwrap().to_string()
+}
@@ -0,0 +1,597 @@
+use crate::dart::{dart_post_int, DartPort};
+use crate::instance::{import_options_from_c, import_report_value, json_file_reader};
+use crate::query::{aggregation_from_c, json_file_writer};
+use crate::{dart_fast_hash, CIsarInstance, CIsarQuery, CIsarTxn};
+use isar_core::core::cursor::IsarQueryCursor;
+use isar_core::core::error::{IsarError, Result};
+use isar_core::core::instance::{IsarInstance, JsonFormat};
+use isar_core::core::reader::IsarReader;
+use isar_core::core::value::IsarValue;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+
+#[cfg(feature = "native")]
+use isar_core::native::native_instance::NativeInstance;
+
+#[cfg(feature = "sqlite")]
+use isar_core::sqlite::sqlite_instance::SQLiteInstance;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+static WORKERS: OnceLock<Sender<Job>> = OnceLock::new();
+
+const MAX_WORKERS: usize = 4;
+
+/// Result of an asynchronous operation. A pointer to it is posted to the Dart port once the
+/// operation completed and it has to be consumed using `isar_async_result` or released using
+/// `isar_async_result_free`.
+pub struct CIsarAsyncResult(Result<AsyncValue>);
+
+enum AsyncValue {
+    Integer(i64),
+    Value(Option<IsarValue>),
+}
+
+fn spawn_worker(receiver: Arc<Mutex<mpsc::Receiver<Job>>>, index: usize) {
+    thread::Builder::new()
+        .name(format!("isar-worker-{index}"))
+        .spawn(move || loop {
+            let job = receiver.lock().unwrap().recv();
+            match job {
+                Ok(job) => job(),
+                Err(_) => break,
+            }
+        })
+        .unwrap();
+}
+
+fn run_async(port: DartPort, job: impl FnOnce() -> Result<AsyncValue> + Send + 'static) {
+    let workers = WORKERS.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let count = thread::available_parallelism().map_or(1, |n| n.get().min(MAX_WORKERS));
+        for index in 0..count {
+            spawn_worker(receiver.clone(), index);
+        }
+        sender
+    });
+    let _ = workers.send(Box::new(move || {
+        let result = Box::into_raw(Box::new(CIsarAsyncResult(job())));
+        if !unsafe { dart_post_int(port, result as i64) } {
+            drop(unsafe { Box::from_raw(result) });
+        }
+    }));
+}
+
+fn get_instance(instance_id: u32, sqlite: bool) -> Result<CIsarInstance> {
+    let instance = if sqlite {
+        #[cfg(feature = "sqlite")]
+        {
+            SQLiteInstance::get_instance(instance_id).map(CIsarInstance::SQLite)
+        }
+        #[cfg(not(feature = "sqlite"))]
+        {
+            None
+        }
+    } else {
+        #[cfg(feature = "native")]
+        {
+            NativeInstance::get_instance(instance_id).map(CIsarInstance::Native)
+        }
+        #[cfg(not(feature = "native"))]
+        {
+            None
+        }
+    };
+    instance.ok_or(IsarError::IllegalArgument {})
+}
+
+/// Runs the job in its own transaction on the calling thread. Write transactions are committed
+/// if the job succeeds and aborted otherwise, the connection is returned to the instance
+/// afterwards. Jobs that consume the transaction have to put it back.
+fn run_txn(
+    instance_id: u32,
+    sqlite: bool,
+    write: bool,
+    job: impl FnOnce(&CIsarInstance, &mut Option<CIsarTxn>) -> Result<AsyncValue>,
+) -> Result<AsyncValue> {
+    let isar = get_instance(instance_id, sqlite)?;
+    let result = (|| {
+        let mut txn = Some(match &isar {
+            #[cfg(feature = "native")]
+            CIsarInstance::Native(isar) => CIsarTxn::Native(isar.begin_txn(write)?),
+            #[cfg(feature = "sqlite")]
+            CIsarInstance::SQLite(isar) => CIsarTxn::SQLite(isar.begin_txn(write)?),
+        });
+        let result = job(&isar, &mut txn);
+        // the txn is only lost if a failed import already aborted it
+        let Some(txn) = txn else {
+            return result;
+        };
+        let commit = write && result.is_ok();
+        match (&isar, txn) {
+            #[cfg(feature = "native")]
+            (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
+                if commit {
+                    isar.commit_txn(txn)?
+                } else {
+                    isar.abort_txn(txn)
+                }
+            }
+            #[cfg(feature = "sqlite")]
+            (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => {
+                if commit {
+                    isar.commit_txn(txn)?
+                } else {
+                    isar.abort_txn(txn)
+                }
+            }
+            _ => return Err(IsarError::IllegalArgument {}),
+        }
+        result
+    })();
+    match isar {
+        #[cfg(feature = "native")]
+        CIsarInstance::Native(isar) => NativeInstance::close(isar, false),
+        #[cfg(feature = "sqlite")]
+        CIsarInstance::SQLite(isar) => SQLiteInstance::close(isar, false),
+    };
+    result
+}
+
+fn query_instance(query: &CIsarQuery) -> bool {
+    match query {
+        #[cfg(feature = "native")]
+        CIsarQuery::Native(_) => false,
+        #[cfg(feature = "sqlite")]
+        CIsarQuery::SQLite(_) => true,
+    }
+}
+
+fn write_json(buffer: &mut Vec<u8>, reader: impl IsarReader) -> Result<()> {
+    if buffer.len() > 1 {
+        buffer.push(b',');
+    }
+    reader.serialize(&mut serde_json::Serializer::new(buffer))
+}
+
+/// Consumes the result. Values are returned as pointer to an `IsarValue` or `0` if they are
+/// null.
+#[no_mangle]
+pub unsafe extern "C" fn isar_async_result(result: *mut CIsarAsyncResult, value: *mut i64) -> u8 {
+    let result = *Box::from_raw(result);
+    isar_try! {
+        @operation(isar_async_result)
+        *value = match result.0? {
+            AsyncValue::Integer(integer) => integer,
+            AsyncValue::Value(Some(isar_value)) => Box::into_raw(Box::new(isar_value)) as i64,
+            AsyncValue::Value(None) => 0,
+        };
+    }
+}
+
+/// Releases a result that is not consumed using `isar_async_result`, for example because the
+/// port was closed before the result arrived.
+#[no_mangle]
+pub unsafe extern "C" fn isar_async_result_free(result: *mut CIsarAsyncResult) {
+    if !result.is_null() {
+        drop(Box::from_raw(result));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_async_count(
+    instance_id: u32,
+    sqlite: bool,
+    collection_index: u16,
+    port: DartPort,
+) {
+    run_async(port, move || {
+        run_txn(instance_id, sqlite, false, |isar, txn| {
+            let count = match (isar, txn.as_ref()) {
+                #[cfg(feature = "native")]
+                (CIsarInstance::Native(isar), Some(CIsarTxn::Native(txn))) => {
+                    isar.count(txn, collection_index)?
+                }
+                #[cfg(feature = "sqlite")]
+                (CIsarInstance::SQLite(isar), Some(CIsarTxn::SQLite(txn))) => {
+                    isar.count(txn, collection_index)?
+                }
+                _ => return Err(IsarError::IllegalArgument {}),
+            };
+            Ok(AsyncValue::Integer(count as i64))
+        })
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_async_query_find(
+    query: &'static CIsarQuery,
+    instance_id: u32,
+    offset: u32,
+    limit: u32,
+    port: DartPort,
+) {
+    let offset = if offset == 0 { None } else { Some(offset) };
+    let limit = if limit == 0 { None } else { Some(limit) };
+    let query = query.clone();
+    run_async(port, move || {
+        run_txn(instance_id, query_instance(&query), false, |isar, txn| {
+            let mut buffer = vec![b'['];
+            match (isar, txn.as_ref(), &query) {
+                #[cfg(feature = "native")]
+                (
+                    CIsarInstance::Native(isar),
+                    Some(CIsarTxn::Native(txn)),
+                    CIsarQuery::Native(query),
+                ) => {
+                    let mut cursor = isar.query_cursor(txn, query, offset, limit)?;
+                    while let Some(reader) = cursor.next() {
+                        write_json(&mut buffer, reader)?;
+                    }
+                }
+                #[cfg(feature = "sqlite")]
+                (
+                    CIsarInstance::SQLite(isar),
+                    Some(CIsarTxn::SQLite(txn)),
+                    CIsarQuery::SQLite(query),
+                ) => {
+                    let mut cursor = isar.query_cursor(txn, query, offset, limit)?;
+                    while let Some(reader) = cursor.next() {
+                        write_json(&mut buffer, reader)?;
+                    }
+                }
+                _ => return Err(IsarError::IllegalArgument {}),
+            }
+            buffer.push(b']');
+            let json = String::from_utf8(buffer).map_err(|_| IsarError::IllegalString {})?;
+            Ok(AsyncValue::Value(Some(IsarValue::String(json))))
+        })
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_async_query_aggregate(
+    query: &'static CIsarQuery,
+    instance_id: u32,
+    aggregation: u8,
+    property_index: u16,
+    port: DartPort,
+) {
+    let query = query.clone();
+    run_async(port, move || {
+        let aggregation = aggregation_from_c(aggregation).ok_or(IsarError::IllegalArgument {})?;
+        run_txn(instance_id, query_instance(&query), false, |isar, txn| {
+            let value = match (isar, txn.as_ref(), &query) {
+                #[cfg(feature = "native")]
+                (
+                    CIsarInstance::Native(isar),
+                    Some(CIsarTxn::Native(txn)),
+                    CIsarQuery::Native(query),
+                ) => isar.query_aggregate(txn, query, aggregation, Some(property_index))?,
+                #[cfg(feature = "sqlite")]
+                (
+                    CIsarInstance::SQLite(isar),
+                    Some(CIsarTxn::SQLite(txn)),
+                    CIsarQuery::SQLite(query),
+                ) => isar.query_aggregate(txn, query, aggregation, Some(property_index))?,
+                _ => return Err(IsarError::IllegalArgument {}),
+            };
+            Ok(AsyncValue::Value(value))
+        })
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_async_query_delete(
+    query: &'static CIsarQuery,
+    instance_id: u32,
+    offset: u32,
+    limit: u32,
+    port: DartPort,
+) {
+    let offset = if offset == 0 { None } else { Some(offset) };
+    let limit = if limit == 0 { None } else { Some(limit) };
+    let query = query.clone();
+    run_async(port, move || {
+        run_txn(instance_id, query_instance(&query), true, |isar, txn| {
+            let count = match (isar, txn.as_ref(), &query) {
+                #[cfg(feature = "native")]
+                (
+                    CIsarInstance::Native(isar),
+                    Some(CIsarTxn::Native(txn)),
+                    CIsarQuery::Native(query),
+                ) => isar.query_delete(txn, query, offset, limit)?,
+                #[cfg(feature = "sqlite")]
+                (
+                    CIsarInstance::SQLite(isar),
+                    Some(CIsarTxn::SQLite(txn)),
+                    CIsarQuery::SQLite(query),
+                ) => isar.query_delete(txn, query, offset, limit)?,
+                _ => return Err(IsarError::IllegalArgument {}),
+            };
+            Ok(AsyncValue::Integer(count as i64))
+        })
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_async_import_json(
+    instance_id: u32,
+    sqlite: bool,
+    collection_index: u16,
+    json: *mut String,
+    port: DartPort,
+) {
+    let json = *Box::from_raw(json);
+    run_async(port, move || {
+        run_txn(instance_id, sqlite, true, |isar, txn| {
+            let mut deserializer = serde_json::Deserializer::from_str(&json);
+            let (new_txn, count) = match (isar, txn.take()) {
+                #[cfg(feature = "native")]
+                (CIsarInstance::Native(isar), Some(CIsarTxn::Native(txn))) => {
+                    let (txn, count) =
+                        isar.import_json(txn, collection_index, &mut deserializer, dart_fast_hash)?;
+                    (CIsarTxn::Native(txn), count)
+                }
+                #[cfg(feature = "sqlite")]
+                (CIsarInstance::SQLite(isar), Some(CIsarTxn::SQLite(txn))) => {
+                    let (txn, count) =
+                        isar.import_json(txn, collection_index, &mut deserializer, dart_fast_hash)?;
+                    (CIsarTxn::SQLite(txn), count)
+                }
+                _ => return Err(IsarError::IllegalArgument {}),
+            };
+            *txn = Some(new_txn);
+            Ok(AsyncValue::Integer(count as i64))
+        })
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_async_import_json_file(
+    instance_id: u32,
+    sqlite: bool,
+    collection_index: u16,
+    path: *mut String,
+    flags: u8,
+    port: DartPort,
+) {
+    let path = *Box::from_raw(path);
+    let options = import_options_from_c(flags);
+    run_async(port, move || {
+        run_txn(instance_id, sqlite, true, |isar, txn| {
+            let reader = json_file_reader(&path)?;
+            let (new_txn, report) = match (isar, txn.take()) {
+                #[cfg(feature = "native")]
+                (CIsarInstance::Native(isar), Some(CIsarTxn::Native(txn))) => {
+                    let (txn, report) = isar.import_json_stream(
+                        txn,
+                        collection_index,
+                        reader,
+                        options,
+                        dart_fast_hash,
+                    )?;
+                    (CIsarTxn::Native(txn), report)
+                }
+                #[cfg(feature = "sqlite")]
+                (CIsarInstance::SQLite(isar), Some(CIsarTxn::SQLite(txn))) => {
+                    let (txn, report) = isar.import_json_stream(
+                        txn,
+                        collection_index,
+                        reader,
+                        options,
+                        dart_fast_hash,
+                    )?;
+                    (CIsarTxn::SQLite(txn), report)
+                }
+                _ => return Err(IsarError::IllegalArgument {}),
+            };
+            *txn = Some(new_txn);
+            let report = import_report_value(&report)?;
+            Ok(AsyncValue::Value(Some(report)))
+        })
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_async_export_snapshot(
+    instance_id: u32,
+    sqlite: bool,
+    path: *mut String,
+    port: DartPort,
+) {
+    let path = *Box::from_raw(path);
+    run_async(port, move || {
+        run_txn(instance_id, sqlite, false, |isar, txn| {
+            let writer = json_file_writer(&path)?;
+            let count = match (isar, txn.as_ref()) {
+                #[cfg(feature = "native")]
+                (CIsarInstance::Native(isar), Some(CIsarTxn::Native(txn))) => {
+                    isar.export_snapshot(txn, writer)?
+                }
+                #[cfg(feature = "sqlite")]
+                (CIsarInstance::SQLite(isar), Some(CIsarTxn::SQLite(txn))) => {
+                    isar.export_snapshot(txn, writer)?
+                }
+                _ => return Err(IsarError::IllegalArgument {}),
+            };
+            Ok(AsyncValue::Integer(count as i64))
+        })
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_async_import_snapshot(
+    instance_id: u32,
+    sqlite: bool,
+    path: *mut String,
+    port: DartPort,
+) {
+    let path = *Box::from_raw(path);
+    run_async(port, move || {
+        run_txn(instance_id, sqlite, true, |isar, txn| {
+            let reader = json_file_reader(&path)?;
+            let (new_txn, count) = match (isar, txn.take()) {
+                #[cfg(feature = "native")]
+                (CIsarInstance::Native(isar), Some(CIsarTxn::Native(txn))) => {
+                    let (txn, count) = isar.import_snapshot(txn, reader)?;
+                    (CIsarTxn::Native(txn), count)
+                }
+                #[cfg(feature = "sqlite")]
+                (CIsarInstance::SQLite(isar), Some(CIsarTxn::SQLite(txn))) => {
+                    let (txn, count) = isar.import_snapshot(txn, reader)?;
+                    (CIsarTxn::SQLite(txn), count)
+                }
+                _ => return Err(IsarError::IllegalArgument {}),
+            };
+            *txn = Some(new_txn);
+            Ok(AsyncValue::Integer(count as i64))
+        })
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_async_query_export_json(
+    query: &'static CIsarQuery,
+    instance_id: u32,
+    path: *mut String,
+    ndjson: bool,
+    port: DartPort,
+) {
+    let path = *Box::from_raw(path);
+    let format = if ndjson {
+        JsonFormat::Ndjson
+    } else {
+        JsonFormat::Array
+    };
+    let query = query.clone();
+    run_async(port, move || {
+        run_txn(instance_id, query_instance(&query), false, |isar, txn| {
+            let writer = json_file_writer(&path)?;
+            let count = match (isar, txn.as_ref(), &query) {
+                #[cfg(feature = "native")]
+                (
+                    CIsarInstance::Native(isar),
+                    Some(CIsarTxn::Native(txn)),
+                    CIsarQuery::Native(query),
+                ) => isar.export_json(txn, query, format, writer)?,
+                #[cfg(feature = "sqlite")]
+                (
+                    CIsarInstance::SQLite(isar),
+                    Some(CIsarTxn::SQLite(txn)),
+                    CIsarQuery::SQLite(query),
+                ) => isar.export_json(txn, query, format, writer)?,
+                _ => return Err(IsarError::IllegalArgument {}),
+            };
+            Ok(AsyncValue::Integer(count as i64))
+        })
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{ERROR_ID_NOT_FOUND, ERROR_ILLEGAL_ARGUMENT};
+    use isar_core::core::data_type::DataType;
+    use isar_core::core::schema::{IsarSchema, PropertySchema};
+    use std::time::Duration;
+
+    fn consume(result: Result<AsyncValue>) -> (u8, i64) {
+        let result = Box::into_raw(Box::new(CIsarAsyncResult(result)));
+        let mut value = 0;
+        let code = unsafe { isar_async_result(result, &mut value) };
+        (code, value)
+    }
+
+    #[test]
+    fn test_async_result() {
+        assert_eq!(consume(Ok(AsyncValue::Integer(5))), (0, 5));
+        assert_eq!(consume(Ok(AsyncValue::Value(None))), (0, 0));
+        assert_eq!(consume(Err(IsarError::IdNotFound {})).0, ERROR_ID_NOT_FOUND);
+
+        let value = IsarValue::String("a".to_string());
+        let (code, pointer) = consume(Ok(AsyncValue::Value(Some(value.clone()))));
+        assert_eq!(code, 0);
+        assert_eq!(*unsafe { Box::from_raw(pointer as *mut IsarValue) }, value);
+    }
+
+    #[test]
+    fn test_async_result_free() {
+        let value = AsyncValue::Value(Some(IsarValue::String("a".to_string())));
+        let result = Box::into_raw(Box::new(CIsarAsyncResult(Ok(value))));
+        unsafe {
+            isar_async_result_free(result);
+            isar_async_result_free(std::ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_run_async() {
+        let (sender, receiver) = mpsc::channel();
+        for i in 0..10 {
+            let sender = sender.clone();
+            run_async(0, move || {
+                sender.send(i).unwrap();
+                Ok(AsyncValue::Integer(i))
+            });
+        }
+        let mut received = (0..10)
+            .map(|_| receiver.recv_timeout(Duration::from_secs(5)).unwrap())
+            .collect::<Vec<_>>();
+        received.sort();
+        assert_eq!(received, (0..10).collect::<Vec<_>>());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_run_txn() {
+        let schema = IsarSchema::new(
+            "Test",
+            Some("id"),
+            vec![PropertySchema::new("value", DataType::Long, None)],
+            vec![],
+            false,
+        );
+        let instance_id = 4242;
+        let dir = std::env::temp_dir().join(format!("isar_async_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let isar = SQLiteInstance::open_instance(
+            instance_id,
+            "test",
+            dir.to_str().unwrap(),
+            vec![schema],
+            0,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let count = run_txn(instance_id, true, false, |isar, txn| {
+            let count = match (isar, txn.as_ref()) {
+                (CIsarInstance::SQLite(isar), Some(CIsarTxn::SQLite(txn))) => isar.count(txn, 0)?,
+                _ => unreachable!(),
+            };
+            Ok(AsyncValue::Integer(count as i64))
+        });
+        assert_eq!(consume(count), (0, 0));
+
+        let failed = run_txn(instance_id, true, true, |_, _| {
+            Err(IsarError::IdNotFound {})
+        });
+        assert_eq!(consume(failed).0, ERROR_ID_NOT_FOUND);
+
+        // the transaction of the failed job is released
+        let txn = isar.begin_txn(true).unwrap();
+        isar.abort_txn(txn);
+
+        let missing = run_txn(instance_id + 1, true, false, |_, _| {
+            Ok(AsyncValue::Integer(1))
+        });
+        assert_eq!(consume(missing).0, ERROR_ILLEGAL_ARGUMENT);
+
+        SQLiteInstance::close(isar, true);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
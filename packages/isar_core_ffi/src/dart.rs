@@ -44,9 +44,12 @@ pub struct DartFunctions {
     pub enter_isolate: unsafe extern "C" fn(DartIsolate),
 }
 
-pub unsafe fn dart_post_int(port: DartPort, value: i64) {
+/// Returns `false` if the value could not be posted.
+pub unsafe fn dart_post_int(port: DartPort, value: i64) -> bool {
     if let Some(dartfn) = DART_FUNCTIONS.get() {
-        (dartfn.post_integer)(port, value);
+        (dartfn.post_integer)(port, value)
+    } else {
+        false
     }
 }
 
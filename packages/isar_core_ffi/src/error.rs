@@ -1,13 +1,27 @@
 use isar_core::core::error::IsarError;
+use serde_json::{json, Map, Value};
 use std::cell::RefCell;
 
 thread_local! {
     pub static ERROR: RefCell<Option<String>> = RefCell::new(None);
+    pub static ERROR_DETAILS: RefCell<Option<String>> = RefCell::new(None);
 }
 
+/// Runs the body and converts an error into its code. `@operation` names the exported function
+/// for the error details.
 #[macro_export]
 macro_rules! isar_try {
-    { $($token:tt)* } => {{
+    {
+        @operation($operation:ident)
+        @context($($key:ident = $value:expr),* $(,)?)
+        $($token:tt)*
+    } => {
+        isar_try! {
+            @run $operation, crate::error::ErrorContext::default()$(.$key($value))*;
+            $($token)*
+        }
+    };
+    { @run $operation:ident, $context:expr; $($token:tt)* } => {{
         #[allow(unused_mut)] {
             let mut l = || -> isar_core::core::error::Result<()> {
                 {$($token)*}
@@ -15,32 +29,113 @@ macro_rules! isar_try {
                 Ok(())
             };
             if let Err(err) = l() {
-                if let Some(code) = crate::error::error_code(&err) {
-                    crate::error::ERROR.replace(None);
-                    code
-                } else {
-                    crate::error::ERROR.replace(Some(err.to_string()));
-                    255
-                }
+                crate::error::set_error(&err, stringify!($operation), $context)
             } else {
                 0
             }
         }
-    }}
+    }};
+    { @operation($operation:ident) $($token:tt)* } => {
+        isar_try! {
+            @run $operation, crate::error::ErrorContext::default();
+            $($token)*
+        }
+    };
 }
 
-pub const fn error_code(err: &IsarError) -> Option<u8> {
-    let code = match err {
-        IsarError::PathError {} => ERROR_PATH,
-        IsarError::WriteTxnRequired {} => ERROR_WRITE_TXN_REQUIRED,
-        IsarError::VersionError {} => ERROR_VERSION,
-        IsarError::ObjectLimitReached {} => ERROR_OBJECT_LIMIT_REACHED,
-        IsarError::InstanceMismatch {} => ERROR_INSTANCE_MISMATCH,
-        IsarError::EncryptionError {} => ERROR_ENCRYPTION,
-        IsarError::DbFull {} => ERROR_DB_FULL,
-        _ => return None,
-    };
-    Some(code)
+/// Additional information about the failed call that is included in the error details.
+#[derive(Default)]
+pub struct ErrorContext {
+    collection_index: Option<u16>,
+    property_index: Option<u16>,
+    index_index: Option<u16>,
+    id: Option<i64>,
+}
+
+impl ErrorContext {
+    pub fn collection(mut self, collection_index: u16) -> Self {
+        self.collection_index = Some(collection_index);
+        self
+    }
+
+    pub fn property(mut self, property_index: u16) -> Self {
+        self.property_index = Some(property_index);
+        self
+    }
+
+    pub fn index(mut self, index_index: u16) -> Self {
+        self.index_index = Some(index_index);
+        self
+    }
+
+    pub fn id(mut self, id: i64) -> Self {
+        self.id = Some(id);
+        self
+    }
+}
+
+pub fn set_error(err: &IsarError, operation: &str, context: ErrorContext) -> u8 {
+    let (code, name) = error_info(err);
+    let message = err.to_string();
+
+    let mut details = Map::new();
+    details.insert("code".to_string(), json!(code));
+    details.insert("name".to_string(), json!(name));
+    details.insert("message".to_string(), json!(message));
+    details.insert("operation".to_string(), json!(operation));
+    let fields = [
+        ("collectionIndex", context.collection_index.map(Value::from)),
+        ("propertyIndex", context.property_index.map(Value::from)),
+        ("indexIndex", context.index_index.map(Value::from)),
+        ("id", context.id.map(Value::from)),
+        ("dbCode", db_code(err).map(Value::from)),
+    ];
+    for (key, value) in fields {
+        if let Some(value) = value {
+            details.insert(key.to_string(), value);
+        }
+    }
+
+    ERROR.replace(Some(message));
+    ERROR_DETAILS.replace(Some(Value::Object(details).to_string()));
+    code
+}
+
+fn db_code(err: &IsarError) -> Option<i32> {
+    match err {
+        IsarError::DbError { code, .. } => Some(*code),
+        _ => None,
+    }
+}
+
+/// Codes are part of the FFI and must never change.
+const fn error_info(err: &IsarError) -> (u8, &'static str) {
+    match err {
+        IsarError::PathError {} => (ERROR_PATH, "PathError"),
+        IsarError::WriteTxnRequired {} => (ERROR_WRITE_TXN_REQUIRED, "WriteTxnRequired"),
+        IsarError::VersionError {} => (ERROR_VERSION, "VersionError"),
+        IsarError::ObjectLimitReached {} => (ERROR_OBJECT_LIMIT_REACHED, "ObjectLimitReached"),
+        IsarError::InstanceMismatch {} => (ERROR_INSTANCE_MISMATCH, "InstanceMismatch"),
+        IsarError::EncryptionError {} => (ERROR_ENCRYPTION, "EncryptionError"),
+        IsarError::DbFull {} => (ERROR_DB_FULL, "DbFull"),
+        IsarError::Busy {} => (ERROR_BUSY, "Busy"),
+        IsarError::SchemaError { .. } => (ERROR_SCHEMA, "SchemaError"),
+        IsarError::UnsupportedOperation {} => (ERROR_UNSUPPORTED_OPERATION, "UnsupportedOperation"),
+        IsarError::InsertIncomplete {} => (ERROR_INSERT_INCOMPLETE, "InsertIncomplete"),
+        IsarError::TransactionClosed {} => (ERROR_TRANSACTION_CLOSED, "TransactionClosed"),
+        IsarError::IdNotFound {} => (ERROR_ID_NOT_FOUND, "IdNotFound"),
+        IsarError::UnknownProperty {} => (ERROR_UNKNOWN_PROPERTY, "UnknownProperty"),
+        IsarError::TransactionActive {} => (ERROR_TRANSACTION_ACTIVE, "TransactionActive"),
+        IsarError::IllegalString {} => (ERROR_ILLEGAL_STRING, "IllegalString"),
+        IsarError::IllegalArgument {} => (ERROR_ILLEGAL_ARGUMENT, "IllegalArgument"),
+        IsarError::JsonError { .. } => (ERROR_JSON, "JsonError"),
+        IsarError::DbCorrupted {} => (ERROR_DB_CORRUPTED, "DbCorrupted"),
+        IsarError::DbError { .. } => (ERROR_DB, "DbError"),
+        IsarError::ConstraintViolation { .. } => {
+            (ERROR_CONSTRAINT_VIOLATION, "ConstraintViolation")
+        }
+        IsarError::VersionMismatch {} => (ERROR_VERSION_MISMATCH, "VersionMismatch"),
+    }
 }
 
 pub const ERROR_PATH: u8 = 1;
@@ -50,6 +145,21 @@ pub const ERROR_OBJECT_LIMIT_REACHED: u8 = 4;
 pub const ERROR_INSTANCE_MISMATCH: u8 = 5;
 pub const ERROR_ENCRYPTION: u8 = 6;
 pub const ERROR_DB_FULL: u8 = 7;
+pub const ERROR_BUSY: u8 = 8;
+pub const ERROR_SCHEMA: u8 = 9;
+pub const ERROR_UNSUPPORTED_OPERATION: u8 = 10;
+pub const ERROR_INSERT_INCOMPLETE: u8 = 11;
+pub const ERROR_TRANSACTION_CLOSED: u8 = 12;
+pub const ERROR_ID_NOT_FOUND: u8 = 13;
+pub const ERROR_UNKNOWN_PROPERTY: u8 = 14;
+pub const ERROR_TRANSACTION_ACTIVE: u8 = 15;
+pub const ERROR_ILLEGAL_STRING: u8 = 16;
+pub const ERROR_ILLEGAL_ARGUMENT: u8 = 17;
+pub const ERROR_JSON: u8 = 18;
+pub const ERROR_DB_CORRUPTED: u8 = 19;
+pub const ERROR_DB: u8 = 20;
+pub const ERROR_CONSTRAINT_VIOLATION: u8 = 21;
+pub const ERROR_VERSION_MISMATCH: u8 = 22;
 
 #[no_mangle]
 pub unsafe extern "C" fn isar_get_error(value: *mut *const u8) -> u32 {
@@ -63,6 +173,21 @@ pub unsafe extern "C" fn isar_get_error(value: *mut *const u8) -> u32 {
     })
 }
 
+/// Returns the last error as JSON object with its `code`, `name`, `message` and `operation`.
+/// Depending on the error it also contains `collectionIndex`, `propertyIndex`, `indexIndex`, `id`
+/// and the `dbCode` of the underlying database.
+#[no_mangle]
+pub unsafe extern "C" fn isar_get_error_details(value: *mut *const u8) -> u32 {
+    ERROR_DETAILS.with_borrow(|e| {
+        if let Some(details) = e.as_ref() {
+            *value = details.as_ptr();
+            details.len() as u32
+        } else {
+            0
+        }
+    })
+}
+
 #[macro_export]
 macro_rules! isar_pause_isolate {
     { $($token:tt)* } => {{
@@ -73,3 +198,70 @@ macro_rules! isar_pause_isolate {
         }
     }}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_details() -> Value {
+        let mut value = std::ptr::null();
+        let len = unsafe { isar_get_error_details(&mut value) };
+        let details = unsafe { std::slice::from_raw_parts(value, len as usize) };
+        serde_json::from_slice(details).unwrap()
+    }
+
+    #[test]
+    fn test_set_error() {
+        let context = ErrorContext::default().collection(2).id(5);
+        let code = set_error(&IsarError::IdNotFound {}, "isar_delete", context);
+        assert_eq!(code, ERROR_ID_NOT_FOUND);
+
+        let mut value = std::ptr::null();
+        let len = unsafe { isar_get_error(&mut value) };
+        let message = unsafe { std::slice::from_raw_parts(value, len as usize) };
+        assert_eq!(message, IsarError::IdNotFound {}.to_string().as_bytes());
+        assert_eq!(
+            error_details(),
+            json!({
+                "code": ERROR_ID_NOT_FOUND,
+                "name": "IdNotFound",
+                "message": IsarError::IdNotFound {}.to_string(),
+                "operation": "isar_delete",
+                "collectionIndex": 2,
+                "id": 5,
+            })
+        );
+
+        let err = IsarError::DbError {
+            code: 13,
+            message: "full".to_string(),
+        };
+        let context = ErrorContext::default().property(1).index(3);
+        assert_eq!(set_error(&err, "isar_update", context), ERROR_DB);
+        let details = error_details();
+        assert_eq!(details["name"], "DbError");
+        assert_eq!(details["operation"], "isar_update");
+        assert_eq!(details["propertyIndex"], 1);
+        assert_eq!(details["indexIndex"], 3);
+        assert_eq!(details["dbCode"], 13);
+        assert_eq!(details.get("collectionIndex"), None);
+    }
+
+    #[test]
+    fn test_isar_try() {
+        let code = isar_try! {
+            @operation(isar_count)
+            @context(collection = 1)
+            return Err(IsarError::TransactionClosed {});
+        };
+        assert_eq!(code, ERROR_TRANSACTION_CLOSED);
+        let details = error_details();
+        assert_eq!(details["operation"], "isar_count");
+        assert_eq!(details["collectionIndex"], 1);
+
+        let code = isar_try! {
+            @operation(isar_count)
+        };
+        assert_eq!(code, 0);
+    }
+}
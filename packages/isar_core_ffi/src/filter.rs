@@ -1,6 +1,6 @@
 use core::slice;
 use isar_core::core::{
-    filter::{ConditionType, Filter, FilterCondition, FilterNested},
+    filter::{ConditionType, Filter, FilterCondition, FilterLink, FilterMapEntry, FilterNested},
     value::IsarValue,
 };
 use std::vec;
@@ -192,6 +192,63 @@ pub unsafe extern "C" fn isar_filter_string_matches(
     Box::into_raw(Box::new(filter))
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn isar_filter_string_regex(
+    property_index: u16,
+    value: *mut IsarValue,
+    case_sensitive: bool,
+) -> *const Filter {
+    let value = *Box::from_raw(value);
+    let filter = Filter::Condition(FilterCondition::new(
+        property_index,
+        ConditionType::StringRegex,
+        vec![Some(value)],
+        case_sensitive,
+    ));
+    Box::into_raw(Box::new(filter))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_filter_geo_within_box(
+    property_index: u16,
+    min_latitude: f64,
+    min_longitude: f64,
+    max_latitude: f64,
+    max_longitude: f64,
+) -> *const Filter {
+    let values = [min_latitude, min_longitude, max_latitude, max_longitude]
+        .into_iter()
+        .map(|v| Some(IsarValue::Real(v)))
+        .collect();
+    let filter = Filter::Condition(FilterCondition::new(
+        property_index,
+        ConditionType::GeoWithinBox,
+        values,
+        false,
+    ));
+    Box::into_raw(Box::new(filter))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_filter_geo_within_radius(
+    property_index: u16,
+    latitude: f64,
+    longitude: f64,
+    radius: f64,
+) -> *const Filter {
+    let values = [latitude, longitude, radius]
+        .into_iter()
+        .map(|v| Some(IsarValue::Real(v)))
+        .collect();
+    let filter = Filter::Condition(FilterCondition::new(
+        property_index,
+        ConditionType::GeoWithinRadius,
+        values,
+        false,
+    ));
+    Box::into_raw(Box::new(filter))
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_filter_nested(
     property_index: u16,
@@ -201,6 +258,38 @@ pub unsafe extern "C" fn isar_filter_nested(
     Box::into_raw(Box::new(filter))
 }
 
+/// Applies a condition of a map property to the value of `key`.
+#[no_mangle]
+pub unsafe extern "C" fn isar_filter_map_entry(
+    key: *mut String,
+    filter: *mut Filter,
+) -> *const Filter {
+    let key = *Box::from_raw(key);
+    let filter = match *Box::from_raw(filter) {
+        Filter::Condition(condition) => Filter::MapEntry(FilterMapEntry::new(
+            condition.property_index,
+            &key,
+            condition.condition_type,
+            condition.values,
+            condition.case_sensitive,
+        )),
+        filter => filter,
+    };
+    Box::into_raw(Box::new(filter))
+}
+
+/// The filter is optional and matched against the linked objects.
+#[no_mangle]
+pub unsafe extern "C" fn isar_filter_link(link_index: u16, filter: *mut Filter) -> *const Filter {
+    let filter = if filter.is_null() {
+        None
+    } else {
+        Some(*Box::from_raw(filter))
+    };
+    let filter = Filter::Link(FilterLink::new(link_index, filter));
+    Box::into_raw(Box::new(filter))
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_filter_and(filters: *mut *mut Filter, lenght: u32) -> *const Filter {
     let filters = slice::from_raw_parts(filters, lenght as usize)
@@ -12,6 +12,8 @@ pub unsafe extern "C" fn isar_insert(
     insert: *mut *const CIsarWriter,
 ) -> u8 {
     isar_try! {
+        @operation(isar_insert)
+        @context(collection = collection_index)
         let txn = *Box::from_raw(txn);
         let new_insert = match (isar, txn) {
             #[cfg(feature = "native")]
@@ -34,6 +36,8 @@ pub unsafe extern "C" fn isar_insert(
 pub unsafe extern "C" fn isar_insert_save(insert: &mut CIsarWriter<'static>, id: IsarI64) -> u8 {
     let id = isar_to_i64(id);
     isar_try! {
+        @operation(isar_insert_save)
+        @context(id = id)
         match insert {
             #[cfg(feature = "native")]
             CIsarWriter::Native(insert) => insert.save(id)?,
@@ -50,6 +54,7 @@ pub unsafe extern "C" fn isar_insert_finish(
     txn: *mut *const CIsarTxn,
 ) -> u8 {
     isar_try! {
+        @operation(isar_insert_finish)
         let insert = *Box::from_raw(insert);
         let new_txn = match insert {
             #[cfg(feature = "native")]
@@ -1,9 +1,16 @@
+use crate::dart::{dart_post_int, DartPort};
+use crate::query::json_file_writer;
 use crate::{
     dart_fast_hash, i64_to_isar, isar_to_i64, CIsarCursor, CIsarInstance, CIsarTxn, IsarI64,
 };
-use isar_core::core::error::IsarError;
-use isar_core::core::instance::{CompactCondition, IsarInstance};
+use isar_core::core::de::{ErrorPolicy, ImportMode, ImportOptions, ImportReport};
+use isar_core::core::error::{IsarError, Result};
+use isar_core::core::filter::Filter;
+use isar_core::core::instance::{CompactCondition, IsarInstance, JsonFormat};
 use isar_core::core::schema::IsarSchema;
+use isar_core::core::value::IsarValue;
+use std::fs::File;
+use std::io::BufReader;
 use std::os::raw::c_char;
 use std::ptr;
 
@@ -51,6 +58,7 @@ pub unsafe extern "C" fn isar_open_instance(
     compact_min_ratio: f32,
 ) -> u8 {
     isar_try! {
+        @operation(isar_open_instance)
         let name = *Box::from_raw(name);
         let path = *Box::from_raw(path);
         let schema_json = *Box::from_raw(schema_json);
@@ -149,6 +157,7 @@ pub unsafe extern "C" fn isar_change_encryption_key(
     };
 
     isar_try! {
+        @operation(isar_change_encryption_key)
         match isar {
             #[cfg(feature = "native")]
             CIsarInstance::Native(isar) => isar.change_encryption_key(encryption_key.as_deref())?,
@@ -158,12 +167,28 @@ pub unsafe extern "C" fn isar_change_encryption_key(
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn isar_set_busy_policy(
+    isar: &'static CIsarInstance,
+    timeout_ms: u32,
+    max_retries: u32,
+) {
+    match isar {
+        // MDBX writers wait for the write lock
+        #[cfg(feature = "native")]
+        CIsarInstance::Native(_) => {}
+        #[cfg(feature = "sqlite")]
+        CIsarInstance::SQLite(isar) => isar.set_busy_policy(timeout_ms, max_retries),
+    }
+}
+
 unsafe fn _isar_txn_begin(
     isar: &'static CIsarInstance,
     txn: *mut *const CIsarTxn,
     write: bool,
 ) -> u8 {
     isar_try! {
+        @operation(isar_txn_begin)
         let new_txn = match isar {
             #[cfg(feature = "native")]
             CIsarInstance::Native(isar) => CIsarTxn::Native(isar.begin_txn(write)?),
@@ -182,6 +207,7 @@ pub unsafe extern "C" fn isar_txn_begin(
 ) -> u8 {
     isar_pause_isolate! {
         isar_try! {
+            @operation(isar_txn_begin)
             let new_txn = match isar {
                 #[cfg(feature = "native")]
                 CIsarInstance::Native(isar) => CIsarTxn::Native(isar.begin_txn(write)?),
@@ -197,6 +223,7 @@ pub unsafe extern "C" fn isar_txn_begin(
 pub unsafe extern "C" fn isar_txn_commit(isar: &'static CIsarInstance, txn: *mut CIsarTxn) -> u8 {
     isar_pause_isolate! {
         isar_try! {
+            @operation(isar_txn_commit)
             let txn = *Box::from_raw(txn);
             match (isar, txn) {
                 #[cfg(feature = "native")]
@@ -225,6 +252,57 @@ pub unsafe extern "C" fn isar_txn_abort(isar: &'static CIsarInstance, txn: *mut
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn isar_txn_savepoint_begin(
+    isar: &'static CIsarInstance,
+    txn: &'static mut CIsarTxn,
+) -> u8 {
+    isar_try! {
+        @operation(isar_txn_savepoint_begin)
+        match (isar, txn) {
+            #[cfg(feature = "native")]
+            (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => isar.begin_savepoint(txn)?,
+            #[cfg(feature = "sqlite")]
+            (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => isar.begin_savepoint(txn)?,
+            _ => return Err(IsarError::IllegalArgument {}),
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_txn_savepoint_release(
+    isar: &'static CIsarInstance,
+    txn: &'static mut CIsarTxn,
+) -> u8 {
+    isar_try! {
+        @operation(isar_txn_savepoint_release)
+        match (isar, txn) {
+            #[cfg(feature = "native")]
+            (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => isar.release_savepoint(txn)?,
+            #[cfg(feature = "sqlite")]
+            (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => isar.release_savepoint(txn)?,
+            _ => return Err(IsarError::IllegalArgument {}),
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_txn_savepoint_rollback(
+    isar: &'static CIsarInstance,
+    txn: &'static mut CIsarTxn,
+) -> u8 {
+    isar_try! {
+        @operation(isar_txn_savepoint_rollback)
+        match (isar, txn) {
+            #[cfg(feature = "native")]
+            (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => isar.rollback_to_savepoint(txn)?,
+            #[cfg(feature = "sqlite")]
+            (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => isar.rollback_to_savepoint(txn)?,
+            _ => return Err(IsarError::IllegalArgument {}),
+        }
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_auto_increment(
     isar: &'static CIsarInstance,
@@ -247,6 +325,8 @@ pub unsafe extern "C" fn isar_cursor(
     cursor: *mut *const CIsarCursor,
 ) -> u8 {
     isar_try! {
+        @operation(isar_cursor)
+        @context(collection = collection_index)
         let new_cursor = match (isar, txn) {
             #[cfg(feature = "native")]
             (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
@@ -274,6 +354,8 @@ pub unsafe extern "C" fn isar_delete(
 ) -> u8 {
     let id = isar_to_i64(id);
     isar_try! {
+        @operation(isar_delete)
+        @context(collection = collection_index, id = id)
         *deleted = match (isar, txn) {
             #[cfg(feature = "native")]
             (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
@@ -288,6 +370,62 @@ pub unsafe extern "C" fn isar_delete(
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn isar_delete_if(
+    isar: &'static CIsarInstance,
+    txn: &'static CIsarTxn,
+    collection_index: u16,
+    id: IsarI64,
+    condition: *mut Filter,
+    deleted: *mut bool,
+) -> u8 {
+    let id = isar_to_i64(id);
+    let condition = *Box::from_raw(condition);
+    isar_try! {
+        @operation(isar_delete_if)
+        @context(collection = collection_index, id = id)
+        *deleted = match (isar, txn) {
+            #[cfg(feature = "native")]
+            (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
+                isar.delete_if(txn, collection_index, id, condition)?
+            }
+            #[cfg(feature = "sqlite")]
+            (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => {
+                isar.delete_if(txn, collection_index, id, condition)?
+            }
+            _ => return Err(IsarError::IllegalArgument {}),
+        };
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_delete_if_version(
+    isar: &'static CIsarInstance,
+    txn: &'static CIsarTxn,
+    collection_index: u16,
+    id: IsarI64,
+    version: IsarI64,
+    deleted: *mut bool,
+) -> u8 {
+    let id = isar_to_i64(id);
+    let version = isar_to_i64(version);
+    isar_try! {
+        @operation(isar_delete_if_version)
+        @context(collection = collection_index, id = id)
+        *deleted = match (isar, txn) {
+            #[cfg(feature = "native")]
+            (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
+                isar.delete_if_version(txn, collection_index, id, version)?
+            }
+            #[cfg(feature = "sqlite")]
+            (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => {
+                isar.delete_if_version(txn, collection_index, id, version)?
+            }
+            _ => return Err(IsarError::IllegalArgument {}),
+        };
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_count(
     isar: &'static CIsarInstance,
@@ -296,6 +434,8 @@ pub unsafe extern "C" fn isar_count(
     count: *mut u32,
 ) -> u8 {
     isar_try! {
+        @operation(isar_count)
+        @context(collection = collection_index)
         let new_count = match (isar, txn) {
             #[cfg(feature = "native")]
             (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
@@ -318,6 +458,8 @@ pub unsafe extern "C" fn isar_clear(
     collection_index: u16,
 ) -> u8 {
     isar_try! {
+        @operation(isar_clear)
+        @context(collection = collection_index)
         match (isar, txn) {
             #[cfg(feature = "native")]
             (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
@@ -332,6 +474,24 @@ pub unsafe extern "C" fn isar_clear(
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn isar_purge_expired(
+    isar: &'static CIsarInstance,
+    txn: &'static CIsarTxn,
+    count: *mut u32,
+) -> u8 {
+    isar_try! {
+        @operation(isar_purge_expired)
+        *count = match (isar, txn) {
+            #[cfg(feature = "native")]
+            (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => isar.purge_expired(txn)?,
+            #[cfg(feature = "sqlite")]
+            (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => isar.purge_expired(txn)?,
+            _ => return Err(IsarError::IllegalArgument {}),
+        };
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_get_size(
     isar: &'static CIsarInstance,
@@ -363,6 +523,8 @@ pub unsafe extern "C" fn isar_import_json(
     let json = *Box::from_raw(json);
     let mut deserializer = serde_json::Deserializer::from_str(&json);
     isar_try! {
+        @operation(isar_import_json)
+        @context(collection = collection_index)
         let (new_txn, new_count) = match (isar, *Box::from_raw(*txn)) {
             #[cfg(feature = "native")]
             (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
@@ -381,10 +543,178 @@ pub unsafe extern "C" fn isar_import_json(
     }
 }
 
+const IMPORT_NDJSON: u8 = 1;
+const IMPORT_INSERT_ONLY: u8 = 2;
+const IMPORT_SKIP_INVALID: u8 = 4;
+
+pub(crate) fn import_options_from_c(flags: u8) -> ImportOptions {
+    ImportOptions {
+        format: if flags & IMPORT_NDJSON != 0 {
+            JsonFormat::Ndjson
+        } else {
+            JsonFormat::Array
+        },
+        mode: if flags & IMPORT_INSERT_ONLY != 0 {
+            ImportMode::InsertOnly
+        } else {
+            ImportMode::Upsert
+        },
+        on_error: if flags & IMPORT_SKIP_INVALID != 0 {
+            ErrorPolicy::Skip
+        } else {
+            ErrorPolicy::Fail
+        },
+        ..ImportOptions::default()
+    }
+}
+
+pub(crate) fn json_file_reader(path: &str) -> Result<BufReader<File>> {
+    let file = File::open(path).map_err(|_| IsarError::PathError {})?;
+    Ok(BufReader::new(file))
+}
+
+pub(crate) fn import_report_value(report: &ImportReport) -> Result<IsarValue> {
+    let json = serde_json::to_string(report).map_err(|e| IsarError::JsonError {
+        message: e.to_string(),
+    })?;
+    Ok(IsarValue::String(json))
+}
+
+/// Imports a JSON array or NDJSON file. `flags` combines `1` (NDJSON), `2` (insert only) and `4`
+/// (skip invalid objects). The report of imported and rejected objects is returned as JSON.
+#[no_mangle]
+pub unsafe extern "C" fn isar_import_json_file(
+    isar: &'static CIsarInstance,
+    txn: *mut *mut CIsarTxn,
+    collection_index: u16,
+    path: *mut String,
+    flags: u8,
+    report: *mut *const IsarValue,
+) -> u8 {
+    let path = *Box::from_raw(path);
+    let options = import_options_from_c(flags);
+    isar_pause_isolate! {
+        isar_try! {
+            @operation(isar_import_json_file)
+            @context(collection = collection_index)
+            let reader = json_file_reader(&path)?;
+            let (new_txn, new_report) = match (isar, *Box::from_raw(*txn)) {
+                #[cfg(feature = "native")]
+                (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
+                    let (txn, report) = isar.import_json_stream(txn, collection_index, reader, options, dart_fast_hash)?;
+                    (CIsarTxn::Native(txn), report)
+                }
+                #[cfg(feature = "sqlite")]
+                (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => {
+                    let (txn, report) = isar.import_json_stream(txn, collection_index, reader, options, dart_fast_hash)?;
+                    (CIsarTxn::SQLite(txn), report)
+                }
+                _ => return Err(IsarError::IllegalArgument {}),
+            };
+            *txn = Box::into_raw(Box::new(new_txn));
+            *report = Box::into_raw(Box::new(import_report_value(&new_report)?));
+        }
+    }
+}
+
+/// Writes all objects of the collection to `path` as a JSON array or NDJSON.
+#[no_mangle]
+pub unsafe extern "C" fn isar_export_json_file(
+    isar: &'static CIsarInstance,
+    txn: &'static CIsarTxn,
+    collection_index: u16,
+    path: *mut String,
+    ndjson: bool,
+    count: *mut u32,
+) -> u8 {
+    let path = *Box::from_raw(path);
+    let format = if ndjson {
+        JsonFormat::Ndjson
+    } else {
+        JsonFormat::Array
+    };
+    isar_pause_isolate! {
+        isar_try! {
+            @operation(isar_export_json_file)
+            @context(collection = collection_index)
+            let writer = json_file_writer(&path)?;
+            let new_count = match (isar, txn) {
+                #[cfg(feature = "native")]
+                (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
+                    isar.export_collection_json(txn, collection_index, format, writer)?
+                }
+                #[cfg(feature = "sqlite")]
+                (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => {
+                    isar.export_collection_json(txn, collection_index, format, writer)?
+                }
+                _ => return Err(IsarError::IllegalArgument {}),
+            };
+            *count = new_count;
+        }
+    }
+}
+
+/// Writes all objects to a binary snapshot at `path` that can be imported by both backends.
+#[no_mangle]
+pub unsafe extern "C" fn isar_export_snapshot(
+    isar: &'static CIsarInstance,
+    txn: &'static CIsarTxn,
+    path: *mut String,
+    count: *mut u32,
+) -> u8 {
+    let path = *Box::from_raw(path);
+    isar_pause_isolate! {
+        isar_try! {
+            @operation(isar_export_snapshot)
+            let writer = json_file_writer(&path)?;
+            let new_count = match (isar, txn) {
+                #[cfg(feature = "native")]
+                (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => isar.export_snapshot(txn, writer)?,
+                #[cfg(feature = "sqlite")]
+                (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => isar.export_snapshot(txn, writer)?,
+                _ => return Err(IsarError::IllegalArgument {}),
+            };
+            *count = new_count;
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_import_snapshot(
+    isar: &'static CIsarInstance,
+    txn: *mut *mut CIsarTxn,
+    path: *mut String,
+    count: *mut u32,
+) -> u8 {
+    let path = *Box::from_raw(path);
+    isar_pause_isolate! {
+        isar_try! {
+            @operation(isar_import_snapshot)
+            let reader = json_file_reader(&path)?;
+            let (new_txn, new_count) = match (isar, *Box::from_raw(*txn)) {
+                #[cfg(feature = "native")]
+                (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
+                    let (txn, count) = isar.import_snapshot(txn, reader)?;
+                    (CIsarTxn::Native(txn), count)
+                }
+                #[cfg(feature = "sqlite")]
+                (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => {
+                    let (txn, count) = isar.import_snapshot(txn, reader)?;
+                    (CIsarTxn::SQLite(txn), count)
+                }
+                _ => return Err(IsarError::IllegalArgument {}),
+            };
+            *txn = Box::into_raw(Box::new(new_txn));
+            *count = new_count;
+        }
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_copy(isar: &'static CIsarInstance, path: *mut String) -> u8 {
     isar_pause_isolate! {
         isar_try! {
+            @operation(isar_copy)
             let path = *Box::from_raw(path);
             match isar {
                 #[cfg(feature = "native")]
@@ -396,9 +726,81 @@ pub unsafe extern "C" fn isar_copy(isar: &'static CIsarInstance, path: *mut Stri
     }
 }
 
+/// Posts the progress of the backup in percent to the port.
+#[no_mangle]
+pub unsafe extern "C" fn isar_backup(
+    isar: &'static CIsarInstance,
+    path: *mut String,
+    port: DartPort,
+) -> u8 {
+    let progress = move |copied: u64, total: u64| {
+        if total > 0 {
+            dart_post_int(port, (copied * 100 / total) as i64);
+        }
+    };
+    isar_pause_isolate! {
+        isar_try! {
+            @operation(isar_backup)
+            let path = *Box::from_raw(path);
+            match isar {
+                #[cfg(feature = "native")]
+                CIsarInstance::Native(isar) => isar.backup(&path, progress)?,
+                #[cfg(feature = "sqlite")]
+                CIsarInstance::SQLite(isar) => isar.backup(&path, progress)?,
+            }
+        }
+    }
+}
+
+/// Replaces the instance with the backup at `path`. If the restore fails, `isar` still points to
+/// the old instance unless it could not be reopened in which case it is set to null.
+#[no_mangle]
+pub unsafe extern "C" fn isar_restore(
+    isar: *mut *const CIsarInstance,
+    path: *mut String,
+    schema_json: *mut String,
+    max_size_mib: u32,
+) -> u8 {
+    isar_pause_isolate! {
+        isar_try! {
+            @operation(isar_restore)
+            let path = *Box::from_raw(path);
+            let schema_json = *Box::from_raw(schema_json);
+            let schemas = IsarSchema::from_json(schema_json.as_bytes())?;
+
+            let old_isar = *Box::from_raw(*isar as *mut CIsarInstance);
+            *isar = ptr::null();
+            let restored = match old_isar {
+                #[cfg(feature = "native")]
+                CIsarInstance::Native(instance) => {
+                    NativeInstance::restore(instance, &path, schemas, max_size_mib)
+                        .map(CIsarInstance::Native)
+                        .map_err(|(instance, e)| (instance.map(CIsarInstance::Native), e))
+                }
+                #[cfg(feature = "sqlite")]
+                CIsarInstance::SQLite(instance) => {
+                    SQLiteInstance::restore(instance, &path, schemas, max_size_mib)
+                        .map(CIsarInstance::SQLite)
+                        .map_err(|(instance, e)| (instance.map(CIsarInstance::SQLite), e))
+                }
+            };
+            match restored {
+                Ok(new_isar) => *isar = Box::into_raw(Box::new(new_isar)),
+                Err((old_isar, e)) => {
+                    if let Some(old_isar) = old_isar {
+                        *isar = Box::into_raw(Box::new(old_isar));
+                    }
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_verify(isar: &'static CIsarInstance, txn: &'static CIsarTxn) -> u8 {
     isar_try! {
+        @operation(isar_verify)
         return match (isar, txn) {
             #[cfg(feature = "native")]
             (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => isar.verify(txn),
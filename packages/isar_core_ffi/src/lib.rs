@@ -6,7 +6,7 @@ use core::slice;
 use isar_core::core::cursor::IsarCursor;
 use isar_core::core::instance::IsarInstance;
 use isar_core::core::reader::IsarReader;
-use isar_core::core::value::IsarValue;
+use isar_core::core::update::PropertyUpdate;
 use isar_core::core::writer::IsarWriter;
 
 #[cfg(feature = "native")]
@@ -17,11 +17,14 @@ use isar_core::sqlite::sqlite_instance::SQLiteInstance;
 
 #[macro_use]
 mod error;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod async_txn;
 pub mod cursor;
 pub mod dart;
 pub mod filter;
 pub mod insert;
 pub mod instance;
+pub mod link;
 pub mod query;
 pub mod reader;
 pub mod update;
@@ -136,7 +139,7 @@ pub enum CIsarReader<'a> {
     SQLiteList(SListReader<'a>),
 }
 
-pub struct CIsarUpdate(pub(crate) Vec<(u16, Option<IsarValue>)>);
+pub struct CIsarUpdate(pub(crate) Vec<PropertyUpdate>);
 
 pub enum CIsarQueryBuilder<'a> {
     #[cfg(feature = "native")]
@@ -145,6 +148,7 @@ pub enum CIsarQueryBuilder<'a> {
     SQLite(SQueryBuilder<'a>),
 }
 
+#[derive(Clone)]
 pub enum CIsarQuery {
     #[cfg(feature = "native")]
     Native(NQuery),
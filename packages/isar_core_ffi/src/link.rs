@@ -0,0 +1,155 @@
+use crate::{i64_to_isar, isar_to_i64, CIsarInstance, CIsarTxn, IsarI64};
+use isar_core::core::error::IsarError;
+use isar_core::core::instance::IsarInstance;
+use std::slice;
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_link(
+    isar: &'static CIsarInstance,
+    txn: &'static CIsarTxn,
+    collection_index: u16,
+    link_index: u16,
+    id: IsarI64,
+    target_id: IsarI64,
+    linked: *mut bool,
+) -> u8 {
+    let id = isar_to_i64(id);
+    let target_id = isar_to_i64(target_id);
+    isar_try! {
+        @operation(isar_link)
+        @context(collection = collection_index, id = id)
+        *linked = match (isar, txn) {
+            #[cfg(feature = "native")]
+            (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
+                isar.link(txn, collection_index, link_index, id, target_id)?
+            }
+            #[cfg(feature = "sqlite")]
+            (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => {
+                isar.link(txn, collection_index, link_index, id, target_id)?
+            }
+            _ => return Err(IsarError::IllegalArgument {}),
+        };
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_unlink(
+    isar: &'static CIsarInstance,
+    txn: &'static CIsarTxn,
+    collection_index: u16,
+    link_index: u16,
+    id: IsarI64,
+    target_id: IsarI64,
+    unlinked: *mut bool,
+) -> u8 {
+    let id = isar_to_i64(id);
+    let target_id = isar_to_i64(target_id);
+    isar_try! {
+        @operation(isar_unlink)
+        @context(collection = collection_index, id = id)
+        *unlinked = match (isar, txn) {
+            #[cfg(feature = "native")]
+            (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
+                isar.unlink(txn, collection_index, link_index, id, target_id)?
+            }
+            #[cfg(feature = "sqlite")]
+            (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => {
+                isar.unlink(txn, collection_index, link_index, id, target_id)?
+            }
+            _ => return Err(IsarError::IllegalArgument {}),
+        };
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_unlink_all(
+    isar: &'static CIsarInstance,
+    txn: &'static CIsarTxn,
+    collection_index: u16,
+    link_index: u16,
+    id: IsarI64,
+    count: *mut u32,
+) -> u8 {
+    let id = isar_to_i64(id);
+    isar_try! {
+        @operation(isar_unlink_all)
+        @context(collection = collection_index, id = id)
+        *count = match (isar, txn) {
+            #[cfg(feature = "native")]
+            (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
+                isar.unlink_all(txn, collection_index, link_index, id)?
+            }
+            #[cfg(feature = "sqlite")]
+            (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => {
+                isar.unlink_all(txn, collection_index, link_index, id)?
+            }
+            _ => return Err(IsarError::IllegalArgument {}),
+        };
+    }
+}
+
+/// Writes up to `capacity` linked ids to `ids`. `count` is set to the total number of linked
+/// ids so the call can be repeated with a larger buffer.
+#[allow(clippy::too_many_arguments)]
+#[no_mangle]
+pub unsafe extern "C" fn isar_get_linked_ids(
+    isar: &'static CIsarInstance,
+    txn: &'static CIsarTxn,
+    collection_index: u16,
+    link_index: u16,
+    id: IsarI64,
+    ids: *mut IsarI64,
+    capacity: u32,
+    count: *mut u32,
+) -> u8 {
+    let id = isar_to_i64(id);
+    isar_try! {
+        @operation(isar_get_linked_ids)
+        @context(collection = collection_index, id = id)
+        let linked_ids = match (isar, txn) {
+            #[cfg(feature = "native")]
+            (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
+                isar.get_linked_ids(txn, collection_index, link_index, id)?
+            }
+            #[cfg(feature = "sqlite")]
+            (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => {
+                isar.get_linked_ids(txn, collection_index, link_index, id)?
+            }
+            _ => return Err(IsarError::IllegalArgument {}),
+        };
+        if !ids.is_null() {
+            let ids = slice::from_raw_parts_mut(ids, capacity as usize);
+            for (target, linked_id) in ids.iter_mut().zip(&linked_ids) {
+                *target = i64_to_isar(*linked_id);
+            }
+        }
+        *count = linked_ids.len() as u32;
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_count_links(
+    isar: &'static CIsarInstance,
+    txn: &'static CIsarTxn,
+    collection_index: u16,
+    link_index: u16,
+    id: IsarI64,
+    count: *mut u32,
+) -> u8 {
+    let id = isar_to_i64(id);
+    isar_try! {
+        @operation(isar_count_links)
+        @context(collection = collection_index, id = id)
+        *count = match (isar, txn) {
+            #[cfg(feature = "native")]
+            (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
+                isar.count_links(txn, collection_index, link_index, id)?
+            }
+            #[cfg(feature = "sqlite")]
+            (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => {
+                isar.count_links(txn, collection_index, link_index, id)?
+            }
+            _ => return Err(IsarError::IllegalArgument {}),
+        };
+    }
+}
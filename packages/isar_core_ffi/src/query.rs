@@ -1,10 +1,13 @@
 use crate::{CIsarInstance, CIsarQuery, CIsarQueryBuilder, CIsarQueryCursor, CIsarTxn};
-use isar_core::core::error::IsarError;
+use isar_core::core::error::{IsarError, Result};
 use isar_core::core::filter::Filter;
-use isar_core::core::instance::{Aggregation, IsarInstance};
+use isar_core::core::instance::{Aggregation, IsarInstance, JsonFormat};
 use isar_core::core::query_builder::{IsarQueryBuilder, Sort};
 use isar_core::core::value::IsarValue;
+use std::fs::File;
+use std::io::BufWriter;
 use std::ptr;
+use std::slice;
 
 #[no_mangle]
 pub unsafe extern "C" fn isar_query_new(
@@ -13,6 +16,8 @@ pub unsafe extern "C" fn isar_query_new(
     query_builder: *mut *const CIsarQueryBuilder,
 ) -> u8 {
     isar_try! {
+        @operation(isar_query_new)
+        @context(collection = collection_index)
         let new_builder = match isar {
             #[cfg(feature = "native")]
             CIsarInstance::Native(isar) => CIsarQueryBuilder::Native(isar.query(collection_index)?),
@@ -57,6 +62,43 @@ pub unsafe extern "C" fn isar_query_add_sort(
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn isar_query_add_distance_sort(
+    builder: &'static mut CIsarQueryBuilder,
+    property_index: u16,
+    latitude: f64,
+    longitude: f64,
+    ascending: bool,
+) {
+    let sort = if ascending { Sort::Asc } else { Sort::Desc };
+    match builder {
+        #[cfg(feature = "native")]
+        CIsarQueryBuilder::Native(builder) => {
+            builder.add_distance_sort(property_index, latitude, longitude, sort)
+        }
+        #[cfg(feature = "sqlite")]
+        CIsarQueryBuilder::SQLite(builder) => {
+            builder.add_distance_sort(property_index, latitude, longitude, sort)
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_query_add_similarity_sort(
+    builder: &'static mut CIsarQueryBuilder,
+    property_index: u16,
+    vector: *const f32,
+    length: u32,
+) {
+    let vector = slice::from_raw_parts(vector, length as usize).to_vec();
+    match builder {
+        #[cfg(feature = "native")]
+        CIsarQueryBuilder::Native(builder) => builder.add_similarity_sort(property_index, vector),
+        #[cfg(feature = "sqlite")]
+        CIsarQueryBuilder::SQLite(builder) => builder.add_similarity_sort(property_index, vector),
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_query_add_distinct(
     builder: &'static mut CIsarQueryBuilder,
@@ -71,20 +113,23 @@ pub unsafe extern "C" fn isar_query_add_distinct(
     }
 }
 
+/// Consumes the builder. Fails if a regex pattern is invalid or a geo condition targets a
+/// property without a geo index.
 #[no_mangle]
-pub unsafe extern "C" fn isar_query_build(builder: *mut CIsarQueryBuilder) -> *mut CIsarQuery {
+pub unsafe extern "C" fn isar_query_build(
+    builder: *mut CIsarQueryBuilder,
+    query: *mut *const CIsarQuery,
+) -> u8 {
     let builder = *Box::from_raw(builder);
-    match builder {
-        #[cfg(feature = "native")]
-        CIsarQueryBuilder::Native(builder) => {
-            let query = builder.build();
-            Box::into_raw(Box::new(CIsarQuery::Native(query)))
-        }
-        #[cfg(feature = "sqlite")]
-        CIsarQueryBuilder::SQLite(builder) => {
-            let query = builder.build();
-            Box::into_raw(Box::new(CIsarQuery::SQLite(query)))
-        }
+    isar_try! {
+        @operation(isar_query_build)
+        let new_query = match builder {
+            #[cfg(feature = "native")]
+            CIsarQueryBuilder::Native(builder) => CIsarQuery::Native(builder.build()?),
+            #[cfg(feature = "sqlite")]
+            CIsarQueryBuilder::SQLite(builder) => CIsarQuery::SQLite(builder.build()?),
+        };
+        *query = Box::into_raw(Box::new(new_query));
     }
 }
 
@@ -101,6 +146,7 @@ pub unsafe extern "C" fn isar_query_cursor(
     let limit = if limit == 0 { None } else { Some(limit) };
 
     isar_try! {
+        @operation(isar_query_cursor)
         let new_cursor = match (isar, txn, query) {
             #[cfg(feature = "native")]
             (CIsarInstance::Native(isar), CIsarTxn::Native(txn), CIsarQuery::Native(query)) => {
@@ -125,6 +171,19 @@ pub const AGGREGATION_MAX: u8 = 3;
 pub const AGGREGATION_SUM: u8 = 4;
 pub const AGGREGATION_AVERAGE: u8 = 5;
 
+pub(crate) fn aggregation_from_c(aggregation: u8) -> Option<Aggregation> {
+    let aggregation = match aggregation {
+        AGGREGATION_COUNT => Aggregation::Count,
+        AGGREGATION_IS_EMPTY => Aggregation::IsEmpty,
+        AGGREGATION_MIN => Aggregation::Min,
+        AGGREGATION_MAX => Aggregation::Max,
+        AGGREGATION_SUM => Aggregation::Sum,
+        AGGREGATION_AVERAGE => Aggregation::Average,
+        _ => return None,
+    };
+    Some(aggregation)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_query_aggregate(
     isar: &'static CIsarInstance,
@@ -134,19 +193,13 @@ pub unsafe extern "C" fn isar_query_aggregate(
     property_index: u16,
     value: *mut *const IsarValue,
 ) -> u8 {
-    let aggregation = match aggregation {
-        AGGREGATION_COUNT => Aggregation::Count,
-        AGGREGATION_IS_EMPTY => Aggregation::IsEmpty,
-        AGGREGATION_MIN => Aggregation::Min,
-        AGGREGATION_MAX => Aggregation::Max,
-        AGGREGATION_SUM => Aggregation::Sum,
-        AGGREGATION_AVERAGE => Aggregation::Average,
-        _ => {
-            *value = ptr::null();
-            return 0;
-        }
+    let Some(aggregation) = aggregation_from_c(aggregation) else {
+        *value = ptr::null();
+        return 0;
     };
     isar_try! {
+        @operation(isar_query_aggregate)
+        @context(property = property_index)
         let new_value = match (isar, txn, query) {
             #[cfg(feature = "native")]
             (CIsarInstance::Native(isar), CIsarTxn::Native(txn), CIsarQuery::Native(query)) => {
@@ -178,6 +231,7 @@ pub unsafe extern "C" fn isar_query_delete(
     let offset = if offset == 0 { None } else { Some(offset) };
     let limit = if limit == 0 { None } else { Some(limit) };
     isar_try! {
+        @operation(isar_query_delete)
         let new_count = match (isar, txn, query) {
             #[cfg(feature = "native")]
             (CIsarInstance::Native(isar), CIsarTxn::Native(txn), CIsarQuery::Native(query)) => {
@@ -193,6 +247,42 @@ pub unsafe extern "C" fn isar_query_delete(
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn isar_query_export_json(
+    isar: &'static CIsarInstance,
+    txn: &'static CIsarTxn,
+    query: &'static CIsarQuery,
+    path: *mut String,
+    ndjson: bool,
+    count: *mut u32,
+) -> u8 {
+    isar_pause_isolate! {
+        isar_try! {
+            @operation(isar_query_export_json)
+            let path = *Box::from_raw(path);
+            let writer = json_file_writer(&path)?;
+            let format = if ndjson { JsonFormat::Ndjson } else { JsonFormat::Array };
+            let new_count = match (isar, txn, query) {
+                #[cfg(feature = "native")]
+                (CIsarInstance::Native(isar), CIsarTxn::Native(txn), CIsarQuery::Native(query)) => {
+                    isar.export_json(txn, query, format, writer)?
+                }
+                #[cfg(feature = "sqlite")]
+                (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn), CIsarQuery::SQLite(query)) => {
+                    isar.export_json(txn, query, format, writer)?
+                }
+                _ => return Err(IsarError::IllegalArgument {}),
+            };
+            *count = new_count;
+        }
+    }
+}
+
+pub(crate) fn json_file_writer(path: &str) -> Result<BufWriter<File>> {
+    let file = File::create(path).map_err(|_| IsarError::PathError {})?;
+    Ok(BufWriter::new(file))
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_query_free(query: *mut CIsarQuery) {
     if !query.is_null() {
@@ -1,6 +1,8 @@
-use crate::{i64_to_isar, CIsarReader, IsarI64};
+use crate::{i64_to_isar, isar_to_i64, CIsarReader, IsarI64};
+use isar_core::core::error::IsarError;
 use isar_core::core::reader::IsarReader;
-use std::{ptr, vec};
+use std::borrow::Cow;
+use std::{ptr, slice, vec};
 
 #[no_mangle]
 pub unsafe extern "C" fn isar_read_id(reader: &'static CIsarReader) -> IsarI64 {
@@ -142,6 +144,23 @@ pub unsafe extern "C" fn isar_read_double(reader: &'static CIsarReader, index: u
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn isar_read_score(reader: &'static CIsarReader) -> f64 {
+    let score = match reader {
+        #[cfg(feature = "native")]
+        CIsarReader::Native(reader) => reader.read_score(),
+        #[cfg(feature = "native")]
+        CIsarReader::NativeList(reader) => reader.read_score(),
+        #[cfg(feature = "sqlite")]
+        CIsarReader::SQLite(reader) => reader.read_score(),
+        #[cfg(feature = "sqlite")]
+        CIsarReader::SQLiteObject(reader) => reader.read_score(),
+        #[cfg(feature = "sqlite")]
+        CIsarReader::SQLiteList(reader) => reader.read_score(),
+    };
+    score.unwrap_or(f64::NAN)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_read_string(
     reader: &'static CIsarReader,
@@ -173,6 +192,149 @@ pub unsafe extern "C" fn isar_read_string(
     }
 }
 
+/// Writes the 16 bytes of the little endian raw decimal value. Returns false if the value is null.
+#[no_mangle]
+pub unsafe extern "C" fn isar_read_decimal(
+    reader: &'static CIsarReader,
+    index: u32,
+    bytes: *mut u8,
+) -> bool {
+    let value = match reader {
+        #[cfg(feature = "native")]
+        CIsarReader::Native(reader) => reader.read_decimal(index),
+        #[cfg(feature = "native")]
+        CIsarReader::NativeList(reader) => reader.read_decimal(index),
+        #[cfg(feature = "sqlite")]
+        CIsarReader::SQLite(reader) => reader.read_decimal(index),
+        #[cfg(feature = "sqlite")]
+        CIsarReader::SQLiteObject(reader) => reader.read_decimal(index),
+        #[cfg(feature = "sqlite")]
+        CIsarReader::SQLiteList(reader) => reader.read_decimal(index),
+    };
+    if let Some(value) = value {
+        ptr::copy_nonoverlapping(value.raw().to_le_bytes().as_ptr(), bytes, 16);
+        true
+    } else {
+        false
+    }
+}
+
+/// Writes the 16 bytes of the UUID. Returns false if the value is null.
+#[no_mangle]
+pub unsafe extern "C" fn isar_read_uuid(
+    reader: &'static CIsarReader,
+    index: u32,
+    bytes: *mut u8,
+) -> bool {
+    let value = match reader {
+        #[cfg(feature = "native")]
+        CIsarReader::Native(reader) => reader.read_uuid(index),
+        #[cfg(feature = "native")]
+        CIsarReader::NativeList(reader) => reader.read_uuid(index),
+        #[cfg(feature = "sqlite")]
+        CIsarReader::SQLite(reader) => reader.read_uuid(index),
+        #[cfg(feature = "sqlite")]
+        CIsarReader::SQLiteObject(reader) => reader.read_uuid(index),
+        #[cfg(feature = "sqlite")]
+        CIsarReader::SQLiteList(reader) => reader.read_uuid(index),
+    };
+    if let Some(value) = value {
+        ptr::copy_nonoverlapping(value.0.as_ptr(), bytes, 16);
+        true
+    } else {
+        false
+    }
+}
+
+/// Returns false if the value is null.
+#[no_mangle]
+pub unsafe extern "C" fn isar_read_date_time_offset(
+    reader: &'static CIsarReader,
+    index: u32,
+    micros: *mut IsarI64,
+    offset: *mut i16,
+) -> bool {
+    let value = match reader {
+        #[cfg(feature = "native")]
+        CIsarReader::Native(reader) => reader.read_date_time_offset(index),
+        #[cfg(feature = "native")]
+        CIsarReader::NativeList(reader) => reader.read_date_time_offset(index),
+        #[cfg(feature = "sqlite")]
+        CIsarReader::SQLite(reader) => reader.read_date_time_offset(index),
+        #[cfg(feature = "sqlite")]
+        CIsarReader::SQLiteObject(reader) => reader.read_date_time_offset(index),
+        #[cfg(feature = "sqlite")]
+        CIsarReader::SQLiteList(reader) => reader.read_date_time_offset(index),
+    };
+    if let Some(value) = value {
+        *micros = i64_to_isar(value.micros);
+        *offset = value.offset;
+        true
+    } else {
+        false
+    }
+}
+
+/// Returns the length of a blob or byte list in bytes or -1 if it is null.
+#[no_mangle]
+pub unsafe extern "C" fn isar_read_blob_length(
+    reader: &'static CIsarReader,
+    index: u32,
+) -> IsarI64 {
+    let length = match reader {
+        #[cfg(feature = "native")]
+        CIsarReader::Native(reader) => reader.read_blob_length(index),
+        #[cfg(feature = "native")]
+        CIsarReader::NativeList(reader) => reader.read_blob_length(index),
+        #[cfg(feature = "sqlite")]
+        CIsarReader::SQLite(reader) => reader.read_blob_length(index),
+        #[cfg(feature = "sqlite")]
+        CIsarReader::SQLiteObject(reader) => reader.read_blob_length(index),
+        #[cfg(feature = "sqlite")]
+        CIsarReader::SQLiteList(reader) => reader.read_blob_length(index),
+    };
+    i64_to_isar(length.map_or(-1, |length| length as i64))
+}
+
+/// Copies up to `length` bytes of a blob or byte list starting at `offset` into `buffer` and
+/// stores the number of copied bytes in `read` so large blobs can be read in chunks.
+#[no_mangle]
+pub unsafe extern "C" fn isar_read_blob(
+    reader: &'static CIsarReader,
+    index: u32,
+    offset: IsarI64,
+    buffer: *mut u8,
+    length: u32,
+    read: *mut u32,
+) -> u8 {
+    let buffer = if buffer.is_null() || length == 0 {
+        &mut []
+    } else {
+        slice::from_raw_parts_mut(buffer, length as usize)
+    };
+    let offset = isar_to_i64(offset);
+    isar_try! {
+        @operation(isar_read_blob)
+        if offset < 0 {
+            return Err(IsarError::IllegalArgument {});
+        }
+        let offset = offset as usize;
+        let chunk = match reader {
+            #[cfg(feature = "native")]
+            CIsarReader::Native(reader) => reader.read_blob_chunk(index, offset, buffer),
+            #[cfg(feature = "native")]
+            CIsarReader::NativeList(reader) => reader.read_blob_chunk(index, offset, buffer),
+            #[cfg(feature = "sqlite")]
+            CIsarReader::SQLite(reader) => reader.read_blob_chunk(index, offset, buffer),
+            #[cfg(feature = "sqlite")]
+            CIsarReader::SQLiteObject(reader) => reader.read_blob_chunk(index, offset, buffer),
+            #[cfg(feature = "sqlite")]
+            CIsarReader::SQLiteList(reader) => reader.read_blob_chunk(index, offset, buffer),
+        };
+        *read = chunk.unwrap_or(0) as u32;
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_read_object(
     reader: &'static CIsarReader,
@@ -242,6 +404,77 @@ pub unsafe extern "C" fn isar_read_list(
     }
 }
 
+/// Returns the length of the map. The keys are read with `isar_read_map_key` and the values
+/// with the map reader.
+#[no_mangle]
+pub unsafe extern "C" fn isar_read_map(
+    reader: &'static CIsarReader,
+    index: u32,
+    map_reader: *mut *mut CIsarReader<'static>,
+    keys: *mut *mut Vec<String>,
+) -> u32 {
+    let map = match reader {
+        #[cfg(feature = "native")]
+        CIsarReader::Native(reader) => reader
+            .read_map(index)
+            .map(|(k, r)| (to_owned_keys(k), CIsarReader::NativeList(r))),
+        #[cfg(feature = "native")]
+        CIsarReader::NativeList(reader) => reader
+            .read_map(index)
+            .map(|(k, r)| (to_owned_keys(k), CIsarReader::NativeList(r))),
+        #[cfg(feature = "sqlite")]
+        CIsarReader::SQLite(reader) => reader
+            .read_map(index)
+            .map(|(k, r)| (to_owned_keys(k), CIsarReader::SQLiteList(r))),
+        #[cfg(feature = "sqlite")]
+        CIsarReader::SQLiteObject(reader) => reader
+            .read_map(index)
+            .map(|(k, r)| (to_owned_keys(k), CIsarReader::SQLiteList(r))),
+        #[cfg(feature = "sqlite")]
+        CIsarReader::SQLiteList(reader) => reader
+            .read_map(index)
+            .map(|(k, r)| (to_owned_keys(k), CIsarReader::SQLiteList(r))),
+    };
+    if let Some((map_keys, new_reader)) = map {
+        let length = map_keys.len() as u32;
+        *map_reader = Box::into_raw(Box::new(new_reader));
+        *keys = Box::into_raw(Box::new(map_keys));
+        length
+    } else {
+        *map_reader = ptr::null_mut();
+        *keys = ptr::null_mut();
+        0
+    }
+}
+
+fn to_owned_keys(keys: Vec<Cow<'_, str>>) -> Vec<String> {
+    keys.into_iter().map(|key| key.into_owned()).collect()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_read_map_key(
+    keys: &'static Vec<String>,
+    index: u32,
+    value: *mut *const u8,
+    is_ascii: *mut bool,
+) -> u32 {
+    if let Some(key) = keys.get(index as usize) {
+        *value = key.as_ptr();
+        *is_ascii = key.is_ascii();
+        key.len() as u32
+    } else {
+        *value = ptr::null();
+        0
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_read_map_keys_free(keys: *mut Vec<String>) {
+    if !keys.is_null() {
+        drop(Box::from_raw(keys));
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_read_to_json(
     reader: &'static CIsarReader,
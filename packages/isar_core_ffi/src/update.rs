@@ -1,7 +1,16 @@
 use crate::{isar_to_i64, CIsarInstance, CIsarQuery, CIsarTxn, CIsarUpdate, IsarI64};
 use isar_core::core::error::IsarError;
+use isar_core::core::filter::Filter;
 use isar_core::core::instance::IsarInstance;
+use isar_core::core::update::{PropertyUpdate, UpdateOperation};
 use isar_core::core::value::IsarValue;
+use std::slice;
+
+pub const UPDATE_SET: u8 = 0;
+pub const UPDATE_INCREMENT: u8 = 1;
+pub const UPDATE_APPEND: u8 = 2;
+pub const UPDATE_REMOVE: u8 = 3;
+pub const UPDATE_SET_AT: u8 = 4;
 
 #[no_mangle]
 pub unsafe extern "C" fn isar_update(
@@ -15,14 +24,76 @@ pub unsafe extern "C" fn isar_update(
     let id = isar_to_i64(id);
     let update = Box::from_raw(update);
     isar_try! {
+        @operation(isar_update)
+        @context(collection = collection_index, id = id)
+        match (isar, txn) {
+            #[cfg(feature = "native")]
+            (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
+                *updated = isar.update_properties(txn, collection_index, id, &update.0)?;
+            }
+            #[cfg(feature = "sqlite")]
+            (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => {
+                *updated = isar.update_properties(txn, collection_index, id, &update.0)?;
+            }
+            _ => return Err(IsarError::IllegalArgument {}),
+        };
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_update_if(
+    isar: &'static CIsarInstance,
+    txn: &CIsarTxn,
+    collection_index: u16,
+    id: IsarI64,
+    condition: *mut Filter,
+    update: *mut CIsarUpdate,
+    updated: *mut bool,
+) -> u8 {
+    let id = isar_to_i64(id);
+    let condition = *Box::from_raw(condition);
+    let update = Box::from_raw(update);
+    isar_try! {
+        @operation(isar_update_if)
+        @context(collection = collection_index, id = id)
+        match (isar, txn) {
+            #[cfg(feature = "native")]
+            (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
+                *updated = isar.update_if(txn, collection_index, id, condition, &update.0)?;
+            }
+            #[cfg(feature = "sqlite")]
+            (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => {
+                *updated = isar.update_if(txn, collection_index, id, condition, &update.0)?;
+            }
+            _ => return Err(IsarError::IllegalArgument {}),
+        };
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_update_if_version(
+    isar: &'static CIsarInstance,
+    txn: &CIsarTxn,
+    collection_index: u16,
+    id: IsarI64,
+    version: IsarI64,
+    update: *mut CIsarUpdate,
+    updated: *mut bool,
+) -> u8 {
+    let id = isar_to_i64(id);
+    let version = isar_to_i64(version);
+    let update = Box::from_raw(update);
+    isar_try! {
+        @operation(isar_update_if_version)
+        @context(collection = collection_index, id = id)
         match (isar, txn) {
             #[cfg(feature = "native")]
             (CIsarInstance::Native(isar), CIsarTxn::Native(txn)) => {
-                *updated = isar.update(txn, collection_index, id, &update.0)?;
+                *updated = isar.update_if_version(txn, collection_index, id, version, &update.0)?;
             }
             #[cfg(feature = "sqlite")]
             (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn)) => {
-                *updated = isar.update(txn, collection_index, id, &update.0)?;
+                *updated = isar.update_if_version(txn, collection_index, id, version, &update.0)?;
             }
             _ => return Err(IsarError::IllegalArgument {}),
         };
@@ -45,14 +116,15 @@ pub unsafe extern "C" fn isar_query_update(
     let update = Box::from_raw(update);
 
     isar_try! {
+        @operation(isar_query_update)
         match (isar, txn, query) {
             #[cfg(feature = "native")]
             (CIsarInstance::Native(isar), CIsarTxn::Native(txn), CIsarQuery::Native(query)) => {
-                *updated = isar.query_update(txn, query, offset, limit, &update.0)?;
+                *updated = isar.query_update_properties(txn, query, offset, limit, &update.0)?;
             }
             #[cfg(feature = "sqlite")]
             (CIsarInstance::SQLite(isar), CIsarTxn::SQLite(txn), CIsarQuery::SQLite(query)) => {
-                *updated = isar.query_update(txn, query, offset, limit, &update.0)?;
+                *updated = isar.query_update_properties(txn, query, offset, limit, &update.0)?;
             }
             _ => return Err(IsarError::IllegalArgument {}),
         };
@@ -75,5 +147,36 @@ pub unsafe extern "C" fn isar_update_add_value(
     } else {
         None
     };
-    update.0.push((property_index, value));
+    update.0.push(PropertyUpdate::set(property_index, value));
+}
+
+/// Adds an operation on the property at `path`. `index` is only used by `UPDATE_SET_AT` and
+/// the value is required by `UPDATE_INCREMENT`.
+#[no_mangle]
+pub unsafe extern "C" fn isar_update_add_operation(
+    update: &'static mut CIsarUpdate,
+    path: *const u16,
+    path_length: u32,
+    operation: u8,
+    index: u32,
+    value: *mut IsarValue,
+) -> u8 {
+    let path = slice::from_raw_parts(path, path_length as usize).to_vec();
+    let value = if !value.is_null() {
+        Some(*Box::from_raw(value))
+    } else {
+        None
+    };
+    isar_try! {
+        @operation(isar_update_add_operation)
+        let operation = match (operation, value) {
+            (UPDATE_SET, value) => UpdateOperation::Set(value),
+            (UPDATE_INCREMENT, Some(value)) => UpdateOperation::Increment(value),
+            (UPDATE_APPEND, value) => UpdateOperation::Append(value),
+            (UPDATE_REMOVE, value) => UpdateOperation::Remove(value),
+            (UPDATE_SET_AT, value) => UpdateOperation::SetAt(index, value),
+            _ => return Err(IsarError::IllegalArgument {}),
+        };
+        update.0.push(PropertyUpdate::new(path, operation));
+    }
 }
@@ -1,6 +1,6 @@
 use crate::{i64_to_isar, isar_to_i64, IsarI64};
-use isar_core::core::value::IsarValue;
-use std::ptr;
+use isar_core::core::value::{DateTimeOffset, Decimal, IsarValue, Uuid};
+use std::{ptr, slice};
 
 #[no_mangle]
 pub unsafe extern "C" fn isar_value_bool(value: bool) -> *const IsarValue {
@@ -23,6 +23,29 @@ pub unsafe extern "C" fn isar_value_string(value: *mut String) -> *const IsarVal
     Box::into_raw(Box::new(IsarValue::String(*Box::from_raw(value))))
 }
 
+/// The value points to the 16 bytes of the little endian raw decimal value.
+#[no_mangle]
+pub unsafe extern "C" fn isar_value_decimal(value: *const u8) -> *const IsarValue {
+    let raw = i128::from_le_bytes(slice::from_raw_parts(value, 16).try_into().unwrap());
+    Box::into_raw(Box::new(IsarValue::Decimal(Decimal::from_raw(raw))))
+}
+
+/// The value points to the 16 bytes of the UUID.
+#[no_mangle]
+pub unsafe extern "C" fn isar_value_uuid(value: *const u8) -> *const IsarValue {
+    let uuid = Uuid(slice::from_raw_parts(value, 16).try_into().unwrap());
+    Box::into_raw(Box::new(IsarValue::Uuid(uuid)))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_value_date_time_offset(
+    micros: IsarI64,
+    offset: i16,
+) -> *const IsarValue {
+    let value = DateTimeOffset::new(isar_to_i64(micros), offset);
+    Box::into_raw(Box::new(IsarValue::DateTimeOffset(value)))
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_value_get_bool(value: *const IsarValue) -> u8 {
     let value = value.as_ref().map(|v| v.bool()).flatten().unwrap_or(false);
@@ -14,6 +14,8 @@ pub unsafe extern "C" fn isar_watch_collection(
         dart_post_int(port, 1);
     });
     isar_try! {
+        @operation(isar_watch_collection)
+        @context(collection = collection_index)
         let new_handle = match isar {
             #[cfg(feature = "native")]
             CIsarInstance::Native(isar) => isar.watch(collection_index, callback)?,
@@ -36,6 +38,8 @@ pub unsafe extern "C" fn isar_watch_object(
         dart_post_int(port, 1);
     });
     isar_try! {
+        @operation(isar_watch_object)
+        @context(collection = collection_index, id = id)
         let new_handle = match isar {
             #[cfg(feature = "native")]
             CIsarInstance::Native(isar) => isar.watch_object(collection_index, id, callback)?,
@@ -57,6 +61,7 @@ pub unsafe extern "C" fn isar_watch_query(
         dart_post_int(port, 1);
     });
     isar_try! {
+        @operation(isar_watch_query)
         let new_handle = match (isar, query) {
             #[cfg(feature = "native")]
             (CIsarInstance::Native(isar), CIsarQuery::Native(query)) => {
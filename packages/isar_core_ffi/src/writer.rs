@@ -1,4 +1,5 @@
 use crate::{isar_to_i64, CIsarWriter, IsarI64};
+use isar_core::core::value::{DateTimeOffset, Decimal, Uuid};
 use isar_core::core::writer::IsarWriter;
 use std::slice;
 
@@ -168,6 +169,79 @@ pub unsafe extern "C" fn isar_write_string(
     }
 }
 
+/// The value points to the 16 bytes of the little endian raw decimal value.
+#[no_mangle]
+pub unsafe extern "C" fn isar_write_decimal(
+    writer: &'static mut CIsarWriter,
+    index: u32,
+    value: *const u8,
+) {
+    let raw = i128::from_le_bytes(slice::from_raw_parts(value, 16).try_into().unwrap());
+    let value = Decimal::from_raw(raw);
+    match writer {
+        #[cfg(feature = "native")]
+        CIsarWriter::Native(writer) => writer.write_decimal(index, value),
+        #[cfg(feature = "native")]
+        CIsarWriter::NativeObject(writer) => writer.write_decimal(index, value),
+        #[cfg(feature = "native")]
+        CIsarWriter::NativeList(writer) => writer.write_decimal(index, value),
+        #[cfg(feature = "sqlite")]
+        CIsarWriter::SQLite(writer) => writer.write_decimal(index, value),
+        #[cfg(feature = "sqlite")]
+        CIsarWriter::SQLiteObject(writer) => writer.write_decimal(index, value),
+        #[cfg(feature = "sqlite")]
+        CIsarWriter::SQLiteList(writer) => writer.write_decimal(index, value),
+    }
+}
+
+/// The value points to the 16 bytes of the UUID.
+#[no_mangle]
+pub unsafe extern "C" fn isar_write_uuid(
+    writer: &'static mut CIsarWriter,
+    index: u32,
+    value: *const u8,
+) {
+    let value = Uuid(slice::from_raw_parts(value, 16).try_into().unwrap());
+    match writer {
+        #[cfg(feature = "native")]
+        CIsarWriter::Native(writer) => writer.write_uuid(index, value),
+        #[cfg(feature = "native")]
+        CIsarWriter::NativeObject(writer) => writer.write_uuid(index, value),
+        #[cfg(feature = "native")]
+        CIsarWriter::NativeList(writer) => writer.write_uuid(index, value),
+        #[cfg(feature = "sqlite")]
+        CIsarWriter::SQLite(writer) => writer.write_uuid(index, value),
+        #[cfg(feature = "sqlite")]
+        CIsarWriter::SQLiteObject(writer) => writer.write_uuid(index, value),
+        #[cfg(feature = "sqlite")]
+        CIsarWriter::SQLiteList(writer) => writer.write_uuid(index, value),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_write_date_time_offset(
+    writer: &'static mut CIsarWriter,
+    index: u32,
+    micros: IsarI64,
+    offset: i16,
+) {
+    let value = DateTimeOffset::new(isar_to_i64(micros), offset);
+    match writer {
+        #[cfg(feature = "native")]
+        CIsarWriter::Native(writer) => writer.write_date_time_offset(index, value),
+        #[cfg(feature = "native")]
+        CIsarWriter::NativeObject(writer) => writer.write_date_time_offset(index, value),
+        #[cfg(feature = "native")]
+        CIsarWriter::NativeList(writer) => writer.write_date_time_offset(index, value),
+        #[cfg(feature = "sqlite")]
+        CIsarWriter::SQLite(writer) => writer.write_date_time_offset(index, value),
+        #[cfg(feature = "sqlite")]
+        CIsarWriter::SQLiteObject(writer) => writer.write_date_time_offset(index, value),
+        #[cfg(feature = "sqlite")]
+        CIsarWriter::SQLiteList(writer) => writer.write_date_time_offset(index, value),
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_write_byte_list(
     writer: &'static mut CIsarWriter,
@@ -192,6 +266,34 @@ pub unsafe extern "C" fn isar_write_byte_list(
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn isar_write_blob(
+    writer: &'static mut CIsarWriter,
+    index: u32,
+    value: *const u8,
+    length: u32,
+) {
+    let bytes = if value.is_null() || length == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(value, length as usize)
+    };
+    match writer {
+        #[cfg(feature = "native")]
+        CIsarWriter::Native(writer) => writer.write_blob(index, bytes),
+        #[cfg(feature = "native")]
+        CIsarWriter::NativeObject(writer) => writer.write_blob(index, bytes),
+        #[cfg(feature = "native")]
+        CIsarWriter::NativeList(writer) => writer.write_blob(index, bytes),
+        #[cfg(feature = "sqlite")]
+        CIsarWriter::SQLite(writer) => writer.write_blob(index, bytes),
+        #[cfg(feature = "sqlite")]
+        CIsarWriter::SQLiteObject(writer) => writer.write_blob(index, bytes),
+        #[cfg(feature = "sqlite")]
+        CIsarWriter::SQLiteList(writer) => writer.write_blob(index, bytes),
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_write_object(
     writer: &'static mut CIsarWriter,
@@ -289,6 +391,42 @@ pub unsafe extern "C" fn isar_write_list(
     Box::into_raw(Box::new(writer))
 }
 
+/// The values are written to the returned writer in the order of the keys and the map is
+/// finished with `isar_write_list_end`.
+#[no_mangle]
+pub unsafe extern "C" fn isar_write_map(
+    writer: &'static mut CIsarWriter,
+    index: u32,
+    keys: *mut *mut String,
+    length: u32,
+) -> *mut CIsarWriter<'static> {
+    let keys = slice::from_raw_parts(keys, length as usize)
+        .iter()
+        .map(|key| *Box::from_raw(*key))
+        .collect::<Vec<_>>();
+    let keys = keys.iter().map(|key| key.as_str()).collect::<Vec<_>>();
+    let writer = match writer {
+        #[cfg(feature = "native")]
+        CIsarWriter::Native(writer) => {
+            CIsarWriter::NativeList(writer.begin_map(index, &keys).unwrap())
+        }
+        #[cfg(feature = "native")]
+        CIsarWriter::NativeObject(writer) => {
+            CIsarWriter::NativeList(writer.begin_map(index, &keys).unwrap())
+        }
+        #[cfg(feature = "sqlite")]
+        CIsarWriter::SQLite(writer) => {
+            CIsarWriter::SQLiteList(writer.begin_map(index, &keys).unwrap())
+        }
+        #[cfg(feature = "sqlite")]
+        CIsarWriter::SQLiteObject(writer) => {
+            CIsarWriter::SQLiteList(writer.begin_map(index, &keys).unwrap())
+        }
+        _ => panic!("Cannot write nested map"),
+    };
+    Box::into_raw(Box::new(writer))
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_write_list_end(
     writer: &'static mut CIsarWriter,
@@ -0,0 +1,314 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, LitStr, Result, Type};
+
+/// Implements `IsarObject` and `IsarCollection` for a struct with named fields.
+///
+/// The id is the `i64` field named `id` or marked with `#[isar(id)]`. Fields can be renamed with
+/// `#[isar(name = "...")]`, skipped with `#[isar(ignore)]` and indexed with `#[isar(index)]`,
+/// `#[isar(index(unique))]` or `#[isar(index(hash))]`.
+#[proc_macro_derive(IsarCollection, attributes(isar))]
+pub fn derive_collection(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input, true)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Implements `IsarObject` and `IsarProperty` for a struct with named fields that is stored as an
+/// embedded object. Non-optional embedded fields require the type to implement `Default`.
+#[proc_macro_derive(IsarEmbedded, attributes(isar))]
+pub fn derive_embedded(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input, false)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+struct Field<'a> {
+    ident: &'a Ident,
+    ty: &'a Type,
+    name: String,
+    id: bool,
+    ignore: bool,
+    // unique, hash
+    index: Option<(bool, bool)>,
+}
+
+fn parse_struct_name(attrs: &[Attribute]) -> Result<Option<String>> {
+    let mut name = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("isar")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("unknown isar attribute"))
+            }
+        })?;
+    }
+    Ok(name)
+}
+
+fn parse_field(field: &syn::Field) -> Result<Field<'_>> {
+    let ident = field.ident.as_ref().unwrap();
+    let mut result = Field {
+        ident,
+        ty: &field.ty,
+        name: ident.to_string().trim_start_matches("r#").to_string(),
+        id: false,
+        ignore: false,
+        index: None,
+    };
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("isar"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("id") {
+                result.id = true;
+            } else if meta.path.is_ident("ignore") {
+                result.ignore = true;
+            } else if meta.path.is_ident("name") {
+                result.name = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("index") {
+                let (mut unique, mut hash) = (false, false);
+                if meta.input.peek(syn::token::Paren) {
+                    meta.parse_nested_meta(|meta| {
+                        if meta.path.is_ident("unique") {
+                            unique = true;
+                        } else if meta.path.is_ident("hash") {
+                            hash = true;
+                        } else {
+                            return Err(meta.error("expected `unique` or `hash`"));
+                        }
+                        Ok(())
+                    })?;
+                }
+                result.index = Some((unique, hash));
+            } else {
+                return Err(meta.error("unknown isar attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(result)
+}
+
+fn expand(input: &DeriveInput, collection: bool) -> Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    input,
+                    "only structs with named fields are supported",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                input,
+                "only structs with named fields are supported",
+            ))
+        }
+    };
+    let mut fields = fields.iter().map(parse_field).collect::<Result<Vec<_>>>()?;
+
+    let ident = &input.ident;
+    let name = parse_struct_name(&input.attrs)?.unwrap_or_else(|| ident.to_string());
+    let krate = quote!(::isar_core::core);
+
+    let id = if collection {
+        if !fields.iter().any(|field| field.id) {
+            if let Some(field) = fields.iter_mut().find(|field| field.ident == "id") {
+                field.id = true;
+            }
+        }
+        let mut ids = fields.iter().filter(|field| field.id);
+        let id = ids.next().ok_or_else(|| {
+            Error::new_spanned(
+                input,
+                "a collection needs an `id: i64` field or a field marked with #[isar(id)]",
+            )
+        })?;
+        if let Some(field) = ids.next() {
+            return Err(Error::new_spanned(field.ident, "only one id is allowed"));
+        }
+        if id.ignore || id.index.is_some() {
+            return Err(Error::new_spanned(
+                id.ident,
+                "the id cannot be ignored or indexed",
+            ));
+        }
+        Some(id)
+    } else {
+        for field in &fields {
+            if field.id || field.index.is_some() {
+                return Err(Error::new_spanned(
+                    field.ident,
+                    "embedded objects have no id or indexes",
+                ));
+            }
+        }
+        None
+    };
+    let properties = fields
+        .iter()
+        .filter(|field| !field.id && !field.ignore)
+        .collect::<Vec<_>>();
+
+    let property_schemas = properties.iter().map(|field| {
+        let (name, ty) = (&field.name, field.ty);
+        quote! {
+            #krate::schema::PropertySchema::new(
+                #name,
+                <#ty as #krate::collection::IsarProperty>::DATA_TYPE,
+                <#ty as #krate::collection::IsarProperty>::target(),
+            )
+        }
+    });
+    let index_schemas = properties.iter().filter_map(|field| {
+        let (unique, hash) = field.index?;
+        let name = &field.name;
+        Some(quote! {
+            #krate::schema::IndexSchema::new(#name, vec![#name], #unique, #hash)
+        })
+    });
+    let id_name = match id {
+        Some(id) => {
+            let id_name = &id.name;
+            quote!(Some(#id_name))
+        }
+        None => quote!(None),
+    };
+    let embedded = !collection;
+
+    let add_embedded = properties.iter().map(|field| {
+        let ty = field.ty;
+        quote!(<#ty as #krate::collection::IsarProperty>::add_schemas(schemas);)
+    });
+    let writes = properties.iter().enumerate().map(|(index, field)| {
+        let (field_ident, ty) = (field.ident, field.ty);
+        let index = index as u32 + 1;
+        quote! {
+            <#ty as #krate::collection::IsarProperty>::write(&self.#field_ident, writer, #index);
+        }
+    });
+    let reads = fields.iter().map(|field| {
+        let (field_ident, ty) = (field.ident, field.ty);
+        if field.id {
+            quote!(#field_ident: reader.read_id())
+        } else if field.ignore {
+            quote!(#field_ident: ::core::default::Default::default())
+        } else {
+            let index = properties
+                .iter()
+                .position(|p| p.ident == field_ident)
+                .unwrap() as u32
+                + 1;
+            quote! {
+                #field_ident: <#ty as #krate::collection::IsarProperty>::read(reader, #index)
+                    .unwrap_or_default()
+            }
+        }
+    });
+    let consts = id
+        .iter()
+        .map(|field| (0, *field))
+        .chain(
+            properties
+                .iter()
+                .enumerate()
+                .map(|(index, field)| (index as u16 + 1, *field)),
+        )
+        .map(|(index, field)| {
+            let name = field.ident.to_string();
+            let const_ident = format_ident!("{}", name.trim_start_matches("r#").to_uppercase());
+            let ty = field.ty;
+            quote! {
+                pub const #const_ident: #krate::collection::Property<#ty> =
+                    #krate::collection::Property::new(#index);
+            }
+        });
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut output = quote! {
+        impl #impl_generics #krate::collection::IsarObject for #ident #ty_generics #where_clause {
+            fn name() -> &'static str {
+                #name
+            }
+
+            fn schema() -> #krate::schema::IsarSchema {
+                #krate::schema::IsarSchema::new(
+                    #name,
+                    #id_name,
+                    vec![#(#property_schemas),*],
+                    vec![#(#index_schemas),*],
+                    #embedded,
+                )
+            }
+
+            fn add_embedded_schemas(schemas: &mut Vec<#krate::schema::IsarSchema>) {
+                #(#add_embedded)*
+            }
+
+            fn write<'a>(&self, writer: &mut impl #krate::writer::IsarWriter<'a>) {
+                #(#writes)*
+            }
+
+            fn read(reader: &impl #krate::reader::IsarReader) -> Self {
+                Self {
+                    #(#reads),*
+                }
+            }
+        }
+
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #(#consts)*
+        }
+    };
+
+    if let Some(id) = id {
+        let id_ident = id.ident;
+        output.extend(quote! {
+            impl #impl_generics #krate::collection::IsarCollection for #ident #ty_generics #where_clause {
+                fn id(&self) -> i64 {
+                    self.#id_ident
+                }
+            }
+        });
+    } else {
+        output.extend(quote! {
+            impl #impl_generics #krate::collection::IsarProperty for #ident #ty_generics #where_clause {
+                const DATA_TYPE: #krate::data_type::DataType = #krate::data_type::DataType::Object;
+
+                type Value = ::core::convert::Infallible;
+
+                fn target() -> Option<&'static str> {
+                    Some(#name)
+                }
+
+                fn add_schemas(schemas: &mut Vec<#krate::schema::IsarSchema>) {
+                    <Self as #krate::collection::IsarObject>::add_schemas(schemas);
+                }
+
+                fn write<'a>(&self, writer: &mut impl #krate::writer::IsarWriter<'a>, index: u32) {
+                    if let Some(mut object) = writer.begin_object(index) {
+                        #krate::collection::IsarObject::write(self, &mut object);
+                        writer.end_object(object);
+                    }
+                }
+
+                fn read(reader: &impl #krate::reader::IsarReader, index: u32) -> Option<Self> {
+                    let object = reader.read_object(index)?;
+                    Some(<Self as #krate::collection::IsarObject>::read(&object))
+                }
+            }
+        });
+    }
+
+    Ok(output)
+}